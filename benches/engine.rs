@@ -0,0 +1,99 @@
+// Run with `cargo bench`, the numbers give a rough budget for AI searches
+#![allow(clippy::needless_return)]
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use std::hint::black_box;
+use tetris_core_mod::{Action, Board, FigureType, Game, GameConfig, Size};
+
+fn board_with_full_rows(count: usize) -> Board {
+    let size = Size {
+        height: 40,
+        width: 10,
+    };
+    let mut board = Board::new(&size);
+    for y in size.height - count..size.height {
+        for x in 0..size.width {
+            board.set_figure_at_xy(x, y, Some(FigureType::Garbage));
+        }
+    }
+    return board;
+}
+
+// A bumpy stack for the placement search to walk around
+fn game_with_stack() -> Game {
+    let mut board = Board::new(&Size {
+        height: 40,
+        width: 10,
+    });
+    for x in 0..10 {
+        for y in 40 - (x * 7 % 5)..40 {
+            board.set_figure_at_xy(x, y, Some(FigureType::Garbage));
+        }
+    }
+    return Game::with_board(board, GameConfig::builder().hidden_rows(20).build());
+}
+
+fn locking(criterion: &mut Criterion) {
+    criterion.bench_function("hard drop and lock", |bencher| {
+        bencher.iter_batched(
+            || Game::with_config(GameConfig::default()),
+            |mut game| {
+                for _ in 0..10 {
+                    game.perform(Action::MoveLeft);
+                    game.perform(Action::HardDrop);
+                }
+                return game;
+            },
+            BatchSize::SmallInput,
+        );
+    });
+}
+
+fn line_clears(criterion: &mut Criterion) {
+    let board = board_with_full_rows(4);
+    let lines: Vec<usize> = (36..40).collect();
+    criterion.bench_function("remove four lines", |bencher| {
+        let mut target = board.clone();
+        bencher.iter(|| {
+            target.clone_from(&board);
+            target.remove_lines_in_place(black_box(&lines));
+        });
+    });
+    criterion.bench_function("find full lines", |bencher| {
+        bencher.iter(|| {
+            (0..board.height())
+                .filter(|y| board.is_line_full(*y))
+                .count()
+        });
+    });
+}
+
+fn placements(criterion: &mut Criterion) {
+    let game = game_with_stack();
+    criterion.bench_function("enumerate placements", |bencher| {
+        bencher.iter(|| black_box(&game).enumerate_placements());
+    });
+    let placement = game.enumerate_placements()[0].clone();
+    let snapshot = game.snapshot();
+    criterion.bench_function("apply placement and restore", |bencher| {
+        let mut game = game_with_stack();
+        bencher.iter(|| {
+            game.apply_placement(&placement);
+            game.restore(&snapshot);
+        });
+    });
+}
+
+fn drawing(criterion: &mut Criterion) {
+    let game = game_with_stack();
+    criterion.bench_function("draw", |bencher| {
+        bencher.iter(|| black_box(&game).draw());
+    });
+    criterion.bench_function("draw into", |bencher| {
+        let mut blocks = vec![];
+        bencher.iter(|| black_box(&game).draw_into(&mut blocks));
+    });
+}
+
+criterion_group!(benches, locking, line_clears, placements, drawing);
+criterion_main!(benches);
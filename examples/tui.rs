@@ -0,0 +1,240 @@
+// Playable terminal frontend, run with `cargo run --example tui --features tui`
+//
+// Arrows move and soft drop, space hard drops, up or x rotates clockwise, z counter-clockwise,
+// a rotates 180, c holds, p pauses, r restarts and q or escape quits.
+
+#![allow(clippy::needless_return)]
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::style::{self, Print, ResetColor, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::io::{self, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tetris_core_mod::{Action, FigureType, Game, GameConfig, Key, KeyState, PieceColor};
+
+const FRAME: Duration = Duration::from_millis(16);
+// Columns used by the hold panel, the field starts right after it
+const FIELD_LEFT: u16 = 16;
+const CELL: &str = "[]";
+const GHOST_CELL: &str = "::";
+
+enum Command {
+    Input(Key, KeyState),
+    Perform(Action),
+    TogglePause,
+    Restart,
+    Quit,
+}
+
+fn main() -> io::Result<()> {
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen, Hide)?;
+    // Release events let the game run its own auto shift, other terminals fall back to taps
+    let releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+    if releases {
+        let flags = KeyboardEnhancementFlags::REPORT_EVENT_TYPES;
+        execute!(stdout, PushKeyboardEnhancementFlags(flags))?;
+    }
+    let result = run(&mut stdout, releases);
+    if releases {
+        execute!(stdout, PopKeyboardEnhancementFlags)?;
+    }
+    execute!(stdout, ResetColor, Show, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    return result;
+}
+
+fn new_game() -> Game {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos() as u64);
+    return Game::with_config(GameConfig::builder().seed(seed).build());
+}
+
+fn run(stdout: &mut io::Stdout, releases: bool) -> io::Result<()> {
+    let mut game = new_game();
+    let mut last_update = Instant::now();
+    queue!(stdout, Clear(ClearType::All))?;
+    loop {
+        while event::poll(Duration::ZERO)? {
+            let key = match event::read()? {
+                Event::Key(key) => key,
+                _ => continue,
+            };
+            for command in commands_for(key, releases) {
+                match command {
+                    Command::Input(key, state) => {
+                        game.input(key, state);
+                    }
+                    Command::Perform(action) => {
+                        game.perform(action);
+                    }
+                    Command::TogglePause if game.is_paused() => {
+                        game.perform(Action::Resume);
+                    }
+                    Command::TogglePause => {
+                        game.perform(Action::Pause);
+                    }
+                    Command::Restart => game = new_game(),
+                    Command::Quit => return Ok(()),
+                }
+            }
+        }
+        let now = Instant::now();
+        game.update(now.duration_since(last_update).as_secs_f64());
+        last_update = now;
+        draw(stdout, &game)?;
+        std::thread::sleep(FRAME);
+    }
+}
+
+fn commands_for(event: KeyEvent, releases: bool) -> Vec<Command> {
+    let state = match event.kind {
+        KeyEventKind::Press => KeyState::Pressed,
+        KeyEventKind::Release => KeyState::Released,
+        // The game repeats held directions itself
+        KeyEventKind::Repeat if releases => return vec![],
+        KeyEventKind::Repeat => KeyState::Pressed,
+    };
+    let key = match event.code {
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Down => Key::SoftDrop,
+        KeyCode::Char(' ') => Key::HardDrop,
+        KeyCode::Up | KeyCode::Char('x') => Key::RotateCW,
+        KeyCode::Char('z') => Key::RotateCCW,
+        KeyCode::Char('a') => Key::Rotate180,
+        KeyCode::Char('c') => Key::Hold,
+        _ if state == KeyState::Released => return vec![],
+        KeyCode::Char('p') => return vec![Command::TogglePause],
+        KeyCode::Char('r') => return vec![Command::Restart],
+        KeyCode::Char('q') | KeyCode::Esc => return vec![Command::Quit],
+        _ => return vec![],
+    };
+    if releases {
+        return vec![Command::Input(key, state)];
+    }
+    // Without release events every press is a tap
+    return match key {
+        Key::SoftDrop => vec![Command::Perform(Action::SoftDrop)],
+        _ => vec![
+            Command::Input(key, KeyState::Pressed),
+            Command::Input(key, KeyState::Released),
+        ],
+    };
+}
+
+fn draw(stdout: &mut io::Stdout, game: &Game) -> io::Result<()> {
+    let size = game.get_visible_size();
+    let palette = game.get_palette();
+    for y in 0..size.height as u16 {
+        queue!(stdout, MoveTo(FIELD_LEFT - 1, y), ResetColor, Print("|"))?;
+        queue!(stdout, Print(" ".repeat(size.width * 2)), Print("|"))?;
+    }
+    let bottom = size.height as u16;
+    queue!(stdout, MoveTo(FIELD_LEFT - 1, bottom))?;
+    queue!(stdout, Print(format!("+{}+", "-".repeat(size.width * 2))))?;
+    for block in game.draw_ghost() {
+        let origin = block.rect.origin;
+        draw_cell(stdout, origin.x, origin.y, block.color, GHOST_CELL)?;
+    }
+    for point in game.access_blocked() {
+        draw_cell(stdout, point.x, point.y, palette.garbage, "##")?;
+    }
+    for (point, figure_type) in game.board_cells() {
+        draw_cell(stdout, point.x, point.y, palette.color(&figure_type), CELL)?;
+    }
+    let active_color = palette.color(&game.active_figure_type());
+    for point in game.access_active_figure() {
+        draw_cell(stdout, point.x, point.y, active_color, CELL)?;
+    }
+    draw_panel(stdout, game, size.width)?;
+    return stdout.flush();
+}
+
+fn draw_cell(stdout: &mut io::Stdout, x: i32, y: i32, color: PieceColor, cell: &str) -> io::Result<()> {
+    if x < 0 || y < 0 {
+        return Ok(());
+    }
+    let column = FIELD_LEFT + x as u16 * 2;
+    queue!(stdout, MoveTo(column, y as u16))?;
+    queue!(
+        stdout,
+        SetForegroundColor(terminal_color(color)),
+        Print(cell)
+    )?;
+    return Ok(());
+}
+
+fn draw_panel(stdout: &mut io::Stdout, game: &Game, field_width: usize) -> io::Result<()> {
+    let right = FIELD_LEFT + field_width as u16 * 2 + 3;
+    queue!(stdout, ResetColor, MoveTo(0, 0), Print("HOLD"))?;
+    draw_preview(stdout, game, 0, 1, game.get_held_figure())?;
+    queue!(stdout, ResetColor, MoveTo(right, 0), Print("NEXT"))?;
+    for (index, figure_type) in game.get_next_queue().into_iter().enumerate() {
+        draw_preview(stdout, game, right, 1 + index as u16 * 3, Some(figure_type))?;
+    }
+    let status = if game.is_game_over() {
+        "GAME OVER, r restarts"
+    } else if game.is_paused() {
+        "PAUSED"
+    } else {
+        ""
+    };
+    let lines = [
+        format!("SCORE {}", game.get_score()),
+        format!("LEVEL {}", game.get_level()),
+        format!("LINES {}", game.get_lines_completed()),
+    ];
+    for (row, line) in lines.iter().enumerate() {
+        queue!(stdout, ResetColor, MoveTo(0, 4 + row as u16), Print(line))?;
+    }
+    let below_field = game.get_visible_size().height as u16 + 1;
+    queue!(stdout, MoveTo(FIELD_LEFT - 1, below_field))?;
+    queue!(stdout, Print(format!("{:<24}", status)))?;
+    return Ok(());
+}
+
+// Figures in their spawn orientation, inside a 4x2 cells box
+fn draw_preview(
+    stdout: &mut io::Stdout,
+    game: &Game,
+    left: u16,
+    top: u16,
+    figure_type: Option<FigureType>,
+) -> io::Result<()> {
+    for row in 0..2 {
+        queue!(stdout, MoveTo(left, top + row), Print("        "))?;
+    }
+    let figure_type = match figure_type {
+        Some(figure_type) => figure_type,
+        None => return Ok(()),
+    };
+    let color = terminal_color(game.get_palette().color(&figure_type));
+    let matrix = figure_type.initial_matrix();
+    // Empty top rows are skipped, like the one above the I
+    let first_row = (0..matrix.height())
+        .find(|y| (0..matrix.width()).any(|x| matrix.at_xy(x, *y) == Some(&1)))
+        .unwrap_or(0);
+    for y in first_row..matrix.height().min(first_row + 2) {
+        for x in 0..matrix.width().min(4) {
+            if matrix.at_xy(x, y) == Some(&1) {
+                let row = top + (y - first_row) as u16;
+                queue!(stdout, MoveTo(left + x as u16 * 2, row))?;
+                queue!(stdout, SetForegroundColor(color), Print(CELL))?;
+            }
+        }
+    }
+    return Ok(());
+}
+
+fn terminal_color(color: PieceColor) -> style::Color {
+    let [r, g, b, _] = color.to_rgba_u8();
+    return style::Color::Rgb { r, g, b };
+}
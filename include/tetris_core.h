@@ -0,0 +1,36 @@
+/* C API of tetris_core_mod, built with the `capi` feature.
+ * Generated layout, regenerate with: cbindgen --config cbindgen.toml --output include/tetris_core.h
+ */
+
+#ifndef TETRIS_CORE_H
+#define TETRIS_CORE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+typedef struct Game Game;
+
+Game *tetris_game_new(uint32_t width, uint32_t height, uint64_t seed);
+
+void tetris_game_free(Game *game);
+
+void tetris_game_update(Game *game, double delta_time);
+
+bool tetris_game_perform(Game *game, uint8_t action_code);
+
+size_t tetris_game_draw_into_buffer(const Game *game, uint8_t *buffer, size_t length);
+
+uint32_t tetris_game_width(const Game *game);
+
+uint32_t tetris_game_height(const Game *game);
+
+uint64_t tetris_game_score(const Game *game);
+
+uint32_t tetris_game_lines(const Game *game);
+
+uint32_t tetris_game_level(const Game *game);
+
+bool tetris_game_is_game_over(const Game *game);
+
+#endif /* TETRIS_CORE_H */
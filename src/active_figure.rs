@@ -1,8 +1,12 @@
-use super::{Color, Figure, FigureType, Point};
+use super::{Figure, FigureType, Palette, PieceColor, PiecePose, Point, Rotation, RotationSystem};
+use alloc::vec::Vec;
 
-#[derive(Debug, Clone, PartialEq)]
+// A figure placed on a board, with the geometry the engine uses. Rotations here turn the matrix
+// without kicks, the kicks and collision checks stay with the game
+#[derive(Debug, Clone, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ActiveFigure {
-    pub figure: Figure,
+    pub(crate) figure: Figure,
     position: Point,
     rotation_step: usize,
 }
@@ -16,34 +20,57 @@ impl ActiveFigure {
         };
     }
 
-    pub fn to_cartesian(&self) -> Vec<Point> {
-        let figure_points = self.figure.to_cartesian();
-        let (dx, dy) = (self.position.x, self.position.y);
+    // Turned clockwise from the spawn orientation as many times as the pose says
+    pub fn from_pose(pose: &PiecePose) -> ActiveFigure {
+        let mut active = ActiveFigure::new(pose.kind.clone(), pose.origin);
+        for _ in 0..pose.rotation.steps() {
+            active = active.rotated();
+        }
+        return active;
+    }
 
-        return figure_points
-            .iter()
-            .map(|point| Point {
-                x: point.x + dx,
-                y: point.y + dy,
-            })
-            .collect();
+    pub fn pose(&self) -> PiecePose {
+        return PiecePose::new(
+            self.get_type(),
+            self.position,
+            Rotation::from_steps(self.rotation_step),
+        );
+    }
+
+    pub(crate) fn to_cartesian(&self) -> Vec<Point> {
+        return self.cells().collect();
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = Point> + '_ {
+        let (dx, dy) = (self.position.x, self.position.y);
+        return self.figure.cells().map(move |point| Point {
+            x: point.x + dx,
+            y: point.y + dy,
+        });
     }
 
-    pub fn color(&self) -> Color {
-        return self.figure.color();
+    pub(crate) fn color(&self, palette: &Palette) -> PieceColor {
+        return palette.color(&self.get_type());
     }
 
-    pub fn position(&self) -> Point {
+    pub(crate) fn position(&self) -> Point {
         return self.position;
     }
 
-    pub fn get_type(&self) -> FigureType {
+    pub(crate) fn rotation_step(&self) -> usize {
+        return self.rotation_step;
+    }
+
+    pub(crate) fn get_type(&self) -> FigureType {
         return self.figure.get_type();
     }
 
-    pub fn left_edge(&self) -> i32 {
-        let points = self.to_cartesian();
-        return points.iter().fold(i32::max_value(), |edge, point| {
+    pub fn kind(&self) -> FigureType {
+        return self.get_type();
+    }
+
+    pub(crate) fn left_edge(&self) -> i32 {
+        return self.cells().fold(i32::MAX, |edge, point| {
             if point.x < edge {
                 return point.x;
             }
@@ -51,9 +78,8 @@ impl ActiveFigure {
         });
     }
 
-    pub fn right_edge(&self) -> i32 {
-        let points = self.to_cartesian();
-        return points.iter().fold(i32::min_value(), |edge, point| {
+    pub(crate) fn right_edge(&self) -> i32 {
+        return self.cells().fold(i32::MIN, |edge, point| {
             if point.x > edge {
                 return point.x;
             }
@@ -61,9 +87,8 @@ impl ActiveFigure {
         });
     }
 
-    pub fn bottom_edge(&self) -> i32 {
-        let points = self.to_cartesian();
-        return points.iter().fold(i32::min_value(), |edge, point| {
+    pub(crate) fn bottom_edge(&self) -> i32 {
+        return self.cells().fold(i32::MIN, |edge, point| {
             if point.y > edge {
                 return point.y;
             }
@@ -80,29 +105,77 @@ impl ActiveFigure {
         };
     }
 
-    pub fn moved_down(&self) -> ActiveFigure {
-        return self.updating_position_by_xy(0, 1);
+    pub fn rotated_counter_clockwise(&self) -> ActiveFigure {
+        let figure = self.figure.rotated_counter_clockwise();
+        return ActiveFigure {
+            figure,
+            position: self.position,
+            rotation_step: self.previous_rotation_step(),
+        };
     }
 
-    pub fn moved_left(&self) -> ActiveFigure {
-        return self.updating_position_by_xy(-1, 0);
+    // Moved by `dx` and `dy` without checking the board
+    pub fn translated(&self, dx: i32, dy: i32) -> ActiveFigure {
+        return ActiveFigure {
+            figure: self.figure.clone(),
+            position: Point {
+                x: self.position().x + dx,
+                y: self.position().y + dy,
+            },
+            ..*self
+        };
     }
 
-    pub fn moved_right(&self) -> ActiveFigure {
-        return self.updating_position_by_xy(1, 0);
+    pub(crate) fn moved_down(&self) -> ActiveFigure {
+        return self.translated(0, 1);
     }
 
-    pub fn wall_kicked_rotation_tests(&self) -> Vec<ActiveFigure> {
-        return self
-            .wall_kick_tests()
-            .iter()
-            .map(|point| self.updating_position_by_xy(point.x, point.y).rotated())
-            .collect();
+    pub(crate) fn moved_up(&self) -> ActiveFigure {
+        return self.translated(0, -1);
     }
 
-    fn wall_kick_tests(&self) -> Vec<Point> {
-        let kick_wall_tests_matrix = self.figure.wall_kick_tests();
-        return kick_wall_tests_matrix[self.rotation_step].clone();
+    pub(crate) fn moved_left(&self) -> ActiveFigure {
+        return self.translated(-1, 0);
+    }
+
+    pub(crate) fn moved_right(&self) -> ActiveFigure {
+        return self.translated(1, 0);
+    }
+
+    pub(crate) fn wall_kicked_rotation_tests(&self, system: &RotationSystem) -> Vec<ActiveFigure> {
+        if system.is_two_state(&self.get_type()) && self.rotation_step == 1 {
+            return self.wall_kicked_tests_for(self.rotated_counter_clockwise(), system);
+        }
+        return self.wall_kicked_tests_for(self.rotated(), system);
+    }
+
+    pub(crate) fn wall_kicked_counter_clockwise_tests(
+        &self,
+        system: &RotationSystem,
+    ) -> Vec<ActiveFigure> {
+        if system.is_two_state(&self.get_type()) && self.rotation_step == 0 {
+            return self.wall_kicked_tests_for(self.rotated(), system);
+        }
+        return self.wall_kicked_tests_for(self.rotated_counter_clockwise(), system);
+    }
+
+    pub(crate) fn wall_kicked_180_tests(&self, system: &RotationSystem) -> Vec<ActiveFigure> {
+        if system.is_two_state(&self.get_type()) {
+            return self.wall_kicked_tests_for(self.clone(), system);
+        }
+        return self.wall_kicked_tests_for(self.rotated().rotated(), system);
+    }
+
+    fn wall_kicked_tests_for(
+        &self,
+        rotated: ActiveFigure,
+        system: &RotationSystem,
+    ) -> Vec<ActiveFigure> {
+        return system
+            .kicks(&self.get_type(), self.rotation_step, rotated.rotation_step)
+            .iter()
+            .map(|point| rotated.translated(point.x, point.y))
+            .collect();
     }
 
     fn next_rotation_step(&self) -> usize {
@@ -118,15 +191,11 @@ impl ActiveFigure {
         }
     }
 
-    fn updating_position_by_xy(&self, x: i32, y: i32) -> ActiveFigure {
-        return ActiveFigure {
-            figure: self.figure.clone(),
-            position: Point {
-                x: self.position().x + x,
-                y: self.position().y + y,
-            },
-            ..*self
-        };
+    fn previous_rotation_step(&self) -> usize {
+        match self.get_type() {
+            FigureType::O => 0,
+            _ => (self.rotation_step + 3) % 4,
+        }
     }
 }
 
@@ -149,15 +218,25 @@ mod active_figure_tests {
     fn test_color() {
         let figure_type = FigureType::T;
         let figure = ActiveFigure::new(FigureType::T, Point { x: 0, y: 0 });
-        assert_eq!(figure.color(), figure_type.color());
+        assert_eq!(figure.color(&Palette::default()), figure_type.color());
     }
     #[test]
     fn test_update_position() {
         let figure = ActiveFigure::new(FigureType::L, Point { x: 0, y: 0 });
-        let moved = figure.updating_position_by_xy(5, 5);
+        let moved = figure.translated(5, 5);
         assert_eq!(moved.position(), Point { x: 5, y: 5 });
     }
     #[test]
+    fn test_pose_follows_moves_and_rotations() {
+        let figure = ActiveFigure::new(FigureType::S, Point { x: 3, y: 0 });
+        let moved = figure.translated(-1, 4).rotated_counter_clockwise();
+        assert_eq!(moved.kind(), FigureType::S);
+        let pose = moved.pose();
+        assert_eq!(pose.origin, Point { x: 2, y: 4 });
+        assert_eq!(pose.rotation, Rotation::Left);
+        assert_eq!(pose.cells(), moved.cells().collect::<Vec<Point>>());
+    }
+    #[test]
     fn test_left_edge() {
         let figure = ActiveFigure::new(FigureType::L, Point { x: 2, y: 2 });
         let edge = figure.left_edge();
@@ -210,6 +289,46 @@ mod active_figure_tests {
         assert_eq!(rotation_04.rotation_step, 0);
     }
     #[test]
+    fn test_wall_kicked_rotation_tests() {
+        let figure = ActiveFigure::new(FigureType::T, Point { x: 3, y: 3 });
+        let tests = figure.wall_kicked_rotation_tests(&RotationSystem::SRS);
+        assert_eq!(tests.len(), 5);
+        assert_eq!(tests[0], figure.rotated());
+        assert_eq!(tests[2].position(), Point { x: 2, y: 2 });
+        assert_eq!(tests[2].rotation_step, 1);
+        let classic_tests = figure.wall_kicked_rotation_tests(&RotationSystem::Classic);
+        assert_eq!(classic_tests, vec![figure.rotated()]);
+    }
+    #[test]
+    fn test_nintendo_two_state_rotation() {
+        let figure = ActiveFigure::new(FigureType::S, Point { x: 3, y: 3 });
+        let nintendo = RotationSystem::Nintendo;
+        let right = figure.wall_kicked_rotation_tests(&nintendo);
+        assert_eq!(right, vec![figure.rotated()]);
+        assert_eq!(
+            right[0].wall_kicked_rotation_tests(&nintendo),
+            vec![figure.clone()]
+        );
+        assert_eq!(figure.wall_kicked_counter_clockwise_tests(&nintendo), right);
+        assert_eq!(
+            figure.wall_kicked_180_tests(&nintendo),
+            vec![figure.clone()]
+        );
+        let t_figure = ActiveFigure::new(FigureType::T, Point { x: 3, y: 3 });
+        let t_tests = t_figure.wall_kicked_counter_clockwise_tests(&nintendo);
+        assert_eq!(t_tests, vec![t_figure.rotated_counter_clockwise()]);
+    }
+    #[test]
+    fn test_counter_clockwise_rotation_steps() {
+        let figure = ActiveFigure::new(FigureType::J, Point { x: 0, y: 0 });
+        let rotated = figure.rotated_counter_clockwise();
+        assert_eq!(rotated.rotation_step, 3);
+        assert_eq!(rotated.rotated_counter_clockwise().rotation_step, 2);
+        assert_eq!(rotated.rotated(), figure);
+        let o_figure = ActiveFigure::new(FigureType::O, Point { x: 0, y: 0 });
+        assert_eq!(o_figure.rotated_counter_clockwise().rotation_step, 0);
+    }
+    #[test]
     fn test_moved_left() {
         let figure = ActiveFigure::new(FigureType::I, Point { x: 1, y: 0 });
         assert_eq!(figure.moved_left().position(), Point { x: 0, y: 0 });
@@ -220,6 +339,11 @@ mod active_figure_tests {
         assert_eq!(figure.moved_right().position(), Point { x: 2, y: 0 });
     }
     #[test]
+    fn test_moved_up() {
+        let figure = ActiveFigure::new(FigureType::I, Point { x: 1, y: 1 });
+        assert_eq!(figure.moved_up().position(), Point { x: 1, y: 0 });
+    }
+    #[test]
     fn test_moved_down() {
         let figure = ActiveFigure::new(FigureType::I, Point { x: 1, y: 0 });
         assert_eq!(figure.moved_down().position(), Point { x: 1, y: 1 });
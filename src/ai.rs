@@ -0,0 +1,217 @@
+use super::board::metrics::{aggregate_height, bumpiness, holes};
+use super::move_validator::{
+    can_move_down, drop_distance, first_valid_rotation, has_valid_position,
+};
+use super::{Action, ActiveFigure, Board, PiecePose, Point, RotationSystem};
+use alloc::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// A final resting position of the active figure and the actions that lead to it
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Placement {
+    // Where the figure locks
+    pub pose: PiecePose,
+    // Board cells taken once locked, sorted by row then column
+    pub cells: Vec<Point>,
+    // Moves and rotations from the spawn position, the figure locks with a hard drop after them
+    pub path: Vec<Action>,
+}
+
+const SEARCH_ACTIONS: [Action; 6] = [
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::MoveDown,
+    Action::Rotate,
+    Action::RotateCCW,
+    Action::Rotate180,
+];
+
+type StateKey = (i32, i32, usize);
+
+fn key_of(figure: &ActiveFigure) -> StateKey {
+    let position = figure.position();
+    return (position.x, position.y, figure.rotation_step());
+}
+
+// Breadth first search over every position reachable with moves, soft drops and rotations
+// (kicks included), so tucks and spins are found with their shortest path.
+pub(crate) fn enumerate_placements(
+    active: &ActiveFigure,
+    board: &Board,
+    rotation_system: &RotationSystem,
+) -> Vec<Placement> {
+    if !has_valid_position(active, board) {
+        return vec![];
+    }
+    let mut states: Vec<(ActiveFigure, Option<(usize, Action)>)> = vec![(active.clone(), None)];
+    let mut visited: BTreeSet<StateKey> = BTreeSet::new();
+    visited.insert(key_of(active));
+    let mut placements: Vec<Placement> = vec![];
+    let mut index = 0;
+    while index < states.len() {
+        let figure = states[index].0.clone();
+        if !can_move_down(&figure, board) {
+            let cells = sorted_cells(&figure);
+            if !placements.iter().any(|placement| placement.cells == cells) {
+                placements.push(Placement {
+                    pose: figure.pose(),
+                    cells,
+                    path: path_to(&states, index),
+                });
+            }
+        }
+        for action in SEARCH_ACTIONS.iter() {
+            if let Some(moved) = apply(&figure, *action, board, rotation_system) {
+                if visited.insert(key_of(&moved)) {
+                    states.push((moved, Some((index, *action))));
+                }
+            }
+        }
+        index += 1;
+    }
+    return placements;
+}
+
+// Replays the placement path and drops the figure, cheaper than searching every placement again
+pub(crate) fn is_reachable(
+    placement: &Placement,
+    active: &ActiveFigure,
+    board: &Board,
+    rotation_system: &RotationSystem,
+) -> bool {
+    if placement.pose.kind != active.get_type() || !has_valid_position(active, board) {
+        return false;
+    }
+    let mut figure = active.clone();
+    for action in &placement.path {
+        figure = match apply(&figure, *action, board, rotation_system) {
+            Some(moved) => moved,
+            None => return false,
+        };
+    }
+    let figure = figure.translated(0, drop_distance(&figure, board));
+    return sorted_cells(&figure) == placement.cells;
+}
+
+// Value of the best placement with the usual one piece weights, None when the figure can't move
+pub(crate) fn best_placement_value(
+    active: &ActiveFigure,
+    board: &Board,
+    rotation_system: &RotationSystem,
+) -> Option<f64> {
+    return enumerate_placements(active, board, rotation_system)
+        .iter()
+        .map(|placement| placement_value(placement, board))
+        .fold(None, |best: Option<f64>, value| match best {
+            Some(best) if best >= value => Some(best),
+            _ => Some(value),
+        });
+}
+
+fn placement_value(placement: &Placement, board: &Board) -> f64 {
+    let mut board = board.clone();
+    for cell in placement.cells.iter().filter(|cell| cell.y >= 0) {
+        let figure_type = Some(placement.pose.kind.clone());
+        board.set_figure_at_xy(cell.x as usize, cell.y as usize, figure_type);
+    }
+    let full_lines: Vec<usize> = (0..board.height())
+        .filter(|line| board.is_line_full(*line))
+        .collect();
+    board.remove_lines_in_place(&full_lines);
+    return -0.51 * aggregate_height(&board) as f64 + 0.76 * full_lines.len() as f64
+        - 0.36 * holes(&board) as f64
+        - 0.18 * bumpiness(&board) as f64;
+}
+
+fn apply(
+    figure: &ActiveFigure,
+    action: Action,
+    board: &Board,
+    rotation_system: &RotationSystem,
+) -> Option<ActiveFigure> {
+    let tests = match action {
+        Action::MoveLeft => vec![figure.moved_left()],
+        Action::MoveRight => vec![figure.moved_right()],
+        Action::MoveDown => vec![figure.moved_down()],
+        Action::Rotate => figure.wall_kicked_rotation_tests(rotation_system),
+        Action::RotateCCW => figure.wall_kicked_counter_clockwise_tests(rotation_system),
+        Action::Rotate180 => figure.wall_kicked_180_tests(rotation_system),
+        _ => vec![],
+    };
+    return first_valid_rotation(tests, board, rotation_system).map(|(_, figure)| figure);
+}
+
+fn path_to(states: &[(ActiveFigure, Option<(usize, Action)>)], index: usize) -> Vec<Action> {
+    let mut path = vec![];
+    let mut current = index;
+    while let Some((parent, action)) = states[current].1 {
+        path.push(action);
+        current = parent;
+    }
+    path.reverse();
+    return path;
+}
+
+fn sorted_cells(figure: &ActiveFigure) -> Vec<Point> {
+    let mut cells = figure.to_cartesian();
+    cells.sort_by_key(|point| (point.y, point.x));
+    return cells;
+}
+
+#[cfg(test)]
+mod ai_tests {
+    use super::super::{FigureType, Game, GameConfig, RandomizerChoice};
+    use super::*;
+
+    fn game_with(rows: Vec<Vec<Option<FigureType>>>, figure: i32) -> Game {
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![figure]))
+            .build();
+        return Game::with_board(Board::from_rows(rows), config);
+    }
+
+    #[test]
+    fn test_placements_on_an_empty_board() {
+        let empty = vec![vec![None; 10]; 20];
+        // Seven horizontal and ten vertical I placements, nine O placements
+        assert_eq!(game_with(empty.clone(), 0).enumerate_placements().len(), 17);
+        let placements = game_with(empty, 3).enumerate_placements();
+        assert_eq!(placements.len(), 9);
+        assert!(placements
+            .iter()
+            .all(|placement| placement.cells.iter().all(|cell| cell.y >= 18)));
+    }
+
+    #[test]
+    fn test_soft_drop_tuck() {
+        let mut rows = vec![vec![None; 10]; 18];
+        let mut overhang = vec![Some(FigureType::Garbage); 6];
+        overhang.extend(vec![None; 4]);
+        rows.push(overhang);
+        rows.push(vec![None; 10]);
+        let mut game = game_with(rows, 0);
+        let tucked: Vec<Point> = (0..4).map(|x| Point { x, y: 19 }).collect();
+        let placement = game
+            .enumerate_placements()
+            .into_iter()
+            .find(|placement| placement.cells == tucked)
+            .unwrap();
+        assert!(placement.path.contains(&Action::MoveDown));
+        assert_eq!(placement.path.last(), Some(&Action::MoveLeft));
+        game.apply_placement(&placement);
+        let bottom = &game.get_board().to_rows()[19];
+        assert!(bottom[..4].iter().all(|cell| *cell == Some(FigureType::I)));
+    }
+
+    #[test]
+    fn test_unreachable_placement_is_ignored() {
+        let mut game = game_with(vec![vec![None; 10]; 20], 3);
+        let mut placement = game.enumerate_placements()[0].clone();
+        placement.cells[0].y -= 5;
+        let board = game.get_board().clone();
+        game.apply_placement(&placement);
+        assert_eq!(game.get_board(), &board);
+    }
+}
@@ -0,0 +1,60 @@
+use super::ClearKind;
+
+// Garbage lines added for each combo step, the last value is used for longer combos
+const COMBO_ATTACK: [usize; 12] = [0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
+const BACK_TO_BACK_ATTACK: usize = 1;
+
+// Outgoing garbage lines for a clear, following the guideline battle rules
+pub fn attack_for(clear_kind: &ClearKind, combo: usize, back_to_back: usize) -> usize {
+    if clear_kind.lines() == 0 {
+        return 0;
+    }
+    let mut attack = base_attack(clear_kind);
+    if clear_kind.is_difficult() && back_to_back > 0 {
+        attack += BACK_TO_BACK_ATTACK;
+    }
+    attack += COMBO_ATTACK[combo.min(COMBO_ATTACK.len() - 1)];
+    return attack;
+}
+
+fn base_attack(clear_kind: &ClearKind) -> usize {
+    return match clear_kind {
+        ClearKind::None => 0,
+        ClearKind::Lines(lines) => match lines {
+            0 | 1 => 0,
+            2 => 1,
+            3 => 2,
+            _ => 4,
+        },
+        ClearKind::TSpin(lines) => lines * 2,
+        ClearKind::TSpinMini(lines) => lines.saturating_sub(1),
+    };
+}
+
+#[cfg(test)]
+mod attack_tests {
+    use super::*;
+
+    #[test]
+    fn test_base_attack() {
+        assert_eq!(attack_for(&ClearKind::Lines(1), 0, 0), 0);
+        assert_eq!(attack_for(&ClearKind::Lines(2), 0, 0), 1);
+        assert_eq!(attack_for(&ClearKind::Lines(3), 0, 0), 2);
+        assert_eq!(attack_for(&ClearKind::Lines(4), 0, 0), 4);
+        assert_eq!(attack_for(&ClearKind::TSpin(2), 0, 0), 4);
+        assert_eq!(attack_for(&ClearKind::TSpinMini(1), 0, 0), 0);
+        assert_eq!(attack_for(&ClearKind::TSpin(0), 0, 0), 0);
+    }
+
+    #[test]
+    fn test_back_to_back_attack() {
+        assert_eq!(attack_for(&ClearKind::Lines(4), 0, 1), 5);
+        assert_eq!(attack_for(&ClearKind::Lines(3), 0, 1), 2);
+    }
+
+    #[test]
+    fn test_combo_attack() {
+        assert_eq!(attack_for(&ClearKind::Lines(1), 2, 0), 1);
+        assert_eq!(attack_for(&ClearKind::Lines(1), 30, 0), 5);
+    }
+}
@@ -0,0 +1,23 @@
+// Helpers shared by the language bindings
+use super::Game;
+
+// Writes one `FigureType::code` per cell, row by row, active figure included.
+// Returns false without writing when the buffer is smaller than the board.
+pub fn cell_codes_into(game: &Game, buffer: &mut [u8]) -> bool {
+    let size = game.get_visible_size();
+    let (width, height) = (size.width, size.height);
+    if buffer.len() < width * height {
+        return false;
+    }
+    for (x, y, square) in game.iter_board() {
+        buffer[y * width + x] = square.map_or(0, |square| square.code());
+    }
+    let code = game.active_figure_type().code();
+    for point in game.access_active_figure() {
+        if point.x >= 0 && point.y >= 0 && (point.x as usize) < width && (point.y as usize) < height
+        {
+            buffer[point.y as usize * width + point.x as usize] = code;
+        }
+    }
+    return true;
+}
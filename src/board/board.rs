@@ -0,0 +1,936 @@
+use super::super::{FigureType, Matrix, Point, Size};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+// Row masks limit the board width
+pub const MAX_WIDTH: usize = 64;
+
+// Each row is kept as a bitmask (bit x set when the cell is taken) for fast collision checks,
+// next to the figure types needed to draw the cells
+#[derive(Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Board {
+    matrix: Matrix<Option<FigureType>>,
+    rows: Vec<u64>,
+    // Permanently unusable cells, they collide like taken ones but never clear or move
+    blocked: Vec<u64>,
+    // Zobrist hash of the taken and blocked cells, kept up to date by every change
+    #[cfg_attr(feature = "serde", serde(skip))]
+    hash: u64,
+}
+
+// The row masks and the hash are computed again from the cells instead of trusting the data.
+// Saves from before blocked cells have none
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Board {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Board, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct BoardData {
+            matrix: Matrix<Option<FigureType>>,
+            #[serde(default)]
+            blocked: Vec<u64>,
+        }
+        let data = BoardData::deserialize(deserializer)?;
+        let (height, width) = (data.matrix.height(), data.matrix.width());
+        let rows = (0..height).filter_map(|line| data.matrix.row_at(line));
+        if rows.map(Vec::len).any(|row_width| row_width != width) {
+            return Err(serde::de::Error::custom("board rows have different widths"));
+        }
+        if data.blocked.len() > height {
+            return Err(serde::de::Error::custom("more blocked rows than board rows"));
+        }
+        return Ok(Board::from_matrix(data.matrix).with_blocked(&data.blocked));
+    }
+}
+
+// Keys of the cells set in a row mask, one step per cell
+fn mask_hash(mut mask: u64, y: usize, blocked: bool) -> u64 {
+    let mut hash = 0;
+    while mask != 0 {
+        hash ^= zobrist_key(mask.trailing_zeros() as usize, y, blocked);
+        mask &= mask - 1;
+    }
+    return hash;
+}
+
+// A fixed pseudo random key per cell and layer (SplitMix64 of the position), so the keys need no
+// table and are the same on every platform
+fn zobrist_key(x: usize, y: usize, blocked: bool) -> u64 {
+    let mut key = ((y as u64) << 7 | (x as u64) << 1 | blocked as u64).wrapping_add(1);
+    key = key.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    key = (key ^ (key >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    key = (key ^ (key >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    return key ^ (key >> 31);
+}
+
+// `clone_from` reuses the allocations of the board it overwrites
+impl Clone for Board {
+    fn clone(&self) -> Board {
+        return Board {
+            matrix: self.matrix.clone(),
+            rows: self.rows.clone(),
+            blocked: self.blocked.clone(),
+            hash: self.hash,
+        };
+    }
+
+    fn clone_from(&mut self, source: &Board) {
+        self.matrix.clone_from(&source.matrix);
+        self.rows.clone_from(&source.rows);
+        self.blocked.clone_from(&source.blocked);
+        self.hash = source.hash;
+    }
+}
+
+impl Board {
+    pub fn new(size: &Size) -> Board {
+        let mut cells = vec![];
+        for _y in 0..size.height {
+            let line = Board::get_empty_line(size.width);
+            cells.push(line);
+        }
+        return Board::from_matrix(Matrix::new(cells));
+    }
+
+    // Columns past `MAX_WIDTH` are cut, the row masks can't hold them
+    fn from_matrix(matrix: Matrix<Option<FigureType>>) -> Board {
+        let matrix = if matrix.width() > MAX_WIDTH {
+            let cells = (0..matrix.height())
+                .filter_map(|line| matrix.row_at(line))
+                .map(|row| row.iter().take(MAX_WIDTH).cloned().collect())
+                .collect();
+            Matrix::new(cells)
+        } else {
+            matrix
+        };
+        let rows = (0..matrix.height())
+            .map(|line| matrix.row_at(line).map_or(0, |row| Board::mask_of(row)))
+            .collect();
+        let blocked = vec![0; matrix.height()];
+        let mut board = Board {
+            matrix,
+            rows,
+            blocked,
+            hash: 0,
+        };
+        board.rehash();
+        return board;
+    }
+
+    // Keeps the blocked cells of `mask`, figures under them are removed
+    fn with_blocked(mut self, mask: &[u64]) -> Board {
+        for y in 0..self.height() {
+            let blocked = mask.get(y).cloned().unwrap_or(0) & self.full_mask();
+            for x in 0..self.width() {
+                if blocked & 1 << x != 0 {
+                    self.set_figure_at_xy(x, y, None);
+                }
+            }
+            self.blocked[y] = blocked;
+        }
+        self.rehash();
+        return self;
+    }
+
+    fn mask_of(line: &[Option<FigureType>]) -> u64 {
+        return line
+            .iter()
+            .enumerate()
+            .filter(|(_, square)| square.is_some())
+            .fold(0, |mask, (x, _)| mask | 1 << x);
+    }
+
+    // Bits set for every column of the board
+    pub fn full_mask(&self) -> u64 {
+        if self.width() >= MAX_WIDTH {
+            return u64::MAX;
+        }
+        return (1 << self.width()) - 1;
+    }
+
+    // Taken and blocked cells of a row, rows outside the board are empty
+    pub fn row_mask(&self, y: i32) -> u64 {
+        if y < 0 {
+            return 0;
+        }
+        let taken = self.rows.get(y as usize).cloned().unwrap_or(0);
+        return taken | self.blocked_mask(y);
+    }
+
+    // Bit x of row y is set when the cell is taken by a figure, blocked cells are left out
+    pub fn taken_rows(&self) -> &[u64] {
+        return &self.rows;
+    }
+
+    pub fn blocked_rows(&self) -> &[u64] {
+        return &self.blocked;
+    }
+
+    pub fn blocked_mask(&self, y: i32) -> u64 {
+        if y < 0 {
+            return 0;
+        }
+        return self.blocked.get(y as usize).cloned().unwrap_or(0);
+    }
+
+    pub fn is_blocked(&self, x: usize, y: usize) -> bool {
+        return x < MAX_WIDTH && self.blocked_mask(y as i32) & 1 << x != 0;
+    }
+
+    // Blocking a cell removes the figure on it, out of bounds positions are ignored
+    pub fn set_blocked(&mut self, x: usize, y: usize, blocked: bool) {
+        if x >= self.width() || y >= self.height() {
+            return;
+        }
+        if blocked {
+            self.set_figure_at_xy(x, y, None);
+        }
+        if self.is_blocked(x, y) != blocked {
+            self.blocked[y] ^= 1 << x;
+            self.hash ^= zobrist_key(x, y, true);
+        }
+    }
+
+    pub fn blocking(&self, points: &[Point]) -> Board {
+        let mut board = self.clone();
+        for point in points.iter().filter(|point| point.x >= 0 && point.y >= 0) {
+            board.set_blocked(point.x as usize, point.y as usize, true);
+        }
+        return board;
+    }
+
+    pub fn has_blocked_cells(&self) -> bool {
+        return self.blocked.iter().any(|mask| *mask != 0);
+    }
+
+    // Keeps the bottom left of the board, rows are added or cut at the top and columns at the right
+    pub fn resized(&self, size: &Size) -> Board {
+        let mut cells = vec![];
+        let mut mask = vec![];
+        for y in 0..size.height {
+            let source = (y + self.height()).checked_sub(size.height);
+            let mut line = Board::get_empty_line(size.width);
+            if let Some(row) = source.and_then(|source| self.get_line(source)) {
+                for (x, square) in row.iter().take(size.width).enumerate() {
+                    line[x] = square.clone();
+                }
+            }
+            cells.push(line);
+            mask.push(source.map_or(0, |source| self.blocked_mask(source as i32)));
+        }
+        return Board::from_matrix(Matrix::new(cells)).with_blocked(&mask);
+    }
+
+    // Shorter rows are padded with empty cells up to the widest one
+    pub fn from_rows(rows: Vec<Vec<Option<FigureType>>>) -> Board {
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut cells = rows;
+        for row in cells.iter_mut() {
+            row.resize(width, None);
+        }
+        return Board::from_matrix(Matrix::new(cells));
+    }
+
+    pub fn to_rows(&self) -> Vec<Vec<Option<FigureType>>> {
+        return (0..self.height())
+            .filter_map(|line| self.get_line(line).cloned())
+            .collect();
+    }
+
+    pub fn size(&self) -> Size {
+        return Size {
+            height: self.height(),
+            width: self.width(),
+        };
+    }
+
+    fn get_empty_line(width: usize) -> Vec<Option<FigureType>> {
+        let mut line: Vec<Option<FigureType>> = vec![];
+        for _x in 0..width {
+            line.push(None);
+        }
+        return line;
+    }
+
+    fn get_garbage_line(width: usize, hole_column: usize) -> Vec<Option<FigureType>> {
+        let mut line: Vec<Option<FigureType>> = vec![];
+        for x in 0..width {
+            if x == hole_column {
+                line.push(None);
+            } else {
+                line.push(Some(FigureType::Garbage));
+            }
+        }
+        return line;
+    }
+
+    pub fn height(&self) -> usize {
+        return self.matrix.height();
+    }
+
+    pub fn width(&self) -> usize {
+        return self.matrix.width();
+    }
+
+    pub fn figure_at_xy(&self, x: usize, y: usize) -> &Option<FigureType> {
+        if let Some(element) = self.matrix.at_xy(x, y) {
+            return element;
+        } else {
+            return &None;
+        }
+    }
+
+    // '.' for empty cells, '#' for blocked ones and the figure symbol for taken ones
+    pub fn symbol_at_xy(&self, x: usize, y: usize) -> char {
+        if self.is_blocked(x, y) {
+            return '#';
+        }
+        return match self.figure_at_xy(x, y) {
+            Some(figure_type) => figure_type.symbol(),
+            None => '.',
+        };
+    }
+
+    pub fn replacing_figure_at_xy(
+        &self,
+        x: usize,
+        y: usize,
+        figure_type: Option<FigureType>,
+    ) -> Board {
+        let mut board = self.clone();
+        board.set_figure_at_xy(x, y, figure_type);
+        return board;
+    }
+
+    // Out of bounds and blocked positions are ignored
+    pub fn set_figure_at_xy(&mut self, x: usize, y: usize, figure_type: Option<FigureType>) {
+        if x >= self.width() || y >= self.height() || self.is_blocked(x, y) {
+            return;
+        }
+        if (self.rows[y] & 1 << x != 0) != figure_type.is_some() {
+            self.rows[y] ^= 1 << x;
+            self.hash ^= zobrist_key(x, y, false);
+        }
+        self.matrix.set_at_xy(x, y, figure_type);
+    }
+
+    // Every cell, row by row from the top left one
+    pub fn iter_cells(&self) -> impl Iterator<Item = (usize, usize, Option<FigureType>)> + '_ {
+        let (height, width) = (self.height(), self.width());
+        return (0..height).flat_map(move |y| {
+            (0..width).map(move |x| (x, y, self.figure_at_xy(x, y).clone()))
+        });
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        if point.x < 0 || point.x as usize >= MAX_WIDTH {
+            return false;
+        }
+        return self.row_mask(point.y) & (1 << point.x) != 0;
+    }
+
+    // Whether a figure with these cells would overlap the stack. Cells out of the sides or below
+    // the board collide, the rows above it are free
+    pub fn collides(&self, points: &[Point]) -> bool {
+        return points.iter().any(|point| {
+            if point.x < 0 || point.x >= self.width() as i32 || point.y >= self.height() as i32 {
+                return true;
+            }
+            return self.row_mask(point.y) & (1 << point.x) != 0;
+        });
+    }
+
+    pub fn get_line(&self, line: usize) -> Option<&Vec<Option<FigureType>>> {
+        return self.matrix.row_at(line);
+    }
+
+    pub fn is_line_empty(&self, line: usize) -> bool {
+        return self.rows.get(line).is_none_or(|mask| *mask == 0);
+    }
+
+    // Blocked cells complete a line, but a line needs at least one taken cell to clear
+    pub fn is_line_full(&self, line: usize) -> bool {
+        return self.rows.get(line).is_some_and(|mask| {
+            *mask != 0 && *mask | self.blocked_mask(line as i32) == self.full_mask()
+        });
+    }
+
+    // Rows from the floor up to the highest taken or blocked cell of each column, 0 when empty
+    pub fn column_heights(&self) -> Vec<usize> {
+        let mut heights = vec![0; self.width()];
+        let mut seen = 0;
+        for y in 0..self.height() {
+            let mut found = self.row_mask(y as i32) & !seen;
+            seen |= found;
+            while found != 0 {
+                heights[found.trailing_zeros() as usize] = self.height() - y;
+                found &= found - 1;
+            }
+            if seen == self.full_mask() {
+                break;
+            }
+        }
+        return heights;
+    }
+
+    // Empty cells with a taken or blocked cell somewhere above them, row by row from the top
+    pub fn hole_positions(&self) -> Vec<Point> {
+        let mut holes = vec![];
+        let mut covered = 0;
+        for y in 0..self.height() {
+            let mask = self.row_mask(y as i32);
+            let mut empty = covered & !mask;
+            while empty != 0 {
+                let x = empty.trailing_zeros() as i32;
+                holes.push(Point { x, y: y as i32 });
+                empty &= empty - 1;
+            }
+            covered |= mask;
+        }
+        return holes;
+    }
+
+    // The first row from the top holding a taken or blocked cell, None when the board is empty
+    pub fn highest_row(&self) -> Option<usize> {
+        return (0..self.height()).find(|&y| self.row_mask(y as i32) != 0);
+    }
+
+    // Pushes the stack up, lines going over the top are lost
+    pub fn adding_garbage_lines(&self, count: usize, hole_column: usize) -> Board {
+        let count = count.min(self.height());
+        let mut new_board_data: Vec<Vec<Option<FigureType>>> = vec![];
+        for line_number in count..self.height() {
+            if let Some(line) = self.get_line(line_number) {
+                new_board_data.push(line.clone());
+            }
+        }
+        for _ in 0..count {
+            new_board_data.push(Board::get_garbage_line(self.width(), hole_column));
+        }
+        return Board::from_matrix(Matrix::new(new_board_data)).with_blocked(&self.blocked);
+    }
+
+    pub fn removing_lines(&self, lines: &[usize]) -> Board {
+        let mut board = self.clone();
+        board.remove_lines_in_place(lines);
+        return board;
+    }
+
+    // The lines above the removed ones fall down, empty lines fill the top
+    pub fn remove_lines_in_place(&mut self, lines: &[usize]) {
+        let mut lines: Vec<usize> = lines
+            .iter()
+            .cloned()
+            .filter(|line| *line < self.height())
+            .collect();
+        lines.sort_unstable();
+        lines.dedup();
+        if self.has_blocked_cells() {
+            self.remove_lines_around_blocked(&lines);
+            return;
+        }
+        // Only the rows down to the lowest removed line move
+        let moved = 0..lines.last().map_or(0, |line| line + 1);
+        self.hash ^= self.rows_hash(moved.clone());
+        for line in lines.iter().rev() {
+            self.matrix.remove_row(*line);
+            self.rows.remove(*line);
+        }
+        for _ in 0..lines.len() {
+            self.matrix.insert_row(0, Board::get_empty_line(self.width()));
+            self.rows.insert(0, 0);
+        }
+        self.hash ^= self.rows_hash(moved);
+    }
+
+    // Zone clears, the lines move under the rest of the stack and land on the `floor` bottom rows.
+    // Blocked cells can't move, boards with them keep the lines in place and return false.
+    pub fn stack_lines_at_bottom(&mut self, lines: &[usize], floor: usize) -> bool {
+        if self.has_blocked_cells() {
+            return false;
+        }
+        let top = self.height().saturating_sub(floor);
+        let mut lines: Vec<usize> = lines.iter().cloned().filter(|line| *line < top).collect();
+        lines.sort_unstable();
+        lines.dedup();
+        let shifted = lines.first().map_or(top, |line| *line)..top;
+        self.hash ^= self.rows_hash(shifted.clone());
+        let mut moved = vec![];
+        for line in lines.iter().rev() {
+            let row = self.matrix.remove_row(*line).unwrap_or_default();
+            moved.push((row, self.rows.remove(*line)));
+        }
+        // The rows between the lines and the floor move up in their place
+        let bottom = top - lines.len();
+        for (row, mask) in moved {
+            self.matrix.insert_row(bottom, row);
+            self.rows.insert(bottom, mask);
+        }
+        self.hash ^= self.rows_hash(shifted);
+        return true;
+    }
+
+    // Same for boards with the same taken and blocked cells, whatever figures they belong to.
+    // Kept up to date on every change, for transposition tables and cheap desync checks
+    pub fn hash(&self) -> u64 {
+        return self.hash;
+    }
+
+    // New boards and new blocked cells, every row counts
+    fn rehash(&mut self) {
+        self.hash = self.rows_hash(0..self.height());
+    }
+
+    // Keys of the cells in the rows, xored out before the rows move and back in after
+    fn rows_hash(&self, rows: Range<usize>) -> u64 {
+        return rows.fold(0, |hash, y| {
+            let taken = self.rows.get(y).cloned().unwrap_or(0);
+            let blocked = self.blocked_mask(y as i32);
+            return hash ^ mask_hash(taken, y, false) ^ mask_hash(blocked, y, true);
+        });
+    }
+
+    // Blocked cells stay in place, each column falls through its free cells instead
+    fn remove_lines_around_blocked(&mut self, lines: &[usize]) {
+        for x in 0..self.width() {
+            let free: Vec<usize> = (0..self.height())
+                .filter(|y| !self.is_blocked(x, *y))
+                .collect();
+            let kept: Vec<Option<FigureType>> = free
+                .iter()
+                .filter(|y| !lines.contains(y))
+                .map(|y| self.figure_at_xy(x, *y).clone())
+                .collect();
+            let empty = free.len() - kept.len();
+            for (slot, y) in free.iter().enumerate() {
+                let square = slot.checked_sub(empty).and_then(|index| kept[index].clone());
+                self.set_figure_at_xy(x, *y, square);
+            }
+        }
+    }
+}
+
+// One line per row, hidden rows included
+impl fmt::Display for Board {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                write!(formatter, "{}", self.symbol_at_xy(x, y))?;
+            }
+            writeln!(formatter)?;
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod board_tests {
+    use super::*;
+
+    #[test]
+    fn test_row_masks_follow_the_cells() {
+        let board = Board::new(&Size {
+            height: 4,
+            width: 3,
+        });
+        assert_eq!(board.full_mask(), 0b111);
+        let board = board.replacing_figure_at_xy(1, 3, Some(FigureType::T));
+        assert_eq!(board.row_mask(3), 0b010);
+        assert!(board.contains(Point { x: 1, y: 3 }));
+        assert!(!board.contains(Point { x: 1, y: -1 }));
+        let board = board
+            .replacing_figure_at_xy(0, 3, Some(FigureType::T))
+            .replacing_figure_at_xy(2, 3, Some(FigureType::T));
+        assert!(board.is_line_full(3));
+        let board = board.adding_garbage_lines(1, 1);
+        assert_eq!(board.row_mask(2), 0b111);
+        assert_eq!(board.row_mask(3), 0b101);
+        let board = board.removing_lines(&[2]);
+        assert_eq!(board.row_mask(2), 0);
+        assert_eq!(board.row_mask(3), 0b101);
+        let board = board.replacing_figure_at_xy(0, 3, None);
+        assert_eq!(board.row_mask(3), 0b100);
+    }
+
+    #[test]
+    fn test_blocked_cells_collide_but_do_not_clear() {
+        let mut board = Board::new(&Size {
+            height: 3,
+            width: 3,
+        });
+        board.set_blocked(2, 2, true);
+        board.set_blocked(0, 0, true);
+        assert!(board.contains(Point { x: 2, y: 2 }));
+        assert_eq!(board.row_mask(2), 0b100);
+        assert!(board.is_line_empty(2));
+        assert!(!board.is_line_full(2));
+        board.set_figure_at_xy(2, 2, Some(FigureType::T));
+        assert_eq!(board.figure_at_xy(2, 2), &None);
+        board.set_figure_at_xy(0, 2, Some(FigureType::T));
+        board.set_figure_at_xy(1, 2, Some(FigureType::T));
+        assert!(board.is_line_full(2));
+        board.set_figure_at_xy(1, 0, Some(FigureType::J));
+        board.set_figure_at_xy(0, 1, Some(FigureType::L));
+        board.remove_lines_in_place(&[2]);
+        // Column 0 falls around nothing, the cell of column 1 falls through row 0
+        assert_eq!(
+            board.to_rows(),
+            vec![
+                vec![None, None, None],
+                vec![None, Some(FigureType::J), None],
+                vec![Some(FigureType::L), None, None],
+            ]
+        );
+        assert!(board.is_blocked(0, 0));
+        assert!(board.is_blocked(2, 2));
+    }
+
+    #[test]
+    fn test_blocked_cells_stay_under_garbage() {
+        let board = Board::new(&Size {
+            height: 2,
+            width: 3,
+        })
+        .blocking(&[Point { x: 0, y: 1 }]);
+        let board = board.adding_garbage_lines(1, 2);
+        assert!(board.is_blocked(0, 1));
+        assert_eq!(board.row_mask(1), 0b011);
+        assert_eq!(board.figure_at_xy(0, 1), &None);
+        assert_eq!(board.figure_at_xy(1, 1), &Some(FigureType::Garbage));
+    }
+
+    #[test]
+    fn test_resized_keeps_the_bottom_left() {
+        let board = Board::new(&Size {
+            height: 3,
+            width: 3,
+        })
+        .replacing_figure_at_xy(0, 2, Some(FigureType::T))
+        .replacing_figure_at_xy(2, 2, Some(FigureType::S))
+        .blocking(&[Point { x: 1, y: 1 }]);
+        let smaller = board.resized(&Size {
+            height: 2,
+            width: 2,
+        });
+        assert_eq!(
+            smaller.to_rows(),
+            vec![vec![None, None], vec![Some(FigureType::T), None]]
+        );
+        assert!(smaller.is_blocked(1, 0));
+        let larger = board.resized(&Size {
+            height: 4,
+            width: 4,
+        });
+        assert!(larger.is_line_empty(0));
+        assert!(larger.is_blocked(1, 2));
+        assert_eq!(larger.figure_at_xy(2, 3), &Some(FigureType::S));
+    }
+
+    #[test]
+    fn test_columns_past_max_width_are_cut() {
+        let board = Board::new(&Size {
+            height: 2,
+            width: MAX_WIDTH + 6,
+        });
+        assert_eq!(board.width(), MAX_WIDTH);
+        let rows = vec![vec![Some(FigureType::T); MAX_WIDTH + 1]];
+        let board = Board::from_rows(rows);
+        assert_eq!(board.width(), MAX_WIDTH);
+        assert!(board.is_line_full(0));
+    }
+
+    #[test]
+    fn test_iter_cells() {
+        let board = Board::new(&Size {
+            height: 2,
+            width: 3,
+        })
+        .replacing_figure_at_xy(2, 1, Some(FigureType::S));
+        let cells: Vec<(usize, usize, Option<FigureType>)> = board.iter_cells().collect();
+        assert_eq!(cells.len(), 6);
+        assert_eq!(cells[0], (0, 0, None));
+        assert_eq!(cells[5], (2, 1, Some(FigureType::S)));
+    }
+
+    #[test]
+    fn test_in_place_mutation() {
+        let mut board = Board::new(&Size {
+            height: 4,
+            width: 2,
+        });
+        board.set_figure_at_xy(0, 1, Some(FigureType::J));
+        board.set_figure_at_xy(0, 3, Some(FigureType::I));
+        board.set_figure_at_xy(1, 3, Some(FigureType::I));
+        board.set_figure_at_xy(5, 3, Some(FigureType::I));
+        board.remove_lines_in_place(&[3, 3, 9]);
+        assert_eq!(
+            board.to_rows(),
+            vec![
+                vec![None, None],
+                vec![None, None],
+                vec![Some(FigureType::J), None],
+                vec![None, None],
+            ]
+        );
+        assert_eq!(board.row_mask(2), 0b01);
+        assert_eq!(board.row_mask(3), 0);
+    }
+
+    #[test]
+    fn test_from_rows_round_trip() {
+        let rows = vec![
+            vec![None, None, None],
+            vec![Some(FigureType::Garbage), None],
+        ];
+        let board = Board::from_rows(rows);
+        assert_eq!(board.size(), Size { height: 2, width: 3 });
+        assert_eq!(
+            board.to_rows(),
+            vec![
+                vec![None, None, None],
+                vec![Some(FigureType::Garbage), None, None],
+            ]
+        );
+        assert_eq!(Board::from_rows(board.to_rows()), board);
+    }
+
+    #[test]
+    fn test_height() {
+        let height = 10;
+        let board = Board::new(&Size { height, width: 10 });
+        assert_eq!(board.height(), height);
+    }
+    #[test]
+    fn test_width() {
+        let width = 10;
+        let board = Board::new(&Size { height: 10, width });
+        assert_eq!(board.width(), width);
+    }
+    #[test]
+    fn test_replacing_figure() {
+        let board = Board::new(&Size {
+            height: 2,
+            width: 1,
+        });
+        let replaced_board = board.replacing_figure_at_xy(0, 0, Some(FigureType::I));
+        assert!(replaced_board.matrix.at_xy(0, 0).is_some());
+    }
+    #[test]
+    fn test_does_not_contains() {
+        let board = Board::new(&Size {
+            height: 4,
+            width: 4,
+        });
+        assert!(!board.contains(Point { x: 0, y: 0 }));
+        assert!(!board.contains(Point { x: 100, y: 100 }));
+        let board_with_figure = board.replacing_figure_at_xy(0, 0, Some(FigureType::I));
+        assert!(board_with_figure.contains(Point { x: 0, y: 0 }));
+    }
+    #[test]
+    fn test_contains() {
+        let board = Board::new(&Size {
+            height: 4,
+            width: 4,
+        });
+        let board_with_figure = board.replacing_figure_at_xy(0, 0, Some(FigureType::I));
+        assert!(board_with_figure.contains(Point { x: 0, y: 0 }));
+    }
+    #[test]
+    fn test_collides() {
+        let board = Board::new(&Size {
+            height: 4,
+            width: 4,
+        })
+        .replacing_figure_at_xy(1, 3, Some(FigureType::I));
+        assert!(!board.collides(&[Point { x: 0, y: 3 }, Point { x: 3, y: -2 }]));
+        assert!(board.collides(&[Point { x: 0, y: 0 }, Point { x: 1, y: 3 }]));
+        assert!(board.collides(&[Point { x: -1, y: 0 }]));
+        assert!(board.collides(&[Point { x: 4, y: 0 }]));
+        assert!(board.collides(&[Point { x: 0, y: 4 }]));
+        assert!(!board.collides(&[]));
+    }
+    #[test]
+    fn test_region_queries() {
+        let mut board = Board::new(&Size {
+            height: 4,
+            width: 4,
+        });
+        assert_eq!(board.column_heights(), vec![0; 4]);
+        assert_eq!(board.highest_row(), None);
+        assert!(board.hole_positions().is_empty());
+        board.set_figure_at_xy(1, 1, Some(FigureType::I));
+        board.set_figure_at_xy(1, 3, Some(FigureType::I));
+        board.set_figure_at_xy(3, 3, Some(FigureType::I));
+        board.set_blocked(0, 2, true);
+        assert_eq!(board.column_heights(), vec![2, 3, 0, 1]);
+        assert_eq!(board.highest_row(), Some(1));
+        assert_eq!(
+            board.hole_positions(),
+            vec![Point { x: 1, y: 2 }, Point { x: 0, y: 3 }]
+        );
+    }
+    #[test]
+    fn test_adding_garbage_lines() {
+        let board = Board::new(&Size {
+            height: 3,
+            width: 3,
+        });
+        let board = board.replacing_figure_at_xy(0, 2, Some(FigureType::T));
+        let garbage_board = board.adding_garbage_lines(1, 1);
+        let expectation = Matrix::new(vec![
+            vec![None, None, None],
+            vec![Some(FigureType::T), None, None],
+            vec![Some(FigureType::Garbage), None, Some(FigureType::Garbage)],
+        ]);
+        assert_eq!(garbage_board.matrix, expectation);
+        assert!(garbage_board.is_line_empty(0));
+        assert!(!garbage_board.is_line_empty(1));
+    }
+    #[test]
+    fn test_removing_lines() {
+        let board = Board::new(&Size {
+            height: 4,
+            width: 1,
+        });
+        let board_02 = board.replacing_figure_at_xy(0, 0, Some(FigureType::I));
+        let board_03 = board_02.replacing_figure_at_xy(0, 3, Some(FigureType::I));
+        let final_board = board_03.removing_lines(&[3]);
+
+        let expectation = Matrix::new(vec![
+            vec![None],
+            vec![Some(FigureType::I)],
+            vec![None],
+            vec![None],
+        ]);
+
+        assert_eq!(final_board.matrix, expectation);
+
+        let final_board_02 = board_03.removing_lines(&[0, 3]);
+        let expectation_02 = Matrix::new(vec![vec![None], vec![None], vec![None], vec![None]]);
+
+        assert_eq!(final_board_02.matrix, expectation_02);
+    }
+
+    #[test]
+    fn test_stack_lines_at_bottom() {
+        let t = Some(FigureType::T);
+        let mut board = Board::from_rows(vec![
+            vec![t.clone(), None],
+            vec![t.clone(), t.clone()],
+            vec![None, t.clone()],
+            vec![t.clone(), t.clone()],
+        ]);
+        // The bottom row is already stacked, the full line lands on it
+        assert!(board.stack_lines_at_bottom(&[1, 3], 1));
+        let expectation = Board::from_rows(vec![
+            vec![t.clone(), None],
+            vec![None, t.clone()],
+            vec![t.clone(), t.clone()],
+            vec![t.clone(), t.clone()],
+        ]);
+        assert_eq!(board, expectation);
+        let mut blocked = board.blocking(&[Point { x: 1, y: 0 }]);
+        assert!(!blocked.stack_lines_at_bottom(&[2], 1));
+    }
+
+    #[test]
+    fn test_display() {
+        let board = Board::new(&Size {
+            height: 2,
+            width: 3,
+        })
+        .replacing_figure_at_xy(0, 1, Some(FigureType::L))
+        .replacing_figure_at_xy(1, 1, Some(FigureType::Garbage))
+        .blocking(&[Point { x: 2, y: 0 }]);
+        assert_eq!(board.to_string(), "..#\nLG.\n");
+    }
+
+    #[test]
+    fn test_clone_from_and_row_bits() {
+        let source = Board::new(&Size {
+            height: 3,
+            width: 4,
+        })
+        .replacing_figure_at_xy(1, 2, Some(FigureType::S))
+        .blocking(&[Point { x: 3, y: 2 }]);
+        assert_eq!(source.taken_rows(), &[0, 0, 0b0010]);
+        assert_eq!(source.blocked_rows(), &[0, 0, 0b1000]);
+        let mut board = Board::new(&Size {
+            height: 5,
+            width: 2,
+        });
+        board.clone_from(&source);
+        assert_eq!(board, source);
+    }
+
+    #[test]
+    fn test_hash_follows_the_cells() {
+        let size = Size {
+            height: 4,
+            width: 3,
+        };
+        let empty = Board::new(&size);
+        let board = empty
+            .replacing_figure_at_xy(0, 3, Some(FigureType::T))
+            .replacing_figure_at_xy(1, 2, Some(FigureType::S));
+        assert_ne!(board.hash(), empty.hash());
+        // Only the taken cells count, in any order
+        let same = empty
+            .replacing_figure_at_xy(1, 2, Some(FigureType::Garbage))
+            .replacing_figure_at_xy(0, 3, Some(FigureType::I))
+            .replacing_figure_at_xy(0, 3, Some(FigureType::J));
+        assert_eq!(same.hash(), board.hash());
+        let removed = board.replacing_figure_at_xy(1, 2, None);
+        let single = empty.replacing_figure_at_xy(0, 3, Some(FigureType::T));
+        assert_eq!(removed.hash(), single.hash());
+        // Cells that fall after a clear hash like cells placed there
+        let cleared = board.removing_lines(&[3]);
+        let placed = empty.replacing_figure_at_xy(1, 3, Some(FigureType::S));
+        assert_eq!(cleared.hash(), placed.hash());
+        let blocked = placed.blocking(&[Point { x: 1, y: 3 }]);
+        assert_ne!(blocked.hash(), empty.hash());
+        let resized = blocked.resized(&size);
+        assert_eq!(resized.hash(), blocked.hash());
+    }
+
+    #[test]
+    fn test_moved_rows_hash_like_new_boards() {
+        let mut rows = vec![vec![None; 4]; 6];
+        rows[1][0] = Some(FigureType::T);
+        rows[2] = vec![Some(FigureType::I); 4];
+        rows[3][2] = Some(FigureType::S);
+        rows[4] = vec![Some(FigureType::O); 4];
+        rows[5][3] = Some(FigureType::Z);
+        let mut board = Board::from_rows(rows);
+        let mut stacked = board.clone();
+        board.remove_lines_in_place(&[2, 4]);
+        assert_eq!(board.hash(), Board::from_rows(board.to_rows()).hash());
+        assert!(stacked.stack_lines_at_bottom(&[2], 1));
+        assert!(stacked.is_line_full(4));
+        assert_eq!(stacked.hash(), Board::from_rows(stacked.to_rows()).hash());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_rebuilds_the_masks() {
+        let board = Board::new(&Size {
+            height: 3,
+            width: 4,
+        })
+        .replacing_figure_at_xy(1, 2, Some(FigureType::T));
+        let mut saved = serde_json::to_value(&board).unwrap();
+        // Saves from before blocked cells, with masks that don't match the cells
+        saved.as_object_mut().unwrap().remove("blocked");
+        saved["rows"] = serde_json::json!([0]);
+        let mut loaded: Board = serde_json::from_value(saved.clone()).unwrap();
+        assert_eq!(loaded, board);
+        assert_eq!(loaded.hash(), board.hash());
+        loaded.set_blocked(0, 2, true);
+        loaded.set_figure_at_xy(3, 2, Some(FigureType::S));
+        assert!(loaded.is_blocked(0, 2));
+        saved["blocked"] = serde_json::json!([0, 0, 0, 0]);
+        assert!(serde_json::from_value::<Board>(saved).is_err());
+    }
+}
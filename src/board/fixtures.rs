@@ -0,0 +1,19 @@
+// Boards shared by the tests of the board modules
+use super::super::FigureType;
+use super::Board;
+
+// One string per row from the top, # is taken and . is empty
+pub(crate) fn board_from(rows: &[&str]) -> Board {
+    let rows = rows
+        .iter()
+        .map(|row| {
+            row.chars()
+                .map(|cell| match cell {
+                    '#' => Some(FigureType::Garbage),
+                    _ => None,
+                })
+                .collect()
+        })
+        .collect();
+    return Board::from_rows(rows);
+}
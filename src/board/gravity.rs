@@ -0,0 +1,192 @@
+// What happens to the cells above cleared lines
+use super::super::FigureType;
+use super::Board;
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClearGravity {
+    // Everything above a cleared line moves down one row per cleared line
+    Naive,
+    // Groups of connected cells fall until they land
+    Sticky,
+    // Every cell falls on its own until it lands
+    Cascade,
+}
+
+impl Default for ClearGravity {
+    fn default() -> ClearGravity {
+        return ClearGravity::Naive;
+    }
+}
+
+type Cells = Vec<Vec<Option<FigureType>>>;
+
+impl Board {
+    pub fn removing_lines_with(&self, lines: &[usize], gravity: &ClearGravity) -> Board {
+        let mut board = self.clone();
+        board.remove_lines_with(lines, gravity);
+        return board;
+    }
+
+    // Sticky and cascade gravity can complete new lines, they are left on the board
+    pub fn remove_lines_with(&mut self, lines: &[usize], gravity: &ClearGravity) {
+        if *gravity == ClearGravity::Naive {
+            self.remove_lines_in_place(lines);
+            return;
+        }
+        let height = self.height();
+        for y in lines.iter().filter(|line| **line < height) {
+            for x in 0..self.width() {
+                self.set_figure_at_xy(x, *y, None);
+            }
+        }
+        let mut cells = self.to_rows();
+        match gravity {
+            ClearGravity::Sticky => self.settle_groups(&mut cells),
+            _ => self.settle_cells(&mut cells),
+        }
+        for (y, row) in cells.into_iter().enumerate() {
+            for (x, square) in row.into_iter().enumerate() {
+                self.set_figure_at_xy(x, y, square);
+            }
+        }
+    }
+
+    fn is_free(&self, cells: &Cells, x: usize, y: usize) -> bool {
+        return y < self.height() && cells[y][x].is_none() && !self.is_blocked(x, y);
+    }
+
+    // Bottom rows first, so each cell lands on the already settled ones
+    fn settle_cells(&self, cells: &mut Cells) {
+        for y in (0..self.height()).rev() {
+            for x in 0..self.width() {
+                if cells[y][x].is_none() {
+                    continue;
+                }
+                let mut landing = y;
+                while self.is_free(cells, x, landing + 1) {
+                    landing += 1;
+                }
+                if landing != y {
+                    cells[landing][x] = cells[y][x].take();
+                }
+            }
+        }
+    }
+
+    fn settle_groups(&self, cells: &mut Cells) {
+        let mut groups = self.groups(cells);
+        let mut moved = true;
+        while moved {
+            moved = false;
+            for group in groups.iter_mut() {
+                while self.can_group_fall(cells, group) {
+                    let squares: Vec<Option<FigureType>> =
+                        group.iter().map(|&(x, y)| cells[y][x].take()).collect();
+                    for (point, square) in group.iter_mut().zip(squares) {
+                        point.1 += 1;
+                        cells[point.1][point.0] = square;
+                    }
+                    moved = true;
+                }
+            }
+        }
+    }
+
+    fn can_group_fall(&self, cells: &Cells, group: &[(usize, usize)]) -> bool {
+        return group.iter().all(|&(x, y)| {
+            return group.contains(&(x, y + 1)) || self.is_free(cells, x, y + 1);
+        });
+    }
+
+    // Taken cells joined by their sides
+    fn groups(&self, cells: &Cells) -> Vec<Vec<(usize, usize)>> {
+        let mut seen = vec![vec![false; self.width()]; self.height()];
+        let mut groups = vec![];
+        for (y, x) in (0..self.height()).flat_map(|y| (0..self.width()).map(move |x| (y, x))) {
+            if seen[y][x] || cells[y][x].is_none() {
+                continue;
+            }
+            seen[y][x] = true;
+            let mut group = vec![];
+            let mut stack = vec![(x, y)];
+            while let Some((x, y)) = stack.pop() {
+                group.push((x, y));
+                let neighbours = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+                for (nx, ny) in neighbours {
+                    let inside = nx < self.width() && ny < self.height();
+                    if inside && !seen[ny][nx] && cells[ny][nx].is_some() {
+                        seen[ny][nx] = true;
+                        stack.push((nx, ny));
+                    }
+                }
+            }
+            groups.push(group);
+        }
+        return groups;
+    }
+}
+
+#[cfg(test)]
+mod gravity_tests {
+    use super::super::super::{Point, Size};
+    use super::super::fixtures::board_from;
+    use super::*;
+    use alloc::string::String;
+
+    fn rows_of(board: &Board) -> Vec<String> {
+        return board
+            .to_rows()
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| if cell.is_some() { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+    }
+
+    #[test]
+    fn test_naive_matches_removing_lines() {
+        let board = board_from(&["#..", "..#", "###", "#.."]);
+        assert_eq!(
+            board.removing_lines_with(&[2], &ClearGravity::Naive),
+            board.removing_lines(&[2])
+        );
+    }
+
+    #[test]
+    fn test_cascade_drops_every_cell() {
+        let board = board_from(&["#..", ".#.", "###", "#.."]);
+        let cleared = board.removing_lines_with(&[2], &ClearGravity::Cascade);
+        assert_eq!(rows_of(&cleared), vec!["...", "...", "#..", "##."]);
+    }
+
+    #[test]
+    fn test_sticky_drops_connected_groups() {
+        let board = board_from(&["##..", ".#..", "####", "#..."]);
+        let cleared = board.removing_lines_with(&[2], &ClearGravity::Sticky);
+        // The three connected cells fall together until column 0 lands on the cell left there
+        assert_eq!(rows_of(&cleared), vec!["....", "....", "##..", "##.."]);
+    }
+
+    #[test]
+    fn test_cells_land_on_blocked_cells() {
+        let board = Board::new(&Size {
+            height: 3,
+            width: 2,
+        })
+        .replacing_figure_at_xy(0, 0, Some(FigureType::T))
+        .blocking(&[Point { x: 0, y: 2 }]);
+        let cleared = board.removing_lines_with(&[], &ClearGravity::Cascade);
+        assert_eq!(cleared.figure_at_xy(0, 1), &Some(FigureType::T));
+        assert!(cleared.is_blocked(0, 2));
+    }
+}
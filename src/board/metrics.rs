@@ -0,0 +1,171 @@
+// Board evaluation heuristics used by bots and training tools.
+// Heights count from the floor, walls and floor count as taken cells.
+use super::Board;
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Metrics {
+    pub column_heights: Vec<usize>,
+    pub aggregate_height: usize,
+    pub max_height: usize,
+    pub bumpiness: usize,
+    pub holes: usize,
+    pub wells: usize,
+    pub row_transitions: usize,
+    pub column_transitions: usize,
+}
+
+impl Metrics {
+    pub fn of(board: &Board) -> Metrics {
+        let column_heights = column_heights(board);
+        return Metrics {
+            aggregate_height: column_heights.iter().sum(),
+            max_height: column_heights.iter().cloned().max().unwrap_or(0),
+            bumpiness: bumpiness_of(&column_heights),
+            holes: holes(board),
+            wells: wells_of(&column_heights),
+            row_transitions: row_transitions(board),
+            column_transitions: column_transitions(board),
+            column_heights,
+        };
+    }
+}
+
+fn is_taken(board: &Board, x: usize, y: usize) -> bool {
+    return board.row_mask(y as i32) & 1 << x != 0;
+}
+
+// Rows from the floor up to the highest taken cell of each column
+pub fn column_heights(board: &Board) -> Vec<usize> {
+    return board.column_heights();
+}
+
+pub fn aggregate_height(board: &Board) -> usize {
+    return column_heights(board).iter().sum();
+}
+
+// Sum of the height differences between neighbouring columns
+pub fn bumpiness(board: &Board) -> usize {
+    return bumpiness_of(&column_heights(board));
+}
+
+fn bumpiness_of(heights: &[usize]) -> usize {
+    return heights
+        .windows(2)
+        .map(|pair| (pair[0] as i32 - pair[1] as i32).unsigned_abs() as usize)
+        .sum();
+}
+
+// Empty cells with a taken cell somewhere above them
+pub fn holes(board: &Board) -> usize {
+    return board.hole_positions().len();
+}
+
+// Sum of the depths of the columns lower than both neighbours, a wall is a neighbour
+// higher than any column
+pub fn wells(board: &Board) -> usize {
+    return wells_of(&column_heights(board));
+}
+
+fn wells_of(heights: &[usize]) -> usize {
+    let mut wells = 0;
+    for (x, height) in heights.iter().enumerate() {
+        let left = if x == 0 { usize::MAX } else { heights[x - 1] };
+        let right = heights.get(x + 1).cloned().unwrap_or(usize::MAX);
+        let edge = left.min(right);
+        if edge != usize::MAX && edge > *height {
+            wells += edge - height;
+        }
+    }
+    return wells;
+}
+
+// Changes between empty and taken cells along each row
+pub fn row_transitions(board: &Board) -> usize {
+    let mut transitions = 0;
+    for y in 0..board.height() {
+        let mut previous = true;
+        for x in 0..board.width() {
+            let taken = is_taken(board, x, y);
+            if taken != previous {
+                transitions += 1;
+            }
+            previous = taken;
+        }
+        if !previous {
+            transitions += 1;
+        }
+    }
+    return transitions;
+}
+
+// Changes between empty and taken cells down each column, the space above the board is empty
+pub fn column_transitions(board: &Board) -> usize {
+    let mut transitions = 0;
+    for x in 0..board.width() {
+        let mut previous = false;
+        for y in 0..board.height() {
+            let taken = is_taken(board, x, y);
+            if taken != previous {
+                transitions += 1;
+            }
+            previous = taken;
+        }
+        if !previous {
+            transitions += 1;
+        }
+    }
+    return transitions;
+}
+
+#[cfg(test)]
+mod metrics_tests {
+    use super::super::fixtures::board_from;
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_heights_and_bumpiness() {
+        let board = board_from(&["....", "#...", "#.#.", "###."]);
+        assert_eq!(column_heights(&board), vec![3, 1, 2, 0]);
+        assert_eq!(aggregate_height(&board), 6);
+        assert_eq!(bumpiness(&board), 2 + 1 + 2);
+    }
+
+    #[test]
+    fn test_holes() {
+        let board = board_from(&["....", "##..", "#.#.", ".##."]);
+        assert_eq!(holes(&board), 2);
+    }
+
+    #[test]
+    fn test_wells() {
+        // Column 1 is a well of depth 2, the last column is closed by the wall
+        let board = board_from(&["#.#.", "#.#.", "###."]);
+        assert_eq!(wells(&board), 2 + 3);
+        let board = board_from(&["...", "...", "#.#"]);
+        assert_eq!(wells(&board), 1);
+    }
+
+    #[test]
+    fn test_transitions() {
+        let board = board_from(&["...", "#.#", "###"]);
+        // Empty row: wall to empty and empty to wall, middle row: two, full row: none
+        assert_eq!(row_transitions(&board), 2 + 2);
+        // Each column goes from empty to taken once, the middle one is taken from row 2
+        assert_eq!(column_transitions(&board), 3);
+    }
+
+    #[test]
+    fn test_metrics_of_board() {
+        let board = board_from(&["...", "#..", "#.#"]);
+        let metrics = Metrics::of(&board);
+        assert_eq!(metrics.column_heights, vec![2, 0, 1]);
+        assert_eq!(metrics.max_height, 2);
+        assert_eq!(metrics.aggregate_height, 3);
+        assert_eq!(metrics.bumpiness, 3);
+        assert_eq!(metrics.holes, 0);
+        assert_eq!(metrics.wells, 1);
+    }
+}
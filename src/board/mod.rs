@@ -0,0 +1,7 @@
+mod board;
+#[cfg(test)]
+pub(crate) mod fixtures;
+mod gravity;
+pub mod metrics;
+pub use board::{Board, MAX_WIDTH};
+pub use gravity::ClearGravity;
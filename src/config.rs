@@ -0,0 +1,660 @@
+use super::modes::GameMode;
+use super::randomizer::{
+    BagRandomizer, ClassicRandomizer, IndexRandomizer, ScriptEnd, ScriptedRandomizer,
+    SequenceRandomizer, WeightedRandomizer,
+};
+use super::items::ItemRules;
+use super::scoring::Scoring;
+use super::special::Special;
+use super::{ClearGravity, FigureType, Palette, Point, Rotation, RotationSystem, Size};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// Frames per row for each NES level, played at 60.0988 frames per second
+const CLASSIC_FRAMES_PER_ROW: [u32; 29] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6, 5, 5, 5, 4, 4, 4, 3, 3, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+];
+const CLASSIC_FRAME_RATE: f64 = 60.0988;
+// Gravity in G counts cells per frame at 60 frames per second
+const GRAVITY_FRAME_RATE: f64 = 60.0;
+// 20G drops figures through a whole 20 rows field in one frame
+const INSTANT_GRAVITY: f64 = 20.0;
+const LINES_PER_LEVEL: usize = 10;
+const VARIABLE_GOAL_LINES: usize = 5;
+// NES auto shift waits 16 frames, then moves every 6 frames
+const CLASSIC_DAS_FRAMES: u32 = 16;
+const CLASSIC_ARR_FRAMES: u32 = 6;
+// NES entry delay of figures locking at the bottom, higher locks wait a few frames more
+const CLASSIC_ENTRY_DELAY_FRAMES: u32 = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GravityCurve {
+    Guideline,
+    Classic,
+    Fixed(f64),
+    // Cells per frame, fractions for slow gravity and 20G for instant drops
+    CellsPerFrame(f64),
+}
+
+impl GravityCurve {
+    // Seconds the active figure waits before falling one row
+    pub fn interval(&self, level: usize) -> f64 {
+        return match self {
+            GravityCurve::Guideline => GravityCurve::guideline_interval(level),
+            GravityCurve::Classic => GravityCurve::classic_interval(level),
+            GravityCurve::Fixed(interval) => *interval,
+            GravityCurve::CellsPerFrame(cells) => 1.0 / (cells * GRAVITY_FRAME_RATE),
+        };
+    }
+
+    // Gravity of the level in G, 1.0 moves one cell every 60th of a second
+    pub fn cells_per_frame(&self, level: usize) -> f64 {
+        return match self {
+            GravityCurve::CellsPerFrame(cells) => *cells,
+            _ => 1.0 / (self.interval(level) * GRAVITY_FRAME_RATE),
+        };
+    }
+
+    pub fn is_instant(&self, level: usize) -> bool {
+        return self.cells_per_frame(level) >= INSTANT_GRAVITY;
+    }
+
+    fn guideline_interval(level: usize) -> f64 {
+        let exponent = level.max(1) as i32 - 1;
+        let base = 0.8 - (exponent as f64) * 0.007;
+        // f64::powi is not available without std
+        let mut interval = 1.0;
+        for _ in 0..exponent {
+            interval *= base;
+        }
+        return interval;
+    }
+
+    fn classic_interval(level: usize) -> f64 {
+        let frames = CLASSIC_FRAMES_PER_ROW.get(level).cloned().unwrap_or(1);
+        return frames as f64 / CLASSIC_FRAME_RATE;
+    }
+}
+
+// Lines needed to leave each level
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LevelGoal {
+    // The same number of lines for every level
+    Fixed(usize),
+    // Guideline variable goal, level N takes 5 * N lines
+    Variable,
+}
+
+impl LevelGoal {
+    pub fn lines_for_level(&self, level: usize) -> usize {
+        return match self {
+            LevelGoal::Fixed(lines) => (*lines).max(1),
+            LevelGoal::Variable => VARIABLE_GOAL_LINES * level.max(1),
+        };
+    }
+}
+
+impl Default for LevelGoal {
+    fn default() -> LevelGoal {
+        return LevelGoal::Fixed(LINES_PER_LEVEL);
+    }
+}
+
+// Built in randomizers that `Game::with_config` can create
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RandomizerChoice {
+    Bag(u64),
+    Sequence(Vec<i32>),
+    Classic(u64),
+    // Weights follow the piece set, see `WeightedRandomizer`
+    Weighted(u64, Vec<u32>),
+    Scripted(Vec<i32>, ScriptEnd),
+}
+
+impl RandomizerChoice {
+    // Bags hold one of each of the `piece_count` figures of the piece set
+    pub fn build(&self, piece_count: usize) -> Box<dyn IndexRandomizer> {
+        return match self {
+            RandomizerChoice::Bag(seed) => Box::new(BagRandomizer::with_size(*seed, piece_count)),
+            RandomizerChoice::Sequence(sequence) => {
+                Box::new(SequenceRandomizer::new(sequence.clone()))
+            }
+            RandomizerChoice::Classic(seed) => {
+                Box::new(ClassicRandomizer::with_size(*seed, piece_count))
+            }
+            RandomizerChoice::Weighted(seed, weights) => {
+                Box::new(WeightedRandomizer::new(*seed, weights.clone()))
+            }
+            RandomizerChoice::Scripted(script, end) => {
+                Box::new(ScriptedRandomizer::new(script.clone(), *end))
+            }
+        };
+    }
+}
+
+// Conditions that end the game
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TopOutRules {
+    // A new figure overlaps the stack where it spawns
+    pub block_out: bool,
+    // A blocked figure first tries to spawn a row higher, partially above the field
+    pub partial_spawn: bool,
+    // A figure locks entirely above the visible field
+    pub lock_out: bool,
+    // Garbage pushes blocks over the top of the board
+    pub push_out: bool,
+}
+
+impl TopOutRules {
+    pub fn guideline() -> TopOutRules {
+        return TopOutRules {
+            block_out: true,
+            partial_spawn: true,
+            lock_out: true,
+            push_out: true,
+        };
+    }
+}
+
+impl Default for TopOutRules {
+    fn default() -> TopOutRules {
+        return TopOutRules {
+            block_out: true,
+            partial_spawn: false,
+            lock_out: false,
+            push_out: true,
+        };
+    }
+}
+
+// Help for beginners and handicaps, the default changes nothing
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Assists {
+    // Seconds per row the gravity never goes below, soft drops still speed it up, 0 keeps the curve
+    pub min_gravity_interval: f64,
+    // Emits `GameEvent::HoldSuggested` when the figure hold brings has a clearly better placement
+    pub hold_suggestion: bool,
+    // Multiplies the lock delay
+    pub lock_delay_factor: f64,
+    // Rows removed from the bottom instead of ending the game, 0 keeps the game over
+    pub clear_rows_on_top_out: usize,
+}
+
+impl Assists {
+    // Slow gravity, hold hints, twice the lock delay and no game over
+    pub fn kids() -> Assists {
+        return Assists {
+            min_gravity_interval: 0.5,
+            hold_suggestion: true,
+            lock_delay_factor: 2.0,
+            clear_rows_on_top_out: 4,
+        };
+    }
+}
+
+impl Default for Assists {
+    fn default() -> Assists {
+        return Assists {
+            min_gravity_interval: 0.0,
+            hold_suggestion: false,
+            lock_delay_factor: 1.0,
+            clear_rows_on_top_out: 0,
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameConfig {
+    pub size: Size,
+    pub randomizer: RandomizerChoice,
+    pub mode: GameMode,
+    pub start_level: usize,
+    pub gravity_curve: GravityCurve,
+    pub preview_count: usize,
+    pub lock_delay: f64,
+    pub max_lock_resets: usize,
+    pub rotation_system: RotationSystem,
+    // Figures spawning in another orientation than `Rotation::Spawn`, the guideline one. Two state
+    // figures of the NES and ARS systems only use the spawn and right states
+    pub spawn_rotations: Vec<(FigureType, Rotation)>,
+    pub soft_drop_factor: f64,
+    pub das: f64,
+    pub arr: f64,
+    pub hold_enabled: bool,
+    pub piece_set: Vec<FigureType>,
+    pub line_clear_delay: f64,
+    pub scoring: Scoring,
+    // Emits `GameEvent::FinesseFault` when a figure locks after more presses than needed
+    pub finesse_check: bool,
+    // Frames per second of the frame mode, every timer then counts whole frames
+    pub tick_rate: Option<u32>,
+    pub top_out: TopOutRules,
+    // Buffer rows above the visible field, the standard is 20 hidden rows over 20 visible ones
+    pub hidden_rows: usize,
+    pub palette: Palette,
+    // Permanently unusable cells, counted from the top left of the visible field
+    pub blocked_cells: Vec<Point>,
+    pub clear_gravity: ClearGravity,
+    // Seconds between a figure locking, or its lines clearing, and the next one spawning (ARE)
+    pub entry_delay: f64,
+    // Pieces `Game::undo_piece` can take back, 0 keeps no history
+    pub undo_depth: usize,
+    pub level_goal: LevelGoal,
+    pub assists: Assists,
+    // Seconds garbage from `Game::queue_garbage` waits before it enters the board
+    pub garbage_delay: f64,
+    // Attacks cancel the queued garbage before anything is sent
+    pub garbage_cancellation: bool,
+    // Mechanic charged by clears and started with `Action::ActivateSpecial`
+    pub special: Special,
+    // Clears that earn items, none by default
+    pub items: ItemRules,
+    // Stack height, in rows from the floor, from which `Game::in_danger` holds. None turns the
+    // danger events off
+    pub danger_height: Option<usize>,
+}
+
+impl GameConfig {
+    pub fn builder() -> GameConfigBuilder {
+        return GameConfigBuilder::new();
+    }
+
+    // NES rules: no hold, no kicks, one preview, instant lock, entry delay, classic scoring and gravity
+    pub fn nes() -> GameConfig {
+        return GameConfig {
+            randomizer: RandomizerChoice::Classic(0),
+            start_level: 0,
+            gravity_curve: GravityCurve::Classic,
+            preview_count: 1,
+            lock_delay: 0.0,
+            max_lock_resets: 0,
+            rotation_system: RotationSystem::Nintendo,
+            spawn_rotations: GameConfig::flat_side_up_spawns(),
+            das: CLASSIC_DAS_FRAMES as f64 / CLASSIC_FRAME_RATE,
+            arr: CLASSIC_ARR_FRAMES as f64 / CLASSIC_FRAME_RATE,
+            entry_delay: CLASSIC_ENTRY_DELAY_FRAMES as f64 / CLASSIC_FRAME_RATE,
+            hold_enabled: false,
+            scoring: Scoring::Classic,
+            ..GameConfig::default()
+        };
+    }
+
+    // NES and ARS spawns, J, L and T point down with their flat side up
+    pub fn flat_side_up_spawns() -> Vec<(FigureType, Rotation)> {
+        return vec![
+            (FigureType::J, Rotation::Reverse),
+            (FigureType::L, Rotation::Reverse),
+            (FigureType::T, Rotation::Reverse),
+        ];
+    }
+
+    pub fn spawn_rotation(&self, figure_type: &FigureType) -> Rotation {
+        return self
+            .spawn_rotations
+            .iter()
+            .find(|(figure, _)| figure == figure_type)
+            .map_or(Rotation::Spawn, |(_, rotation)| *rotation);
+    }
+
+    pub fn level_for_lines(&self, lines: usize) -> usize {
+        return self.level_progress(lines).0;
+    }
+
+    pub fn lines_to_next_level(&self, lines: usize) -> usize {
+        let (level, remaining) = self.level_progress(lines);
+        return self.level_goal.lines_for_level(level) - remaining;
+    }
+
+    // The level reached and the lines already cleared towards the next one
+    fn level_progress(&self, lines: usize) -> (usize, usize) {
+        if let LevelGoal::Fixed(goal) = self.level_goal {
+            let goal = goal.max(1);
+            return (self.start_level + lines / goal, lines % goal);
+        }
+        let mut level = self.start_level;
+        let mut remaining = lines;
+        while remaining >= self.level_goal.lines_for_level(level) {
+            remaining -= self.level_goal.lines_for_level(level);
+            level += 1;
+        }
+        return (level, remaining);
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> GameConfig {
+        return GameConfig {
+            size: Size {
+                height: 20,
+                width: 10,
+            },
+            randomizer: RandomizerChoice::Bag(0),
+            mode: GameMode::Marathon,
+            start_level: 1,
+            gravity_curve: GravityCurve::Guideline,
+            preview_count: 5,
+            lock_delay: 0.5,
+            max_lock_resets: 15,
+            rotation_system: RotationSystem::SRS,
+            spawn_rotations: vec![],
+            soft_drop_factor: 20.0,
+            das: 0.167,
+            arr: 0.033,
+            hold_enabled: true,
+            piece_set: FigureType::standard_set(),
+            line_clear_delay: 0.0,
+            scoring: Scoring::Standard,
+            finesse_check: false,
+            tick_rate: None,
+            top_out: TopOutRules::default(),
+            hidden_rows: 0,
+            palette: Palette::default(),
+            blocked_cells: vec![],
+            clear_gravity: ClearGravity::Naive,
+            entry_delay: 0.0,
+            undo_depth: 0,
+            level_goal: LevelGoal::default(),
+            assists: Assists::default(),
+            garbage_delay: 0.5,
+            garbage_cancellation: true,
+            special: Special::None,
+            items: ItemRules::default(),
+            danger_height: None,
+        };
+    }
+}
+
+pub struct GameConfigBuilder {
+    config: GameConfig,
+}
+
+impl GameConfigBuilder {
+    pub fn new() -> GameConfigBuilder {
+        return GameConfigBuilder {
+            config: GameConfig::default(),
+        };
+    }
+
+    pub fn size(mut self, size: Size) -> GameConfigBuilder {
+        self.config.size = size;
+        return self;
+    }
+
+    pub fn randomizer(mut self, randomizer: RandomizerChoice) -> GameConfigBuilder {
+        self.config.randomizer = randomizer;
+        return self;
+    }
+
+    pub fn seed(self, seed: u64) -> GameConfigBuilder {
+        return self.randomizer(RandomizerChoice::Bag(seed));
+    }
+
+    pub fn mode(mut self, mode: GameMode) -> GameConfigBuilder {
+        self.config.mode = mode;
+        return self;
+    }
+
+    pub fn start_level(mut self, start_level: usize) -> GameConfigBuilder {
+        self.config.start_level = start_level;
+        return self;
+    }
+
+    pub fn gravity_curve(mut self, gravity_curve: GravityCurve) -> GameConfigBuilder {
+        self.config.gravity_curve = gravity_curve;
+        return self;
+    }
+
+    pub fn preview_count(mut self, preview_count: usize) -> GameConfigBuilder {
+        self.config.preview_count = preview_count;
+        return self;
+    }
+
+    pub fn lock_delay(mut self, lock_delay: f64) -> GameConfigBuilder {
+        self.config.lock_delay = lock_delay;
+        return self;
+    }
+
+    pub fn max_lock_resets(mut self, max_lock_resets: usize) -> GameConfigBuilder {
+        self.config.max_lock_resets = max_lock_resets;
+        return self;
+    }
+
+    // Replaces the orientation set before for the figure
+    pub fn spawn_rotation(mut self, figure_type: FigureType, rotation: Rotation) -> GameConfigBuilder {
+        self.config.spawn_rotations.retain(|(figure, _)| figure != &figure_type);
+        self.config.spawn_rotations.push((figure_type, rotation));
+        return self;
+    }
+
+    pub fn rotation_system(mut self, rotation_system: RotationSystem) -> GameConfigBuilder {
+        self.config.rotation_system = rotation_system;
+        return self;
+    }
+
+    pub fn soft_drop_factor(mut self, soft_drop_factor: f64) -> GameConfigBuilder {
+        self.config.soft_drop_factor = soft_drop_factor;
+        return self;
+    }
+
+    pub fn das(mut self, das: f64) -> GameConfigBuilder {
+        self.config.das = das;
+        return self;
+    }
+
+    pub fn arr(mut self, arr: f64) -> GameConfigBuilder {
+        self.config.arr = arr;
+        return self;
+    }
+
+    pub fn hold_enabled(mut self, hold_enabled: bool) -> GameConfigBuilder {
+        self.config.hold_enabled = hold_enabled;
+        return self;
+    }
+
+    pub fn piece_set(mut self, piece_set: Vec<FigureType>) -> GameConfigBuilder {
+        self.config.piece_set = piece_set;
+        return self;
+    }
+
+    pub fn line_clear_delay(mut self, line_clear_delay: f64) -> GameConfigBuilder {
+        self.config.line_clear_delay = line_clear_delay;
+        return self;
+    }
+
+    pub fn scoring(mut self, scoring: Scoring) -> GameConfigBuilder {
+        self.config.scoring = scoring;
+        return self;
+    }
+
+    pub fn finesse_check(mut self, finesse_check: bool) -> GameConfigBuilder {
+        self.config.finesse_check = finesse_check;
+        return self;
+    }
+
+    pub fn tick_rate(mut self, tick_rate: u32) -> GameConfigBuilder {
+        self.config.tick_rate = Some(tick_rate);
+        return self;
+    }
+
+    pub fn top_out(mut self, top_out: TopOutRules) -> GameConfigBuilder {
+        self.config.top_out = top_out;
+        return self;
+    }
+
+    pub fn hidden_rows(mut self, hidden_rows: usize) -> GameConfigBuilder {
+        self.config.hidden_rows = hidden_rows;
+        return self;
+    }
+
+    pub fn palette(mut self, palette: Palette) -> GameConfigBuilder {
+        self.config.palette = palette;
+        return self;
+    }
+
+    pub fn blocked_cells(mut self, blocked_cells: Vec<Point>) -> GameConfigBuilder {
+        self.config.blocked_cells = blocked_cells;
+        return self;
+    }
+
+    pub fn clear_gravity(mut self, clear_gravity: ClearGravity) -> GameConfigBuilder {
+        self.config.clear_gravity = clear_gravity;
+        return self;
+    }
+
+    pub fn entry_delay(mut self, entry_delay: f64) -> GameConfigBuilder {
+        self.config.entry_delay = entry_delay;
+        return self;
+    }
+
+    pub fn undo_depth(mut self, undo_depth: usize) -> GameConfigBuilder {
+        self.config.undo_depth = undo_depth;
+        return self;
+    }
+
+    pub fn level_goal(mut self, level_goal: LevelGoal) -> GameConfigBuilder {
+        self.config.level_goal = level_goal;
+        return self;
+    }
+
+    pub fn assists(mut self, assists: Assists) -> GameConfigBuilder {
+        self.config.assists = assists;
+        return self;
+    }
+
+    pub fn garbage_delay(mut self, garbage_delay: f64) -> GameConfigBuilder {
+        self.config.garbage_delay = garbage_delay;
+        return self;
+    }
+
+    pub fn garbage_cancellation(mut self, garbage_cancellation: bool) -> GameConfigBuilder {
+        self.config.garbage_cancellation = garbage_cancellation;
+        return self;
+    }
+
+    pub fn special(mut self, special: Special) -> GameConfigBuilder {
+        self.config.special = special;
+        return self;
+    }
+
+    pub fn items(mut self, items: ItemRules) -> GameConfigBuilder {
+        self.config.items = items;
+        return self;
+    }
+
+    pub fn danger_height(mut self, danger_height: usize) -> GameConfigBuilder {
+        self.config.danger_height = Some(danger_height);
+        return self;
+    }
+
+    pub fn build(self) -> GameConfig {
+        return self.config;
+    }
+}
+
+impl Default for GameConfigBuilder {
+    fn default() -> GameConfigBuilder {
+        return GameConfigBuilder::new();
+    }
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let config = GameConfig::builder()
+            .size(Size {
+                height: 40,
+                width: 12,
+            })
+            .seed(9)
+            .lock_delay(1.0)
+            .hold_enabled(false)
+            .scoring(Scoring::Simple100)
+            .build();
+        assert_eq!(config.size.width, 12);
+        assert_eq!(config.randomizer, RandomizerChoice::Bag(9));
+        assert_eq!(config.lock_delay, 1.0);
+        assert!(!config.hold_enabled);
+        assert_eq!(config.scoring, Scoring::Simple100);
+        assert_eq!(config.preview_count, GameConfig::default().preview_count);
+    }
+
+    #[test]
+    fn test_nes_preset() {
+        let config = GameConfig::nes();
+        assert!(!config.hold_enabled);
+        assert_eq!(config.rotation_system, RotationSystem::Nintendo);
+        assert_eq!(config.scoring, Scoring::Classic);
+        assert_eq!(config.randomizer, RandomizerChoice::Classic(0));
+        assert_eq!(config.start_level, 0);
+        assert_eq!(config.size, GameConfig::default().size);
+        let randomizer = config.randomizer.build(7);
+        assert!((0..7).contains(&randomizer.random()));
+    }
+
+    #[test]
+    fn test_guideline_curve() {
+        let curve = GravityCurve::Guideline;
+        assert_eq!(curve.interval(1), 1.0);
+        assert!((curve.interval(2) - 0.793).abs() < 1e-9);
+        assert!(curve.interval(15) < curve.interval(14));
+    }
+
+    #[test]
+    fn test_classic_curve() {
+        let curve = GravityCurve::Classic;
+        assert!((curve.interval(0) - 48.0 / CLASSIC_FRAME_RATE).abs() < 1e-9);
+        assert!((curve.interval(40) - 1.0 / CLASSIC_FRAME_RATE).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cells_per_frame_curve() {
+        let curve = GravityCurve::CellsPerFrame(0.5);
+        assert!((curve.interval(1) - 1.0 / 30.0).abs() < 1e-9);
+        assert!(!curve.is_instant(1));
+        assert!(GravityCurve::CellsPerFrame(INSTANT_GRAVITY).is_instant(1));
+        let fixed = GravityCurve::Fixed(1.0);
+        assert!((fixed.cells_per_frame(1) - 1.0 / 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_level_for_lines() {
+        let config = GameConfig {
+            start_level: 3,
+            ..GameConfig::default()
+        };
+        assert_eq!(config.level_for_lines(0), 3);
+        assert_eq!(config.level_for_lines(9), 3);
+        assert_eq!(config.level_for_lines(25), 5);
+        assert_eq!(config.lines_to_next_level(25), 5);
+    }
+
+    #[test]
+    fn test_variable_goal() {
+        let config = GameConfig {
+            level_goal: LevelGoal::Variable,
+            ..GameConfig::default()
+        };
+        assert_eq!(config.level_for_lines(4), 1);
+        assert_eq!(config.level_for_lines(5), 2);
+        assert_eq!(config.level_for_lines(14), 2);
+        assert_eq!(config.level_for_lines(15), 3);
+        assert_eq!(config.lines_to_next_level(0), 5);
+        assert_eq!(config.lines_to_next_level(7), 8);
+        let from_zero = GameConfig {
+            start_level: 0,
+            ..config
+        };
+        assert_eq!(from_zero.level_for_lines(5), 1);
+        assert_eq!(from_zero.level_for_lines(10), 2);
+    }
+}
@@ -0,0 +1,404 @@
+// Gym-style environment for reinforcement learning: `reset` starts an episode, `step` plays one
+// frame with the chosen action and returns the observation, the reward and whether it ended
+use super::board::metrics::Metrics;
+use super::{Action, FigureType, Game, GameConfig, GameEvent, RandomizerChoice};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// What the agent sees of the visible field, as flat tensors of floats
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Observation {
+    pub width: usize,
+    pub height: usize,
+    // One value per cell, row by row from the top left one: 1 for taken and blocked cells
+    pub board: Vec<f32>,
+    // Same layout as `board`, 1 for the cells of the active figure
+    pub active_cells: Vec<f32>,
+    // One hot over the piece set of the config, all zeros when there is no figure
+    pub active: Vec<f32>,
+    pub held: Vec<f32>,
+    // One one hot per preview, in queue order
+    pub next: Vec<f32>,
+}
+
+impl Observation {
+    // Every part one after the other, in the order of the fields
+    pub fn to_vec(&self) -> Vec<f32> {
+        let mut values = Vec::with_capacity(self.len());
+        for part in [
+            &self.board,
+            &self.active_cells,
+            &self.active,
+            &self.held,
+            &self.next,
+        ] {
+            values.extend_from_slice(part);
+        }
+        return values;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.board.len()
+            + self.active_cells.len()
+            + self.active.len()
+            + self.held.len()
+            + self.next.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+}
+
+// What happened during a step, for the reward function
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition<'a> {
+    pub events: &'a [GameEvent],
+    pub lines_cleared: usize,
+    pub score_gained: u64,
+    pub pieces_placed: usize,
+    // Board metrics before and after the step
+    pub before: &'a Metrics,
+    pub after: &'a Metrics,
+    pub done: bool,
+    // Done because the stack reached the top, not because the mode goal was reached
+    pub topped_out: bool,
+}
+
+pub type RewardFn = Box<dyn FnMut(&Transition) -> f64>;
+
+// A part of the reward, `RewardShaping` multiplies each one by its weight
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RewardTerm {
+    LinesCleared,
+    // Holes added by the step, negative when holes were uncovered
+    HolesDelta,
+    // Change of the aggregate height of the columns
+    HeightDelta,
+    // 1 for every step that does not top out
+    Survival,
+    // 1 for the step that tops out
+    GameOver,
+}
+
+impl RewardTerm {
+    pub fn value(&self, transition: &Transition) -> f64 {
+        let delta = |before: usize, after: usize| after as f64 - before as f64;
+        return match self {
+            RewardTerm::LinesCleared => transition.lines_cleared as f64,
+            RewardTerm::HolesDelta => delta(transition.before.holes, transition.after.holes),
+            RewardTerm::HeightDelta => delta(
+                transition.before.aggregate_height,
+                transition.after.aggregate_height,
+            ),
+            RewardTerm::Survival if transition.topped_out => 0.0,
+            RewardTerm::Survival => 1.0,
+            RewardTerm::GameOver if transition.topped_out => 1.0,
+            RewardTerm::GameOver => 0.0,
+        };
+    }
+}
+
+// Weighted sum of reward terms, penalties get negative weights
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RewardShaping {
+    pub terms: Vec<(RewardTerm, f64)>,
+}
+
+impl RewardShaping {
+    pub fn new() -> RewardShaping {
+        return RewardShaping::default();
+    }
+
+    pub fn term(mut self, term: RewardTerm, weight: f64) -> RewardShaping {
+        self.terms.push((term, weight));
+        return self;
+    }
+
+    pub fn reward(&self, transition: &Transition) -> f64 {
+        return self
+            .terms
+            .iter()
+            .map(|(term, weight)| term.value(transition) * weight)
+            .sum();
+    }
+}
+
+pub struct Env {
+    config: GameConfig,
+    game: Game,
+    reward: RewardFn,
+    // Metrics of the board as it was at the last step, computed again only when it changed
+    metrics: Metrics,
+    board_hash: u64,
+}
+
+impl Env {
+    // Rewards the lines cleared
+    pub fn new(config: GameConfig) -> Env {
+        return Env::with_reward(
+            config,
+            Box::new(|transition| transition.lines_cleared as f64),
+        );
+    }
+
+    pub fn with_shaping(config: GameConfig, shaping: RewardShaping) -> Env {
+        return Env::with_reward(
+            config,
+            Box::new(move |transition| shaping.reward(transition)),
+        );
+    }
+
+    pub fn with_reward(config: GameConfig, reward: RewardFn) -> Env {
+        let game = Game::with_config(config.clone());
+        let metrics = game.board_metrics();
+        let board_hash = game.get_board().hash();
+        return Env {
+            config,
+            game,
+            reward,
+            metrics,
+            board_hash,
+        };
+    }
+
+    // Starts a new episode with the config as given, so with the same pieces every time
+    pub fn reset(&mut self) -> Observation {
+        self.game = Game::with_config(self.config.clone());
+        self.refresh_metrics();
+        return self.observe();
+    }
+
+    // Starts a new episode with another seed, sequence and scripted randomizers keep their figures
+    pub fn reset_with_seed(&mut self, seed: u64) -> Observation {
+        match &mut self.config.randomizer {
+            RandomizerChoice::Bag(config_seed)
+            | RandomizerChoice::Classic(config_seed)
+            | RandomizerChoice::Weighted(config_seed, _) => {
+                *config_seed = seed;
+            }
+            RandomizerChoice::Sequence(_) | RandomizerChoice::Scripted(..) => {}
+        }
+        return self.reset();
+    }
+
+    // Plays one frame. Once done, steps change nothing and reward nothing until the next reset.
+    // Pause and Resume play the frame without an action, a paused episode would never end
+    pub fn step(&mut self, action: Action) -> (Observation, f64, bool) {
+        if self.is_done() {
+            return (self.observe(), 0.0, true);
+        }
+        let lines = self.game.get_lines_completed();
+        let score = self.game.get_score();
+        let pieces = self.game.get_pieces_placed();
+        if !action.is_pause_control() {
+            self.game.queue_action(action);
+        }
+        let events = self.game.step_frame();
+        let before = self.metrics.clone();
+        self.refresh_metrics();
+        let done = self.is_done();
+        let transition = Transition {
+            events: &events,
+            lines_cleared: self.game.get_lines_completed() - lines,
+            score_gained: self.game.get_score().saturating_sub(score),
+            pieces_placed: self.game.get_pieces_placed() - pieces,
+            before: &before,
+            after: &self.metrics,
+            done,
+            topped_out: self.game.is_game_over(),
+        };
+        let reward = (self.reward)(&transition);
+        return (self.observe(), reward, done);
+    }
+
+    pub fn is_done(&self) -> bool {
+        return self.game.is_game_over() || self.game.is_finished();
+    }
+
+    pub fn game(&self) -> &Game {
+        return &self.game;
+    }
+
+    pub fn observe(&self) -> Observation {
+        let board = self.game.get_board();
+        let hidden_rows = self.config.hidden_rows.min(board.height());
+        let (width, height) = (board.width(), board.height() - hidden_rows);
+        let mut cells = vec![0.0; width * height];
+        for y in 0..height {
+            let mask = board.row_mask((y + hidden_rows) as i32);
+            for (x, cell) in cells[y * width..(y + 1) * width].iter_mut().enumerate() {
+                if mask & 1 << x != 0 {
+                    *cell = 1.0;
+                }
+            }
+        }
+        let mut active_cells = vec![0.0; width * height];
+        let waiting = self.game.is_clearing() || self.game.is_in_entry_delay();
+        let active = match waiting || self.is_done() {
+            true => None,
+            false => Some(self.game.active_figure_type()),
+        };
+        if active.is_some() {
+            for point in self.game.access_active_figure() {
+                if point.x >= 0 && (point.x as usize) < width && (point.y as usize) < height {
+                    active_cells[point.y as usize * width + point.x as usize] = 1.0;
+                }
+            }
+        }
+        let next = self.game.get_next_queue();
+        return Observation {
+            width,
+            height,
+            board: cells,
+            active_cells,
+            active: self.one_hot(active.as_ref()),
+            held: self.one_hot(self.game.get_held_figure().as_ref()),
+            next: next
+                .iter()
+                .flat_map(|figure_type| self.one_hot(Some(figure_type)))
+                .collect(),
+        };
+    }
+
+    fn one_hot(&self, figure_type: Option<&FigureType>) -> Vec<f32> {
+        return self
+            .config
+            .piece_set
+            .iter()
+            .map(|piece| if Some(piece) == figure_type { 1.0 } else { 0.0 })
+            .collect();
+    }
+
+    fn refresh_metrics(&mut self) {
+        let board_hash = self.game.get_board().hash();
+        if board_hash != self.board_hash {
+            self.metrics = self.game.board_metrics();
+            self.board_hash = board_hash;
+        }
+    }
+}
+
+#[cfg(test)]
+mod env_tests {
+    use super::super::Board;
+    use super::*;
+
+    fn env() -> Env {
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![5, 0]))
+            .build();
+        return Env::new(config);
+    }
+
+    #[test]
+    fn test_pausing_steps_like_doing_nothing() {
+        let mut env = env();
+        env.reset();
+        let mut steps = 0;
+        while env.game().get_pieces_placed() == 0 && steps < 10_000 {
+            env.step(Action::Pause);
+            steps += 1;
+        }
+        assert!(!env.game().is_paused());
+        assert!(env.game().get_pieces_placed() > 0);
+    }
+
+    #[test]
+    fn test_observation_layout() {
+        let mut env = env();
+        let observation = env.reset();
+        assert_eq!((observation.width, observation.height), (10, 20));
+        assert!(observation.board.iter().all(|cell| *cell == 0.0));
+        assert_eq!(observation.active, vec![0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+        assert!(observation.held.iter().all(|cell| *cell == 0.0));
+        assert_eq!(
+            observation.next.len(),
+            7 * env.game().get_next_queue().len()
+        );
+        assert_eq!(observation.next[0], 1.0);
+        let (observation, reward, done) = env.step(Action::HardDrop);
+        assert_eq!((reward, done), (0.0, false));
+        assert_eq!(observation.board.iter().sum::<f32>(), 4.0);
+        assert_eq!(observation.to_vec().len(), observation.len());
+    }
+
+    #[test]
+    fn test_active_cells_follow_the_figure() {
+        let mut env = env();
+        let observation = env.reset();
+        let (moved, _, _) = env.step(Action::MoveLeftWall);
+        assert_eq!(moved.active_cells.iter().sum::<f32>(), 4.0);
+        assert_ne!(moved.active_cells, observation.active_cells);
+        // The T is against the left wall now
+        let column: f32 = (0..20).map(|y| moved.active_cells[y * 10]).sum();
+        assert!(column > 0.0);
+    }
+
+    #[test]
+    fn test_reward_function_and_reset() {
+        let config = GameConfig::builder().seed(3).build();
+        let reward: RewardFn = Box::new(|transition| {
+            return transition.pieces_placed as f64 - transition.after.holes as f64;
+        });
+        let mut env = Env::with_reward(config, reward);
+        assert_eq!(env.step(Action::MoveLeft).1, 0.0);
+        let (_, reward, _) = env.step(Action::HardDrop);
+        assert_eq!(reward, 1.0 - env.game().board_metrics().holes as f64);
+        // Pieces dropped in the middle top out
+        let mut done = false;
+        while !done {
+            done = env.step(Action::HardDrop).2;
+        }
+        assert_eq!(env.step(Action::HardDrop), (env.observe(), 0.0, true));
+        let first = env.reset();
+        assert!(!env.is_done());
+        assert!(first.board.iter().all(|cell| *cell == 0.0));
+        let reseeded = env.reset_with_seed(4);
+        assert_eq!(env.reset_with_seed(4), reseeded);
+    }
+
+    #[test]
+    fn test_reward_shaping() {
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![3]))
+            .build();
+        let shaping = RewardShaping::new()
+            .term(RewardTerm::LinesCleared, 10.0)
+            .term(RewardTerm::HeightDelta, -0.5)
+            .term(RewardTerm::Survival, 0.1)
+            .term(RewardTerm::GameOver, -100.0);
+        let mut env = Env::with_shaping(config, shaping.clone());
+        assert_eq!(env.step(Action::MoveLeft).1, 0.1);
+        // The O adds two cells to two columns
+        let (_, reward, _) = env.step(Action::HardDrop);
+        assert!((reward - (0.1 - 2.0)).abs() < 1e-9);
+        let mut last = 0.0;
+        while !env.is_done() {
+            last = env.step(Action::HardDrop).1;
+        }
+        assert!(last < -100.0);
+        let before = Metrics::of(&Board::from_rows(vec![vec![None, Some(FigureType::I)]]));
+        let after = Metrics::of(&Board::from_rows(vec![
+            vec![Some(FigureType::I), None],
+            vec![None, Some(FigureType::I)],
+        ]));
+        let transition = Transition {
+            events: &[],
+            lines_cleared: 1,
+            score_gained: 100,
+            pieces_placed: 1,
+            before: &before,
+            after: &after,
+            done: false,
+            topped_out: false,
+        };
+        assert_eq!(RewardTerm::HolesDelta.value(&transition), 1.0);
+        assert_eq!(RewardTerm::HeightDelta.value(&transition), 2.0);
+        assert_eq!(shaping.reward(&transition), 10.0 - 1.0 + 0.1);
+    }
+}
@@ -0,0 +1,44 @@
+use super::finesse::FinesseMove;
+use super::items::Item;
+use super::modes::ModeResult;
+use super::{ClearKind, FigureType, PiecePose};
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameEvent {
+    // Where the piece locked, counted from the first visible row like `Game::active_pose`
+    PieceLocked(PiecePose),
+    LinesCleared(Vec<usize>),
+    // A chain reaction clear after sticky or cascade gravity, with the combo it reached
+    Combo(usize),
+    PieceHeld(FigureType),
+    LevelUp(usize),
+    TSpin(ClearKind),
+    GarbageReceived(usize),
+    // Lines of queued garbage cancelled by an attack, before it was sent
+    GarbageCancelled(usize),
+    Attack(usize),
+    GameOver,
+    DigRaceCompleted,
+    ModeFinished(ModeResult),
+    // More moves and rotations than needed were pressed for the locked figure
+    FinesseFault { used: usize, optimal: Vec<FinesseMove> },
+    // Assists: holding would bring this figure, which fits better than the active one
+    HoldSuggested(FigureType),
+    // Assists: the bottom rows were removed instead of ending the game
+    TopOutAvoided(usize),
+    // The special mechanic started, for this many seconds
+    SpecialActivated(f64),
+    // The special mechanic ended, the lines it stacked cleared for these points
+    SpecialEnded { lines: usize, score: u64 },
+    // A clear earned an item, hand it to `Game::apply_item` of this game or another one
+    ItemEarned(Item),
+    ItemApplied(Item),
+    // Index of an objective of `objectives::Objectives` that was completed or failed
+    ObjectiveCompleted(usize),
+    ObjectiveFailed(usize),
+    // The stack reached `GameConfig::danger_height`, or went back under it
+    DangerEntered,
+    DangerLeft,
+}
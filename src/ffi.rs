@@ -0,0 +1,157 @@
+// C API, include/tetris_core.h declares these functions (regenerate it with cbindgen)
+use super::bindings::cell_codes_into;
+use super::{Action, Game, GameConfig, Size};
+use alloc::boxed::Box;
+
+// Creates a game seeded with `seed`, free it with `tetris_game_free`
+#[no_mangle]
+pub extern "C" fn tetris_game_new(width: u32, height: u32, seed: u64) -> *mut Game {
+    let config = GameConfig::builder()
+        .size(Size {
+            height: height as usize,
+            width: width as usize,
+        })
+        .seed(seed)
+        .build();
+    return Box::into_raw(Box::new(Game::with_config(config)));
+}
+
+/// # Safety
+/// `game` must come from `tetris_game_new` and not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn tetris_game_free(game: *mut Game) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// # Safety
+/// `game` must be a live pointer returned by `tetris_game_new`.
+#[no_mangle]
+pub unsafe extern "C" fn tetris_game_update(game: *mut Game, delta_time: f64) {
+    if let Some(game) = game.as_mut() {
+        game.update(delta_time);
+    }
+}
+
+// Uses the `Action::code` values, returns false for unknown codes
+/// # Safety
+/// `game` must be a live pointer returned by `tetris_game_new`.
+#[no_mangle]
+pub unsafe extern "C" fn tetris_game_perform(game: *mut Game, action_code: u8) -> bool {
+    let action = Action::from_code(action_code);
+    return match (game.as_mut(), action) {
+        (Some(game), Some(action)) => {
+            game.perform(action);
+            true
+        }
+        _ => false,
+    };
+}
+
+// Writes width * height `FigureType::code` values, row by row, active figure included.
+// Returns the number of cells of the board, nothing is written when `length` is smaller.
+/// # Safety
+/// `game` must be a live pointer returned by `tetris_game_new` and `buffer` must point to
+/// `length` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn tetris_game_draw_into_buffer(
+    game: *const Game,
+    buffer: *mut u8,
+    length: usize,
+) -> usize {
+    let game = match game.as_ref() {
+        Some(game) => game,
+        None => return 0,
+    };
+    let size = game.get_visible_size();
+    let cells = size.width * size.height;
+    if !buffer.is_null() && length >= cells {
+        cell_codes_into(game, core::slice::from_raw_parts_mut(buffer, length));
+    }
+    return cells;
+}
+
+/// # Safety
+/// `game` must be a live pointer returned by `tetris_game_new`.
+#[no_mangle]
+pub unsafe extern "C" fn tetris_game_width(game: *const Game) -> u32 {
+    return game.as_ref().map_or(0, |game| game.get_visible_size().width as u32);
+}
+
+/// # Safety
+/// `game` must be a live pointer returned by `tetris_game_new`.
+#[no_mangle]
+pub unsafe extern "C" fn tetris_game_height(game: *const Game) -> u32 {
+    return game.as_ref().map_or(0, |game| game.get_visible_size().height as u32);
+}
+
+/// # Safety
+/// `game` must be a live pointer returned by `tetris_game_new`.
+#[no_mangle]
+pub unsafe extern "C" fn tetris_game_score(game: *const Game) -> u64 {
+    return game.as_ref().map_or(0, |game| game.get_score());
+}
+
+/// # Safety
+/// `game` must be a live pointer returned by `tetris_game_new`.
+#[no_mangle]
+pub unsafe extern "C" fn tetris_game_lines(game: *const Game) -> u32 {
+    return game.as_ref().map_or(0, |game| game.get_lines_completed() as u32);
+}
+
+/// # Safety
+/// `game` must be a live pointer returned by `tetris_game_new`.
+#[no_mangle]
+pub unsafe extern "C" fn tetris_game_level(game: *const Game) -> u32 {
+    return game.as_ref().map_or(0, |game| game.get_level() as u32);
+}
+
+/// # Safety
+/// `game` must be a live pointer returned by `tetris_game_new`.
+#[no_mangle]
+pub unsafe extern "C" fn tetris_game_is_game_over(game: *const Game) -> bool {
+    return game.as_ref().is_none_or(|game| game.is_game_over());
+}
+
+#[cfg(test)]
+mod ffi_tests {
+    use super::*;
+    use alloc::vec;
+    use core::ptr;
+
+    #[test]
+    fn test_game_through_the_c_api() {
+        unsafe {
+            let game = tetris_game_new(10, 20, 3);
+            assert_eq!(tetris_game_width(game), 10);
+            assert_eq!(tetris_game_height(game), 20);
+            let mut small = [0u8; 4];
+            assert_eq!(
+                tetris_game_draw_into_buffer(game, small.as_mut_ptr(), small.len()),
+                200
+            );
+            assert_eq!(small, [0; 4]);
+            let mut buffer = vec![0u8; 200];
+            tetris_game_draw_into_buffer(game, buffer.as_mut_ptr(), buffer.len());
+            assert_eq!(buffer.iter().filter(|cell| **cell != 0).count(), 4);
+            assert!(tetris_game_perform(game, Action::HardDrop.code()));
+            assert!(!tetris_game_perform(game, 255));
+            tetris_game_update(game, 0.1);
+            tetris_game_draw_into_buffer(game, buffer.as_mut_ptr(), buffer.len());
+            assert_eq!(buffer.iter().filter(|cell| **cell != 0).count(), 8);
+            assert!(tetris_game_score(game) > 0);
+            assert!(!tetris_game_is_game_over(game));
+            tetris_game_free(game);
+        }
+    }
+
+    #[test]
+    fn test_null_game() {
+        unsafe {
+            assert!(!tetris_game_perform(ptr::null_mut(), 0));
+            assert_eq!(tetris_game_draw_into_buffer(ptr::null(), ptr::null_mut(), 0), 0);
+            tetris_game_free(ptr::null_mut());
+        }
+    }
+}
@@ -1,9 +1,11 @@
 use super::figure_type::FigureType;
 use super::matrix::Matrix;
 use super::geometry::Point;
-use super::graphics::Color;
+use super::graphics::PieceColor;
+use alloc::vec::Vec;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Figure {
     figure_type: FigureType,
     matrix: Matrix<u8>,
@@ -22,7 +24,7 @@ impl Figure {
         return self.figure_type.clone();
     }
 
-    pub fn color(&self) -> Color {
+    pub fn color(&self) -> PieceColor {
         return self.figure_type.color();
     }
 
@@ -37,21 +39,33 @@ impl Figure {
         };
     }
 
+    pub fn rotated_counter_clockwise(&self) -> Self {
+        return Figure {
+            matrix: self.matrix.rotated_counter_clockwise(),
+            figure_type: self.figure_type.clone(),
+        };
+    }
+
+    // Custom figures with symmetric shapes have fewer than 4 distinct orientations
+    pub fn orientation_count(&self) -> usize {
+        return self.matrix.orientation_count();
+    }
+
     pub fn to_cartesian(&self) -> Vec<Point> {
-        let mut points = vec![];
-        for y in 0..self.matrix.height() {
-            for x in 0..self.matrix.width() {
-                if let Some(element) = self.matrix.at_xy(x, y) {
-                    if *element == 1 {
-                        points.push(Point {
-                            x: x as i32,
-                            y: y as i32,
-                        });
-                    }
-                }
-            }
-        }
-        return points;
+        return self.cells().collect();
+    }
+
+    // Same points as `to_cartesian` without collecting them
+    pub fn cells(&self) -> impl Iterator<Item = Point> + '_ {
+        let (height, width) = (self.matrix.height(), self.matrix.width());
+        return (0..height).flat_map(move |y| {
+            (0..width)
+                .filter(move |x| self.matrix.at_xy(*x, y) == Some(&1))
+                .map(move |x| Point {
+                    x: x as i32,
+                    y: y as i32,
+                })
+        });
     }
 }
 
@@ -205,6 +219,16 @@ mod figure_tests {
         assert_eq!(full_loop_rotation.matrix, how_it_should_always_look_like);
     }
     #[test]
+    fn test_counter_clockwise_rotation_reverts_rotation() {
+        let figures = vec![FigureType::I, FigureType::J, FigureType::S, FigureType::T];
+        for figure_type in figures {
+            let figure = Figure::new(figure_type);
+            assert_eq!(figure.rotated().rotated_counter_clockwise(), figure);
+            let counter_clockwise = figure.rotated_counter_clockwise();
+            assert_eq!(counter_clockwise, figure.rotated().rotated().rotated());
+        }
+    }
+    #[test]
     fn test_draw() {
         let figure = Figure::new(FigureType::T);
         let drawed = figure.to_cartesian();
@@ -0,0 +1,90 @@
+use super::geometry::Point;
+use super::graphics::PieceColor;
+use super::matrix::Matrix;
+use super::rotation_system::KickTable;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+// A user defined piece, used through `FigureType::Custom`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FigureDef {
+    pub name: String,
+    pub matrix: Matrix<u8>,
+    pub color: PieceColor,
+    pub kicks: KickTable,
+    // Moves the spawn position from the one of the standard pieces
+    pub spawn_offset: Point,
+}
+
+impl FigureDef {
+    // Rotations need a square matrix, so the shape is padded with empty cells
+    pub fn new(name: &str, shape: Vec<Vec<u8>>, color: PieceColor) -> FigureDef {
+        let side = shape
+            .iter()
+            .map(|row| row.len())
+            .chain(core::iter::once(shape.len()))
+            .max()
+            .unwrap_or(0);
+        let mut data = shape;
+        data.resize(side, vec![]);
+        for row in data.iter_mut() {
+            row.resize(side, 0);
+        }
+        return FigureDef {
+            name: name.to_string(),
+            matrix: Matrix::new(data),
+            color,
+            kicks: KickTable::JLSTZ,
+            spawn_offset: Point { x: 0, y: 0 },
+        };
+    }
+
+    pub fn with_kicks(mut self, kicks: KickTable) -> FigureDef {
+        self.kicks = kicks;
+        return self;
+    }
+
+    pub fn with_spawn_offset(mut self, spawn_offset: Point) -> FigureDef {
+        self.spawn_offset = spawn_offset;
+        return self;
+    }
+}
+
+#[cfg(test)]
+mod figure_def_tests {
+    use super::*;
+
+    fn color() -> PieceColor {
+        return PieceColor::rgb(255, 255, 255);
+    }
+
+    #[test]
+    fn test_shape_is_padded_to_a_square() {
+        let def = FigureDef::new("domino", vec![vec![1, 1]], color());
+        assert_eq!(def.matrix, Matrix::new(vec![vec![1, 1], vec![0, 0]]));
+        let def = FigureDef::new("tall", vec![vec![1], vec![1], vec![1]], color());
+        assert_eq!(
+            def.matrix,
+            Matrix::new(vec![vec![1, 0, 0], vec![1, 0, 0], vec![1, 0, 0]])
+        );
+    }
+
+    #[test]
+    fn test_pentomino_rotates_around_its_matrix() {
+        let shape = vec![
+            vec![0, 1, 1, 0, 0],
+            vec![1, 1, 0, 0, 0],
+            vec![0, 1, 0, 0, 0],
+        ];
+        let def = FigureDef::new("F", shape, color());
+        let rotated = def.matrix.rotated();
+        assert_eq!(rotated.width(), 5);
+        assert_eq!(rotated.row_at(0), Some(&vec![0, 0, 0, 1, 0]));
+        assert_eq!(rotated.row_at(1), Some(&vec![0, 0, 1, 1, 1]));
+        assert_eq!(rotated.row_at(2), Some(&vec![0, 0, 0, 0, 1]));
+        assert_eq!(rotated.rotated().rotated().rotated(), def.matrix);
+        assert_eq!(def.matrix.orientation_count(), 4);
+    }
+}
@@ -1,229 +1,222 @@
-// Based on https://tetris.fandom.com/wiki/SRS
-
-use super::matrix::Matrix;
-use super::geometry::Point;
-use super::graphics::Color;
-
-const I_COLOR: Color = Color {
-    red: 108.0 / 255.0,
-    green: 237.0 / 255.0,
-    blue: 238.0 / 255.0,
-    alpha: 1.0,
-    name: "I",
-};
-
-const J_COLOR: Color = Color {
-    red: 0.0,
-    green: 33.0 / 255.0,
-    blue: 230.0 / 255.0,
-    alpha: 1.0,
-    name: "J",
-};
-
-const L_COLOR: Color = Color {
-    red: 229.0 / 255.0,
-    green: 162.0 / 255.0,
-    blue: 67.0 / 255.0,
-    alpha: 1.0,
-    name: "L",
-};
-
-const O_COLOR: Color = Color {
-    red: 241.0 / 255.0,
-    green: 238.0 / 255.0,
-    blue: 79.0 / 255.0,
-    alpha: 1.0,
-    name: "O",
-};
-
-const Z_COLOR: Color = Color {
-    red: 110.0 / 255.0,
-    green: 235.0 / 255.0,
-    blue: 71.0 / 255.0,
-    alpha: 1.0,
-    name: "Z",
-};
-
-const T_COLOR: Color = Color {
-    red: 146.0 / 255.0,
-    green: 45.0 / 255.0,
-    blue: 231.0 / 255.0,
-    alpha: 1.0,
-    name: "T",
-};
-
-const S_COLOR: Color = Color {
-    red: 221.0 / 255.0,
-    green: 47.0 / 255.0,
-    blue: 23.0 / 255.0,
-    alpha: 1.0,
-    name: "S",
-};
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum FigureType {
-    I,
-    T,
-    L,
-    J,
-    O,
-    Z,
-    S,
-}
-
-impl FigureType {
-    pub fn color(&self) -> Color {
-        return match self {
-            FigureType::I => I_COLOR,
-            FigureType::J => J_COLOR,
-            FigureType::L => L_COLOR,
-            FigureType::O => O_COLOR,
-            FigureType::S => S_COLOR,
-            FigureType::T => T_COLOR,
-            FigureType::Z => Z_COLOR,
-        };
-    }
-
-    pub fn initial_matrix(&self) -> Matrix<u8> {
-        let vectors = match self {
-            FigureType::I => self.draw_i(),
-            FigureType::J => self.draw_j(),
-            FigureType::L => self.draw_l(),
-            FigureType::O => self.draw_o(),
-            FigureType::S => self.draw_s(),
-            FigureType::T => self.draw_t(),
-            FigureType::Z => self.draw_z(),
-        };
-        return Matrix::new(vectors);
-    }
-
-    pub fn wall_kick(&self) -> Vec<Vec<Point>> {
-        return match self {
-            FigureType::O => vec![vec![]],
-            FigureType::I => FigureType::wall_kick_i(),
-            _ => FigureType::wall_kick_default(),
-        };
-    }
-
-    fn draw_i(&self) -> Vec<Vec<u8>> {
-        return vec![
-            vec![0, 0, 0, 0], //
-            vec![1, 1, 1, 1],
-            vec![0, 0, 0, 0],
-            vec![0, 0, 0, 0],
-        ];
-    }
-
-    fn draw_j(&self) -> Vec<Vec<u8>> {
-        return vec![
-            vec![1, 0, 0], //
-            vec![1, 1, 1],
-            vec![0, 0, 0],
-        ];
-    }
-
-    fn draw_l(&self) -> Vec<Vec<u8>> {
-        return vec![
-            vec![0, 0, 1], //
-            vec![1, 1, 1],
-            vec![0, 0, 0],
-        ];
-    }
-
-    fn draw_o(&self) -> Vec<Vec<u8>> {
-        return vec![
-            vec![1, 1], //
-            vec![1, 1],
-        ];
-    }
-
-    fn draw_s(&self) -> Vec<Vec<u8>> {
-        return vec![
-            vec![0, 1, 1], //
-            vec![1, 1, 0],
-            vec![0, 0, 0],
-        ];
-    }
-
-    fn draw_t(&self) -> Vec<Vec<u8>> {
-        return vec![
-            vec![0, 1, 0], //
-            vec![1, 1, 1],
-            vec![0, 0, 0],
-        ];
-    }
-
-    fn draw_z(&self) -> Vec<Vec<u8>> {
-        return vec![
-            vec![1, 1, 0], //
-            vec![0, 1, 1],
-            vec![0, 0, 0],
-        ];
-    }
-
-    fn wall_kick_default() -> Vec<Vec<Point>> {
-        return vec![
-            vec![
-                Point { x: 0, y: 0 },
-                Point { x: -1, y: 0 },
-                Point { x: -1, y: 1 },
-                Point { x: 0, y: -2 },
-                Point { x: -1, y: -2 },
-            ],
-            vec![
-                Point { x: 0, y: 0 },
-                Point { x: 1, y: 0 },
-                Point { x: -1, y: 1 },
-                Point { x: 0, y: 2 },
-                Point { x: 1, y: 2 },
-            ],
-            vec![
-                Point { x: 0, y: 0 },
-                Point { x: 1, y: 0 },
-                Point { x: 1, y: 1 },
-                Point { x: 0, y: -2 },
-                Point { x: 1, y: -2 },
-            ],
-            vec![
-                Point { x: 0, y: 0 },
-                Point { x: -1, y: 0 },
-                Point { x: -1, y: -1 },
-                Point { x: 0, y: 2 },
-                Point { x: -1, y: 2 },
-            ],
-        ];
-    }
-
-    fn wall_kick_i() -> Vec<Vec<Point>> {
-        return vec![
-            vec![
-                Point { x: 0, y: 0 },
-                Point { x: -2, y: 0 },
-                Point { x: 1, y: 0 },
-                Point { x: -2, y: -1 },
-                Point { x: 1, y: 2 },
-            ],
-            vec![
-                Point { x: 0, y: 0 },
-                Point { x: -1, y: 0 },
-                Point { x: 2, y: 0 },
-                Point { x: -1, y: 2 },
-                Point { x: 2, y: -1 },
-            ],
-            vec![
-                Point { x: 0, y: 0 },
-                Point { x: 2, y: 0 },
-                Point { x: -1, y: 0 },
-                Point { x: 2, y: 1 },
-                Point { x: -1, y: -2 },
-            ],
-            vec![
-                Point { x: 0, y: 0 },
-                Point { x: 1, y: 0 },
-                Point { x: -2, y: 0 },
-                Point { x: 1, y: -2 },
-                Point { x: -2, y: 1 },
-            ],
-        ];
-    }
-}
+// Based on https://tetris.fandom.com/wiki/SRS
+
+use super::figure_def::FigureDef;
+use super::matrix::Matrix;
+use super::geometry::{Point, Rect, Size};
+use super::graphics::PieceColor;
+use super::rotation_system::RotationSystem;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FigureType {
+    I,
+    T,
+    L,
+    J,
+    O,
+    Z,
+    S,
+    Garbage,
+    Custom(Arc<FigureDef>),
+}
+
+// Custom figures hash by name and shape, their colors and kicks are left out
+impl Hash for FigureType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.code().hash(state);
+        if let FigureType::Custom(def) = self {
+            def.name.hash(state);
+            def.matrix.hash(state);
+        }
+    }
+}
+
+impl FigureType {
+    pub fn color(&self) -> PieceColor {
+        return match self {
+            FigureType::I => PieceColor::I,
+            FigureType::J => PieceColor::J,
+            FigureType::L => PieceColor::L,
+            FigureType::O => PieceColor::O,
+            FigureType::S => PieceColor::S,
+            FigureType::T => PieceColor::T,
+            FigureType::Z => PieceColor::Z,
+            FigureType::Garbage => PieceColor::Garbage,
+            FigureType::Custom(def) => def.color,
+        };
+    }
+
+    pub fn custom(def: FigureDef) -> FigureType {
+        return FigureType::Custom(Arc::new(def));
+    }
+
+    // The seven tetrominoes, in the order used to map randomizer values
+    pub fn standard_set() -> Vec<FigureType> {
+        return vec![
+            FigureType::I,
+            FigureType::J,
+            FigureType::L,
+            FigureType::O,
+            FigureType::S,
+            FigureType::T,
+            FigureType::Z,
+        ];
+    }
+
+    // Stable cell codes for bindings, 0 is left for empty cells
+    pub fn code(&self) -> u8 {
+        return match self {
+            FigureType::I => 1,
+            FigureType::J => 2,
+            FigureType::L => 3,
+            FigureType::O => 4,
+            FigureType::S => 5,
+            FigureType::T => 6,
+            FigureType::Z => 7,
+            FigureType::Garbage => 8,
+            FigureType::Custom(_) => 9,
+        };
+    }
+
+    // Character used by the text renderings, custom figures use the first letter of their name
+    pub fn symbol(&self) -> char {
+        return match self {
+            FigureType::I => 'I',
+            FigureType::J => 'J',
+            FigureType::L => 'L',
+            FigureType::O => 'O',
+            FigureType::S => 'S',
+            FigureType::T => 'T',
+            FigureType::Z => 'Z',
+            FigureType::Garbage => 'G',
+            FigureType::Custom(def) => def.name.chars().next().unwrap_or('?'),
+        };
+    }
+
+    // Moves the figure from the centered spawn position
+    pub fn spawn_offset(&self) -> Point {
+        return match self {
+            // The O has always spawned left of center, in the columns of the J, L and T
+            FigureType::O => Point { x: -1, y: 0 },
+            FigureType::Custom(def) => def.spawn_offset,
+            _ => Point { x: 0, y: 0 },
+        };
+    }
+
+    // Cells of the spawn orientation inside its matrix
+    pub fn bounding_box(&self) -> Rect {
+        let matrix = self.initial_matrix();
+        let (mut left, mut top) = (matrix.width(), matrix.height());
+        let (mut right, mut bottom) = (0, 0);
+        for y in 0..matrix.height() {
+            for x in 0..matrix.width() {
+                if matrix.at_xy(x, y).is_some_and(|cell| *cell != 0) {
+                    left = left.min(x);
+                    top = top.min(y);
+                    right = right.max(x + 1);
+                    bottom = bottom.max(y + 1);
+                }
+            }
+        }
+        if right == 0 {
+            return Rect {
+                origin: Point { x: 0, y: 0 },
+                size: Size {
+                    height: 0,
+                    width: 0,
+                },
+            };
+        }
+        return Rect {
+            origin: Point {
+                x: left as i32,
+                y: top as i32,
+            },
+            size: Size {
+                height: bottom - top,
+                width: right - left,
+            },
+        };
+    }
+
+    pub fn initial_matrix(&self) -> Matrix<u8> {
+        let vectors = match self {
+            FigureType::I => self.draw_i(),
+            FigureType::J => self.draw_j(),
+            FigureType::L => self.draw_l(),
+            FigureType::O => self.draw_o(),
+            FigureType::S => self.draw_s(),
+            FigureType::T => self.draw_t(),
+            FigureType::Z => self.draw_z(),
+            FigureType::Garbage => vec![vec![1]],
+            FigureType::Custom(def) => return def.matrix.clone(),
+        };
+        return Matrix::new(vectors);
+    }
+
+    pub fn wall_kick(&self) -> Vec<Vec<Point>> {
+        return (0..4)
+            .map(|step| RotationSystem::SRS.kicks(self, step, step + 1))
+            .collect();
+    }
+
+    fn draw_i(&self) -> Vec<Vec<u8>> {
+        return vec![
+            vec![0, 0, 0, 0], //
+            vec![1, 1, 1, 1],
+            vec![0, 0, 0, 0],
+            vec![0, 0, 0, 0],
+        ];
+    }
+
+    fn draw_j(&self) -> Vec<Vec<u8>> {
+        return vec![
+            vec![1, 0, 0], //
+            vec![1, 1, 1],
+            vec![0, 0, 0],
+        ];
+    }
+
+    fn draw_l(&self) -> Vec<Vec<u8>> {
+        return vec![
+            vec![0, 0, 1], //
+            vec![1, 1, 1],
+            vec![0, 0, 0],
+        ];
+    }
+
+    fn draw_o(&self) -> Vec<Vec<u8>> {
+        return vec![
+            vec![1, 1], //
+            vec![1, 1],
+        ];
+    }
+
+    fn draw_s(&self) -> Vec<Vec<u8>> {
+        return vec![
+            vec![0, 1, 1], //
+            vec![1, 1, 0],
+            vec![0, 0, 0],
+        ];
+    }
+
+    fn draw_t(&self) -> Vec<Vec<u8>> {
+        return vec![
+            vec![0, 1, 0], //
+            vec![1, 1, 1],
+            vec![0, 0, 0],
+        ];
+    }
+
+    fn draw_z(&self) -> Vec<Vec<u8>> {
+        return vec![
+            vec![1, 1, 0], //
+            vec![0, 1, 1],
+            vec![0, 0, 0],
+        ];
+    }
+}
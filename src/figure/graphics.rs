@@ -1,8 +1,8 @@
-#[derive(Debug, Clone, PartialEq)]
-pub struct Color {
-    pub red: f32,
-    pub green: f32,
-    pub blue: f32,
-    pub alpha: f32,
-    pub name: &'static str, //char,
-}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Color {
+    pub red: f32,
+    pub green: f32,
+    pub blue: f32,
+    pub alpha: f32,
+    pub name: &'static str, //char,
+}
@@ -1,29 +1,96 @@
-#[derive(Debug, Clone, PartialEq)]
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Matrix<T> {
     data: Vec<Vec<T>>,
 }
 
+// `clone_from` keeps the rows allocated, for searches copying boards over and over
+impl<T: Clone> Clone for Matrix<T> {
+    fn clone(&self) -> Matrix<T> {
+        return Matrix {
+            data: self.data.clone(),
+        };
+    }
+
+    fn clone_from(&mut self, source: &Matrix<T>) {
+        self.data.clone_from(&source.data);
+    }
+}
+
 impl<T> Matrix<T> {
     pub fn new(data: Vec<Vec<T>>) -> Matrix<T> {
         return Matrix { data };
     }
 
+    // Rows become columns, works for any width and height
+    pub fn transposed(&self) -> Matrix<T>
+    where
+        T: Clone,
+    {
+        let data = (0..self.width())
+            .map(|x| self.data.iter().map(|row| row[x].clone()).collect())
+            .collect();
+        return Matrix { data };
+    }
+
+    // Mirrors every row, left becomes right
+    pub fn reflected_horizontally(&self) -> Matrix<T>
+    where
+        T: Clone,
+    {
+        let data = self
+            .data
+            .iter()
+            .map(|row| row.iter().rev().cloned().collect())
+            .collect();
+        return Matrix { data };
+    }
+
+    // Flips the row order, top becomes bottom
+    pub fn reflected_vertically(&self) -> Matrix<T>
+    where
+        T: Clone,
+    {
+        let data = self.data.iter().rev().cloned().collect();
+        return Matrix { data };
+    }
+
     pub fn rotated(&self) -> Matrix<T>
     where
         T: Clone,
     {
-        let mut data = vec![];
-        let matrix_length = self.data.len();
-        for i in 0..matrix_length {
-            let mut vec = vec![];
-            for j in 0..matrix_length {
-                let y_position = (matrix_length - 1) - j;
-                let element = self.data[y_position][i].clone();
-                vec.push(element);
-            }
-            data.push(vec);
+        return self.transposed().reflected_horizontally();
+    }
+
+    pub fn rotated_counter_clockwise(&self) -> Matrix<T>
+    where
+        T: Clone,
+    {
+        return self.transposed().reflected_vertically();
+    }
+
+    pub fn rotated_180(&self) -> Matrix<T>
+    where
+        T: Clone,
+    {
+        return self.reflected_horizontally().reflected_vertically();
+    }
+
+    // Distinct orientations the matrix goes through when rotated: 1, 2 or 4
+    pub fn orientation_count(&self) -> usize
+    where
+        T: Clone + PartialEq,
+    {
+        let mut rotated = self.rotated();
+        let mut count = 1;
+        while rotated != *self && count < 4 {
+            rotated = rotated.rotated();
+            count += 1;
         }
-        return Matrix { data };
+        return count;
     }
 
     pub fn height(&self) -> usize {
@@ -63,6 +130,25 @@ impl<T> Matrix<T> {
         return Matrix { data };
     }
 
+    // Out of bounds positions are ignored
+    pub fn set_at_xy(&mut self, x: usize, y: usize, element: T) {
+        if x >= self.width() || y >= self.height() {
+            return;
+        }
+        self.data[y][x] = element;
+    }
+
+    pub fn remove_row(&mut self, line: usize) -> Option<Vec<T>> {
+        if line >= self.data.len() {
+            return None;
+        }
+        return Some(self.data.remove(line));
+    }
+
+    pub fn insert_row(&mut self, line: usize, row: Vec<T>) {
+        self.data.insert(line.min(self.data.len()), row);
+    }
+
     pub fn row_at(&self, line: usize) -> Option<&Vec<T>> {
         if line >= self.data.len() {
             return None;
@@ -71,3 +157,50 @@ impl<T> Matrix<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod matrix_tests {
+    use super::*;
+
+    #[test]
+    fn test_rotations_of_a_rectangle() {
+        let matrix = Matrix::new(vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        let rotated = matrix.rotated();
+        assert_eq!(
+            rotated,
+            Matrix::new(vec![vec![4, 1], vec![5, 2], vec![6, 3]])
+        );
+        assert_eq!(rotated.width(), 2);
+        assert_eq!(rotated.rotated_counter_clockwise(), matrix);
+        let flipped = Matrix::new(vec![vec![6, 5, 4], vec![3, 2, 1]]);
+        assert_eq!(matrix.rotated_180(), flipped);
+        assert_eq!(rotated.rotated(), flipped);
+    }
+
+    #[test]
+    fn test_transposed_and_reflected() {
+        let matrix = Matrix::new(vec![vec![1, 2], vec![3, 4]]);
+        assert_eq!(
+            matrix.transposed(),
+            Matrix::new(vec![vec![1, 3], vec![2, 4]])
+        );
+        assert_eq!(
+            matrix.reflected_horizontally(),
+            Matrix::new(vec![vec![2, 1], vec![4, 3]])
+        );
+        assert_eq!(
+            matrix.reflected_vertically(),
+            Matrix::new(vec![vec![3, 4], vec![1, 2]])
+        );
+    }
+
+    #[test]
+    fn test_orientation_count() {
+        let square = Matrix::new(vec![vec![1, 1], vec![1, 1]]);
+        assert_eq!(square.orientation_count(), 1);
+        let bar = Matrix::new(vec![vec![0, 0, 0], vec![1, 1, 1], vec![0, 0, 0]]);
+        assert_eq!(bar.orientation_count(), 2);
+        let corner = Matrix::new(vec![vec![1, 0], vec![1, 1]]);
+        assert_eq!(corner.orientation_count(), 4);
+    }
+}
@@ -1,9 +1,17 @@
 mod figure;
+mod figure_def;
 mod figure_type;
 mod matrix;
+mod palette;
+mod rotation_system;
+pub mod sets;
 mod utilities;
-pub use utilities::{block, geometry, graphics};
+pub use utilities::{block, cell, geometry, graphics};
 pub use figure::Figure;
+pub use figure_def::FigureDef;
 pub use figure_type::FigureType;
 pub use matrix::Matrix;
+pub use palette::Palette;
+pub use rotation_system::{KickTable, RotationSystem};
 pub use utilities::block::Block;
+pub use utilities::cell::Cell;
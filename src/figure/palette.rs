@@ -0,0 +1,104 @@
+use super::figure_type::FigureType;
+use super::graphics::PieceColor;
+
+// Colors used to draw each figure, custom figures keep the color of their definition
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Palette {
+    pub i: PieceColor,
+    pub j: PieceColor,
+    pub l: PieceColor,
+    pub o: PieceColor,
+    pub s: PieceColor,
+    pub t: PieceColor,
+    pub z: PieceColor,
+    pub garbage: PieceColor,
+    pub ghost: PieceColor,
+}
+
+impl Palette {
+    pub fn color(&self, figure_type: &FigureType) -> PieceColor {
+        return match figure_type {
+            FigureType::I => self.i,
+            FigureType::J => self.j,
+            FigureType::L => self.l,
+            FigureType::O => self.o,
+            FigureType::S => self.s,
+            FigureType::T => self.t,
+            FigureType::Z => self.z,
+            FigureType::Garbage => self.garbage,
+            FigureType::Custom(def) => def.color,
+        };
+    }
+
+    // Replaces the color of one figure, custom figures are left unchanged
+    pub fn with(mut self, figure_type: &FigureType, color: PieceColor) -> Palette {
+        let slot = match figure_type {
+            FigureType::I => &mut self.i,
+            FigureType::J => &mut self.j,
+            FigureType::L => &mut self.l,
+            FigureType::O => &mut self.o,
+            FigureType::S => &mut self.s,
+            FigureType::T => &mut self.t,
+            FigureType::Z => &mut self.z,
+            FigureType::Garbage => &mut self.garbage,
+            FigureType::Custom(_) => return self,
+        };
+        *slot = color;
+        return self;
+    }
+
+    pub fn with_ghost(mut self, color: PieceColor) -> Palette {
+        self.ghost = color;
+        return self;
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        return Palette {
+            i: FigureType::I.color(),
+            j: FigureType::J.color(),
+            l: FigureType::L.color(),
+            o: FigureType::O.color(),
+            s: FigureType::S.color(),
+            t: FigureType::T.color(),
+            z: FigureType::Z.color(),
+            garbage: FigureType::Garbage.color(),
+            ghost: PieceColor::Ghost,
+        };
+    }
+}
+
+#[cfg(test)]
+mod palette_tests {
+    use super::super::FigureDef;
+    use super::*;
+    use alloc::vec;
+
+    fn red() -> PieceColor {
+        return PieceColor::rgb(255, 0, 0);
+    }
+
+    #[test]
+    fn test_default_matches_figure_colors() {
+        let palette = Palette::default();
+        for figure_type in FigureType::standard_set() {
+            assert_eq!(palette.color(&figure_type), figure_type.color());
+        }
+        assert_eq!(
+            palette.color(&FigureType::Garbage),
+            FigureType::Garbage.color()
+        );
+    }
+
+    #[test]
+    fn test_with_replaces_one_color() {
+        let palette = Palette::default().with(&FigureType::T, red());
+        assert_eq!(palette.color(&FigureType::T), red());
+        assert_eq!(palette.color(&FigureType::I), FigureType::I.color());
+        let custom = FigureType::custom(FigureDef::new("A", vec![vec![1]], FigureType::I.color()));
+        let palette = palette.with(&custom, red());
+        assert_eq!(palette.color(&custom), FigureType::I.color());
+    }
+}
@@ -0,0 +1,214 @@
+// Kick tables based on https://tetris.fandom.com/wiki/SRS
+// The wiki uses y pointing up, these tables are flipped to the board's y pointing down.
+
+use super::figure_type::FigureType;
+use super::geometry::Point;
+use alloc::vec;
+use alloc::vec::Vec;
+
+type KickRow = [(i32, i32); 5];
+
+// Rows ordered as 0->R, R->0, R->2, 2->R, 2->L, L->2, L->0, 0->L
+const JLSTZ_KICKS: [KickRow; 8] = [
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+];
+
+const I_KICKS: [KickRow; 8] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)],
+    [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)],
+    [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)],
+    [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)],
+    [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)],
+    [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)],
+    [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)],
+    [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)],
+];
+
+// ARS tries one cell right then one cell left, T and I can also kick up from the floor
+const ARS_KICKS: [(i32, i32); 3] = [(0, 0), (1, 0), (-1, 0)];
+const ARS_T_FLOOR_KICKS: [(i32, i32); 1] = [(0, -1)];
+const ARS_I_KICKS: [(i32, i32); 6] = [(0, 0), (1, 0), (2, 0), (-1, 0), (0, -1), (0, -2)];
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RotationSystem {
+    SRS,
+    Classic,
+    Sega,
+    // NES rotation, no kicks and right handed two state I, S and Z
+    Nintendo,
+    // Arika rotation from TGM, simple wall kicks, floor kicks and two state I, S and Z
+    ARS,
+}
+
+impl RotationSystem {
+    // Offsets to try, in order, when rotating from one rotation step to another
+    pub fn kicks(&self, figure_type: &FigureType, from: usize, to: usize) -> Vec<Point> {
+        return match self {
+            RotationSystem::SRS => RotationSystem::srs_kicks(figure_type, from % 4, to % 4),
+            RotationSystem::Classic | RotationSystem::Sega | RotationSystem::Nintendo => {
+                vec![Point { x: 0, y: 0 }]
+            }
+            RotationSystem::ARS => RotationSystem::ars_kicks(figure_type),
+        };
+    }
+
+    // Figures that only flip between their spawn and right states
+    pub fn is_two_state(&self, figure_type: &FigureType) -> bool {
+        return matches!(self, RotationSystem::Nintendo | RotationSystem::ARS)
+            && matches!(figure_type, FigureType::I | FigureType::S | FigureType::Z);
+    }
+
+    // ARS center column rule: J, L and T don't kick when the first cell blocking the plain
+    // rotation, from the top left of their 3x3 box, is in the middle column
+    pub fn has_center_column_rule(&self, figure_type: &FigureType) -> bool {
+        return *self == RotationSystem::ARS
+            && matches!(figure_type, FigureType::J | FigureType::L | FigureType::T);
+    }
+
+    fn ars_kicks(figure_type: &FigureType) -> Vec<Point> {
+        let kicks = match figure_type {
+            FigureType::O => &ARS_KICKS[..1],
+            FigureType::I => &ARS_I_KICKS[..],
+            _ => &ARS_KICKS[..],
+        };
+        let floor_kicks = match figure_type {
+            FigureType::T => &ARS_T_FLOOR_KICKS[..],
+            _ => &[],
+        };
+        return kicks
+            .iter()
+            .chain(floor_kicks)
+            .map(|&(x, y)| Point { x, y })
+            .collect();
+    }
+
+    fn srs_kicks(figure_type: &FigureType, from: usize, to: usize) -> Vec<Point> {
+        return match figure_type {
+            FigureType::O => KickTable::None.kicks(from, to),
+            FigureType::I => KickTable::I.kicks(from, to),
+            FigureType::Custom(def) => def.kicks.kicks(from, to),
+            _ => KickTable::JLSTZ.kicks(from, to),
+        };
+    }
+}
+
+// SRS wall kicks of a figure, custom tables use the same row order as the built in ones
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KickTable {
+    JLSTZ,
+    I,
+    None,
+    Custom(Vec<Vec<Point>>),
+}
+
+impl KickTable {
+    pub fn kicks(&self, from: usize, to: usize) -> Vec<Point> {
+        let no_kicks = vec![Point { x: 0, y: 0 }];
+        let row = match (from % 4, to % 4) {
+            (0, 1) => 0,
+            (1, 0) => 1,
+            (1, 2) => 2,
+            (2, 1) => 3,
+            (2, 3) => 4,
+            (3, 2) => 5,
+            (3, 0) => 6,
+            (0, 3) => 7,
+            _ => return no_kicks,
+        };
+        let table = match self {
+            KickTable::JLSTZ => &JLSTZ_KICKS,
+            KickTable::I => &I_KICKS,
+            KickTable::None => return no_kicks,
+            KickTable::Custom(rows) => return rows.get(row).cloned().unwrap_or(no_kicks),
+        };
+        return table[row].iter().map(|&(x, y)| Point { x, y }).collect();
+    }
+}
+
+impl Default for RotationSystem {
+    fn default() -> RotationSystem {
+        return RotationSystem::SRS;
+    }
+}
+
+#[cfg(test)]
+mod rotation_system_tests {
+    use super::*;
+
+    #[test]
+    fn test_srs_jlstz_clockwise_from_spawn() {
+        let kicks = RotationSystem::SRS.kicks(&FigureType::T, 0, 1);
+        let expectation = vec![
+            Point { x: 0, y: 0 },
+            Point { x: -1, y: 0 },
+            Point { x: -1, y: -1 },
+            Point { x: 0, y: 2 },
+            Point { x: -1, y: 2 },
+        ];
+        assert_eq!(kicks, expectation);
+    }
+
+    #[test]
+    fn test_custom_figure_kicks() {
+        use super::super::figure_def::FigureDef;
+        let color = FigureType::T.color();
+        let plain = FigureType::custom(FigureDef::new("plus", vec![vec![1]], color));
+        assert_eq!(RotationSystem::SRS.kicks(&plain, 0, 1).len(), 5);
+        let kicks = vec![vec![Point { x: 0, y: 0 }, Point { x: 3, y: 0 }]];
+        let def = FigureDef::new("plus", vec![vec![1]], color).with_kicks(KickTable::Custom(kicks));
+        let custom = FigureType::custom(def);
+        assert_eq!(RotationSystem::SRS.kicks(&custom, 0, 1)[1], Point { x: 3, y: 0 });
+        assert_eq!(RotationSystem::SRS.kicks(&custom, 1, 2), vec![Point { x: 0, y: 0 }]);
+    }
+
+    #[test]
+    fn test_srs_counter_clockwise_reverts_clockwise() {
+        for figure_type in [FigureType::I, FigureType::L] {
+            for from in 0..4 {
+                let to = (from + 1) % 4;
+                let clockwise = RotationSystem::SRS.kicks(&figure_type, from, to);
+                let reverted = RotationSystem::SRS.kicks(&figure_type, to, from);
+                for (kick, back) in clockwise.iter().zip(reverted.iter()) {
+                    assert_eq!(kick.x, -back.x);
+                    assert_eq!(kick.y, -back.y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_o_does_not_kick() {
+        let kicks = RotationSystem::SRS.kicks(&FigureType::O, 0, 1);
+        assert_eq!(kicks, vec![Point { x: 0, y: 0 }]);
+    }
+
+    #[test]
+    fn test_ars_kicks() {
+        let kicks = RotationSystem::ARS.kicks(&FigureType::T, 0, 1);
+        assert_eq!(kicks[1], Point { x: 1, y: 0 });
+        assert_eq!(kicks[2], Point { x: -1, y: 0 });
+        assert_eq!(kicks.last(), Some(&Point { x: 0, y: -1 }));
+        assert_eq!(RotationSystem::ARS.kicks(&FigureType::L, 0, 1).len(), 3);
+        assert_eq!(RotationSystem::ARS.kicks(&FigureType::O, 0, 0).len(), 1);
+        assert!(RotationSystem::ARS.is_two_state(&FigureType::S));
+        assert!(RotationSystem::ARS.has_center_column_rule(&FigureType::J));
+        assert!(!RotationSystem::SRS.has_center_column_rule(&FigureType::J));
+    }
+
+    #[test]
+    fn test_classic_does_not_kick() {
+        let kicks = RotationSystem::Classic.kicks(&FigureType::I, 0, 1);
+        assert_eq!(kicks, vec![Point { x: 0, y: 0 }]);
+    }
+}
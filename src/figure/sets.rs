@@ -0,0 +1,314 @@
+// Ready-made piece sets for `GameConfig::piece_set`, the pieces besides the tetrominoes are custom figures
+
+use super::figure_def::FigureDef;
+use super::figure_type::FigureType;
+use super::geometry::Point;
+use super::graphics::PieceColor;
+use super::rotation_system::KickTable;
+use alloc::vec::Vec;
+
+// A piece described with static data, made into a `FigureDef` when the set is used
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetShape {
+    pub name: &'static str,
+    pub shape: &'static [&'static [u8]],
+    pub color: PieceColor,
+    pub spawn_offset: Point,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetPiece {
+    Standard(FigureType),
+    Shape(SetShape),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FigureSet {
+    pub name: &'static str,
+    pub pieces: &'static [SetPiece],
+}
+
+impl SetShape {
+    // Matrices wider than 3 cells rotate like the I and use its kicks
+    pub fn definition(&self) -> FigureDef {
+        let shape = self.shape.iter().map(|row| row.to_vec()).collect();
+        let def = FigureDef::new(self.name, shape, self.color);
+        let kicks = if def.matrix.width() > 3 {
+            KickTable::I
+        } else {
+            KickTable::JLSTZ
+        };
+        return def.with_kicks(kicks).with_spawn_offset(self.spawn_offset);
+    }
+}
+
+impl SetPiece {
+    pub fn figure_type(&self) -> FigureType {
+        return match self {
+            SetPiece::Standard(figure_type) => figure_type.clone(),
+            SetPiece::Shape(shape) => FigureType::custom(shape.definition()),
+        };
+    }
+}
+
+impl FigureSet {
+    // The figures in randomizer order, ready for `GameConfig::piece_set`
+    pub fn figures(&self) -> Vec<FigureType> {
+        return self.pieces.iter().map(SetPiece::figure_type).collect();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.pieces.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.pieces.is_empty();
+    }
+}
+
+const NO_OFFSET: Point = Point { x: 0, y: 0 };
+
+const fn shape(
+    name: &'static str,
+    shape: &'static [&'static [u8]],
+    color: PieceColor,
+    spawn_offset: Point,
+) -> SetPiece {
+    return SetPiece::Shape(SetShape {
+        name,
+        shape,
+        color,
+        spawn_offset,
+    });
+}
+
+// The seven standard pieces, with their SRS kicks and T-spins
+pub const TETROMINOES: FigureSet = FigureSet {
+    name: "tetrominoes",
+    pieces: &[
+        SetPiece::Standard(FigureType::I),
+        SetPiece::Standard(FigureType::J),
+        SetPiece::Standard(FigureType::L),
+        SetPiece::Standard(FigureType::O),
+        SetPiece::Standard(FigureType::S),
+        SetPiece::Standard(FigureType::T),
+        SetPiece::Standard(FigureType::Z),
+    ],
+};
+
+// The straight and the corner trominoes, they spawn where the I and the O do
+pub const TROMINOES: FigureSet = FigureSet {
+    name: "trominoes",
+    pieces: &[
+        shape(
+            "I3",
+            &[&[0, 0, 0], &[1, 1, 1], &[0, 0, 0]],
+            PieceColor::rgb(108, 237, 238),
+            NO_OFFSET,
+        ),
+        shape(
+            "L3",
+            &[&[1, 0], &[1, 1]],
+            PieceColor::rgb(229, 162, 67),
+            Point { x: -1, y: 0 },
+        ),
+    ],
+};
+
+// The 18 one-sided pentominoes, mirrored pieces are named with a quote
+pub const PENTOMINOES: FigureSet = FigureSet {
+    name: "pentominoes",
+    pieces: &[
+        // The I5 sits in the middle row of its matrix, one row up keeps it at the height of the I
+        shape(
+            "I5",
+            &[&[0, 0, 0, 0, 0], &[0, 0, 0, 0, 0], &[1, 1, 1, 1, 1]],
+            PieceColor::rgb(108, 237, 238),
+            Point { x: 0, y: -1 },
+        ),
+        shape(
+            "F",
+            &[&[0, 1, 1], &[1, 1, 0], &[0, 1, 0]],
+            PieceColor::rgb(221, 47, 23),
+            NO_OFFSET,
+        ),
+        shape(
+            "F'",
+            &[&[1, 1, 0], &[0, 1, 1], &[0, 1, 0]],
+            PieceColor::rgb(110, 235, 71),
+            NO_OFFSET,
+        ),
+        shape(
+            "L5",
+            &[&[0, 0, 0, 1], &[1, 1, 1, 1]],
+            PieceColor::rgb(229, 162, 67),
+            NO_OFFSET,
+        ),
+        shape(
+            "J5",
+            &[&[1, 0, 0, 0], &[1, 1, 1, 1]],
+            PieceColor::rgb(0, 33, 230),
+            NO_OFFSET,
+        ),
+        shape(
+            "N",
+            &[&[1, 1, 0, 0], &[0, 1, 1, 1]],
+            PieceColor::rgb(200, 30, 90),
+            NO_OFFSET,
+        ),
+        shape(
+            "N'",
+            &[&[0, 0, 1, 1], &[1, 1, 1, 0]],
+            PieceColor::rgb(40, 170, 90),
+            NO_OFFSET,
+        ),
+        shape(
+            "P",
+            &[&[1, 1, 1], &[1, 1, 0], &[0, 0, 0]],
+            PieceColor::rgb(241, 238, 79),
+            NO_OFFSET,
+        ),
+        shape(
+            "P'",
+            &[&[1, 1, 1], &[0, 1, 1], &[0, 0, 0]],
+            PieceColor::rgb(250, 200, 120),
+            NO_OFFSET,
+        ),
+        shape(
+            "T5",
+            &[&[1, 1, 1], &[0, 1, 0], &[0, 1, 0]],
+            PieceColor::rgb(146, 45, 231),
+            NO_OFFSET,
+        ),
+        shape(
+            "U",
+            &[&[1, 0, 1], &[1, 1, 1], &[0, 0, 0]],
+            PieceColor::rgb(240, 120, 200),
+            NO_OFFSET,
+        ),
+        shape(
+            "V",
+            &[&[1, 0, 0], &[1, 0, 0], &[1, 1, 1]],
+            PieceColor::rgb(60, 110, 200),
+            NO_OFFSET,
+        ),
+        shape(
+            "W",
+            &[&[1, 0, 0], &[1, 1, 0], &[0, 1, 1]],
+            PieceColor::rgb(150, 90, 40),
+            NO_OFFSET,
+        ),
+        shape(
+            "X",
+            &[&[0, 1, 0], &[1, 1, 1], &[0, 1, 0]],
+            PieceColor::rgb(230, 230, 230),
+            NO_OFFSET,
+        ),
+        shape(
+            "Y",
+            &[&[0, 1, 0, 0], &[1, 1, 1, 1]],
+            PieceColor::rgb(120, 200, 230),
+            NO_OFFSET,
+        ),
+        shape(
+            "Y'",
+            &[&[0, 0, 1, 0], &[1, 1, 1, 1]],
+            PieceColor::rgb(180, 140, 240),
+            NO_OFFSET,
+        ),
+        shape(
+            "Z5",
+            &[&[1, 1, 0], &[0, 1, 0], &[0, 1, 1]],
+            PieceColor::rgb(255, 90, 60),
+            NO_OFFSET,
+        ),
+        shape(
+            "S5",
+            &[&[0, 1, 1], &[0, 1, 0], &[1, 1, 0]],
+            PieceColor::rgb(160, 220, 60),
+            NO_OFFSET,
+        ),
+    ],
+};
+
+#[cfg(test)]
+mod sets_tests {
+    use super::super::super::{Game, GameConfig, RandomizerChoice};
+    use super::*;
+    use alloc::vec;
+
+    fn cell_count(figure_type: &FigureType) -> usize {
+        let matrix = figure_type.initial_matrix();
+        return (0..matrix.height())
+            .flat_map(|y| (0..matrix.width()).map(move |x| (x, y)))
+            .filter(|(x, y)| matrix.at_xy(*x, *y) == Some(&1))
+            .count();
+    }
+
+    #[test]
+    fn test_sets_have_the_right_pieces() {
+        assert_eq!(TETROMINOES.figures(), FigureType::standard_set());
+        assert_eq!(TROMINOES.len(), 2);
+        assert_eq!(PENTOMINOES.len(), 18);
+        for figure_type in PENTOMINOES.figures() {
+            assert_eq!(cell_count(&figure_type), 5);
+        }
+        for figure_type in TROMINOES.figures() {
+            assert_eq!(cell_count(&figure_type), 3);
+        }
+    }
+
+    #[test]
+    fn test_pentominoes_are_all_different() {
+        // No piece matches another one in any orientation
+        let matrices: Vec<_> = PENTOMINOES
+            .figures()
+            .iter()
+            .map(|figure_type| figure_type.initial_matrix())
+            .collect();
+        for (index, matrix) in matrices.iter().enumerate() {
+            let mut orientation = matrix.clone();
+            for _ in 0..4 {
+                for other in &matrices[index + 1..] {
+                    assert!(!same_cells(&orientation, other));
+                }
+                orientation = orientation.rotated();
+            }
+        }
+    }
+
+    // Compares the cells with the empty rows and columns around them trimmed
+    fn same_cells(first: &super::super::Matrix<u8>, second: &super::super::Matrix<u8>) -> bool {
+        let cells = |matrix: &super::super::Matrix<u8>| {
+            let mut cells: Vec<(i32, i32)> = (0..matrix.height())
+                .flat_map(|y| (0..matrix.width()).map(move |x| (x, y)))
+                .filter(|(x, y)| matrix.at_xy(*x, *y) == Some(&1))
+                .map(|(x, y)| (x as i32, y as i32))
+                .collect();
+            let left = cells.iter().map(|cell| cell.0).min().unwrap_or(0);
+            let top = cells.iter().map(|cell| cell.1).min().unwrap_or(0);
+            for cell in cells.iter_mut() {
+                *cell = (cell.0 - left, cell.1 - top);
+            }
+            cells.sort();
+            return cells;
+        };
+        return cells(first) == cells(second);
+    }
+
+    #[test]
+    fn test_pentomino_game_spawns_centered() {
+        let config = GameConfig::builder()
+            .piece_set(PENTOMINOES.figures())
+            .randomizer(RandomizerChoice::Sequence(vec![0]))
+            .build();
+        let game = Game::with_config(config);
+        let mut columns: Vec<i32> = game
+            .access_active_figure()
+            .iter()
+            .map(|point| point.x)
+            .collect();
+        columns.sort();
+        assert_eq!(columns, vec![2, 3, 4, 5, 6]);
+    }
+}
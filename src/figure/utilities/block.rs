@@ -1,14 +1,15 @@
 use super::geometry::{Point, Rect, Size};
-use super::graphics::Color;
+use super::graphics::PieceColor;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Block {
 	pub rect: Rect,
-	pub color: Color,
+	pub color: PieceColor,
 }
 
 impl Block {
-	pub fn new(x: i32, y: i32, height: usize, width: usize, color: Color) -> Block {
+	pub fn new(x: i32, y: i32, height: usize, width: usize, color: PieceColor) -> Block {
 		return Block {
 			rect: Rect {
 				origin: Point { x, y },
@@ -23,6 +24,6 @@ impl Block {
 	}
 
 	pub fn position(&self) -> Point {
-		return self.rect.origin.clone();
+		return self.rect.origin;
 	}
 }
@@ -0,0 +1,67 @@
+use super::super::FigureType;
+use super::block::Block;
+use super::geometry::{Point, Size};
+use super::graphics::PieceColor;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// One square of the field, in cell coordinates, with the figure it belongs to
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cell {
+	pub x: i32,
+	pub y: i32,
+	pub color: PieceColor,
+	pub kind: FigureType,
+}
+
+impl Cell {
+	pub fn new(x: i32, y: i32, color: PieceColor, kind: FigureType) -> Cell {
+		return Cell { x, y, color, kind };
+	}
+
+	pub fn position(&self) -> Point {
+		return Point { x: self.x, y: self.y };
+	}
+
+	pub fn to_block(&self) -> Block {
+		return Block::new(self.x, self.y, 1, 1, self.color);
+	}
+}
+
+// Greedy rectangles: each run of a row grows down while the rows below repeat it. Cells outside
+// `size` are left out
+pub(crate) fn merged_blocks(cells: &[Cell], size: &Size) -> Vec<Block> {
+	let (width, height) = (size.width, size.height);
+	let mut grid: Vec<Option<PieceColor>> = vec![None; width * height];
+	for cell in cells {
+		if cell.x >= 0 && cell.y >= 0 && (cell.x as usize) < width && (cell.y as usize) < height {
+			grid[cell.y as usize * width + cell.x as usize] = Some(cell.color);
+		}
+	}
+	let mut blocks = vec![];
+	for y in 0..height {
+		for x in 0..width {
+			let color = match grid[y * width + x].take() {
+				Some(color) => color,
+				None => continue,
+			};
+			let mut run = 1;
+			while x + run < width && grid[y * width + x + run] == Some(color) {
+				grid[y * width + x + run] = None;
+				run += 1;
+			}
+			let mut rows = 1;
+			while y + rows < height {
+				let below = &mut grid[(y + rows) * width + x..(y + rows) * width + x + run];
+				if !below.iter().all(|square| *square == Some(color)) {
+					break;
+				}
+				below.fill(None);
+				rows += 1;
+			}
+			blocks.push(Block::new(x as i32, y as i32, rows, run, color));
+		}
+	}
+	return blocks;
+}
@@ -1,4 +1,124 @@
+use alloc::format;
+use alloc::string::String;
+
+// The color of a square, by what it belongs to so frontends can map it to their own textures,
+// or any RGBA value for custom palettes and figures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PieceColor {
+    I,
+    J,
+    L,
+    O,
+    S,
+    T,
+    Z,
+    Garbage,
+    Ghost,
+    Rgba([u8; 4]),
+}
+
+impl PieceColor {
+    pub const fn rgb(red: u8, green: u8, blue: u8) -> PieceColor {
+        return PieceColor::Rgba([red, green, blue, 255]);
+    }
+
+    pub fn to_rgba_u8(self) -> [u8; 4] {
+        return match self {
+            PieceColor::I => [108, 237, 238, 255],
+            PieceColor::J => [0, 33, 230, 255],
+            PieceColor::L => [229, 162, 67, 255],
+            PieceColor::O => [241, 238, 79, 255],
+            PieceColor::S => [221, 47, 23, 255],
+            PieceColor::T => [146, 45, 231, 255],
+            PieceColor::Z => [110, 235, 71, 255],
+            PieceColor::Garbage => [128, 128, 128, 255],
+            PieceColor::Ghost => [255, 255, 255, 64],
+            PieceColor::Rgba(rgba) => rgba,
+        };
+    }
+
+    // Channels from 0 to 1
+    pub fn to_rgba_f32(self) -> [f32; 4] {
+        return self.to_rgba_u8().map(|channel| channel as f32 / 255.0);
+    }
+
+    // `#rrggbb`, with the alpha appended when the color is not opaque
+    pub fn to_hex(self) -> String {
+        let [red, green, blue, alpha] = self.to_rgba_u8();
+        if alpha == 255 {
+            return format!("#{:02x}{:02x}{:02x}", red, green, blue);
+        }
+        return format!("#{:02x}{:02x}{:02x}{:02x}", red, green, blue, alpha);
+    }
+
+    // The name `Color` had for it, empty for RGBA values
+    pub fn name(self) -> &'static str {
+        return match self {
+            PieceColor::I => "I",
+            PieceColor::J => "J",
+            PieceColor::L => "L",
+            PieceColor::O => "O",
+            PieceColor::S => "S",
+            PieceColor::T => "T",
+            PieceColor::Z => "Z",
+            PieceColor::Garbage => "garbage",
+            PieceColor::Ghost => "ghost",
+            PieceColor::Rgba(_) => "",
+        };
+    }
+
+    // Compatibility with code written against `Color`
+    pub fn to_color(self) -> Color {
+        let [red, green, blue, alpha] = self.to_rgba_f32();
+        return Color {
+            red,
+            green,
+            blue,
+            alpha,
+            name: self.name(),
+        };
+    }
+}
+
+impl From<PieceColor> for Color {
+    fn from(color: PieceColor) -> Color {
+        return color.to_color();
+    }
+}
+
+// Known names come back as their variant, anything else as its RGBA value
+impl From<Color> for PieceColor {
+    fn from(color: Color) -> PieceColor {
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+        let rgba = [
+            channel(color.red),
+            channel(color.green),
+            channel(color.blue),
+            channel(color.alpha),
+        ];
+        let named = [
+            PieceColor::I,
+            PieceColor::J,
+            PieceColor::L,
+            PieceColor::O,
+            PieceColor::S,
+            PieceColor::T,
+            PieceColor::Z,
+            PieceColor::Garbage,
+            PieceColor::Ghost,
+        ];
+        return named
+            .iter()
+            .copied()
+            .find(|known| known.name() == color.name && known.to_rgba_u8() == rgba)
+            .unwrap_or(PieceColor::Rgba(rgba));
+    }
+}
+
+// RGBA with float channels, superseded by `PieceColor`
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Color {
     pub red: f32,
     pub green: f32,
@@ -6,3 +126,68 @@ pub struct Color {
     pub alpha: f32,
     pub name: &'static str, //char,
 }
+
+// Names are static, so only the ones known by the crate can be restored
+#[cfg(feature = "serde")]
+const KNOWN_NAMES: [&str; 9] = ["I", "J", "L", "O", "S", "T", "Z", "garbage", "ghost"];
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Color {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        use alloc::string::String;
+
+        #[derive(serde::Deserialize)]
+        struct ColorData {
+            red: f32,
+            green: f32,
+            blue: f32,
+            alpha: f32,
+            name: String,
+        }
+        let data = ColorData::deserialize(deserializer)?;
+        let name = KNOWN_NAMES
+            .iter()
+            .find(|known| **known == data.name)
+            .cloned()
+            .unwrap_or("");
+        return Ok(Color {
+            red: data.red,
+            green: data.green,
+            blue: data.blue,
+            alpha: data.alpha,
+            name,
+        });
+    }
+}
+
+#[cfg(test)]
+mod graphics_tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(PieceColor::J.to_rgba_u8(), [0, 33, 230, 255]);
+        assert_eq!(
+            PieceColor::Garbage.to_rgba_f32(),
+            [128.0 / 255.0, 128.0 / 255.0, 128.0 / 255.0, 1.0]
+        );
+        assert_eq!(PieceColor::I.to_hex(), "#6cedee");
+        assert_eq!(PieceColor::Ghost.to_hex(), "#ffffff40");
+        assert_eq!(PieceColor::rgb(1, 2, 3).to_hex(), "#010203");
+    }
+
+    #[test]
+    fn test_color_round_trip() {
+        for color in [
+            PieceColor::T,
+            PieceColor::Ghost,
+            PieceColor::rgb(10, 20, 30),
+        ] {
+            assert_eq!(PieceColor::from(color.to_color()), color);
+        }
+        // A standard color under another name is only its value
+        let mut renamed = PieceColor::T.to_color();
+        renamed.name = "";
+        assert_eq!(PieceColor::from(renamed), PieceColor::rgb(146, 45, 231));
+    }
+}
@@ -1,3 +1,4 @@
 pub mod geometry;
 pub mod graphics;
 pub mod block;
+pub mod cell;
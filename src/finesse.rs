@@ -0,0 +1,160 @@
+// Finesse: the fewest key presses that bring a figure from its spawn position to the place it
+// locked at, hard drop excluded. Placements that need a soft drop are not judged.
+use super::move_validator::{drop_distance, first_valid_rotation, has_valid_position, shifted};
+use super::{ActiveFigure, Board, Point, RotationSystem};
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FinesseMove {
+    TapLeft,
+    TapRight,
+    // Hold the key until the figure stops against a wall or a block
+    DasLeft,
+    DasRight,
+    RotateCW,
+    RotateCCW,
+    Rotate180,
+}
+
+const MOVES: [FinesseMove; 7] = [
+    FinesseMove::TapLeft,
+    FinesseMove::TapRight,
+    FinesseMove::DasLeft,
+    FinesseMove::DasRight,
+    FinesseMove::RotateCW,
+    FinesseMove::RotateCCW,
+    FinesseMove::Rotate180,
+];
+
+// Shortest sequence from `spawn` to the cells of `locked`, None when a soft drop is needed
+pub(crate) fn optimal_moves(
+    spawn: &ActiveFigure,
+    locked: &ActiveFigure,
+    board: &Board,
+    rotation_system: &RotationSystem,
+) -> Option<Vec<FinesseMove>> {
+    if !has_valid_position(spawn, board) {
+        return None;
+    }
+    let target = sorted_cells(locked);
+    let mut states: Vec<(ActiveFigure, Option<(usize, FinesseMove)>)> = vec![(spawn.clone(), None)];
+    let mut index = 0;
+    while index < states.len() {
+        let figure = states[index].0.clone();
+        if sorted_cells(&dropped(&figure, board)) == target {
+            return Some(path_to(&states, index));
+        }
+        for finesse_move in MOVES.iter() {
+            if let Some(moved) = apply(&figure, *finesse_move, board, rotation_system) {
+                let seen = states.iter().any(|(state, _)| {
+                    state.position() == moved.position()
+                        && state.rotation_step() == moved.rotation_step()
+                });
+                if !seen {
+                    states.push((moved, Some((index, *finesse_move))));
+                }
+            }
+        }
+        index += 1;
+    }
+    return None;
+}
+
+fn apply(
+    figure: &ActiveFigure,
+    finesse_move: FinesseMove,
+    board: &Board,
+    rotation_system: &RotationSystem,
+) -> Option<ActiveFigure> {
+    let tests = match finesse_move {
+        FinesseMove::TapLeft => vec![figure.moved_left()],
+        FinesseMove::TapRight => vec![figure.moved_right()],
+        FinesseMove::DasLeft => return Some(shifted(figure, board, ActiveFigure::moved_left)),
+        FinesseMove::DasRight => return Some(shifted(figure, board, ActiveFigure::moved_right)),
+        FinesseMove::RotateCW => figure.wall_kicked_rotation_tests(rotation_system),
+        FinesseMove::RotateCCW => figure.wall_kicked_counter_clockwise_tests(rotation_system),
+        FinesseMove::Rotate180 => figure.wall_kicked_180_tests(rotation_system),
+    };
+    return first_valid_rotation(tests, board, rotation_system).map(|(_, figure)| figure);
+}
+
+fn dropped(figure: &ActiveFigure, board: &Board) -> ActiveFigure {
+    return figure.translated(0, drop_distance(figure, board));
+}
+
+fn path_to(
+    states: &[(ActiveFigure, Option<(usize, FinesseMove)>)],
+    index: usize,
+) -> Vec<FinesseMove> {
+    let mut path = vec![];
+    let mut current = index;
+    while let Some((parent, finesse_move)) = states[current].1 {
+        path.push(finesse_move);
+        current = parent;
+    }
+    path.reverse();
+    return path;
+}
+
+fn sorted_cells(figure: &ActiveFigure) -> Vec<Point> {
+    let mut cells = figure.to_cartesian();
+    cells.sort_by_key(|point| (point.y, point.x));
+    return cells;
+}
+
+#[cfg(test)]
+mod finesse_tests {
+    use super::super::{FigureType, Size};
+    use super::*;
+
+    fn empty_board() -> Board {
+        return Board::new(&Size {
+            height: 20,
+            width: 10,
+        });
+    }
+
+    fn spawn(figure_type: FigureType) -> ActiveFigure {
+        return ActiveFigure::new(figure_type, Point { x: 3, y: 0 });
+    }
+
+    #[test]
+    fn test_no_moves_for_the_spawn_column() {
+        let board = empty_board();
+        let figure = spawn(FigureType::T);
+        let moves = optimal_moves(&figure, &dropped(&figure, &board), &board, &RotationSystem::SRS);
+        assert_eq!(moves, Some(vec![]));
+    }
+
+    #[test]
+    fn test_das_to_the_wall() {
+        let board = empty_board();
+        let figure = spawn(FigureType::O);
+        let locked = dropped(&shifted(&figure, &board, ActiveFigure::moved_left), &board);
+        let moves = optimal_moves(&figure, &locked, &board, &RotationSystem::SRS);
+        assert_eq!(moves, Some(vec![FinesseMove::DasLeft]));
+    }
+
+    #[test]
+    fn test_das_and_tap_back() {
+        let board = empty_board();
+        let figure = spawn(FigureType::O);
+        let wall = shifted(&figure, &board, ActiveFigure::moved_left);
+        let locked = dropped(&wall.moved_right(), &board);
+        let moves = optimal_moves(&figure, &locked, &board, &RotationSystem::SRS).unwrap();
+        assert_eq!(moves.len(), 2);
+    }
+
+    #[test]
+    fn test_tuck_is_not_judged() {
+        let mut board = empty_board();
+        for x in 0..6 {
+            board.set_figure_at_xy(x, 18, Some(FigureType::Garbage));
+        }
+        let figure = spawn(FigureType::I);
+        let locked = ActiveFigure::new(FigureType::I, Point { x: 0, y: 18 });
+        assert_eq!(optimal_moves(&figure, &locked, &board, &RotationSystem::SRS), None);
+    }
+}
@@ -1,266 +1,3372 @@
-use super::move_validator::{can_move_down, has_valid_position};
-use super::{ActiveFigure, Block, Board, FigureType, Point, Size};
-
-const MOVING_PERIOD: f64 = 1f64; //secs
-
-pub enum Action {
-    MoveDown,
-    MoveLeft,
-    MoveRight,
-    Rotate,
-}
-
-pub trait Randomizer {
-    fn random(&self) -> i32;
-}
-
-#[derive(PartialEq)]
-pub enum GameState {
-    Playing,
-    GameOver,
-}
-
-pub struct Game {
-    board: Board,
-    score: u64,
-    active: ActiveFigure,
-    next: ActiveFigure,
-    waiting_time: f64,
-    randomizer: Box<dyn Randomizer + 'static>,
-    state: GameState,
-    lines: usize
-}
-
-impl Game {
-    pub fn new(size: &Size, randomizer: Box<dyn Randomizer + 'static>) -> Game {
-        let start_point = Game::figure_start_point(size.width);
-        let active = Game::random_figure(start_point, &randomizer);
-        let next = Game::random_figure(start_point, &randomizer);
-
-        let board = Board::new(size);
-        return Game {
-            board,
-            score: 0,
-            active,
-            next,
-            waiting_time: 0.0,
-            randomizer,
-            state: GameState::Playing,
-            lines: 0,
-        };
-    }
-
-    fn figure_start_point(width: usize) -> Point {
-        let mid_point = (width as i32).wrapping_div(2) - 2;
-        return Point { x: mid_point, y: 0 };
-    }
-
-    fn random_figure(position: Point, randomizer: &Box<dyn Randomizer + 'static>) -> ActiveFigure {
-        let figure = match randomizer.random() {
-            0 => FigureType::I,
-            1 => FigureType::J,
-            2 => FigureType::L,
-            3 => FigureType::O,
-            4 => FigureType::S,
-            5 => FigureType::T,
-            _ => FigureType::Z,
-        };
-        return ActiveFigure::new(figure, position);
-    }
-
-    pub fn is_game_over(&self) -> bool {
-        return self.state == GameState::GameOver;
-    }
-
-    // DRAWING FUNCTIONS
-
-    pub fn draw(&self) -> Vec<Block> {
-        let board = self.draw_board();
-        let figure = self.draw_active_figure();
-        return board.iter().chain(&figure).cloned().collect();
-    }
-
-    pub fn draw_active_figure(&self) -> Vec<Block> {
-        let figure = self.active.to_cartesian();
-        return figure
-            .iter()
-            .map(|point| Block::new(point.x, point.y, 1, 1, self.active.color()))
-            .collect();
-    }
-
-    pub fn access_active_figure(&self) -> Vec<Point> {
-        return self.active.to_cartesian();
-    }
-
-    pub fn active_figure_color(&self) -> crate::Color {
-        self.active.figure.color()
-    }
-
-    pub fn draw_board(&self) -> Vec<Block> {
-        let mut blocks = vec![];
-        for y in 0..self.board.height() {
-            for x in 0..self.board.width() {
-                if let Some(square) = self.board.figure_at_xy(x, y) {
-                    let block = Block::new(x as i32, y as i32, 1, 1, square.color());
-                    blocks.push(block);
-                }
-            }
-        }
-        return blocks;
-    }
-
-
-    pub fn access_board(&self) -> Vec<Point> {
-        let mut points = vec![];
-        for y in 0..self.board.height() {
-            for x in 0..self.board.width() {
-                if let Some(_square) = self.board.figure_at_xy(x, y) {
-                    let point = Point{x: x as i32, y: y as i32}; // it does not matter what block is there
-                    points.push(point);
-                }
-            }
-        }
-        return points;
-    }
-    // GAME UPDATE
-
-    pub fn update(&mut self, delta_time: f64) {
-        self.waiting_time += delta_time;
-        if self.waiting_time > MOVING_PERIOD {
-            self.update_game();
-            self.waiting_time = 0.0;
-        }
-    }
-
-    fn update_game(&mut self) {
-        if self.state == GameState::GameOver {
-            return;
-        }
-        if can_move_down(&self.active, &self.board) {
-            self.move_down();
-        } else {
-            self.update_next_figure();
-        }
-    }
-
-    fn update_next_figure(&mut self) {
-        self.add_active_figure_to_board();
-        let completed_lines_count = self.remove_completed_lines();
-        self.add_score_for(completed_lines_count);
-        self.add_new_active_figure();
-        self.update_state();
-    }
-
-    fn update_state(&mut self) {
-        if self.check_is_game_over() {
-            self.state = GameState::GameOver;
-        }
-    }
-
-    // MOVEMENT FUNCTIONS
-
-    pub fn perform(&mut self, action: Action) {
-        match action {
-            Action::MoveLeft => self.move_left(),
-            Action::MoveRight => self.move_right(),
-            Action::MoveDown => self.move_down(),
-            Action::Rotate => self.rotate_active_figure(),
-        }
-    }
-
-    fn move_left(&mut self) {
-        self.update_active_with(self.active.moved_left());
-    }
-
-    fn move_right(&mut self) {
-        self.update_active_with(self.active.moved_right());
-    }
-
-    fn move_down(&mut self) {
-        self.update_active_with(self.active.moved_down());
-    }
-
-    fn rotate_active_figure(&mut self) {
-        if let Some(rotated) = self.wall_kicked_rotated_active_figure() {
-            self.update_active_with(rotated);
-        }
-    }
-
-    // WALL KICK
-
-    fn wall_kicked_rotated_active_figure(&self) -> Option<ActiveFigure> {
-        return self
-            .active
-            .wall_kicked_rotation_tests()
-            .into_iter()
-            .find(|figure| has_valid_position(figure, &self.board));
-    }
-
-    // Game state mutation
-
-    fn update_active_with(&mut self, new_active: ActiveFigure) {
-        if has_valid_position(&new_active, &self.board) {
-            self.active = new_active;
-        }
-    }
-
-    fn add_active_figure_to_board(&mut self) {
-        for point in self.active.to_cartesian() {
-            self.board = self.board.replacing_figure_at_xy(
-                point.x as usize,
-                point.y as usize,
-                Some(self.active.get_type()),
-            );
-        }
-    }
-
-    fn add_new_active_figure(&mut self) {
-        let start_point = Game::figure_start_point(self.board.width());
-        self.update_active_with(self.next.clone());
-        self.next = Game::random_figure(start_point, &self.randomizer);
-    }
-
-    fn remove_completed_lines(&mut self) -> usize {
-        let lines = self.lines_completed();
-        self.board = self.board.removing_lines(&lines);
-        self.lines += lines.len();
-        return lines.len();
-    }
-
-    // Lines checks
-
-    fn lines_completed(&self) -> Vec<usize> {
-        let mut completed_lines: Vec<usize> = vec![];
-        for line_number in 0..self.board.height() {
-            if self.is_line_completed(line_number) {
-                completed_lines.push(line_number);
-            }
-        }
-        return completed_lines;
-    }
-
-    fn is_line_completed(&self, line_number: usize) -> bool {
-        if let Some(line) = self.board.get_line(line_number) {
-            return !line.contains(&None);
-        }
-        return false;
-    }
-
-    // Score
-
-    fn add_score_for(&mut self, completed_lines: usize) {
-        self.score += (completed_lines as u64) * 100;
-    }
-
-    fn check_is_game_over(&self) -> bool {
-        return self.active.position().y == 0 && !has_valid_position(&self.active, &self.board);
-    }
-
-    pub fn get_score(&self) -> u64 {
-        return self.score;
-    }
-
-    pub fn get_lines_completed(&self) -> usize {
-        return self.lines;
-    }
-}
\ No newline at end of file
+#[cfg(test)]
+pub(crate) mod fixtures;
+mod garbage;
+mod history;
+mod items;
+mod placements;
+mod queries;
+mod rng_trace;
+mod rollback;
+mod snapshot;
+mod special;
+mod spectator;
+mod trainer;
+mod validation;
+
+use super::ai::{best_placement_value, enumerate_placements, is_reachable, Placement};
+use super::attack::attack_for;
+use super::board::metrics::Metrics;
+use super::board::MAX_WIDTH;
+use super::cell::merged_blocks;
+use super::finesse::optimal_moves;
+use super::input::{Direction, InputState, Key, KeyState};
+use super::modes::{GameMode, ModeResult};
+use super::move_validator::{
+    can_move_down, drop_distance, first_valid_rotation, has_valid_position, shifted,
+};
+use super::protocol::InputFrame;
+use super::randomizer::{Dealer, SavedDealer};
+use super::scoring::{ClearKind, ScoringRules};
+use super::stats::Stats;
+use super::t_spin::{detect_t_spin, TSpin};
+use super::{
+    ActiveFigure, BagRandomizer, Block, Board, Cell, ClearGravity, Figure, FigureType, GameConfig,
+    GameEvent, Palette, PieceColor, PiecePose, Point, Rotation, Size,
+};
+pub use super::randomizer::{IndexRandomizer, Randomizer};
+pub use garbage::PendingGarbage;
+pub use placements::PlacementRecord;
+pub use queries::ClearPrediction;
+pub use rng_trace::RngDraw;
+pub use snapshot::GameSnapshot;
+pub use spectator::SpectatorView;
+pub use validation::Rejection;
+use history::{PieceState, UndonePiece};
+use items::ItemState;
+use special::SpecialMeter;
+use validation::RefereeState;
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+// Frame length of `step_frame` when the config has no tick rate
+const DEFAULT_TICK_RATE: u32 = 60;
+// Placement value the held figure has to win by before holding is suggested
+const HOLD_SUGGESTION_MARGIN: f64 = 0.5;
+// Gravity builds up in fixed point fractions of a cell
+const GRAVITY_UNITS_PER_CELL: u64 = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Action {
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Rotate,
+    RotateCCW,
+    Rotate180,
+    Hold,
+    HardDrop,
+    SoftDrop,
+    SoftDropStart,
+    SoftDropStop,
+    Pause,
+    Resume,
+    // Drops the figure to the stack without locking it
+    SonicDrop,
+    // Slide the figure as far as it goes in one call
+    MoveLeftWall,
+    MoveRightWall,
+    // Starts `GameConfig::special` when its meter allows it
+    ActivateSpecial,
+}
+
+impl Action {
+    // Stable codes, used by replays and bindings
+    pub fn code(self) -> u8 {
+        return match self {
+            Action::MoveDown => 0,
+            Action::MoveLeft => 1,
+            Action::MoveRight => 2,
+            Action::Rotate => 3,
+            Action::RotateCCW => 4,
+            Action::Rotate180 => 5,
+            Action::Hold => 6,
+            Action::HardDrop => 7,
+            Action::SoftDrop => 8,
+            Action::SoftDropStart => 9,
+            Action::SoftDropStop => 10,
+            Action::Pause => 11,
+            Action::Resume => 12,
+            Action::SonicDrop => 13,
+            Action::MoveLeftWall => 14,
+            Action::MoveRightWall => 15,
+            Action::ActivateSpecial => 16,
+        };
+    }
+
+    pub fn from_code(code: u8) -> Option<Action> {
+        return match code {
+            0 => Some(Action::MoveDown),
+            1 => Some(Action::MoveLeft),
+            2 => Some(Action::MoveRight),
+            3 => Some(Action::Rotate),
+            4 => Some(Action::RotateCCW),
+            5 => Some(Action::Rotate180),
+            6 => Some(Action::Hold),
+            7 => Some(Action::HardDrop),
+            8 => Some(Action::SoftDrop),
+            9 => Some(Action::SoftDropStart),
+            10 => Some(Action::SoftDropStop),
+            11 => Some(Action::Pause),
+            12 => Some(Action::Resume),
+            13 => Some(Action::SonicDrop),
+            14 => Some(Action::MoveLeftWall),
+            15 => Some(Action::MoveRightWall),
+            16 => Some(Action::ActivateSpecial),
+            _ => None,
+        };
+    }
+
+    // Pause and Resume stop the clock instead of playing
+    pub fn is_pause_control(self) -> bool {
+        return matches!(self, Action::Pause | Action::Resume);
+    }
+}
+
+// Whether the game runs, stored next to the figure. Frontends read `phase()`, which derives the
+// finer `GamePhase` from it
+#[derive(Debug, Clone, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum GameState {
+    Playing,
+    Paused,
+    GameOver,
+    Finished,
+}
+
+// How much of the time given to `update_clamped` was played
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LagReport {
+    // Frames simulated, without a tick rate the last one can be shorter
+    pub steps: usize,
+    // Seconds left unplayed because the update reached its step cap
+    pub dropped_time: f64,
+}
+
+impl LagReport {
+    pub fn is_lagging(&self) -> bool {
+        return self.dropped_time > 0.0;
+    }
+}
+
+// What the engine is doing, for frontends to drive animations and input gating
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GamePhase {
+    // A figure appeared since the last update
+    Spawning,
+    Falling,
+    // The figure rests on the stack and the lock delay runs
+    Locking,
+    Clearing,
+    // Entry delay before the next figure
+    Are,
+    Paused,
+    GameOver,
+    // The mode goal was reached
+    Finished,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Game {
+    board: Board,
+    score: u64,
+    active: ActiveFigure,
+    next: VecDeque<FigureType>,
+    held: Option<FigureType>,
+    can_hold: bool,
+    gravity_progress: u64,
+    lock_time: f64,
+    lock_resets: usize,
+    lowest_row: i32,
+    last_rotation_kick: Option<usize>,
+    last_clear_kind: ClearKind,
+    combo: Option<usize>,
+    back_to_back: Option<usize>,
+    soft_dropping: bool,
+    input_state: InputState,
+    clearing_lines: Vec<usize>,
+    last_cleared_lines: Vec<usize>,
+    clear_time: f64,
+    elapsed: f64,
+    // Time since the active piece spawned, and time spent in line clears and entry delays
+    piece_time: f64,
+    idle_time: f64,
+    frame_time: f64,
+    stats: Stats,
+    piece_inputs: usize,
+    queued_actions: VecDeque<Action>,
+    buffered_rotation: Option<Action>,
+    buffered_hold: bool,
+    entry_time: Option<f64>,
+    spawning: bool,
+    // Shared with the snapshots taken since they last grew, see `Game::snapshot`
+    placements: Rc<Vec<PlacementRecord>>,
+    rng_trace: Rc<Vec<RngDraw>>,
+    dealt: Rc<Vec<FigureType>>,
+    // The randomizer before it dealt anything, see `reset_keeping_sequence`
+    opening: Option<SavedDealer>,
+    pending_garbage: Vec<PendingGarbage>,
+    special: SpecialMeter,
+    items: ItemState,
+    referee: RefereeState,
+    // Whether the last danger event was `DangerEntered`
+    danger: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    spawn_snapshot: Option<PieceState>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    undo_history: VecDeque<PieceState>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    redo_history: Vec<UndonePiece>,
+    #[cfg_attr(feature = "serde", serde(with = "super::randomizer::serde_state"))]
+    randomizer: Dealer,
+    state: GameState,
+    lines: usize,
+    level: usize,
+    config: GameConfig,
+    events: Vec<GameEvent>,
+}
+
+impl Game {
+    pub fn new<R: IndexRandomizer + 'static>(size: &Size, randomizer: R) -> Game {
+        return Game::new_with_config(size, randomizer, GameConfig::default());
+    }
+
+    // Uses the built in 7-bag randomizer, the same seed always deals the same figures
+    pub fn new_with_seed(size: &Size, seed: u64) -> Game {
+        return Game::new(size, BagRandomizer::new(seed));
+    }
+
+    // Board size and randomizer are taken from the config
+    pub fn with_config(config: GameConfig) -> Game {
+        let size = config.size.clone();
+        let randomizer = config.randomizer.build(config.piece_set.len());
+        return Game::new_with_config(&size, randomizer, config);
+    }
+
+    // Deals from a randomizer that picks the figures itself, the board size is taken from the config
+    pub fn with_randomizer<R: Randomizer + 'static>(config: GameConfig, randomizer: R) -> Game {
+        let size = config.size.clone();
+        return Game::from_dealer(&size, Dealer::Figures(Box::new(randomizer)), config);
+    }
+
+    // Starts on a pre-filled board, its size replaces the one in the config
+    pub fn with_board(board: Board, config: GameConfig) -> Game {
+        let config = GameConfig {
+            size: board.size(),
+            ..config
+        };
+        let mut game = Game::with_config(config);
+        let blocked = Game::blocked_points(&game.config);
+        game.board = board.resized(&game.board.size()).blocking(&blocked);
+        game.active = game.spawned(game.active.clone());
+        game.reset_lock_state();
+        game.update_state();
+        game.take_events();
+        game.record_spawn();
+        game.check_hold_suggestion();
+        return game;
+    }
+
+    pub fn new_with_config<R: IndexRandomizer + 'static>(
+        size: &Size,
+        randomizer: R,
+        config: GameConfig,
+    ) -> Game {
+        return Game::from_dealer(size, Dealer::Indexed(Box::new(randomizer)), config);
+    }
+
+    // Boards wider than `MAX_WIDTH` are cut to it
+    fn from_dealer(size: &Size, mut randomizer: Dealer, config: GameConfig) -> Game {
+        let size = &Size {
+            height: size.height,
+            width: size.width.min(MAX_WIDTH),
+        };
+        let opening = randomizer.save();
+        let mut dealt = vec![];
+        let mut rng_trace = vec![];
+        for _ in 0..config.preview_count.max(1) + 1 {
+            Game::deal(&mut randomizer, &mut dealt, &mut rng_trace, &config.piece_set);
+        }
+        let active = Game::spawn_figure(dealt[0].clone(), size.width, &config);
+        let lowest_row = active.position().y;
+        let next = dealt[1..].iter().cloned().collect();
+
+        let board = Board::new(&Size {
+            height: size.height + config.hidden_rows,
+            width: size.width,
+        })
+        .blocking(&Game::blocked_points(&config));
+        let mut game = Game {
+            board,
+            score: 0,
+            active,
+            next,
+            held: None,
+            can_hold: true,
+            gravity_progress: 0,
+            lock_time: 0.0,
+            lock_resets: 0,
+            lowest_row,
+            last_rotation_kick: None,
+            last_clear_kind: ClearKind::None,
+            combo: None,
+            back_to_back: None,
+            soft_dropping: false,
+            input_state: InputState::new(),
+            clearing_lines: vec![],
+            last_cleared_lines: vec![],
+            clear_time: 0.0,
+            elapsed: 0.0,
+            piece_time: 0.0,
+            idle_time: 0.0,
+            frame_time: 0.0,
+            stats: Stats::new(),
+            piece_inputs: 0,
+            queued_actions: VecDeque::new(),
+            buffered_rotation: None,
+            buffered_hold: false,
+            entry_time: None,
+            spawning: true,
+            placements: Rc::default(),
+            rng_trace: Rc::new(rng_trace),
+            dealt: Rc::new(dealt),
+            opening,
+            pending_garbage: vec![],
+            special: SpecialMeter::default(),
+            items: ItemState::new(config.items.seed),
+            referee: RefereeState::default(),
+            danger: false,
+            spawn_snapshot: None,
+            undo_history: VecDeque::new(),
+            redo_history: vec![],
+            randomizer,
+            state: GameState::Playing,
+            lines: 0,
+            level: config.start_level,
+            config,
+            events: vec![],
+        };
+        game.record_spawn();
+        game.check_hold_suggestion();
+        return game;
+    }
+
+    // Figures are centered on the cells of their matrix, leaning left, and kept inside the board
+    // when they fit. The top of the matrix goes in the last hidden row, the SRS spawn height,
+    // without cells above the board
+    fn spawn_point(figure: &ActiveFigure, width: usize, hidden_rows: usize) -> Point {
+        let offset = figure.get_type().spawn_offset();
+        let cells: Vec<Point> = figure.cells().collect();
+        let left_cell = cells.iter().map(|cell| cell.x).min().unwrap_or(0);
+        let right_cell = cells.iter().map(|cell| cell.x + 1).max().unwrap_or(0);
+        let top_cell = cells.iter().map(|cell| cell.y).min().unwrap_or(0);
+        let free = (width as i32 - (right_cell - left_cell)).max(0);
+        let left = (free / 2 + offset.x).clamp(0, free);
+        let row = hidden_rows.saturating_sub(1) as i32;
+        let top = (row + offset.y).max(-top_cell);
+        return Point {
+            x: left - left_cell,
+            y: top,
+        };
+    }
+
+    // In the orientation of `GameConfig::spawn_rotations`, which also picks the first kicks
+    fn spawn_figure(figure_type: FigureType, width: usize, config: &GameConfig) -> ActiveFigure {
+        let mut rotation = config.spawn_rotation(&figure_type);
+        if config.rotation_system.is_two_state(&figure_type) {
+            rotation = Rotation::from_steps(rotation.steps() % 2);
+        }
+        let origin = Point { x: 0, y: 0 };
+        let figure = ActiveFigure::from_pose(&PiecePose::new(figure_type, origin, rotation));
+        let position = Game::spawn_point(&figure, width, config.hidden_rows);
+        return figure.translated(position.x, position.y);
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        return self.state == GameState::GameOver;
+    }
+
+    // The mode goal was reached
+    pub fn is_finished(&self) -> bool {
+        return self.state == GameState::Finished;
+    }
+
+    fn has_ended(&self) -> bool {
+        return self.is_game_over() || self.is_finished();
+    }
+
+    pub fn phase(&self) -> GamePhase {
+        return match self.state {
+            GameState::GameOver => GamePhase::GameOver,
+            GameState::Finished => GamePhase::Finished,
+            GameState::Paused => GamePhase::Paused,
+            GameState::Playing if self.is_clearing() => GamePhase::Clearing,
+            GameState::Playing if self.is_in_entry_delay() => GamePhase::Are,
+            GameState::Playing if self.spawning => GamePhase::Spawning,
+            GameState::Playing if can_move_down(&self.active, &self.board) => GamePhase::Falling,
+            GameState::Playing => GamePhase::Locking,
+        };
+    }
+
+    // DRAWING FUNCTIONS
+
+    pub fn draw(&self) -> Vec<Block> {
+        return self.blocks().collect();
+    }
+
+    // Reuses the given vector, so frontends don't allocate every frame
+    pub fn draw_into(&self, blocks: &mut Vec<Block>) {
+        blocks.clear();
+        blocks.extend(self.blocks());
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = Block> + '_ {
+        return self.board_blocks().chain(self.active_figure_blocks());
+    }
+
+    // The same squares as `draw`, in cell coordinates with the figure type each belongs to
+    pub fn draw_cells(&self) -> Vec<Cell> {
+        return self.draw_cells_iter().collect();
+    }
+
+    pub fn draw_cells_into(&self, cells: &mut Vec<Cell>) {
+        cells.clear();
+        cells.extend(self.draw_cells_iter());
+    }
+
+    fn draw_cells_iter(&self) -> impl Iterator<Item = Cell> + '_ {
+        return self
+            .board_draw_cells()
+            .chain(self.active_figure_draw_cells());
+    }
+
+    // Adjacent cells of the same color merged into larger blocks, fewer draws for renderers with
+    // a low fill rate
+    pub fn draw_optimized(&self) -> Vec<Block> {
+        return merged_blocks(&self.draw_cells(), &self.get_visible_size());
+    }
+
+    // The visible rows as text, with the active figure over the stack, for terminals and bug reports
+    pub fn render_ascii(&self) -> String {
+        let hidden_rows = self.config.hidden_rows;
+        let mut rows: Vec<Vec<char>> = (hidden_rows..self.board.height())
+            .map(|y| {
+                (0..self.board.width())
+                    .map(|x| self.board.symbol_at_xy(x, y))
+                    .collect()
+            })
+            .collect();
+        let symbol = self.active.get_type().symbol();
+        for point in self.access_active_figure() {
+            let cell = rows
+                .get_mut(point.y as usize)
+                .and_then(|row| row.get_mut(point.x as usize));
+            if let Some(cell) = cell {
+                *cell = symbol;
+            }
+        }
+        let mut text = String::new();
+        for row in rows {
+            text.extend(row);
+            text.push('\n');
+        }
+        return text;
+    }
+
+    pub fn draw_active_figure(&self) -> Vec<Block> {
+        return self.active_figure_blocks().collect();
+    }
+
+    pub fn draw_active_figure_into(&self, blocks: &mut Vec<Block>) {
+        blocks.clear();
+        blocks.extend(self.active_figure_blocks());
+    }
+
+    pub fn active_figure_blocks(&self) -> impl Iterator<Item = Block> + '_ {
+        return self.active_figure_draw_cells().map(|cell| cell.to_block());
+    }
+
+    fn active_figure_draw_cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        let color = self.active.color(&self.config.palette);
+        let kind = self.active.get_type();
+        return self
+            .active
+            .cells()
+            .filter_map(move |point| self.visible_point(point))
+            .map(move |point| Cell::new(point.x, point.y, color, kind.clone()));
+    }
+
+    pub fn draw_ghost(&self) -> Vec<Block> {
+        return self.ghost_figure_blocks().collect();
+    }
+
+    // Where the active figure would land on a hard drop, drawn with the palette's ghost color
+    pub fn ghost_figure_blocks(&self) -> impl Iterator<Item = Block> + '_ {
+        let ghost = self
+            .active
+            .translated(0, drop_distance(&self.active, &self.board));
+        let color = self.config.palette.ghost;
+        return ghost
+            .to_cartesian()
+            .into_iter()
+            .filter_map(move |point| self.visible_point(point))
+            .map(move |point| Block::new(point.x, point.y, 1, 1, color));
+    }
+
+    pub fn access_active_figure(&self) -> Vec<Point> {
+        return self
+            .active
+            .cells()
+            .filter_map(|point| self.visible_point(point))
+            .collect();
+    }
+
+    // Where the active figure is and which way it faces, counted from the first visible row like
+    // the drawing functions. None while there is no figure to control
+    pub fn active_pose(&self) -> Option<PiecePose> {
+        if self.has_ended() || self.is_waiting_for_figure() {
+            return None;
+        }
+        return Some(self.visible_pose(&self.active));
+    }
+
+    // Rows of the pose count from the first visible row, hidden rows are above it
+    fn visible_pose(&self, figure: &ActiveFigure) -> PiecePose {
+        let mut pose = figure.pose();
+        pose.origin.y -= self.config.hidden_rows as i32;
+        return pose;
+    }
+
+    // Drawing coordinates start at the first visible row, cells in the hidden rows are left out
+    fn visible_point(&self, point: Point) -> Option<Point> {
+        let y = point.y - self.config.hidden_rows as i32;
+        if y < 0 {
+            return None;
+        }
+        return Some(Point { x: point.x, y });
+    }
+
+    fn visible_rows(&self, rows: &[usize]) -> Vec<usize> {
+        let hidden_rows = self.config.hidden_rows;
+        return rows
+            .iter()
+            .filter(|row| **row >= hidden_rows)
+            .map(|row| row - hidden_rows)
+            .collect();
+    }
+
+    // Size of the visible field, `get_board()` also holds the hidden rows above it
+    pub fn get_visible_size(&self) -> Size {
+        return Size {
+            height: self.board.height() - self.config.hidden_rows,
+            width: self.board.width(),
+        };
+    }
+
+    pub fn get_hidden_rows(&self) -> usize {
+        return self.config.hidden_rows;
+    }
+
+    pub fn active_figure_type(&self) -> FigureType {
+        return self.active.get_type();
+    }
+
+    // Clockwise quarter turns from the spawn orientation, 0 to 3, for sprite based renderers
+    pub fn active_rotation(&self) -> u8 {
+        return self.active.rotation_step() as u8;
+    }
+
+    pub fn active_figure_color(&self) -> PieceColor {
+        return self.active.color(&self.config.palette);
+    }
+
+    pub fn get_palette(&self) -> &Palette {
+        return &self.config.palette;
+    }
+
+    // Skins can be swapped at any time, the next draw uses the new colors
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.config.palette = palette;
+    }
+
+    pub fn draw_next(&self, count: usize) -> Vec<Vec<Block>> {
+        return self
+            .get_next_queue()
+            .into_iter()
+            .take(count)
+            .map(|figure_type| self.draw_figure_at(figure_type, Point { x: 0, y: 0 }))
+            .collect();
+    }
+
+    pub fn draw_next_figure_at(&self, origin: Point) -> Vec<Block> {
+        return self.draw_figure_at(self.get_next_figure_type(), origin);
+    }
+
+    fn draw_figure_at(&self, figure_type: FigureType, origin: Point) -> Vec<Block> {
+        let color = self.config.palette.color(&figure_type);
+        let figure = Figure::new(figure_type);
+        return figure
+            .to_cartesian()
+            .iter()
+            .map(|point| {
+                Block::new(origin.x + point.x, origin.y + point.y, 1, 1, color)
+            })
+            .collect();
+    }
+
+    pub fn draw_board(&self) -> Vec<Block> {
+        return self.board_blocks().collect();
+    }
+
+    pub fn draw_board_into(&self, blocks: &mut Vec<Block>) {
+        blocks.clear();
+        blocks.extend(self.board_blocks());
+    }
+
+    pub fn board_blocks(&self) -> impl Iterator<Item = Block> + '_ {
+        return self.board_draw_cells().map(|cell| cell.to_block());
+    }
+
+    fn board_draw_cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        let palette = &self.config.palette;
+        return self.iter_board().filter_map(move |(x, y, square)| {
+            return square
+                .map(|square| Cell::new(x as i32, y as i32, palette.color(&square), square));
+        });
+    }
+
+    pub fn iter_board(&self) -> impl Iterator<Item = (usize, usize, Option<FigureType>)> + '_ {
+        let hidden_rows = self.config.hidden_rows;
+        return self
+            .board
+            .iter_cells()
+            .filter(move |(_, y, _)| *y >= hidden_rows)
+            .map(move |(x, y, square)| (x, y - hidden_rows, square));
+    }
+
+
+    // The config's blocked cells count from the first visible row
+    fn blocked_points(config: &GameConfig) -> Vec<Point> {
+        let hidden_rows = config.hidden_rows as i32;
+        return config
+            .blocked_cells
+            .iter()
+            .map(|point| Point {
+                x: point.x,
+                y: point.y + hidden_rows,
+            })
+            .collect();
+    }
+
+    // Permanently unusable cells of the visible field, drawn apart from the figures
+    pub fn access_blocked(&self) -> Vec<Point> {
+        let hidden_rows = self.config.hidden_rows;
+        let mut points = vec![];
+        for y in hidden_rows..self.board.height() {
+            for x in 0..self.board.width() {
+                if self.board.is_blocked(x, y) {
+                    points.push(Point {
+                        x: x as i32,
+                        y: (y - hidden_rows) as i32,
+                    });
+                }
+            }
+        }
+        return points;
+    }
+
+    pub fn access_board(&self) -> Vec<Point> {
+        let mut points = vec![];
+        let hidden_rows = self.config.hidden_rows;
+        for y in hidden_rows..self.board.height() {
+            for x in 0..self.board.width() {
+                if let Some(_square) = self.board.figure_at_xy(x, y) {
+                    let point = Point{x: x as i32, y: (y - hidden_rows) as i32}; // it does not matter what block is there
+                    points.push(point);
+                }
+            }
+        }
+        return points;
+    }
+
+    // Like `access_board`, keeping the type of each taken cell
+    pub fn board_cells(&self) -> Vec<(Point, FigureType)> {
+        return self
+            .iter_board()
+            .filter_map(|(x, y, square)| {
+                let point = Point {
+                    x: x as i32,
+                    y: y as i32,
+                };
+                return square.map(|square| (point, square));
+            })
+            .collect();
+    }
+
+    pub fn get_board(&self) -> &Board {
+        return &self.board;
+    }
+
+    // Heuristics of the locked cells, the active figure is left out
+    pub fn board_metrics(&self) -> Metrics {
+        return Metrics::of(&self.board);
+    }
+
+    // GAME UPDATE
+
+    // With a tick rate the elapsed time is cut in whole frames, the rest waits for the next update
+    pub fn update(&mut self, delta_time: f64) -> Vec<GameEvent> {
+        self.process_queued_actions();
+        if self.is_paused() {
+            return self.take_events();
+        }
+        match self.config.tick_rate {
+            Some(tick_rate) => {
+                let frame = 1.0 / tick_rate as f64;
+                self.frame_time += delta_time;
+                while self.frame_time >= frame {
+                    self.frame_time -= frame;
+                    self.advance(1.0);
+                }
+            }
+            None => self.advance(delta_time),
+        }
+        return self.take_events();
+    }
+
+    // Like `update`, but the time is played frame by frame, a tick long or 1/60s without a tick
+    // rate, so pieces keep locking and spawning through a lag spike instead of one big step. Past
+    // `max_steps` frames the rest of the time is dropped, to keep a long freeze from stalling the
+    // frontend
+    pub fn update_clamped(
+        &mut self,
+        delta_time: f64,
+        max_steps: usize,
+    ) -> (Vec<GameEvent>, LagReport) {
+        self.process_queued_actions();
+        let mut report = LagReport::default();
+        if self.is_paused() {
+            return (self.take_events(), report);
+        }
+        match self.config.tick_rate {
+            Some(tick_rate) => {
+                let frame = 1.0 / tick_rate as f64;
+                self.frame_time += delta_time;
+                while self.frame_time >= frame && report.steps < max_steps {
+                    self.frame_time -= frame;
+                    self.advance(1.0);
+                    report.steps += 1;
+                }
+                if self.frame_time >= frame {
+                    report.dropped_time = self.frame_time - self.frame_time % frame;
+                    self.frame_time %= frame;
+                }
+            }
+            None => {
+                let frame = 1.0 / DEFAULT_TICK_RATE as f64;
+                // Float rounding leaves a few nanoseconds after whole frames, they are not a step
+                let rounding = 1e-9;
+                let mut left = delta_time;
+                while left > rounding && report.steps < max_steps {
+                    let step = left.min(frame);
+                    self.advance(step);
+                    left -= step;
+                    report.steps += 1;
+                }
+                if left > rounding {
+                    report.dropped_time = left;
+                }
+            }
+        }
+        return (self.take_events(), report);
+    }
+
+    // Advances exactly one frame, the timing stays the same on every machine in frame mode
+    pub fn step_frame(&mut self) -> Vec<GameEvent> {
+        self.process_queued_actions();
+        if self.is_paused() {
+            return self.take_events();
+        }
+        match self.config.tick_rate {
+            Some(_) => self.advance(1.0),
+            None => self.advance(1.0 / DEFAULT_TICK_RATE as f64),
+        }
+        return self.take_events();
+    }
+
+    // Runs the actions at the start of their frame, after stepping up to it. Actions of a frame
+    // already played run on the next one, and a paused game does not step until resumed
+    pub fn apply_input_frame(&mut self, input: &InputFrame) -> Vec<GameEvent> {
+        let mut events = vec![];
+        while self.current_frame() < input.frame as u64 && !self.has_ended() && !self.is_paused() {
+            events.extend(self.step_frame());
+        }
+        self.queued_actions.extend(input.actions.iter().copied());
+        events.extend(self.step_frame());
+        return events;
+    }
+
+    // Frames played so far, at the default tick rate without one in the config
+    fn current_frame(&self) -> u64 {
+        if self.config.tick_rate.is_some() {
+            return self.elapsed as u64;
+        }
+        return (self.elapsed * DEFAULT_TICK_RATE as f64 + 0.5) as u64;
+    }
+
+    // Timers count seconds, or frames when the config has a tick rate
+    fn advance(&mut self, delta: f64) {
+        self.update_auto_repeat(delta);
+        self.update_game(delta);
+    }
+
+    // A duration in the unit of the timers, rounded to whole frames in frame mode
+    fn span(&self, seconds: f64) -> f64 {
+        return match self.config.tick_rate {
+            Some(tick_rate) => (seconds * tick_rate as f64 + 0.5) as u64 as f64,
+            None => seconds,
+        };
+    }
+
+    fn update_game(&mut self, delta_time: f64) {
+        if self.has_ended() {
+            return;
+        }
+        self.spawning = false;
+        self.elapsed += delta_time;
+        if self.is_waiting_for_figure() {
+            self.idle_time += delta_time;
+        } else {
+            self.piece_time += delta_time;
+        }
+        self.update_pending_garbage(delta_time);
+        self.update_special(delta_time);
+        self.update_items(delta_time);
+        if self.has_ended() {
+            return;
+        }
+        if self.is_clearing() {
+            self.update_clearing(delta_time);
+        } else if self.entry_time.is_some() {
+            self.update_entry_delay(delta_time);
+        } else if can_move_down(&self.active, &self.board) {
+            if !self.is_gravity_frozen() || self.soft_dropping {
+                self.update_gravity(delta_time);
+            }
+        } else {
+            self.update_lock_delay(delta_time);
+        }
+        self.apply_instant_gravity();
+        self.check_mode_finished();
+    }
+
+    // Slow gravity takes several updates per cell, fast gravity moves several cells per update
+    fn update_gravity(&mut self, delta_time: f64) {
+        let units = delta_time / self.gravity_span() * GRAVITY_UNITS_PER_CELL as f64 + 0.5;
+        let max_units = (self.board.height() as u64 + 1) * GRAVITY_UNITS_PER_CELL;
+        self.gravity_progress = self.gravity_progress.saturating_add(units as u64).min(max_units);
+        while self.gravity_progress >= GRAVITY_UNITS_PER_CELL {
+            self.gravity_progress -= GRAVITY_UNITS_PER_CELL;
+            if !self.move_down() {
+                self.gravity_progress = 0;
+                return;
+            }
+            if self.soft_dropping {
+                self.add_score_for_soft_drop(1);
+            }
+        }
+    }
+
+    // Intervals shorter than a frame are kept as fractions so gravity above 1G keeps its speed
+    fn gravity_span(&self) -> f64 {
+        let interval = self.gravity_interval();
+        return match self.config.tick_rate {
+            Some(tick_rate) if interval * (tick_rate as f64) < 1.0 => interval * tick_rate as f64,
+            _ => self.span(interval),
+        };
+    }
+
+    // With 20G a figure rests on the stack from the frame it spawns
+    fn apply_instant_gravity(&mut self) {
+        if !self.spawning || self.has_ended() || self.is_waiting_for_figure() {
+            return;
+        }
+        let capped = self.config.assists.min_gravity_interval > 0.0
+            || self.is_gravity_frozen()
+            || self.gravity_slowdown() > 1.0;
+        if self.config.gravity_curve.is_instant(self.level) && !capped {
+            while self.move_down() {}
+        }
+    }
+
+    fn update_lock_delay(&mut self, delta_time: f64) {
+        self.lock_time += delta_time;
+        let lock_delay = self.config.lock_delay * self.config.assists.lock_delay_factor;
+        if self.lock_time >= self.span(lock_delay) {
+            self.update_next_figure();
+        }
+    }
+
+    fn update_next_figure(&mut self) {
+        self.record_lock();
+        let t_spin = self.check_t_spin();
+        self.check_finesse();
+        self.add_active_figure_to_board();
+        self.emit(GameEvent::PieceLocked(self.visible_pose(&self.active)));
+        self.stats.record_piece(&self.active.get_type());
+        if self.is_locked_out() && !self.clear_rows_for_top_out() {
+            self.record_placement(0, ClearKind::None);
+            self.top_out();
+            return;
+        }
+        self.last_cleared_lines.clear();
+        let completed_lines_count = self.start_clearing_lines();
+        let clear_kind = Game::clear_kind_for(completed_lines_count, t_spin);
+        if clear_kind.is_t_spin() {
+            self.emit(GameEvent::TSpin(clear_kind.clone()));
+        }
+        self.update_chains(&clear_kind);
+        self.stats.record_clear(&clear_kind, self.get_combo());
+        self.send_attack_for(&clear_kind);
+        self.score_or_hold(&clear_kind);
+        self.charge_special(&clear_kind);
+        self.earn_items(&clear_kind);
+        self.record_placement(completed_lines_count, clear_kind.clone());
+        self.last_clear_kind = clear_kind;
+        self.update_level();
+        self.check_mode_finished();
+        if !self.is_clearing() || self.config.line_clear_delay <= 0.0 {
+            self.finish_clearing_lines();
+        }
+    }
+
+    // Line clear phase, the completed lines stay on the board until the delay is over
+
+    fn update_clearing(&mut self, delta_time: f64) {
+        self.clear_time += delta_time;
+        if self.clear_time >= self.span(self.config.line_clear_delay) {
+            self.finish_clearing_lines();
+        }
+    }
+
+    fn finish_clearing_lines(&mut self) {
+        while self.is_clearing() {
+            let lines = core::mem::take(&mut self.clearing_lines);
+            // Stacked lines stay whole, they can't start chain reactions
+            if self.stack_cleared_lines(&lines) {
+                break;
+            }
+            self.board.remove_lines_with(&lines, &self.config.clear_gravity);
+            if !self.start_chain_clear() {
+                break;
+            }
+            // Each chain reaction waits for its own line clear delay
+            if self.config.line_clear_delay > 0.0 {
+                self.clear_time = 0.0;
+                return;
+            }
+        }
+        self.clear_time = 0.0;
+        if self.config.entry_delay > 0.0 && !self.has_ended() {
+            self.entry_time = Some(0.0);
+            return;
+        }
+        self.spawn_after_entry_delay();
+    }
+
+    // Entry delay (ARE) phase between a figure locking, or its lines clearing, and the next spawn
+
+    fn update_entry_delay(&mut self, delta_time: f64) {
+        let entry_time = self.entry_time.unwrap_or(0.0) + delta_time;
+        self.entry_time = Some(entry_time);
+        if entry_time >= self.span(self.config.entry_delay) {
+            self.spawn_after_entry_delay();
+        }
+    }
+
+    fn spawn_after_entry_delay(&mut self) {
+        self.entry_time = None;
+        self.piece_time = 0.0;
+        self.add_new_active_figure();
+        self.update_state();
+        if !self.has_ended() {
+            self.record_spawn();
+            self.apply_buffered_actions();
+            self.check_hold_suggestion();
+        }
+    }
+
+    pub fn is_in_entry_delay(&self) -> bool {
+        return self.entry_time.is_some();
+    }
+
+    // Line clears and the entry delay leave no figure to control
+    fn is_waiting_for_figure(&self) -> bool {
+        return self.is_clearing() || self.is_in_entry_delay();
+    }
+
+    // Lines completed by the cells falling after a sticky or cascade clear count as a combo
+    fn start_chain_clear(&mut self) -> bool {
+        if self.config.clear_gravity == ClearGravity::Naive || self.has_ended() {
+            return false;
+        }
+        let completed_lines_count = self.start_clearing_lines();
+        if completed_lines_count == 0 {
+            return false;
+        }
+        let clear_kind = Game::clear_kind_for(completed_lines_count, None);
+        self.update_chains(&clear_kind);
+        self.emit(GameEvent::Combo(self.get_combo()));
+        self.stats.record_clear(&clear_kind, self.get_combo());
+        self.send_attack_for(&clear_kind);
+        self.score_or_hold(&clear_kind);
+        self.earn_items(&clear_kind);
+        self.record_chain_clear(completed_lines_count);
+        self.last_clear_kind = clear_kind;
+        self.update_level();
+        self.check_mode_finished();
+        return true;
+    }
+
+    pub fn is_clearing(&self) -> bool {
+        return !self.clearing_lines.is_empty();
+    }
+
+    pub fn get_clearing_lines(&self) -> Vec<usize> {
+        return self.visible_rows(&self.clearing_lines);
+    }
+
+    // Visible rows of the last clear, kept until the next figure locks, chain reactions replace them
+    pub fn last_cleared_lines(&self) -> &[usize] {
+        return &self.last_cleared_lines;
+    }
+
+    fn update_state(&mut self) {
+        if self.check_is_game_over() {
+            self.top_out();
+        }
+    }
+
+    // The stack reaches `GameConfig::danger_height`, for music speedups and red flashes. Blocked
+    // cells are not part of the stack
+    pub fn in_danger(&self) -> bool {
+        let danger_height = match self.config.danger_height {
+            Some(danger_height) => danger_height,
+            None => return false,
+        };
+        let top = self.board.taken_rows().iter().position(|row| *row != 0);
+        return top.is_some_and(|row| self.board.height() - row >= danger_height);
+    }
+
+    // Reported once per change, with the events of the call that made it
+    fn check_danger(&mut self) {
+        let danger = self.in_danger();
+        if danger != self.danger {
+            self.danger = danger;
+            self.emit(if danger {
+                GameEvent::DangerEntered
+            } else {
+                GameEvent::DangerLeft
+            });
+        }
+    }
+
+    fn top_out(&mut self) {
+        // The assists clear rows until the active figure fits again
+        while self.clear_rows_for_top_out() {
+            if self.is_waiting_for_figure() || has_valid_position(&self.active, &self.board) {
+                return;
+            }
+        }
+        if !self.has_ended() {
+            self.state = GameState::GameOver;
+            self.emit(GameEvent::GameOver);
+        }
+    }
+
+    // Assists, the bottom rows are removed instead of ending the game while the board has cells
+    fn clear_rows_for_top_out(&mut self) -> bool {
+        let height = self.board.height();
+        let rows = self.config.assists.clear_rows_on_top_out.min(height);
+        let empty = self.board.taken_rows().iter().all(|row| *row == 0);
+        if rows == 0 || empty || self.has_ended() {
+            return false;
+        }
+        self.remove_bottom_rows(rows);
+        self.emit(GameEvent::TopOutAvoided(rows));
+        return true;
+    }
+
+    fn remove_bottom_rows(&mut self, rows: usize) {
+        let height = self.board.height();
+        let rows = rows.min(height);
+        let bottom: Vec<usize> = (height - rows..height).collect();
+        self.board.remove_lines_in_place(&bottom);
+        self.special.remove_bottom_rows(rows);
+        // Lines waiting to clear moved down with the rest of the board
+        self.clearing_lines = self
+            .clearing_lines
+            .iter()
+            .filter(|line| **line < height - rows)
+            .map(|line| line + rows)
+            .collect();
+    }
+
+    // PAUSE
+
+    pub fn pause(&mut self) {
+        if self.state == GameState::Playing {
+            self.state = GameState::Paused;
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.state == GameState::Paused {
+            self.state = GameState::Playing;
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        return self.state == GameState::Paused;
+    }
+
+    // MOVEMENT FUNCTIONS
+
+    pub fn perform(&mut self, action: Action) -> Vec<GameEvent> {
+        self.apply_action(action);
+        return self.take_events();
+    }
+
+    fn apply_action(&mut self, action: Action) {
+        if self.is_paused() && action != Action::Resume {
+            return;
+        }
+        // There is no figure to control until the cleared lines are gone
+        if self.is_waiting_for_figure() && Game::moves_figure(action) {
+            return;
+        }
+        match action {
+            Action::MoveLeft => self.move_left(),
+            Action::MoveRight => self.move_right(),
+            Action::MoveDown => {
+                self.move_down();
+            }
+            Action::Rotate => self.rotate_active_figure(),
+            Action::RotateCCW => self.rotate_active_figure_counter_clockwise(),
+            Action::Rotate180 => self.rotate_active_figure_180(),
+            Action::Hold => self.hold_active_figure(),
+            Action::HardDrop => self.hard_drop(),
+            Action::SoftDrop => self.soft_drop(),
+            Action::SoftDropStart => self.soft_dropping = true,
+            Action::SoftDropStop => self.soft_dropping = false,
+            Action::Pause => self.pause(),
+            Action::Resume => self.resume(),
+            Action::SonicDrop => self.sonic_drop(),
+            Action::MoveLeftWall => self.move_to_wall(ActiveFigure::moved_left),
+            Action::MoveRightWall => self.move_to_wall(ActiveFigure::moved_right),
+            Action::ActivateSpecial => self.activate_special(),
+        }
+    }
+
+    // ACTION QUEUE
+
+    // Queued actions run in order at the start of the next `update` or `step_frame`
+    pub fn queue_action(&mut self, action: Action) {
+        self.queued_actions.push_back(action);
+    }
+
+    pub fn get_queued_actions(&self) -> Vec<Action> {
+        return self.queued_actions.iter().cloned().collect();
+    }
+
+    fn process_queued_actions(&mut self) {
+        while let Some(action) = self.queued_actions.pop_front() {
+            if self.is_waiting_for_figure() && !self.is_paused() && self.buffer_action(action) {
+                continue;
+            }
+            self.apply_action(action);
+        }
+    }
+
+    // Rotations (IRS) and hold (IHS) queued while there is no figure apply to the next one
+    fn buffer_action(&mut self, action: Action) -> bool {
+        match action {
+            Action::Rotate | Action::RotateCCW | Action::Rotate180 => {
+                self.buffered_rotation = Some(action);
+            }
+            Action::Hold => self.buffered_hold = true,
+            _ => return false,
+        }
+        return true;
+    }
+
+    fn apply_buffered_actions(&mut self) {
+        let rotation = self.buffered_rotation.take();
+        if core::mem::take(&mut self.buffered_hold) {
+            self.hold_active_figure();
+        }
+        if let Some(rotation) = rotation {
+            self.apply_action(rotation);
+        }
+    }
+
+    fn moves_figure(action: Action) -> bool {
+        return !matches!(
+            action,
+            Action::SoftDropStart
+                | Action::SoftDropStop
+                | Action::Pause
+                | Action::Resume
+                | Action::ActivateSpecial
+        );
+    }
+
+    fn move_left(&mut self) {
+        self.piece_inputs += 1;
+        self.update_active_with(self.active.moved_left());
+    }
+
+    fn move_right(&mut self) {
+        self.piece_inputs += 1;
+        self.update_active_with(self.active.moved_right());
+    }
+
+    // Counts as one input, like a held direction in the finesse checks
+    fn move_to_wall(&mut self, step: fn(&ActiveFigure) -> ActiveFigure) {
+        self.piece_inputs += 1;
+        let moved = shifted(&self.active, &self.board, step);
+        if moved != self.active {
+            self.update_active_with(moved);
+        }
+    }
+
+    fn move_down(&mut self) -> bool {
+        return self.update_active_with(self.active.moved_down());
+    }
+
+    fn soft_drop(&mut self) {
+        if self.move_down() {
+            self.add_score_for_soft_drop(1);
+        }
+    }
+
+    fn hard_drop(&mut self) {
+        if self.has_ended() {
+            return;
+        }
+        let dropped_cells = drop_distance(&self.active, &self.board);
+        if dropped_cells > 0 {
+            self.active = self.active.translated(0, dropped_cells);
+            self.last_rotation_kick = None;
+        }
+        self.add_score_for_hard_drop(dropped_cells as u64);
+        self.update_next_figure();
+    }
+
+    fn sonic_drop(&mut self) {
+        while self.move_down() {}
+    }
+
+    fn rotate_active_figure(&mut self) {
+        let tests = self
+            .active
+            .wall_kicked_rotation_tests(&self.config.rotation_system);
+        self.rotate_active_figure_with(tests);
+    }
+
+    fn rotate_active_figure_counter_clockwise(&mut self) {
+        let tests = self
+            .active
+            .wall_kicked_counter_clockwise_tests(&self.config.rotation_system);
+        self.rotate_active_figure_with(tests);
+    }
+
+    fn rotate_active_figure_180(&mut self) {
+        let tests = self
+            .active
+            .wall_kicked_180_tests(&self.config.rotation_system);
+        self.rotate_active_figure_with(tests);
+    }
+
+    fn rotate_active_figure_with(&mut self, tests: Vec<ActiveFigure>) {
+        self.piece_inputs += 1;
+        if let Some((kick_index, rotated)) = self.first_valid_figure(tests) {
+            self.update_active_with(rotated);
+            self.last_rotation_kick = Some(kick_index);
+        }
+    }
+
+    // INPUT
+
+    pub fn input(&mut self, key: Key, key_state: KeyState) -> Vec<GameEvent> {
+        // Releases still go through so no key is left held after resuming
+        if self.is_paused() && key_state == KeyState::Pressed {
+            return self.take_events();
+        }
+        match (key, key_state) {
+            (Key::Left, KeyState::Pressed) => self.press_direction(Direction::Left),
+            (Key::Right, KeyState::Pressed) => self.press_direction(Direction::Right),
+            (Key::Left, KeyState::Released) => self.input_state.release(Direction::Left),
+            (Key::Right, KeyState::Released) => self.input_state.release(Direction::Right),
+            (Key::SoftDrop, KeyState::Pressed) => self.soft_dropping = true,
+            (Key::SoftDrop, KeyState::Released) => self.soft_dropping = false,
+            (_, KeyState::Released) => {}
+            _ if self.is_waiting_for_figure() => {}
+            (Key::HardDrop, KeyState::Pressed) => self.hard_drop(),
+            (Key::RotateCW, KeyState::Pressed) => self.rotate_active_figure(),
+            (Key::RotateCCW, KeyState::Pressed) => self.rotate_active_figure_counter_clockwise(),
+            (Key::Rotate180, KeyState::Pressed) => self.rotate_active_figure_180(),
+            (Key::Hold, KeyState::Pressed) => self.hold_active_figure(),
+        }
+        return self.take_events();
+    }
+
+    fn press_direction(&mut self, direction: Direction) {
+        self.input_state.press(direction);
+        if !self.is_waiting_for_figure() {
+            self.piece_inputs += 1;
+            self.shift(direction);
+        }
+    }
+
+    fn shift(&mut self, direction: Direction) -> bool {
+        return match direction {
+            Direction::Left => self.update_active_with(self.active.moved_left()),
+            Direction::Right => self.update_active_with(self.active.moved_right()),
+        };
+    }
+
+    fn update_auto_repeat(&mut self, delta_time: f64) {
+        let das = self.span(self.config.das);
+        let arr = self.span(self.config.arr);
+        let moves = self.input_state.advance(delta_time, das, arr);
+        if self.is_waiting_for_figure() {
+            return;
+        }
+        if let Some(direction) = self.input_state.direction() {
+            for _ in 0..moves.min(self.board.width()) {
+                if !self.shift(direction) {
+                    break;
+                }
+            }
+        }
+    }
+
+    // HOLD
+
+    fn hold_active_figure(&mut self) {
+        if !self.config.hold_enabled || !self.can_hold || self.has_ended() {
+            return;
+        }
+        let active_type = self.active.get_type();
+        match self.held.take() {
+            Some(held_type) => {
+                let width = self.board.width();
+                let figure = Game::spawn_figure(held_type, width, &self.config);
+                self.active = self.spawned(figure);
+            }
+            None => self.spawn_next_figure(),
+        }
+        self.reset_lock_state();
+        self.piece_inputs = 0;
+        self.spawning = true;
+        self.held = Some(active_type.clone());
+        self.can_hold = false;
+        self.emit(GameEvent::PieceHeld(active_type));
+        self.stats.record_hold();
+        self.update_state();
+    }
+
+    pub fn get_held_figure(&self) -> Option<FigureType> {
+        return self.held.clone();
+    }
+
+    // AI
+
+    // Every distinct place the active figure can lock at, empty while the figure can't be moved
+    pub fn enumerate_placements(&self) -> Vec<Placement> {
+        if self.has_ended() || self.is_paused() || self.is_waiting_for_figure() {
+            return vec![];
+        }
+        return enumerate_placements(&self.active, &self.board, &self.config.rotation_system);
+    }
+
+    // Plays the placement path and hard drops, placements that are not reachable are ignored
+    pub fn apply_placement(&mut self, placement: &Placement) -> Vec<GameEvent> {
+        let blocked = self.has_ended() || self.is_paused() || self.is_waiting_for_figure();
+        let rotation_system = &self.config.rotation_system;
+        if blocked || !is_reachable(placement, &self.active, &self.board, rotation_system) {
+            return self.take_events();
+        }
+        let mut events = vec![];
+        for action in &placement.path {
+            events.extend(self.perform(*action));
+        }
+        events.extend(self.perform(Action::HardDrop));
+        return events;
+    }
+
+    // Assists, holding is suggested when the figure it brings has a clearly better placement
+    pub fn suggested_hold(&self) -> Option<FigureType> {
+        if !self.config.hold_enabled || !self.can_hold || self.is_waiting_for_figure() {
+            return None;
+        }
+        if self.has_ended() || self.is_paused() {
+            return None;
+        }
+        let swap = self.held.clone().unwrap_or_else(|| self.next[0].clone());
+        let rotation_system = &self.config.rotation_system;
+        let width = self.board.width();
+        let figure = Game::spawn_figure(swap.clone(), width, &self.config);
+        let swap_value = best_placement_value(&figure, &self.board, rotation_system)?;
+        let value = best_placement_value(&self.active, &self.board, rotation_system);
+        return match value {
+            Some(value) if value + HOLD_SUGGESTION_MARGIN >= swap_value => None,
+            _ => Some(swap),
+        };
+    }
+
+    fn check_hold_suggestion(&mut self) {
+        if !self.config.assists.hold_suggestion {
+            return;
+        }
+        if let Some(figure_type) = self.suggested_hold() {
+            self.emit(GameEvent::HoldSuggested(figure_type));
+        }
+    }
+
+    // GARBAGE
+
+    pub fn add_garbage_lines(&mut self, count: usize, hole_column: usize) -> Vec<GameEvent> {
+        self.push_garbage_lines(count, hole_column);
+        return self.take_events();
+    }
+
+    fn push_garbage_lines(&mut self, count: usize, hole_column: usize) {
+        if self.has_ended() || count == 0 {
+            return;
+        }
+        let hole_column = hole_column.min(self.board.width().saturating_sub(1));
+        let pushed_out = self.config.top_out.push_out
+            && (0..count).any(|line| !self.board.is_line_empty(line));
+        self.board = self.board.adding_garbage_lines(count, hole_column);
+        self.keep_stacked_lines_at_bottom(count);
+        self.emit(GameEvent::GarbageReceived(count));
+        if self.is_waiting_for_figure() {
+            self.clearing_lines = self.pushed_up_clearing_lines(count);
+            if pushed_out {
+                self.top_out();
+            }
+            return;
+        }
+        let pushed_active = self.pushed_up_active_figure(count);
+        match pushed_active {
+            Some(active) if !pushed_out => self.active = active,
+            _ => self.top_out(),
+        }
+    }
+
+    fn pushed_up_clearing_lines(&self, rows: usize) -> Vec<usize> {
+        return self
+            .clearing_lines
+            .iter()
+            .filter(|line| **line >= rows)
+            .map(|line| line - rows)
+            .collect();
+    }
+
+    fn pushed_up_active_figure(&self, max_rows: usize) -> Option<ActiveFigure> {
+        let mut active = self.active.clone();
+        for _ in 0..=max_rows {
+            if has_valid_position(&active, &self.board) {
+                return Some(active);
+            }
+            active = active.moved_up();
+        }
+        return None;
+    }
+
+    // WALL KICK
+
+    fn first_valid_figure(&self, tests: Vec<ActiveFigure>) -> Option<(usize, ActiveFigure)> {
+        return first_valid_rotation(tests, &self.board, &self.config.rotation_system);
+    }
+
+    // Game state mutation
+
+    fn update_active_with(&mut self, new_active: ActiveFigure) -> bool {
+        if has_valid_position(&new_active, &self.board) {
+            self.active = new_active;
+            self.last_rotation_kick = None;
+            self.on_active_moved();
+            return true;
+        }
+        return false;
+    }
+
+    // Lock delay
+
+    fn on_active_moved(&mut self) {
+        let row = self.active.position().y;
+        if row > self.lowest_row {
+            self.lowest_row = row;
+            self.lock_resets = 0;
+            self.lock_time = 0.0;
+        } else if self.lock_time > 0.0 && self.lock_resets < self.config.max_lock_resets {
+            self.lock_resets += 1;
+            self.lock_time = 0.0;
+        }
+    }
+
+    fn reset_lock_state(&mut self) {
+        self.gravity_progress = 0;
+        self.lock_time = 0.0;
+        self.lock_resets = 0;
+        self.lowest_row = self.active.position().y;
+        self.last_rotation_kick = None;
+    }
+
+    fn add_active_figure_to_board(&mut self) {
+        let figure_type = self.active.get_type();
+        for point in self.active.to_cartesian() {
+            if point.x < 0 || point.y < 0 {
+                continue;
+            }
+            self.board
+                .set_figure_at_xy(point.x as usize, point.y as usize, Some(figure_type.clone()));
+        }
+    }
+
+    fn add_new_active_figure(&mut self) {
+        let next = self.pop_next_figure();
+        self.active = self.spawned(next);
+        self.reset_lock_state();
+        self.piece_inputs = 0;
+        self.can_hold = true;
+        self.spawning = true;
+    }
+
+    fn spawn_next_figure(&mut self) {
+        let next = self.pop_next_figure();
+        self.active = self.spawned(next);
+    }
+
+    fn spawned(&self, figure: ActiveFigure) -> ActiveFigure {
+        if self.config.top_out.partial_spawn && !has_valid_position(&figure, &self.board) {
+            let raised = figure.moved_up();
+            if has_valid_position(&raised, &self.board) {
+                return raised;
+            }
+        }
+        return figure;
+    }
+
+    // Next queue
+
+    fn pop_next_figure(&mut self) -> ActiveFigure {
+        self.fill_next_queue();
+        let figure_type = self.next.pop_front().unwrap_or(FigureType::I);
+        self.fill_next_queue();
+        return Game::spawn_figure(figure_type, self.board.width(), &self.config);
+    }
+
+    fn fill_next_queue(&mut self) {
+        while self.next.len() < self.config.preview_count.max(1) {
+            let figure = self.next_random_figure();
+            self.next.push_back(figure);
+        }
+    }
+
+    pub fn get_next_figure_type(&self) -> FigureType {
+        return self.next[0].clone();
+    }
+
+    pub fn get_next_queue(&self) -> Vec<FigureType> {
+        return self.next.iter().take(self.config.preview_count).cloned().collect();
+    }
+
+    pub fn get_preview_count(&self) -> usize {
+        return self.config.preview_count;
+    }
+
+    pub fn set_preview_count(&mut self, count: usize) {
+        self.config.preview_count = count;
+        self.fill_next_queue();
+    }
+
+    fn start_clearing_lines(&mut self) -> usize {
+        let lines = self.lines_completed();
+        if lines.is_empty() {
+            return 0;
+        }
+        self.lines += lines.len();
+        self.clearing_lines = lines.clone();
+        self.clear_time = 0.0;
+        self.last_cleared_lines = self.visible_rows(&lines);
+        self.emit(GameEvent::LinesCleared(self.last_cleared_lines.clone()));
+        return self.clearing_lines.len();
+    }
+
+    // Events
+
+    fn emit(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    fn take_events(&mut self) -> Vec<GameEvent> {
+        self.check_danger();
+        return core::mem::take(&mut self.events);
+    }
+
+    // Lines checks
+
+    // The stacked lines at the bottom are full but wait for the special mechanic to end
+    fn lines_completed(&self) -> Vec<usize> {
+        return self.lines_completed_on(&self.board);
+    }
+
+    fn lines_completed_on(&self, board: &Board) -> Vec<usize> {
+        let mut completed_lines: Vec<usize> = vec![];
+        for line_number in 0..board.height() - self.get_stacked_lines() {
+            if board.is_line_full(line_number) {
+                completed_lines.push(line_number);
+            }
+        }
+        return completed_lines;
+    }
+
+    // Score
+
+    fn score_for(&self, clear_kind: &ClearKind) -> u64 {
+        return self.config.scoring.score_clear(
+            clear_kind,
+            self.level,
+            self.get_combo(),
+            self.get_back_to_back(),
+        );
+    }
+
+    fn add_score_for_hard_drop(&mut self, dropped_cells: u64) {
+        self.score += self.config.scoring.score_hard_drop(dropped_cells);
+    }
+
+    fn add_score_for_soft_drop(&mut self, dropped_cells: u64) {
+        self.score += self.config.scoring.score_soft_drop(dropped_cells);
+    }
+
+    // T-spins
+
+    fn check_t_spin(&self) -> Option<TSpin> {
+        let kick_index = self.last_rotation_kick?;
+        return detect_t_spin(&self.active, &self.board, kick_index);
+    }
+
+    fn clear_kind_for(lines: usize, t_spin: Option<TSpin>) -> ClearKind {
+        return match (t_spin, lines) {
+            (Some(TSpin::Full), _) => ClearKind::TSpin(lines),
+            (Some(TSpin::Mini), _) => ClearKind::TSpinMini(lines),
+            (None, 0) => ClearKind::None,
+            (None, _) => ClearKind::Lines(lines),
+        };
+    }
+
+    pub fn get_last_clear_kind(&self) -> ClearKind {
+        return self.last_clear_kind.clone();
+    }
+
+    // Combos and back-to-back
+
+    fn update_chains(&mut self, clear_kind: &ClearKind) {
+        if clear_kind.lines() == 0 {
+            self.combo = None;
+            return;
+        }
+        self.combo = Some(self.combo.map_or(0, |combo| combo + 1));
+        if clear_kind.is_difficult() {
+            self.back_to_back = Some(self.back_to_back.map_or(0, |chain| chain + 1));
+        } else {
+            self.back_to_back = None;
+        }
+    }
+
+    pub fn get_combo(&self) -> usize {
+        return self.combo.unwrap_or(0);
+    }
+
+    pub fn get_back_to_back(&self) -> usize {
+        return self.back_to_back.unwrap_or(0);
+    }
+
+    // Attack
+
+    fn send_attack_for(&mut self, clear_kind: &ClearKind) {
+        let attack = attack_for(clear_kind, self.get_combo(), self.get_back_to_back());
+        let attack = self.cancel_pending_garbage(attack);
+        if attack > 0 {
+            self.emit(GameEvent::Attack(attack));
+        }
+    }
+
+    // Levels
+
+    // For progress bars, it follows `GameConfig::level_goal`
+    pub fn get_lines_to_next_level(&self) -> usize {
+        return self.config.lines_to_next_level(self.lines);
+    }
+
+    fn update_level(&mut self) {
+        let level = self.config.level_for_lines(self.lines);
+        if level > self.level {
+            self.level = level;
+            self.emit(GameEvent::LevelUp(level));
+        }
+    }
+
+    fn gravity_interval(&self) -> f64 {
+        let assists = &self.config.assists;
+        let interval = self.config.gravity_curve.interval(self.level);
+        let interval = interval.max(assists.min_gravity_interval) * self.gravity_slowdown();
+        if self.soft_dropping {
+            return interval / self.config.soft_drop_factor;
+        }
+        return interval;
+    }
+
+    pub fn get_level(&self) -> usize {
+        return self.level;
+    }
+
+    // Block out, the figure that just spawned overlaps the stack
+    fn check_is_game_over(&self) -> bool {
+        return self.config.top_out.block_out && !has_valid_position(&self.active, &self.board);
+    }
+
+    // Lock out, every cell of the locked figure is above the visible field
+    fn is_locked_out(&self) -> bool {
+        let hidden_rows = self.config.hidden_rows as i32;
+        return self.config.top_out.lock_out
+            && self.active.cells().all(|point| point.y < hidden_rows);
+    }
+
+    // MODES
+
+    fn check_mode_finished(&mut self) {
+        if self.has_ended() {
+            return;
+        }
+        let finished = match self.config.mode {
+            GameMode::Marathon => false,
+            GameMode::Sprint { lines } => self.lines >= lines,
+            GameMode::Ultra { seconds } => self.get_elapsed_time() >= seconds,
+        };
+        if finished {
+            self.state = GameState::Finished;
+            self.emit(GameEvent::ModeFinished(self.mode_result()));
+        }
+    }
+
+    fn mode_result(&self) -> ModeResult {
+        return ModeResult {
+            score: self.score,
+            lines: self.lines,
+            level: self.level,
+            elapsed: self.get_elapsed_time(),
+        };
+    }
+
+    pub fn get_elapsed_time(&self) -> f64 {
+        return self.seconds(self.elapsed);
+    }
+
+    // Same clock as `get_elapsed_time`, for timers that display it
+    pub fn elapsed(&self) -> Duration {
+        return Duration::try_from_secs_f64(self.get_elapsed_time()).unwrap_or_default();
+    }
+
+    // Seconds since the active piece spawned, holding keeps the clock running
+    pub fn get_piece_time(&self) -> f64 {
+        return self.seconds(self.piece_time);
+    }
+
+    // Seconds spent without a piece to control, in line clears and entry delays
+    pub fn get_idle_time(&self) -> f64 {
+        return self.seconds(self.idle_time);
+    }
+
+    // Timers count frames in frame mode
+    fn seconds(&self, time: f64) -> f64 {
+        if let Some(tick_rate) = self.config.tick_rate {
+            return time / tick_rate as f64;
+        }
+        return time;
+    }
+
+    // FINESSE
+
+    // Compares the moves and rotations pressed for the figure about to lock with the fewest possible
+    fn check_finesse(&mut self) {
+        if !self.config.finesse_check {
+            return;
+        }
+        let spawn = Game::spawn_figure(self.active.get_type(), self.board.width(), &self.config);
+        let rotation_system = &self.config.rotation_system;
+        if let Some(optimal) = optimal_moves(&spawn, &self.active, &self.board, rotation_system) {
+            if self.piece_inputs > optimal.len() {
+                let used = self.piece_inputs;
+                self.emit(GameEvent::FinesseFault { used, optimal });
+            }
+        }
+    }
+
+    // STATS
+
+    pub fn stats(&self) -> Stats {
+        let mut stats = self.stats.clone();
+        stats.elapsed = self.get_elapsed_time();
+        return stats;
+    }
+
+    // Same as `stats().total_pieces` without copying the statistics
+    pub fn get_pieces_placed(&self) -> usize {
+        return self.stats.total_pieces;
+    }
+
+    pub fn get_score(&self) -> u64 {
+        return self.score;
+    }
+
+    pub fn get_lines_completed(&self) -> usize {
+        return self.lines;
+    }
+}
+
+#[cfg(test)]
+mod game_tests {
+    use super::super::scoring::{HARD_DROP_POINTS_PER_CELL, SOFT_DROP_POINTS_PER_CELL};
+    use super::super::{
+        FigureDef, FinesseMove, GravityCurve, LevelGoal, RandomizerChoice, Rotation,
+        RotationSystem, SequenceRandomizer, TopOutRules,
+    };
+    use super::fixtures::o_slot_game;
+    use super::*;
+
+    fn locked(kind: FigureType, x: i32, y: i32) -> GameEvent {
+        return GameEvent::PieceLocked(PiecePose::new(kind, Point { x, y }, Rotation::Spawn));
+    }
+
+    fn game_with_sequence(values: Vec<i32>) -> Game {
+        let size = Size {
+            height: 20,
+            width: 10,
+        };
+        return Game::new(&size, SequenceRandomizer::new(values));
+    }
+
+    #[test]
+    fn test_new_accepts_boxed_and_plain_randomizers() {
+        let size = Size {
+            height: 20,
+            width: 10,
+        };
+        let boxed: Box<dyn IndexRandomizer> = Box::new(SequenceRandomizer::new(vec![1]));
+        let game = Game::new(&size, boxed);
+        assert_eq!(game.active.get_type(), FigureType::J);
+        let game = Game::new(&size, BagRandomizer::new(1));
+        assert!(!game.is_game_over());
+    }
+
+    #[test]
+    fn test_new_with_seed_is_reproducible() {
+        let size = Size {
+            height: 20,
+            width: 10,
+        };
+        let mut first = Game::new_with_seed(&size, 1234);
+        let mut second = Game::new_with_seed(&size, 1234);
+        for _ in 0..10 {
+            assert_eq!(first.active, second.active);
+            assert_eq!(first.get_next_queue(), second.get_next_queue());
+            first.perform(Action::HardDrop);
+            second.perform(Action::HardDrop);
+        }
+    }
+
+    #[test]
+    fn test_hold_with_empty_slot_spawns_next() {
+        let mut game = game_with_sequence(vec![0, 5, 3]);
+        game.perform(Action::Hold);
+        assert_eq!(game.get_held_figure(), Some(FigureType::I));
+        assert_eq!(game.active.get_type(), FigureType::T);
+        assert_eq!(game.next[0], FigureType::O);
+    }
+
+    #[test]
+    fn test_nes_preset_game() {
+        let mut game = Game::with_config(GameConfig::nes());
+        assert_eq!(game.get_level(), 0);
+        assert_eq!(game.get_next_queue().len(), 1);
+        game.perform(Action::Hold);
+        assert_eq!(game.get_held_figure(), None);
+    }
+
+    #[test]
+    fn test_palette_colors_drawing() {
+        let mut game = game_with_sequence(vec![3, 0]);
+        let color = FigureType::Z.color();
+        game.set_palette(Palette::default().with(&FigureType::O, color));
+        assert!(game.draw_active_figure().iter().all(|block| block.color == color));
+        assert_eq!(game.active_figure_color(), color);
+        assert_eq!(game.draw_next(1)[0][0].color, FigureType::I.color());
+        game.perform(Action::HardDrop);
+        assert!(game.draw_board().iter().all(|block| block.color == color));
+    }
+
+    #[test]
+    fn test_render_ascii() {
+        let mut game = game_with_sequence(vec![3]);
+        fill_row_except(&mut game, 19, &[0]);
+        let text = game.render_ascii();
+        let rows: Vec<&str> = text.lines().collect();
+        assert_eq!(rows.len(), 20);
+        assert_eq!(rows[game.active.position().y as usize], "...OO.....");
+        assert_eq!(rows[19], ".ZZZZZZZZZ");
+    }
+
+    #[test]
+    fn test_ghost_lands_where_hard_drop_does() {
+        let mut game = game_with_sequence(vec![3, 0]);
+        let ghost = game.draw_ghost();
+        assert!(ghost.iter().all(|block| block.color == game.get_palette().ghost));
+        let mut landed: Vec<Point> = ghost.iter().map(|block| block.position()).collect();
+        game.perform(Action::HardDrop);
+        let mut locked: Vec<Point> = game.draw_board().iter().map(|block| block.position()).collect();
+        landed.sort_by_key(|point| (point.y, point.x));
+        locked.sort_by_key(|point| (point.y, point.x));
+        assert_eq!(landed, locked);
+    }
+
+    #[test]
+    fn test_blocked_cells_are_kept_out_of_clears() {
+        let config = GameConfig {
+            randomizer: RandomizerChoice::Sequence(vec![0]),
+            blocked_cells: vec![Point { x: 9, y: 19 }],
+            ..GameConfig::default()
+        };
+        let mut game = Game::with_config(config);
+        assert_eq!(game.access_blocked(), vec![Point { x: 9, y: 19 }]);
+        // Two I pieces and a vertical one fill the bottom row up to the blocked cell
+        game.perform(Action::MoveLeft);
+        game.perform(Action::MoveLeft);
+        game.perform(Action::MoveLeft);
+        game.perform(Action::HardDrop);
+        game.perform(Action::MoveRight);
+        game.perform(Action::HardDrop);
+        game.perform(Action::Rotate);
+        for _ in 0..3 {
+            game.perform(Action::MoveRight);
+        }
+        assert_eq!(game.active.right_edge(), 8);
+        let events = game.perform(Action::HardDrop);
+        assert!(events.contains(&GameEvent::LinesCleared(vec![19])));
+        assert!(game.board.is_blocked(9, 19));
+        assert_eq!(game.access_board().len(), 3);
+    }
+
+    #[test]
+    fn test_with_board_keeps_blocked_cells() {
+        let board = Board::new(&Size {
+            height: 20,
+            width: 10,
+        })
+        .blocking(&[Point { x: 0, y: 19 }]);
+        let config = GameConfig {
+            hidden_rows: 2,
+            ..GameConfig::default()
+        };
+        let game = Game::with_board(board, config);
+        assert!(game.board.is_blocked(0, 21));
+        assert_eq!(game.access_blocked(), vec![Point { x: 0, y: 19 }]);
+    }
+
+    #[test]
+    fn test_hold_only_once_per_piece() {
+        let mut game = game_with_sequence(vec![0, 5, 3]);
+        game.perform(Action::Hold);
+        game.perform(Action::Hold);
+        assert_eq!(game.get_held_figure(), Some(FigureType::I));
+        assert_eq!(game.active.get_type(), FigureType::T);
+    }
+
+    #[test]
+    fn test_hold_swaps_after_lock() {
+        let mut game = game_with_sequence(vec![0, 5, 3]);
+        game.perform(Action::Hold);
+        for _ in 0..10 {
+            game.perform(Action::MoveDown);
+        }
+        game.update_next_figure();
+        game.perform(Action::Hold);
+        assert_eq!(game.get_held_figure(), Some(FigureType::O));
+        assert_eq!(game.active.get_type(), FigureType::I);
+        assert_eq!(
+            game.active.position(),
+            Game::spawn_figure(FigureType::I, 10, &GameConfig::default()).position()
+        );
+    }
+
+    #[test]
+    fn test_pause_freezes_the_game() {
+        let mut game = game_with_sequence(vec![3]);
+        game.pause();
+        assert!(game.is_paused());
+        game.update(10.0);
+        let spawn = Game::spawn_figure(FigureType::O, 10, &GameConfig::default()).position();
+        assert_eq!(game.active.position(), spawn);
+        game.perform(Action::MoveLeft);
+        game.input(Key::Right, KeyState::Pressed);
+        assert_eq!(game.active.position(), spawn);
+        game.perform(Action::Resume);
+        assert!(!game.is_paused());
+        game.perform(Action::MoveLeft);
+        assert_eq!(game.active.position().x, 2);
+    }
+
+    #[test]
+    fn test_cannot_pause_after_game_over() {
+        let mut game = game_with_sequence(vec![3]);
+        game.top_out();
+        game.perform(Action::Pause);
+        assert!(!game.is_paused());
+        assert!(game.is_game_over());
+    }
+
+    #[test]
+    fn test_last_cleared_lines_last_until_the_next_lock() {
+        let mut game = game_with_sequence(vec![0, 3]);
+        assert!(game.last_cleared_lines().is_empty());
+        fill_row_except(&mut game, 18, &[3, 4, 5, 6]);
+        fill_row_except(&mut game, 19, &[7]);
+        game.perform(Action::HardDrop);
+        assert_eq!(game.last_cleared_lines(), &[18]);
+        game.update(1.0);
+        assert_eq!(game.last_cleared_lines(), &[18]);
+        game.perform(Action::HardDrop);
+        assert!(game.last_cleared_lines().is_empty());
+    }
+
+    #[test]
+    fn test_line_clear_delay() {
+        let mut game = game_with_sequence(vec![0, 3]);
+        game.config.line_clear_delay = 0.3;
+        fill_row_except(&mut game, 19, &[3, 4, 5, 6]);
+        let events = game.perform(Action::HardDrop);
+        assert!(events.contains(&GameEvent::LinesCleared(vec![19])));
+        assert!(game.is_clearing());
+        assert_eq!(game.get_clearing_lines(), vec![19]);
+        assert!(!game.board.is_line_empty(19));
+        game.perform(Action::HardDrop);
+        assert_eq!(game.active.get_type(), FigureType::I);
+        game.update(0.2);
+        assert!(game.is_clearing());
+        game.update(0.2);
+        assert!(!game.is_clearing());
+        assert!(game.board.is_line_empty(19));
+        assert_eq!(game.active.get_type(), FigureType::O);
+    }
+
+    #[test]
+    fn test_no_line_clear_delay_by_default() {
+        let mut game = game_with_sequence(vec![0, 3]);
+        fill_row_except(&mut game, 19, &[3, 4, 5, 6]);
+        game.perform(Action::HardDrop);
+        assert!(!game.is_clearing());
+        assert!(game.board.is_line_empty(19));
+    }
+
+    #[test]
+    fn test_custom_piece_set() {
+        let color = FigureType::I.color();
+        let pentomino = FigureType::custom(FigureDef::new("I5", vec![vec![1, 1, 1, 1, 1]], color));
+        let config = GameConfig::builder()
+            .piece_set(vec![pentomino.clone(), FigureType::O])
+            .randomizer(RandomizerChoice::Sequence(vec![0, 1, 7]))
+            .build();
+        let mut game = Game::with_config(config);
+        assert_eq!(game.active.get_type(), pentomino);
+        assert_eq!(game.get_next_queue()[..2], [FigureType::O, FigureType::O]);
+        game.perform(Action::HardDrop);
+        let bottom: Vec<Block> = game.draw_board();
+        assert_eq!(bottom.len(), 5);
+        assert!(bottom.iter().all(|block| block.position().y == 19));
+    }
+
+    fn spawn_columns(figure_type: FigureType, width: usize) -> Vec<i32> {
+        let figure = Game::spawn_figure(figure_type, width, &GameConfig::default());
+        let mut columns: Vec<i32> = figure.to_cartesian().iter().map(|point| point.x).collect();
+        columns.sort();
+        columns.dedup();
+        return columns;
+    }
+
+    #[test]
+    fn test_spawn_fits_narrow_boards() {
+        assert_eq!(spawn_columns(FigureType::I, 4), vec![0, 1, 2, 3]);
+        assert_eq!(spawn_columns(FigureType::T, 3), vec![0, 1, 2]);
+        assert_eq!(spawn_columns(FigureType::O, 2), vec![0, 1]);
+        assert_eq!(spawn_columns(FigureType::O, 10), vec![3, 4]);
+        // Empty columns around a custom shape do not move it off center
+        let color = FigureType::I.color();
+        let def = FigureDef::new("dot", vec![vec![0, 0, 0], vec![0, 0, 1]], color);
+        assert_eq!(spawn_columns(FigureType::custom(def.clone()), 5), vec![2]);
+        let offset = def.with_spawn_offset(Point { x: 5, y: 0 });
+        assert_eq!(spawn_columns(FigureType::custom(offset), 5), vec![4]);
+    }
+
+    #[test]
+    fn test_spawn_rotations() {
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![5]))
+            .spawn_rotation(FigureType::T, Rotation::Right)
+            .spawn_rotation(FigureType::T, Rotation::Reverse)
+            .build();
+        assert_eq!(config.spawn_rotations, vec![(FigureType::T, Rotation::Reverse)]);
+        let mut game = Game::with_config(config);
+        let pose = game.active_pose().unwrap();
+        assert_eq!(pose.rotation, Rotation::Reverse);
+        let mut columns: Vec<i32> = pose.cells().iter().map(|point| point.x).collect();
+        columns.dedup();
+        assert_eq!(columns, vec![3, 4, 5, 4]);
+        assert!(pose.cells().iter().all(|point| point.y >= 0));
+        // The kicks are the ones of the spawn state
+        game.perform(Action::Rotate);
+        assert_eq!(game.active_pose().unwrap().rotation, Rotation::Left);
+        // Two state figures stay in their two states
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![0]))
+            .rotation_system(RotationSystem::Nintendo)
+            .spawn_rotation(FigureType::I, Rotation::Reverse)
+            .build();
+        let game = Game::with_config(config);
+        assert_eq!(game.active_pose().unwrap().rotation, Rotation::Spawn);
+        let nes = GameConfig::nes();
+        assert_eq!(nes.spawn_rotation(&FigureType::T), Rotation::Reverse);
+        assert_eq!(nes.spawn_rotation(&FigureType::I), Rotation::Spawn);
+    }
+
+    #[test]
+    fn test_custom_piece_set_bag() {
+        let config = GameConfig::builder()
+            .piece_set(vec![FigureType::O, FigureType::T])
+            .seed(3)
+            .build();
+        let game = Game::with_config(config);
+        let queue = game.get_next_queue();
+        assert!(queue.iter().all(|figure| *figure == FigureType::O || *figure == FigureType::T));
+        assert_ne!(game.active.get_type(), queue[0]);
+    }
+
+    #[test]
+    fn test_with_board() {
+        let mut rows = vec![vec![None; 10]; 18];
+        rows.push(vec![Some(FigureType::Garbage); 9]);
+        rows.push(vec![Some(FigureType::Garbage); 9]);
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![3]))
+            .build();
+        let mut game = Game::with_board(Board::from_rows(rows.clone()), config);
+        assert_eq!(game.get_board().size(), Size { height: 20, width: 10 });
+        assert!(!game.board.is_line_empty(19));
+        assert_eq!(game.draw_board().len(), 18);
+        game.perform(Action::HardDrop);
+        assert_eq!(game.get_board().to_rows()[17][4], Some(FigureType::O));
+        assert!(!game.is_game_over());
+    }
+
+    #[test]
+    fn test_danger_events() {
+        let mut game = o_slot_game(3, GameConfig::builder().danger_height(3));
+        assert!(game.in_danger());
+        let events = game.perform(Action::HardDrop);
+        assert!(events.contains(&GameEvent::DangerLeft));
+        assert!(!game.in_danger());
+        let mut events = vec![];
+        while !game.in_danger() {
+            events.extend(game.perform(Action::HardDrop));
+        }
+        assert_eq!(events.last(), Some(&GameEvent::DangerEntered));
+        assert!(!game.perform(Action::MoveLeft).contains(&GameEvent::DangerEntered));
+        // Off without a threshold
+        assert!(!o_slot_game(3, GameConfig::builder()).in_danger());
+    }
+
+    #[test]
+    fn test_with_full_board_is_game_over() {
+        let rows = vec![vec![Some(FigureType::Garbage); 10]; 20];
+        let game = Game::with_board(Board::from_rows(rows), GameConfig::default());
+        assert!(game.is_game_over());
+    }
+
+    #[test]
+    fn test_sprint_finishes_after_the_line_goal() {
+        let mut game = game_with_sequence(vec![0, 3]);
+        game.config.mode = GameMode::Sprint { lines: 1 };
+        game.update(1.5);
+        fill_row_except(&mut game, 19, &[3, 4, 5, 6]);
+        let events = game.perform(Action::HardDrop);
+        let result = ModeResult {
+            score: game.get_score(),
+            lines: 1,
+            level: 1,
+            elapsed: 1.5,
+        };
+        assert!(events.contains(&GameEvent::ModeFinished(result)));
+        assert!(game.is_finished());
+        assert!(!game.is_game_over());
+        game.update(1.0);
+        assert_eq!(game.get_elapsed_time(), 1.5);
+        assert!(game.perform(Action::HardDrop).is_empty());
+    }
+
+    #[test]
+    fn test_ultra_finishes_after_the_time_limit() {
+        let mut game = game_with_sequence(vec![3]);
+        game.config.mode = GameMode::Ultra { seconds: 2.0 };
+        assert!(game.update(1.0).is_empty());
+        game.pause();
+        game.update(5.0);
+        game.resume();
+        let events = game.update(1.0);
+        assert!(matches!(events.last(), Some(GameEvent::ModeFinished(_))));
+        assert!(game.is_finished());
+    }
+
+    #[test]
+    fn test_marathon_never_finishes() {
+        let mut game = game_with_sequence(vec![3]);
+        game.update(1000.0);
+        assert!(!game.is_finished());
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut game = game_with_sequence(vec![0, 3, 0]);
+        game.update(0.5);
+        fill_row_except(&mut game, 19, &[3, 4, 5, 6]);
+        game.perform(Action::HardDrop);
+        game.perform(Action::Hold);
+        game.perform(Action::HardDrop);
+        let stats = game.stats();
+        assert_eq!(stats.total_pieces, 2);
+        assert_eq!(stats.pieces_of(&FigureType::I), 2);
+        assert_eq!(stats.singles, 1);
+        assert_eq!(stats.holds, 1);
+        assert_eq!(stats.elapsed, 0.5);
+        assert_eq!(stats.pps(), 4.0);
+    }
+
+    fn finesse_faults(events: &[GameEvent]) -> Vec<(usize, Vec<FinesseMove>)> {
+        return events
+            .iter()
+            .filter_map(|event| match event {
+                GameEvent::FinesseFault { used, optimal } => Some((*used, optimal.clone())),
+                _ => None,
+            })
+            .collect();
+    }
+
+    #[test]
+    fn test_finesse_fault() {
+        let mut game = game_with_sequence(vec![3]);
+        game.config.finesse_check = true;
+        for _ in 0..3 {
+            game.perform(Action::MoveLeft);
+        }
+        let events = game.perform(Action::HardDrop);
+        assert_eq!(finesse_faults(&events), vec![(3, vec![FinesseMove::DasLeft])]);
+        game.input(Key::Left, KeyState::Pressed);
+        game.update(0.5);
+        game.input(Key::Left, KeyState::Released);
+        assert!(finesse_faults(&game.perform(Action::HardDrop)).is_empty());
+    }
+
+    #[test]
+    fn test_finesse_counts_rotations() {
+        let mut game = game_with_sequence(vec![5]);
+        game.config.finesse_check = true;
+        game.perform(Action::Rotate);
+        game.perform(Action::Rotate);
+        let events = game.perform(Action::HardDrop);
+        assert_eq!(finesse_faults(&events), vec![(2, vec![FinesseMove::Rotate180])]);
+        game.perform(Action::MoveRight);
+        assert!(finesse_faults(&game.perform(Action::HardDrop)).is_empty());
+    }
+
+    fn frame_game() -> Game {
+        let mut game = game_with_sequence(vec![3]);
+        game.config.tick_rate = Some(60);
+        return game;
+    }
+
+    #[test]
+    fn test_step_frame_gravity_in_frames() {
+        let mut game = frame_game();
+        let start = game.active.position();
+        for _ in 0..60 {
+            game.step_frame();
+        }
+        assert_eq!(game.active.position(), start);
+        game.step_frame();
+        assert_eq!(game.active.position().y, start.y + 1);
+        assert_eq!(game.get_elapsed_time(), 61.0 / 60.0);
+    }
+
+    #[test]
+    fn test_fractional_gravity_keeps_the_remainder() {
+        let mut game = frame_game();
+        game.config.gravity_curve = GravityCurve::CellsPerFrame(0.5);
+        let start = game.active.position();
+        game.step_frame();
+        assert_eq!(game.active.position(), start);
+        game.step_frame();
+        assert_eq!(game.active.position().y, start.y + 1);
+        game.config.gravity_curve = GravityCurve::CellsPerFrame(3.0);
+        game.step_frame();
+        assert_eq!(game.active.position().y, start.y + 4);
+    }
+
+    #[test]
+    fn test_instant_gravity_rests_on_the_stack() {
+        let mut game = frame_game();
+        game.config.gravity_curve = GravityCurve::CellsPerFrame(20.0);
+        game.config.lock_delay = 0.0;
+        game.step_frame();
+        assert!(!can_move_down(&game.active, &game.board));
+        let pose = game.active_pose().unwrap();
+        let events = game.step_frame();
+        assert!(events.contains(&GameEvent::PieceLocked(pose)));
+        // The next figure spawned and landed on the first one in the same frame
+        assert!(!can_move_down(&game.active, &game.board));
+        assert!(game.active.position().y > game.config.hidden_rows as i32);
+    }
+
+    #[test]
+    fn test_step_frame_auto_repeat_in_frames() {
+        let mut game = frame_game();
+        let start = game.active.position();
+        game.input(Key::Left, KeyState::Pressed);
+        // 0.167s of DAS are 10 frames, then 0.033s of ARR are 2 frames per move
+        for _ in 0..9 {
+            game.step_frame();
+        }
+        assert_eq!(game.active.position().x, start.x - 1);
+        game.step_frame();
+        assert_eq!(game.active.position().x, start.x - 2);
+        game.step_frame();
+        game.step_frame();
+        assert_eq!(game.active.position().x, start.x - 3);
+    }
+
+    #[test]
+    fn test_update_in_frame_mode_keeps_the_remainder() {
+        let mut game = frame_game();
+        game.update(0.01);
+        assert_eq!(game.get_elapsed_time(), 0.0);
+        game.update(0.01);
+        assert_eq!(game.get_elapsed_time(), 1.0 / 60.0);
+    }
+
+    #[test]
+    fn test_update_clamped_catches_up() {
+        // One big update only lets the figure fall, frame by frame it locks and the next spawns
+        let mut game = game_with_sequence(vec![3]);
+        game.update(10.0);
+        assert_eq!(game.get_pieces_placed(), 0);
+        let mut game = game_with_sequence(vec![3]);
+        let (events, report) = game.update_clamped(30.0, 2000);
+        assert!(game.get_pieces_placed() > 0);
+        assert!(events.contains(&locked(FigureType::O, 3, 18)));
+        assert_eq!(report.steps, 1800);
+        assert!(!report.is_lagging());
+        let (_, report) = game.update_clamped(10.0, 60);
+        assert_eq!(report.steps, 60);
+        assert!((report.dropped_time - 9.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_update_clamped_in_frame_mode() {
+        let mut game = frame_game();
+        let (_, report) = game.update_clamped(1.01, 30);
+        assert_eq!(report.steps, 30);
+        assert!((report.dropped_time - 0.5).abs() < 1e-9);
+        assert_eq!(game.get_elapsed_time(), 0.5);
+        // The part of a frame left is kept like `update` does
+        let (_, report) = game.update_clamped(0.01, 30);
+        assert_eq!(report.steps, 1);
+        assert!(!report.is_lagging());
+    }
+
+    fn game_with_top_out(rows: Vec<Vec<Option<FigureType>>>, top_out: TopOutRules) -> Game {
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![3]))
+            .top_out(top_out)
+            .build();
+        return Game::with_board(Board::from_rows(rows), config);
+    }
+
+    #[test]
+    fn test_partial_spawn() {
+        let mut rows = vec![vec![None; 10]; 20];
+        rows[1] = vec![Some(FigureType::Garbage); 9];
+        let game = game_with_top_out(rows.clone(), TopOutRules::default());
+        assert!(game.is_game_over());
+        let game = game_with_top_out(rows, TopOutRules::guideline());
+        assert!(!game.is_game_over());
+        assert_eq!(game.active.position().y, -1);
+    }
+
+    #[test]
+    fn test_lock_out() {
+        let mut rows = vec![vec![None; 10]; 20];
+        rows[0][7] = Some(FigureType::Garbage);
+        let above_the_field = ActiveFigure::new(FigureType::O, Point { x: 6, y: -2 });
+        let mut game = game_with_top_out(rows.clone(), TopOutRules::guideline());
+        game.active = above_the_field.clone();
+        let events = game.perform(Action::HardDrop);
+        assert_eq!(events, vec![locked(FigureType::O, 6, -2), GameEvent::GameOver]);
+        let mut game = game_with_top_out(rows, TopOutRules::default());
+        game.active = above_the_field;
+        game.perform(Action::HardDrop);
+        assert!(!game.is_game_over());
+    }
+
+    #[test]
+    fn test_push_out_disabled() {
+        let mut rows = vec![vec![None; 10]; 20];
+        rows[0][0] = Some(FigureType::Garbage);
+        let top_out = TopOutRules {
+            push_out: false,
+            ..TopOutRules::default()
+        };
+        let mut game = game_with_top_out(rows.clone(), top_out);
+        game.add_garbage_lines(1, 9);
+        assert!(!game.is_game_over());
+        let mut game = game_with_top_out(rows, TopOutRules::default());
+        game.add_garbage_lines(1, 9);
+        assert!(game.is_game_over());
+    }
+
+    #[test]
+    fn test_active_type_and_rotation() {
+        let mut game = game_with_sequence(vec![5]);
+        assert_eq!(game.active_figure_type(), FigureType::T);
+        assert_eq!(game.active_rotation(), 0);
+        game.perform(Action::RotateCCW);
+        assert_eq!(game.active_rotation(), 3);
+        game.perform(Action::Rotate180);
+        assert_eq!(game.active_rotation(), 1);
+    }
+
+    #[test]
+    fn test_hidden_rows() {
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![3]))
+            .hidden_rows(20)
+            .build();
+        let mut game = Game::with_config(config);
+        assert_eq!(game.get_board().height(), 40);
+        assert_eq!(game.get_visible_size(), Size { height: 20, width: 10 });
+        assert_eq!(game.iter_board().count(), 200);
+        // The figure spawns in the last hidden row, only its bottom half is drawn
+        assert_eq!(game.active.position().y, 19);
+        assert_eq!(game.access_active_figure(), vec![Point { x: 3, y: 0 }, Point { x: 4, y: 0 }]);
+        assert_eq!(game.draw_active_figure().len(), 2);
+        game.perform(Action::HardDrop);
+        let rows: Vec<i32> = game.board_cells().iter().map(|(point, _)| point.y).collect();
+        assert_eq!(rows, vec![18, 18, 19, 19]);
+    }
+
+    #[test]
+    fn test_hidden_rows_with_board() {
+        let mut rows = vec![vec![None; 10]; 19];
+        rows.push(vec![Some(FigureType::Garbage); 9]);
+        let config = GameConfig::builder().hidden_rows(4).build();
+        let game = Game::with_board(Board::from_rows(rows), config);
+        assert_eq!(game.get_board().height(), 24);
+        assert_eq!(game.get_visible_size().height, 20);
+        assert_eq!(game.draw_board().len(), 9);
+        assert!(game.draw_board().iter().all(|block| block.position().y == 19));
+    }
+
+    #[test]
+    fn test_hold_disabled() {
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![0, 5, 3]))
+            .hold_enabled(false)
+            .build();
+        let mut game = Game::with_config(config);
+        assert!(game.perform(Action::Hold).is_empty());
+        assert_eq!(game.get_held_figure(), None);
+        assert_eq!(game.active.get_type(), FigureType::I);
+    }
+
+    #[test]
+    fn test_with_config_uses_size_and_randomizer() {
+        let config = GameConfig::builder()
+            .size(Size {
+                height: 30,
+                width: 12,
+            })
+            .randomizer(RandomizerChoice::Sequence(vec![3, 1]))
+            .preview_count(3)
+            .build();
+        let game = Game::with_config(config);
+        assert_eq!(game.board.width(), 12);
+        assert_eq!(game.board.height(), 30);
+        assert_eq!(game.active.get_type(), FigureType::O);
+        assert_eq!(game.get_next_queue(), vec![FigureType::J, FigureType::O, FigureType::J]);
+    }
+
+    #[test]
+    fn test_with_config_cuts_wide_boards_and_plays_empty_ones() {
+        let wide = GameConfig::builder()
+            .size(Size {
+                height: 20,
+                width: 100,
+            })
+            .build();
+        let mut game = Game::with_config(wide);
+        assert_eq!(game.get_visible_size().width, MAX_WIDTH);
+        game.perform(Action::HardDrop);
+        assert!(!game.has_ended());
+        let empty = GameConfig::builder()
+            .size(Size {
+                height: 0,
+                width: 0,
+            })
+            .build();
+        let mut game = Game::with_config(empty);
+        game.perform(Action::HardDrop);
+        game.perform(Action::SonicDrop);
+        assert_eq!(game.get_board().width(), 0);
+    }
+
+    #[test]
+    fn test_next_queue_keeps_randomizer_order() {
+        let mut game = game_with_sequence(vec![0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(
+            game.get_next_queue(),
+            vec![FigureType::J, FigureType::L, FigureType::O, FigureType::S, FigureType::T]
+        );
+        game.perform(Action::HardDrop);
+        assert_eq!(game.active.get_type(), FigureType::J);
+        assert_eq!(game.get_next_queue()[4], FigureType::Z);
+    }
+
+    #[test]
+    fn test_preview_count_does_not_skip_pieces() {
+        let mut game = game_with_sequence(vec![0, 1, 2, 3, 4, 5, 6]);
+        game.set_preview_count(2);
+        assert_eq!(game.get_next_queue(), vec![FigureType::J, FigureType::L]);
+        game.perform(Action::HardDrop);
+        assert_eq!(game.get_next_queue(), vec![FigureType::L, FigureType::O]);
+        game.set_preview_count(7);
+        assert_eq!(game.get_next_queue().len(), 7);
+    }
+
+    #[test]
+    fn test_draw_into_reuses_the_vector() {
+        let mut game = game_with_sequence(vec![0, 3]);
+        game.perform(Action::HardDrop);
+        let mut blocks = vec![Block::new(-1, -1, 1, 1, FigureType::Z.color())];
+        game.draw_into(&mut blocks);
+        assert_eq!(blocks, game.draw());
+        assert_eq!(blocks.len(), 8);
+        game.draw_board_into(&mut blocks);
+        assert_eq!(blocks, game.draw_board());
+        game.draw_active_figure_into(&mut blocks);
+        assert_eq!(blocks, game.draw_active_figure());
+        assert_eq!(game.blocks().count(), 8);
+    }
+
+    #[test]
+    fn test_draw_cells() {
+        let mut game = game_with_sequence(vec![3, 0]);
+        game.add_garbage_lines(1, 0);
+        game.perform(Action::HardDrop);
+        let cells = game.draw_cells();
+        assert_eq!(cells.len(), 17);
+        assert_eq!(
+            cells[0],
+            Cell::new(3, 17, FigureType::O.color(), FigureType::O),
+        );
+        assert_eq!(cells[12].kind, FigureType::Garbage);
+        assert_eq!(cells[16].kind, FigureType::I);
+        let blocks: Vec<Block> = cells.iter().map(|cell| cell.to_block()).collect();
+        assert_eq!(blocks, game.draw());
+        let mut reused = vec![];
+        game.draw_cells_into(&mut reused);
+        assert_eq!(reused, cells);
+    }
+
+    #[test]
+    fn test_draw_optimized() {
+        let mut game = game_with_sequence(vec![3, 0]);
+        game.add_garbage_lines(2, 0);
+        game.perform(Action::HardDrop);
+        let blocks = game.draw_optimized();
+        // The I, the O, and the garbage rows right of their holes
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[1], Block::new(3, 16, 2, 2, FigureType::O.color()));
+        assert_eq!(blocks[2], Block::new(1, 18, 2, 9, FigureType::Garbage.color()));
+        let area: usize = blocks
+            .iter()
+            .map(|block| block.size().height * block.size().width)
+            .sum();
+        assert_eq!(area, game.draw_cells().len());
+    }
+
+    #[test]
+    fn test_board_cells() {
+        let mut game = game_with_sequence(vec![3]);
+        game.add_garbage_lines(1, 0);
+        game.perform(Action::HardDrop);
+        let cells = game.board_cells();
+        assert_eq!(cells.len(), 13);
+        assert_eq!(cells[0], (Point { x: 3, y: 17 }, FigureType::O));
+        assert_eq!(cells[12], (Point { x: 9, y: 19 }, FigureType::Garbage));
+        let points: Vec<Point> = cells.into_iter().map(|(point, _)| point).collect();
+        assert_eq!(points, game.access_board());
+    }
+
+    #[test]
+    fn test_draw_next() {
+        let game = game_with_sequence(vec![0, 3]);
+        let drawn = game.draw_next(2);
+        assert_eq!(drawn.len(), 2);
+        assert_eq!(drawn[0].len(), 4);
+        assert_eq!(drawn[0][0].color, FigureType::O.color());
+        assert_eq!(drawn[0][3].position(), Point { x: 1, y: 1 });
+    }
+
+    #[test]
+    fn test_draw_next_figure_at() {
+        let game = game_with_sequence(vec![0, 3]);
+        assert_eq!(game.get_next_figure_type(), FigureType::O);
+        let drawn = game.draw_next_figure_at(Point { x: 12, y: 2 });
+        assert_eq!(drawn.len(), 4);
+        assert_eq!(drawn[0].color, FigureType::O.color());
+        assert_eq!(drawn[3].position(), Point { x: 13, y: 3 });
+    }
+
+    #[test]
+    fn test_start_level_from_config() {
+        let size = Size {
+            height: 20,
+            width: 10,
+        };
+        let config = GameConfig {
+            start_level: 4,
+            ..GameConfig::default()
+        };
+        let game = Game::new_with_config(&size, SequenceRandomizer::new(vec![0]), config);
+        assert_eq!(game.get_level(), 4);
+        assert_eq!(game.gravity_interval(), game.config.gravity_curve.interval(4));
+    }
+
+    #[test]
+    fn test_variable_goal_level_up() {
+        let mut game = game_with_sequence(vec![3]);
+        game.config.level_goal = LevelGoal::Variable;
+        game.lines = 4;
+        assert_eq!(game.get_lines_to_next_level(), 1);
+        fill_row_except(&mut game, 19, &[3, 4]);
+        let events = game.perform(Action::HardDrop);
+        assert!(events.contains(&GameEvent::LevelUp(2)));
+        assert_eq!(game.get_lines_to_next_level(), 10);
+    }
+
+    #[test]
+    fn test_level_up_event() {
+        let mut game = game_with_sequence(vec![3]);
+        game.lines = 9;
+        for x in 2..10 {
+            game.board = game.board.replacing_figure_at_xy(x, 19, Some(FigureType::I));
+        }
+        for _ in 0..3 {
+            game.perform(Action::MoveLeft);
+        }
+        let events = game.perform(Action::HardDrop);
+        assert!(events.contains(&GameEvent::LevelUp(2)));
+        assert_eq!(game.get_level(), 2);
+    }
+
+    fn drop_without_locking(game: &mut Game) {
+        while can_move_down(&game.active, &game.board) {
+            game.perform(Action::MoveDown);
+        }
+    }
+
+    #[test]
+    fn test_move_to_wall() {
+        let mut game = game_with_sequence(vec![3]);
+        game.perform(Action::MoveLeftWall);
+        assert_eq!(game.active.left_edge(), 0);
+        assert_eq!(game.piece_inputs, 1);
+        game.perform(Action::MoveRightWall);
+        assert_eq!(game.active.right_edge(), 9);
+        let y = game.active.bottom_edge() as usize;
+        game.board = game.board.replacing_figure_at_xy(4, y, Some(FigureType::Z));
+        game.perform(Action::MoveLeftWall);
+        assert_eq!(game.active.left_edge(), 5);
+        assert_eq!(game.piece_inputs, 3);
+    }
+
+    #[test]
+    fn test_sonic_drop_does_not_lock() {
+        let mut game = game_with_sequence(vec![3, 5]);
+        let score = game.get_score();
+        assert!(game.perform(Action::SonicDrop).is_empty());
+        assert!(!can_move_down(&game.active, &game.board));
+        assert_eq!(game.active.get_type(), FigureType::O);
+        assert_eq!(game.get_score(), score);
+        game.perform(Action::MoveLeft);
+        let pose = game.active_pose().unwrap();
+        assert_eq!(game.update(0.5), vec![GameEvent::PieceLocked(pose)]);
+    }
+
+    #[test]
+    fn test_lock_delay() {
+        let mut game = game_with_sequence(vec![3, 5]);
+        drop_without_locking(&mut game);
+        assert!(game.update(0.3).is_empty());
+        let pose = game.active_pose().unwrap();
+        assert_eq!(game.update(0.2), vec![GameEvent::PieceLocked(pose)]);
+    }
+
+    #[test]
+    fn test_assists_cap_gravity_and_extend_lock_delay() {
+        let mut game = game_with_sequence(vec![3, 5]);
+        game.config.gravity_curve = GravityCurve::Fixed(0.1);
+        game.config.assists.min_gravity_interval = 1.0;
+        game.config.assists.lock_delay_factor = 2.0;
+        let start = game.active.position();
+        game.update(0.5);
+        assert_eq!(game.active.position(), start);
+        game.update(0.5);
+        assert_eq!(game.active.position().y, start.y + 1);
+        drop_without_locking(&mut game);
+        assert!(game.update(0.5).is_empty());
+        let pose = game.active_pose().unwrap();
+        assert_eq!(game.update(0.5), vec![GameEvent::PieceLocked(pose)]);
+    }
+
+    #[test]
+    fn test_assists_clear_rows_instead_of_game_over() {
+        let mut game = game_with_sequence(vec![3]);
+        game.config.assists.clear_rows_on_top_out = 4;
+        for y in 2..20 {
+            fill_row_except(&mut game, y, &[9]);
+        }
+        let events = game.perform(Action::HardDrop);
+        assert!(events.contains(&GameEvent::TopOutAvoided(4)));
+        assert!(!game.is_game_over());
+        assert!(game.board.is_line_empty(3));
+        assert!(!game.board.is_line_empty(4));
+    }
+
+    #[test]
+    fn test_hold_suggestion() {
+        let mut game = game_with_sequence(vec![3, 3, 0]);
+        game.config.assists.hold_suggestion = true;
+        for y in 16..20 {
+            fill_row_except(&mut game, y, &[0]);
+        }
+        assert_eq!(game.suggested_hold(), None);
+        game.perform(Action::MoveRightWall);
+        let events = game.perform(Action::HardDrop);
+        // The I clears the four rows, the O would cover the well
+        assert!(events.contains(&GameEvent::HoldSuggested(FigureType::I)));
+        game.perform(Action::Hold);
+        assert_eq!(game.suggested_hold(), None);
+    }
+
+    #[test]
+    fn test_move_resets_lock_delay() {
+        let mut game = game_with_sequence(vec![3, 5]);
+        drop_without_locking(&mut game);
+        game.update(0.4);
+        game.perform(Action::MoveLeft);
+        assert!(game.update(0.4).is_empty());
+        let pose = game.active_pose().unwrap();
+        assert_eq!(game.update(0.1), vec![GameEvent::PieceLocked(pose)]);
+    }
+
+    #[test]
+    fn test_lock_resets_are_limited() {
+        let mut game = game_with_sequence(vec![3, 5]);
+        game.config.max_lock_resets = 2;
+        drop_without_locking(&mut game);
+        for _ in 0..2 {
+            game.update(0.4);
+            game.perform(Action::MoveLeft);
+        }
+        game.update(0.4);
+        game.perform(Action::MoveRight);
+        let pose = game.active_pose().unwrap();
+        assert_eq!(game.update(0.1), vec![GameEvent::PieceLocked(pose)]);
+    }
+
+    #[test]
+    fn test_rotation_kicks_off_the_wall() {
+        let mut game = game_with_sequence(vec![0]);
+        game.perform(Action::Rotate);
+        for _ in 0..5 {
+            game.perform(Action::MoveRight);
+        }
+        assert_eq!(game.active.right_edge(), 9);
+        game.perform(Action::Rotate);
+        assert_eq!(game.active.position(), Point { x: 6, y: 0 });
+        assert_eq!(game.active.right_edge(), 9);
+    }
+
+    #[test]
+    fn test_classic_rotation_does_not_kick() {
+        let mut game = game_with_sequence(vec![0]);
+        game.config.rotation_system = RotationSystem::Classic;
+        game.perform(Action::Rotate);
+        for _ in 0..5 {
+            game.perform(Action::MoveRight);
+        }
+        let before = game.active.clone();
+        game.perform(Action::Rotate);
+        assert_eq!(game.active, before);
+    }
+
+    #[test]
+    fn test_counter_clockwise_rotation_kicks_off_the_wall() {
+        let mut game = game_with_sequence(vec![5]);
+        game.perform(Action::Rotate);
+        for _ in 0..4 {
+            game.perform(Action::MoveLeft);
+        }
+        assert_eq!(game.active.left_edge(), 0);
+        game.perform(Action::RotateCCW);
+        assert_eq!(game.active.left_edge(), 0);
+        assert_eq!(game.active, ActiveFigure::new(FigureType::T, Point { x: 0, y: 0 }));
+    }
+
+    #[test]
+    fn test_rotate_180() {
+        let mut game = game_with_sequence(vec![5]);
+        game.perform(Action::MoveDown);
+        game.perform(Action::Rotate180);
+        assert_eq!(game.active.figure, Figure::new(FigureType::T).rotated().rotated());
+    }
+
+    #[test]
+    fn test_t_spin_double() {
+        let mut game = game_with_sequence(vec![5]);
+        // Leave a T shaped slot at the bottom with an overhang on the left
+        for x in 0..10 {
+            if x != 4 {
+                game.board = game.board.replacing_figure_at_xy(x, 19, Some(FigureType::I));
+            }
+            if !(3..=5).contains(&x) {
+                game.board = game.board.replacing_figure_at_xy(x, 18, Some(FigureType::I));
+            }
+        }
+        game.board = game.board.replacing_figure_at_xy(3, 17, Some(FigureType::I));
+        game.perform(Action::Rotate);
+        drop_without_locking(&mut game);
+        game.perform(Action::Rotate);
+        let events = game.perform(Action::HardDrop);
+        assert!(events.contains(&GameEvent::TSpin(ClearKind::TSpin(2))));
+        assert_eq!(game.get_last_clear_kind(), ClearKind::TSpin(2));
+        assert_eq!(game.get_score(), 1200);
+    }
+
+    #[test]
+    fn test_no_t_spin_without_rotation() {
+        let mut game = game_with_sequence(vec![5]);
+        game.perform(Action::HardDrop);
+        assert_eq!(game.get_last_clear_kind(), ClearKind::None);
+    }
+
+    fn fill_row_except(game: &mut Game, y: usize, holes: &[usize]) {
+        for x in 0..game.board.width() {
+            if !holes.contains(&x) {
+                game.board = game.board.replacing_figure_at_xy(x, y, Some(FigureType::Z));
+            }
+        }
+    }
+
+    fn game_with_chain_setup(gravity: ClearGravity, delay: f64) -> Game {
+        let mut game = game_with_sequence(vec![3]);
+        game.config.clear_gravity = gravity;
+        game.config.line_clear_delay = delay;
+        fill_row_except(&mut game, 19, &[3, 4]);
+        fill_row_except(&mut game, 18, &[0, 3, 4]);
+        game.board = game.board.replacing_figure_at_xy(0, 17, Some(FigureType::Z));
+        return game;
+    }
+
+    #[test]
+    fn test_cascade_chain_clear() {
+        let mut game = game_with_chain_setup(ClearGravity::Cascade, 0.0);
+        let events = game.perform(Action::HardDrop);
+        let clears: Vec<&GameEvent> = events
+            .iter()
+            .filter(|event| **event == GameEvent::LinesCleared(vec![19]))
+            .collect();
+        assert_eq!(clears.len(), 2);
+        assert!(events.contains(&GameEvent::Combo(1)));
+        assert_eq!(game.get_combo(), 1);
+        assert_eq!(game.get_lines_completed(), 2);
+        assert_eq!(game.placement_history()[0].lines_cleared, 2);
+        assert_eq!(game.placement_history()[0].score, game.get_score());
+        assert!(game.access_board().is_empty());
+    }
+
+    #[test]
+    fn test_chain_clear_waits_for_the_delay() {
+        let mut game = game_with_chain_setup(ClearGravity::Sticky, 0.5);
+        game.perform(Action::HardDrop);
+        let events = game.update(0.5);
+        assert!(game.is_clearing());
+        assert!(events.contains(&GameEvent::Combo(1)));
+        game.update(0.5);
+        assert!(!game.is_clearing());
+        assert!(game.access_board().is_empty());
+    }
+
+    #[test]
+    fn test_naive_gravity_does_not_chain() {
+        let mut game = game_with_chain_setup(ClearGravity::Naive, 0.0);
+        let events = game.perform(Action::HardDrop);
+        assert!(!events.iter().any(|event| matches!(event, GameEvent::Combo(_))));
+        assert_eq!(game.get_lines_completed(), 1);
+    }
+
+    #[test]
+    fn test_queued_actions_run_at_the_next_update() {
+        let mut game = game_with_sequence(vec![0]);
+        let start = game.active.position();
+        game.queue_action(Action::MoveLeft);
+        game.queue_action(Action::MoveLeft);
+        assert_eq!(game.active.position(), start);
+        assert_eq!(game.get_queued_actions().len(), 2);
+        game.update(0.0);
+        assert_eq!(game.active.position().x, start.x - 2);
+        assert!(game.get_queued_actions().is_empty());
+    }
+
+    #[test]
+    fn test_queued_resume_runs_while_paused() {
+        let mut game = game_with_sequence(vec![0]);
+        game.perform(Action::Pause);
+        game.queue_action(Action::Resume);
+        game.queue_action(Action::MoveRight);
+        game.step_frame();
+        assert!(!game.is_paused());
+        assert_eq!(game.active.position().x, 4);
+    }
+
+    #[test]
+    fn test_initial_rotation_and_hold_are_buffered() {
+        let mut game = game_with_sequence(vec![3, 5, 4]);
+        game.config.line_clear_delay = 0.5;
+        fill_row_except(&mut game, 19, &[3, 4]);
+        fill_row_except(&mut game, 18, &[3, 4]);
+        game.perform(Action::HardDrop);
+        assert!(game.is_clearing());
+        game.queue_action(Action::Hold);
+        game.queue_action(Action::Rotate);
+        game.queue_action(Action::MoveLeft);
+        game.update(0.0);
+        assert_eq!(game.get_held_figure(), None);
+        game.update(0.5);
+        // The T was held as it spawned, the S took its place already rotated
+        assert_eq!(game.get_held_figure(), Some(FigureType::T));
+        assert_eq!(game.active.get_type(), FigureType::S);
+        assert_eq!(game.active.rotation_step(), 1);
+    }
+
+    #[test]
+    fn test_entry_delay_before_the_next_spawn() {
+        let mut game = game_with_sequence(vec![3, 5]);
+        game.config.entry_delay = 0.2;
+        game.perform(Action::HardDrop);
+        assert!(game.is_in_entry_delay());
+        assert_eq!(game.active.get_type(), FigureType::O);
+        // Moves are ignored while there is no figure, the queued rotation is kept for the next one
+        game.perform(Action::MoveLeft);
+        game.queue_action(Action::RotateCCW);
+        game.update(0.1);
+        assert!(game.is_in_entry_delay());
+        assert!(game.enumerate_placements().is_empty());
+        game.update(0.1);
+        assert!(!game.is_in_entry_delay());
+        assert_eq!(game.active.get_type(), FigureType::T);
+        assert_eq!(game.active.rotation_step(), 3);
+    }
+
+    #[test]
+    fn test_entry_delay_follows_the_line_clear_delay() {
+        let mut game = game_with_sequence(vec![3, 5]);
+        game.config.line_clear_delay = 0.5;
+        game.config.entry_delay = 0.2;
+        fill_row_except(&mut game, 19, &[3, 4]);
+        game.perform(Action::HardDrop);
+        game.update(0.5);
+        assert!(!game.is_clearing());
+        assert!(game.is_in_entry_delay());
+        game.update(0.2);
+        assert_eq!(game.active.get_type(), FigureType::T);
+    }
+
+    #[test]
+    fn test_piece_and_idle_clocks() {
+        let mut game = game_with_sequence(vec![5, 3]);
+        game.config.line_clear_delay = 0.5;
+        game.config.entry_delay = 0.25;
+        fill_row_except(&mut game, 19, &[3, 4]);
+        game.update(0.25);
+        game.perform(Action::Hold);
+        game.update(0.25);
+        game.perform(Action::HardDrop);
+        assert_eq!(game.placement_history()[0].piece_time, 0.5);
+        game.update(0.5);
+        game.update(0.25);
+        assert_eq!(game.get_idle_time(), 0.75);
+        assert_eq!(game.get_piece_time(), 0.0);
+        game.update(0.5);
+        assert_eq!(game.get_piece_time(), 0.5);
+        assert_eq!(game.elapsed(), Duration::from_millis(1750));
+    }
+
+    #[test]
+    fn test_phases() {
+        let mut game = game_with_sequence(vec![3, 5]);
+        game.config.line_clear_delay = 0.5;
+        game.config.entry_delay = 0.2;
+        assert_eq!(game.phase(), GamePhase::Spawning);
+        game.update(0.1);
+        assert_eq!(game.phase(), GamePhase::Falling);
+        drop_without_locking(&mut game);
+        assert_eq!(game.phase(), GamePhase::Locking);
+        game.perform(Action::Pause);
+        assert_eq!(game.phase(), GamePhase::Paused);
+        game.perform(Action::Resume);
+        fill_row_except(&mut game, 19, &[3, 4]);
+        game.perform(Action::HardDrop);
+        assert_eq!(game.phase(), GamePhase::Clearing);
+        game.update(0.5);
+        assert_eq!(game.phase(), GamePhase::Are);
+        game.update(0.2);
+        assert_eq!(game.phase(), GamePhase::Spawning);
+        game.perform(Action::Hold);
+        assert_eq!(game.phase(), GamePhase::Spawning);
+        game.add_garbage_lines(20, 0);
+        assert_eq!(game.phase(), GamePhase::GameOver);
+    }
+
+    #[test]
+    fn test_combo() {
+        let mut game = game_with_sequence(vec![0]);
+        for y in 16..20 {
+            fill_row_except(&mut game, y, &[0, 1, 2, 3]);
+        }
+        game.perform(Action::MoveLeft);
+        game.perform(Action::MoveLeft);
+        game.perform(Action::MoveLeft);
+        game.perform(Action::HardDrop);
+        assert_eq!(game.get_combo(), 0);
+        game.perform(Action::MoveLeft);
+        game.perform(Action::MoveLeft);
+        game.perform(Action::MoveLeft);
+        let score = game.get_score();
+        game.perform(Action::HardDrop);
+        assert_eq!(game.get_combo(), 1);
+        assert_eq!(game.get_score() - score, 2 * 18 + 100 + 50);
+        game.perform(Action::HardDrop);
+        assert_eq!(game.get_combo(), 0);
+        assert_eq!(game.combo, None);
+    }
+
+    #[test]
+    fn test_back_to_back_tetris() {
+        let mut game = game_with_sequence(vec![0]);
+        for y in 12..20 {
+            fill_row_except(&mut game, y, &[9]);
+        }
+        game.perform(Action::Rotate);
+        for _ in 0..4 {
+            game.perform(Action::MoveRight);
+        }
+        game.perform(Action::HardDrop);
+        assert_eq!(game.get_back_to_back(), 0);
+        assert_eq!(game.back_to_back, Some(0));
+        game.perform(Action::Rotate);
+        for _ in 0..4 {
+            game.perform(Action::MoveRight);
+        }
+        let score = game.get_score();
+        let events = game.perform(Action::HardDrop);
+        assert!(events.contains(&GameEvent::Attack(5)));
+        assert_eq!(game.get_back_to_back(), 1);
+        assert_eq!(game.get_score() - score, 2 * 16 + 600 + 50);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let size = Size {
+            height: 20,
+            width: 10,
+        };
+        let mut game = Game::new(&size, BagRandomizer::new(9));
+        game.perform(Action::HardDrop);
+        game.perform(Action::Hold);
+        let json = serde_json::to_string(&game).unwrap();
+        let mut restored: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+        for _ in 0..5 {
+            game.perform(Action::HardDrop);
+            restored.perform(Action::HardDrop);
+        }
+        assert_eq!(restored.draw(), game.draw());
+        assert_eq!(restored.get_next_queue(), game.get_next_queue());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_fails_for_custom_randomizer() {
+        struct Custom;
+        impl IndexRandomizer for Custom {
+            fn random(&self) -> i32 {
+                return 0;
+            }
+        }
+        let size = Size {
+            height: 20,
+            width: 10,
+        };
+        let game = Game::new(&size, Custom);
+        assert!(serde_json::to_string(&game).is_err());
+        let game = Game::with_randomizer(GameConfig::default(), NoRepeats);
+        assert!(serde_json::to_string(&game).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_keeps_the_dealer_kind() {
+        let randomizer = super::super::Indexed::new(BagRandomizer::new(9));
+        let game = Game::with_randomizer(GameConfig::default(), randomizer);
+        let json = serde_json::to_string(&game).unwrap();
+        // Only the figure randomizer itself could resume from the state
+        assert!(serde_json::from_str::<Game>(&json).is_err());
+        // Saves from before the kind was recorded hold the state of an index randomizer alone
+        let game = Game::with_config(GameConfig::builder().seed(9).build());
+        let mut saved = serde_json::to_value(&game).unwrap();
+        for field in ["randomizer", "opening"] {
+            saved[field] = saved[field]["state"].clone();
+        }
+        let mut restored: Game = serde_json::from_value(saved).unwrap();
+        restored.perform(Action::HardDrop);
+        assert!(restored.rng_trace().iter().all(|draw| draw.value.is_some()));
+        assert!(restored.reset_keeping_sequence());
+        assert_eq!(restored.get_next_queue(), game.get_next_queue());
+    }
+
+    // Deals I and O in turn, from the history alone
+    struct NoRepeats;
+
+    impl Randomizer for NoRepeats {
+        fn next(&mut self, history: &[FigureType]) -> FigureType {
+            return match history.last() {
+                Some(FigureType::I) => FigureType::O,
+                _ => FigureType::I,
+            };
+        }
+
+        fn bag_index(&self) -> Option<usize> {
+            return Some(0);
+        }
+    }
+
+    #[test]
+    fn test_with_randomizer_passes_the_history() {
+        let mut game = Game::with_randomizer(GameConfig::default(), NoRepeats);
+        assert_eq!(game.active_figure_type(), FigureType::I);
+        assert_eq!(game.get_next_figure_type(), FigureType::O);
+        game.perform(Action::HardDrop);
+        game.perform(Action::HardDrop);
+        let kinds: Vec<FigureType> = game
+            .rng_trace()
+            .iter()
+            .map(|draw| draw.kind.clone())
+            .collect();
+        assert_eq!(kinds.len(), 8);
+        assert!(kinds.windows(2).all(|pair| pair[0] != pair[1]));
+        assert!(game
+            .rng_trace()
+            .iter()
+            .all(|draw| draw.value.is_none() && draw.bag_index == Some(0)));
+    }
+
+    #[test]
+    fn test_add_garbage_lines() {
+        let mut game = game_with_sequence(vec![3]);
+        game.perform(Action::HardDrop);
+        let events = game.add_garbage_lines(2, 0);
+        assert_eq!(events, vec![GameEvent::GarbageReceived(2)]);
+        assert!(game.board.contains(Point { x: 3, y: 17 }));
+        assert!(!game.board.contains(Point { x: 0, y: 19 }));
+        assert_eq!(game.board.figure_at_xy(1, 19), &Some(FigureType::Garbage));
+    }
+
+    #[test]
+    fn test_garbage_pushes_active_figure_up() {
+        let mut game = game_with_sequence(vec![3]);
+        drop_without_locking(&mut game);
+        game.add_garbage_lines(1, 9);
+        assert_eq!(game.active.bottom_edge(), 18);
+        assert!(!game.is_game_over());
+    }
+
+    #[test]
+    fn test_garbage_top_out() {
+        let mut game = game_with_sequence(vec![3]);
+        game.board = game.board.replacing_figure_at_xy(0, 1, Some(FigureType::I));
+        let events = game.add_garbage_lines(2, 9);
+        assert!(events.contains(&GameEvent::GameOver));
+        assert!(game.is_game_over());
+    }
+
+    #[test]
+    fn test_perform_returns_events() {
+        let mut game = game_with_sequence(vec![0, 5, 3]);
+        assert_eq!(game.perform(Action::Hold), vec![GameEvent::PieceHeld(FigureType::I)]);
+        assert_eq!(game.perform(Action::HardDrop), vec![locked(FigureType::T, 3, 18)]);
+        assert!(game.perform(Action::MoveLeft).is_empty());
+    }
+
+    #[test]
+    fn test_lines_cleared_event() {
+        let mut game = game_with_sequence(vec![3]);
+        for x in 2..10 {
+            game.board = game.board.replacing_figure_at_xy(x, 19, Some(FigureType::I));
+        }
+        game.perform(Action::MoveLeft);
+        game.perform(Action::MoveLeft);
+        game.perform(Action::MoveLeft);
+        let events = game.perform(Action::HardDrop);
+        assert_eq!(
+            events,
+            vec![
+                locked(FigureType::O, 0, 18),
+                GameEvent::LinesCleared(vec![19])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_game_over_event_emitted_once() {
+        let mut game = game_with_sequence(vec![3]);
+        let mut game_over_events = 0;
+        for _ in 0..20 {
+            let events = game.perform(Action::HardDrop);
+            game_over_events += events.iter().filter(|e| **e == GameEvent::GameOver).count();
+        }
+        assert!(game.is_game_over());
+        assert_eq!(game_over_events, 1);
+    }
+
+    #[test]
+    fn test_soft_drop_scores() {
+        let mut game = game_with_sequence(vec![3]);
+        game.perform(Action::SoftDrop);
+        game.perform(Action::SoftDrop);
+        assert_eq!(game.active.position(), Point { x: 3, y: 2 });
+        assert_eq!(game.get_score(), 2 * SOFT_DROP_POINTS_PER_CELL);
+        game.perform(Action::MoveDown);
+        assert_eq!(game.get_score(), 2 * SOFT_DROP_POINTS_PER_CELL);
+    }
+
+    #[test]
+    fn test_soft_drop_mode_speeds_up_gravity() {
+        let mut game = game_with_sequence(vec![3]);
+        game.perform(Action::SoftDropStart);
+        game.update(0.06);
+        assert_eq!(game.active.position(), Point { x: 3, y: 1 });
+        assert_eq!(game.get_score(), SOFT_DROP_POINTS_PER_CELL);
+        game.perform(Action::SoftDropStop);
+        game.update(0.06);
+        assert_eq!(game.active.position(), Point { x: 3, y: 1 });
+    }
+
+    #[test]
+    fn test_input_moves_and_auto_repeats() {
+        let mut game = game_with_sequence(vec![3]);
+        game.input(Key::Left, KeyState::Pressed);
+        assert_eq!(game.active.position().x, 2);
+        game.update(0.1);
+        assert_eq!(game.active.position().x, 2);
+        game.update(0.08);
+        assert_eq!(game.active.position().x, 1);
+        game.update(0.03);
+        assert_eq!(game.active.position().x, 0);
+        game.input(Key::Left, KeyState::Released);
+        game.input(Key::Right, KeyState::Pressed);
+        game.update(0.1);
+        assert_eq!(game.active.position().x, 1);
+    }
+
+    #[test]
+    fn test_input_instant_arr_reaches_the_wall() {
+        let mut game = game_with_sequence(vec![3]);
+        game.config.arr = 0.0;
+        game.input(Key::Right, KeyState::Pressed);
+        game.update(0.2);
+        assert_eq!(game.active.right_edge(), 9);
+    }
+
+    #[test]
+    fn test_input_actions() {
+        let mut game = game_with_sequence(vec![3, 5]);
+        game.input(Key::SoftDrop, KeyState::Pressed);
+        assert!(game.soft_dropping);
+        game.input(Key::SoftDrop, KeyState::Released);
+        assert!(!game.soft_dropping);
+        let events = game.input(Key::HardDrop, KeyState::Pressed);
+        assert_eq!(events, vec![locked(FigureType::O, 3, 18)]);
+        assert!(game.input(Key::HardDrop, KeyState::Released).is_empty());
+    }
+
+    #[test]
+    fn test_hard_drop_locks_and_scores() {
+        let mut game = game_with_sequence(vec![3, 5]);
+        game.perform(Action::HardDrop);
+        assert_eq!(game.get_score(), 18 * HARD_DROP_POINTS_PER_CELL);
+        assert!(game.board.contains(Point { x: 3, y: 19 }));
+        assert!(game.board.contains(Point { x: 4, y: 18 }));
+        assert_eq!(game.active.get_type(), FigureType::T);
+    }
+}
@@ -0,0 +1,25 @@
+// Boards shared by the tests of the game and the modules built on it
+use super::super::{Board, FigureType, GameConfigBuilder, RandomizerChoice};
+use super::Game;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// `height` rows 10 wide, the bottom `rows` of them garbage open at columns 3 and 4
+pub(crate) fn o_slot_rows(height: usize, rows: usize) -> Vec<Vec<Option<FigureType>>> {
+    let mut board = vec![vec![None; 10]; height - rows];
+    for _ in 0..rows {
+        let mut row = vec![Some(FigureType::Garbage); 10];
+        row[3] = None;
+        row[4] = None;
+        board.push(row);
+    }
+    return board;
+}
+
+// A 20x10 board dealing only O pieces, so each one dropped clears two of the rows
+pub(crate) fn o_slot_game(rows: usize, config: GameConfigBuilder) -> Game {
+    let config = config
+        .randomizer(RandomizerChoice::Sequence(vec![3]))
+        .build();
+    return Game::with_board(Board::from_rows(o_slot_rows(20, rows)), config);
+}
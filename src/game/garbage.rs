@@ -0,0 +1,157 @@
+use super::super::GameEvent;
+use super::Game;
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+
+// Garbage waiting to be pushed into the board, see `Game::queue_garbage`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PendingGarbage {
+    pub lines: usize,
+    pub hole_column: usize,
+    // Counted like the other timers, in seconds or in frames
+    time_left: f64,
+}
+
+impl Hash for PendingGarbage {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.lines.hash(state);
+        self.hole_column.hash(state);
+        self.time_left.to_bits().hash(state);
+    }
+}
+
+impl Game {
+    // Garbage sent by an opponent, it enters the board once `GameConfig::garbage_delay` is over
+    pub fn queue_garbage(&mut self, lines: usize, hole_column: usize) -> Vec<GameEvent> {
+        if self.has_ended() || lines == 0 {
+            return self.take_events();
+        }
+        let time_left = self.span(self.config.garbage_delay);
+        self.pending_garbage.push(PendingGarbage {
+            lines,
+            hole_column,
+            time_left,
+        });
+        self.push_ready_garbage();
+        return self.take_events();
+    }
+
+    // Versus matches apply their own rules to every game, queued garbage keeps its timer
+    pub fn set_garbage_rules(&mut self, garbage_delay: f64, garbage_cancellation: bool) {
+        self.config.garbage_delay = garbage_delay;
+        self.config.garbage_cancellation = garbage_cancellation;
+    }
+
+    // Queued garbage, oldest first
+    pub fn pending_garbage(&self) -> &[PendingGarbage] {
+        return &self.pending_garbage;
+    }
+
+    pub fn get_pending_garbage(&self) -> usize {
+        return self
+            .pending_garbage
+            .iter()
+            .map(|pending| pending.lines)
+            .sum();
+    }
+
+    pub(super) fn update_pending_garbage(&mut self, delta_time: f64) {
+        for pending in self.pending_garbage.iter_mut() {
+            pending.time_left -= delta_time;
+        }
+        self.push_ready_garbage();
+    }
+
+    // Garbage waits while a special mechanic is active
+    pub(super) fn push_ready_garbage(&mut self) {
+        if self.is_special_active() {
+            return;
+        }
+        while self
+            .pending_garbage
+            .first()
+            .is_some_and(|pending| pending.time_left <= 0.0)
+        {
+            let pending = self.pending_garbage.remove(0);
+            self.push_garbage_lines(pending.lines, pending.hole_column);
+        }
+    }
+
+    // Attacks cancel the queued garbage first, oldest first, and only the rest is sent
+    pub(super) fn cancel_pending_garbage(&mut self, attack: usize) -> usize {
+        if !self.config.garbage_cancellation {
+            return attack;
+        }
+        let mut left = attack;
+        while left > 0 && !self.pending_garbage.is_empty() {
+            let cancelled = left.min(self.pending_garbage[0].lines);
+            left -= cancelled;
+            self.pending_garbage[0].lines -= cancelled;
+            if self.pending_garbage[0].lines == 0 {
+                self.pending_garbage.remove(0);
+            }
+        }
+        if left < attack {
+            self.emit(GameEvent::GarbageCancelled(attack - left));
+        }
+        return left;
+    }
+}
+
+#[cfg(test)]
+mod garbage_tests {
+    use super::super::super::{Action, FigureType, GameConfig};
+    use super::super::fixtures::o_slot_game;
+    use super::*;
+    use alloc::vec;
+
+    // The O clears the two bottom rows, a double sends one line
+    fn double_ready_game(cancellation: bool) -> Game {
+        let config = GameConfig::builder().garbage_cancellation(cancellation);
+        return o_slot_game(2, config);
+    }
+
+    #[test]
+    fn test_queued_garbage_waits_for_the_delay() {
+        let mut game = double_ready_game(true);
+        assert!(game.queue_garbage(2, 0).is_empty());
+        assert_eq!(game.get_pending_garbage(), 2);
+        game.update(0.3);
+        assert_eq!(game.pending_garbage()[0].hole_column, 0);
+        let events = game.update(0.3);
+        assert!(events.contains(&GameEvent::GarbageReceived(2)));
+        assert_eq!(game.get_pending_garbage(), 0);
+        assert_eq!(game.get_board().figure_at_xy(0, 19), &None);
+        assert_eq!(
+            game.get_board().figure_at_xy(1, 19),
+            &Some(FigureType::Garbage)
+        );
+    }
+
+    #[test]
+    fn test_attack_cancels_queued_garbage() {
+        let mut game = double_ready_game(true);
+        game.queue_garbage(1, 0);
+        game.queue_garbage(2, 0);
+        let events = game.perform(Action::HardDrop);
+        assert!(events.contains(&GameEvent::GarbageCancelled(1)));
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, GameEvent::Attack(_))));
+        assert_eq!(game.get_pending_garbage(), 2);
+        let mut game = double_ready_game(false);
+        game.queue_garbage(1, 0);
+        let events = game.perform(Action::HardDrop);
+        assert!(events.contains(&GameEvent::Attack(1)));
+        assert_eq!(game.get_pending_garbage(), 1);
+    }
+
+    #[test]
+    fn test_no_delay_pushes_the_garbage_at_once() {
+        let mut game = double_ready_game(true);
+        game.set_garbage_rules(0.0, true);
+        let events = game.queue_garbage(1, 0);
+        assert_eq!(events, vec![GameEvent::GarbageReceived(1)]);
+    }
+}
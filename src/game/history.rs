@@ -0,0 +1,184 @@
+use super::super::FigureType;
+use super::{Game, GameSnapshot, PlacementRecord, RngDraw};
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+// A state to undo to. The placement and randomizer histories only grow while playing,
+// so the state keeps their lengths instead of copies
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct PieceState {
+    snapshot: GameSnapshot,
+    placements: usize,
+    rng_trace: usize,
+    dealt: usize,
+}
+
+// The state an undo left, with the history entries it cut for the redo to put back
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct UndonePiece {
+    state: PieceState,
+    spawn: Option<PieceState>,
+    placements: Vec<PlacementRecord>,
+    rng_trace: Vec<RngDraw>,
+    dealt: Vec<FigureType>,
+}
+
+// Undo and redo by whole pieces, the states are kept while `GameConfig::undo_depth` is above 0
+impl Game {
+    fn piece_state(&self) -> PieceState {
+        return PieceState {
+            snapshot: self.state_snapshot(),
+            placements: self.placements.len(),
+            rng_trace: self.rng_trace.len(),
+            dealt: self.dealt.len(),
+        };
+    }
+
+    // Called when a figure spawns, undoing the piece comes back here
+    pub(super) fn record_spawn(&mut self) {
+        if self.config.undo_depth > 0 {
+            self.spawn_snapshot = Some(self.piece_state());
+        }
+    }
+
+    pub(super) fn record_lock(&mut self) {
+        if let Some(snapshot) = self.spawn_snapshot.take() {
+            self.undo_history.push_back(snapshot);
+            while self.undo_history.len() > self.config.undo_depth {
+                self.undo_history.pop_front();
+            }
+            self.redo_history.clear();
+        }
+    }
+
+    // Goes back to the spawn of the last locked piece, with its board, score and queue
+    pub fn undo_piece(&mut self) -> bool {
+        let state = match self.undo_history.pop_back() {
+            Some(state) => state,
+            None => return false,
+        };
+        let undone = UndonePiece {
+            state: self.piece_state(),
+            spawn: self.spawn_snapshot.take(),
+            placements: cut(Rc::make_mut(&mut self.placements), state.placements),
+            rng_trace: cut(Rc::make_mut(&mut self.rng_trace), state.rng_trace),
+            dealt: cut(Rc::make_mut(&mut self.dealt), state.dealt),
+        };
+        self.redo_history.push(undone);
+        self.restore_state(&state.snapshot);
+        self.spawn_snapshot = Some(state);
+        return true;
+    }
+
+    // Replays an undone piece, locking a new piece forgets the undone ones
+    pub fn redo_piece(&mut self) -> bool {
+        let mut undone = match self.redo_history.pop() {
+            Some(undone) => undone,
+            None => return false,
+        };
+        if let Some(state) = self.spawn_snapshot.take() {
+            self.undo_history.push_back(state);
+        }
+        self.restore_state(&undone.state.snapshot);
+        Rc::make_mut(&mut self.placements).append(&mut undone.placements);
+        Rc::make_mut(&mut self.rng_trace).append(&mut undone.rng_trace);
+        Rc::make_mut(&mut self.dealt).append(&mut undone.dealt);
+        self.spawn_snapshot = undone.spawn;
+        return true;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        return !self.undo_history.is_empty();
+    }
+
+    pub fn can_redo(&self) -> bool {
+        return !self.redo_history.is_empty();
+    }
+}
+
+// The entries past `len`, none when a restored snapshot already left the list shorter
+fn cut<T>(list: &mut Vec<T>, len: usize) -> Vec<T> {
+    return list.split_off(len.min(list.len()));
+}
+
+#[cfg(test)]
+mod history_tests {
+    use super::super::super::{Action, GameConfig, RandomizerChoice};
+    use super::*;
+    use alloc::vec;
+
+    fn practice_game(depth: usize) -> Game {
+        let config = GameConfig {
+            randomizer: RandomizerChoice::Sequence(vec![0, 3, 5, 6]),
+            undo_depth: depth,
+            ..GameConfig::default()
+        };
+        return Game::with_config(config);
+    }
+
+    #[test]
+    fn test_undo_restores_the_piece() {
+        let mut game = practice_game(5);
+        assert!(!game.undo_piece());
+        let start = game.draw();
+        let queue = game.get_next_queue();
+        game.perform(Action::MoveLeft);
+        game.perform(Action::HardDrop);
+        assert_ne!(game.get_score(), 0);
+        assert!(game.undo_piece());
+        assert_eq!(game.draw(), start);
+        assert_eq!(game.get_next_queue(), queue);
+        assert_eq!(game.get_score(), 0);
+        assert!(!game.can_undo());
+    }
+
+    #[test]
+    fn test_redo_replays_the_undone_piece() {
+        let mut game = practice_game(5);
+        game.perform(Action::HardDrop);
+        game.perform(Action::HardDrop);
+        let after = game.draw();
+        assert!(game.undo_piece());
+        assert!(game.undo_piece());
+        assert!(game.redo_piece());
+        assert!(game.redo_piece());
+        assert!(!game.redo_piece());
+        assert_eq!(game.draw(), after);
+        assert!(game.undo_piece());
+        game.perform(Action::MoveRight);
+        game.perform(Action::HardDrop);
+        assert!(!game.can_redo());
+    }
+
+    #[test]
+    fn test_undo_cuts_the_histories_and_redo_puts_them_back() {
+        let mut game = practice_game(5);
+        game.perform(Action::HardDrop);
+        game.perform(Action::HardDrop);
+        let placements = game.placement_history().to_vec();
+        let trace = game.rng_trace().to_vec();
+        assert!(game.undo_piece());
+        assert!(game.undo_piece());
+        assert!(game.placement_history().is_empty());
+        assert_eq!(game.rng_trace(), &trace[..trace.len() - 2]);
+        assert!(game.redo_piece());
+        assert_eq!(game.placement_history(), &placements[..1]);
+        assert!(game.redo_piece());
+        assert_eq!(game.placement_history(), &placements[..]);
+        assert_eq!(game.rng_trace(), &trace[..]);
+    }
+
+    #[test]
+    fn test_history_depth() {
+        let mut game = practice_game(2);
+        for _ in 0..4 {
+            game.perform(Action::HardDrop);
+        }
+        assert!(game.undo_piece());
+        assert!(game.undo_piece());
+        assert!(!game.undo_piece());
+        let mut disabled = practice_game(0);
+        disabled.perform(Action::HardDrop);
+        assert!(!disabled.undo_piece());
+    }
+}
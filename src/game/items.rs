@@ -0,0 +1,215 @@
+use super::super::items::Item;
+use super::super::move_validator::has_valid_position;
+use super::super::randomizer::XorShift;
+use super::super::{ClearKind, GameEvent};
+use super::Game;
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+
+// Progress towards the next item and the timers of the items in use
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ItemState {
+    rng: XorShift,
+    // Lines cleared since the last item earned for lines
+    lines: usize,
+    // Counted like the other timers
+    slow_gravity_left: f64,
+}
+
+impl Hash for ItemState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rng.hash(state);
+        self.lines.hash(state);
+        self.slow_gravity_left.to_bits().hash(state);
+    }
+}
+
+impl ItemState {
+    pub(super) fn new(seed: u64) -> ItemState {
+        return ItemState {
+            rng: XorShift::new(seed),
+            lines: 0,
+            slow_gravity_left: 0.0,
+        };
+    }
+}
+
+impl Game {
+    // Uses an item on this game, whoever earned it
+    pub fn apply_item(&mut self, item: Item) -> Vec<GameEvent> {
+        if self.has_ended() {
+            return self.take_events();
+        }
+        match item {
+            Item::ClearBottomRow => self.clear_bottom_row(),
+            Item::ShuffleBoard => self.shuffle_board(),
+            Item::SlowGravity => {
+                let duration = self.config.items.slow_gravity_duration;
+                self.items.slow_gravity_left = self.span(duration);
+            }
+        }
+        self.emit(GameEvent::ItemApplied(item));
+        return self.take_events();
+    }
+
+    // Seconds left of `Item::SlowGravity`
+    pub fn get_slow_gravity_time(&self) -> f64 {
+        return self.seconds(self.items.slow_gravity_left.max(0.0));
+    }
+
+    pub(super) fn earn_items(&mut self, clear_kind: &ClearKind) {
+        let rules = &self.config.items;
+        if !rules.is_enabled() {
+            return;
+        }
+        let mut earned = 0;
+        let lines = self.items.lines + clear_kind.lines();
+        if let Some(count) = lines.checked_div(rules.lines_per_item) {
+            earned += count;
+            self.items.lines = lines - count * rules.lines_per_item;
+        }
+        if rules.difficult_clears && clear_kind.is_difficult() {
+            earned += 1;
+        }
+        for _ in 0..earned {
+            let pool = &self.config.items.pool;
+            let item = pool[self.items.rng.next_below(pool.len() as u64) as usize];
+            self.emit(GameEvent::ItemEarned(item));
+        }
+    }
+
+    pub(super) fn update_items(&mut self, delta_time: f64) {
+        self.items.slow_gravity_left = (self.items.slow_gravity_left - delta_time).max(0.0);
+    }
+
+    // Gravity intervals are multiplied by it
+    pub(super) fn gravity_slowdown(&self) -> f64 {
+        if self.items.slow_gravity_left > 0.0 {
+            return self.config.items.slow_gravity_factor.max(1.0);
+        }
+        return 1.0;
+    }
+
+    fn clear_bottom_row(&mut self) {
+        if self.board.taken_rows().iter().all(|row| *row == 0) {
+            return;
+        }
+        self.remove_bottom_rows(1);
+        self.settle_active_figure();
+    }
+
+    // The cells of each row trade places, so no row gets full or loses a hole
+    fn shuffle_board(&mut self) {
+        for y in 0..self.board.height() {
+            let free: Vec<usize> = (0..self.board.width())
+                .filter(|x| !self.board.is_blocked(*x, y))
+                .collect();
+            let mut cells: Vec<_> = free
+                .iter()
+                .map(|x| self.board.figure_at_xy(*x, y).clone())
+                .collect();
+            for index in (1..cells.len()).rev() {
+                let other = self.items.rng.next_below(index as u64 + 1) as usize;
+                cells.swap(index, other);
+            }
+            for (x, cell) in free.into_iter().zip(cells) {
+                self.board.set_figure_at_xy(x, y, cell);
+            }
+        }
+        self.settle_active_figure();
+    }
+
+    // Cells moved into the active figure, it goes down with the stack or up out of it
+    fn settle_active_figure(&mut self) {
+        if self.is_waiting_for_figure() || has_valid_position(&self.active, &self.board) {
+            return;
+        }
+        let lowered = self.active.moved_down();
+        if has_valid_position(&lowered, &self.board) {
+            self.active = lowered;
+            return;
+        }
+        match self.pushed_up_active_figure(self.board.height()) {
+            Some(active) => self.active = active,
+            None => self.top_out(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod items_tests {
+    use super::super::super::items::ItemRules;
+    use super::super::super::{Action, Board, FigureType, GameConfig, RandomizerChoice};
+    use super::super::fixtures::o_slot_rows;
+    use super::*;
+    use alloc::vec;
+
+    // The O clears two rows, over two rows with a single hole
+    fn item_game(rules: ItemRules) -> Game {
+        let mut rows = o_slot_rows(18, 2);
+        for hole in [0, 9] {
+            let mut row = vec![Some(FigureType::Garbage); 10];
+            row[hole] = None;
+            rows.push(row);
+        }
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![3]))
+            .items(rules)
+            .build();
+        return Game::with_board(Board::from_rows(rows), config);
+    }
+
+    #[test]
+    fn test_clears_earn_items() {
+        let mut game = item_game(ItemRules::default());
+        let events = game.perform(Action::HardDrop);
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, GameEvent::ItemEarned(_))));
+        let rules = ItemRules {
+            lines_per_item: 2,
+            pool: vec![Item::SlowGravity],
+            ..ItemRules::default()
+        };
+        let mut game = item_game(rules);
+        let events = game.perform(Action::HardDrop);
+        assert!(events.contains(&GameEvent::ItemEarned(Item::SlowGravity)));
+    }
+
+    #[test]
+    fn test_clear_bottom_row() {
+        let mut game = item_game(ItemRules::default());
+        let events = game.apply_item(Item::ClearBottomRow);
+        assert_eq!(events, vec![GameEvent::ItemApplied(Item::ClearBottomRow)]);
+        let board = game.get_board();
+        assert_eq!(board.figure_at_xy(0, 19), &None);
+        assert_eq!(board.figure_at_xy(3, 18), &None);
+        assert_eq!(board.figure_at_xy(4, 17), &None);
+        assert!(board.is_line_empty(16));
+    }
+
+    #[test]
+    fn test_shuffle_keeps_the_cell_count_of_each_row() {
+        let mut game = item_game(ItemRules::default());
+        let before = game.get_board().taken_rows().to_vec();
+        game.apply_item(Item::ShuffleBoard);
+        let after = game.get_board().taken_rows();
+        for (row, shuffled) in before.iter().zip(after) {
+            assert_eq!(row.count_ones(), shuffled.count_ones());
+        }
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn test_slow_gravity_wears_off() {
+        let mut game = item_game(ItemRules::default());
+        game.apply_item(Item::SlowGravity);
+        assert_eq!(game.get_slow_gravity_time(), 10.0);
+        // Level 1 gravity takes a second per row, three with the item
+        game.update(2.0);
+        assert_eq!(game.access_active_figure()[0].y, 0);
+        game.update(8.0);
+        assert_eq!(game.get_slow_gravity_time(), 0.0);
+    }
+}
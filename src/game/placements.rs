@@ -0,0 +1,94 @@
+use super::super::{ClearKind, PiecePose, Point};
+use super::Game;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+// One locked piece, cells and pose count from the first visible row like the drawing functions
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlacementRecord {
+    pub pose: PiecePose,
+    pub cells: Vec<Point>,
+    pub lines_cleared: usize,
+    pub clear_kind: ClearKind,
+    // Points scored by the piece, drops included, and the total score after it
+    pub score_delta: u64,
+    pub score: u64,
+    // Seconds played when the piece locked, and seconds from its spawn to its lock
+    pub time: f64,
+    pub piece_time: f64,
+}
+
+impl Game {
+    // Every piece locked so far, oldest first
+    pub fn placement_history(&self) -> &[PlacementRecord] {
+        return &self.placements;
+    }
+
+    pub(super) fn record_placement(&mut self, lines_cleared: usize, clear_kind: ClearKind) {
+        let hidden_rows = self.config.hidden_rows as i32;
+        let shifted = |point: Point| Point {
+            x: point.x,
+            y: point.y - hidden_rows,
+        };
+        let previous_score = self
+            .placements
+            .last()
+            .map_or(0, |placement| placement.score);
+        let record = PlacementRecord {
+            pose: self.visible_pose(&self.active),
+            cells: self.active.cells().map(shifted).collect(),
+            lines_cleared,
+            clear_kind,
+            score_delta: self.score.saturating_sub(previous_score),
+            score: self.score,
+            time: self.get_elapsed_time(),
+            piece_time: self.get_piece_time(),
+        };
+        Rc::make_mut(&mut self.placements).push(record);
+    }
+
+    // Chain reaction clears belong to the piece that started them
+    pub(super) fn record_chain_clear(&mut self, lines_cleared: usize) {
+        let score = self.score;
+        if let Some(placement) = Rc::make_mut(&mut self.placements).last_mut() {
+            placement.lines_cleared += lines_cleared;
+            placement.score_delta += score.saturating_sub(placement.score);
+            placement.score = score;
+        }
+    }
+}
+
+#[cfg(test)]
+mod placements_tests {
+    use super::super::super::scoring::HARD_DROP_POINTS_PER_CELL;
+    use super::super::super::{Action, FigureType, GameConfig, RandomizerChoice, Rotation};
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_placements_are_logged() {
+        let config = GameConfig {
+            randomizer: RandomizerChoice::Sequence(vec![3, 0]),
+            ..GameConfig::default()
+        };
+        let mut game = Game::with_config(config);
+        game.update(0.5);
+        game.perform(Action::HardDrop);
+        game.perform(Action::Rotate);
+        game.perform(Action::HardDrop);
+        let history = game.placement_history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(
+            history[0].pose,
+            PiecePose::new(FigureType::O, Point { x: 3, y: 18 }, Rotation::Spawn)
+        );
+        assert!(history[0].cells.contains(&Point { x: 3, y: 19 }));
+        assert_eq!(history[0].lines_cleared, 0);
+        assert_eq!(history[0].score_delta, 18 * HARD_DROP_POINTS_PER_CELL);
+        assert_eq!(history[0].time, 0.5);
+        assert_eq!(history[1].pose.kind, FigureType::I);
+        assert_eq!(history[1].pose.rotation, Rotation::Right);
+        assert_eq!(history[1].score, game.get_score());
+    }
+}
@@ -0,0 +1,258 @@
+use super::super::move_validator::{can_move_down, drop_distance, has_valid_position, shifted};
+use super::super::scoring::ClearKind;
+use super::super::t_spin::detect_t_spin;
+use super::super::{ActiveFigure, PiecePose};
+use super::{Action, Game};
+use alloc::vec::Vec;
+
+// What locking a pose would clear, rows count from the first visible row like `last_cleared_lines`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClearPrediction {
+    pub lines: Vec<usize>,
+    pub clear_kind: ClearKind,
+    // Nothing left on the board once the lines are gone
+    pub perfect_clear: bool,
+}
+
+impl ClearPrediction {
+    pub fn lines_cleared(&self) -> usize {
+        return self.lines.len();
+    }
+}
+
+impl Game {
+    // Whether performing the action now would change anything, for UIs greying out moves and bots
+    // pruning them
+    pub fn can_perform(&self, action: &Action) -> bool {
+        let playing = !self.has_ended() && !self.is_paused();
+        return match action {
+            Action::Pause => playing,
+            Action::Resume => self.is_paused(),
+            Action::SoftDropStart => playing && !self.soft_dropping,
+            Action::SoftDropStop => playing && self.soft_dropping,
+            Action::ActivateSpecial => playing && self.special_duration().is_some(),
+            _ => self.would_result_in(action).is_some(),
+        };
+    }
+
+    // Where the active figure would be after the action, without performing it, counted like
+    // `active_pose`. None when the action can't move the figure, or does not move figures at all.
+    // A hard drop gives the pose the figure locks in, a hold the pose of the figure coming out
+    pub fn would_result_in(&self, action: &Action) -> Option<PiecePose> {
+        if self.has_ended() || self.is_paused() || self.is_waiting_for_figure() {
+            return None;
+        }
+        let active = &self.active;
+        let system = &self.config.rotation_system;
+        let figure = match action {
+            Action::MoveLeft => Some(active.moved_left()),
+            Action::MoveRight => Some(active.moved_right()),
+            Action::MoveDown | Action::SoftDrop => Some(active.moved_down()),
+            Action::MoveLeftWall => self.shifted_to_wall(ActiveFigure::moved_left),
+            Action::MoveRightWall => self.shifted_to_wall(ActiveFigure::moved_right),
+            Action::SonicDrop if can_move_down(active, &self.board) => Some(self.dropped()),
+            Action::HardDrop => Some(self.dropped()),
+            Action::Rotate => self.kicked(active.wall_kicked_rotation_tests(system)),
+            Action::RotateCCW => self.kicked(active.wall_kicked_counter_clockwise_tests(system)),
+            Action::Rotate180 => self.kicked(active.wall_kicked_180_tests(system)),
+            Action::Hold => self.held_out(),
+            _ => None,
+        };
+        return figure
+            .filter(|figure| has_valid_position(figure, &self.board))
+            .map(|figure| self.visible_pose(&figure));
+    }
+
+    // Whether the pose fits the board, counted like `active_pose`. Rows above the board are free
+    pub fn is_valid_pose(&self, pose: &PiecePose) -> bool {
+        let hidden_rows = self.config.hidden_rows as i32;
+        let figure = ActiveFigure::from_pose(&pose.translated(0, hidden_rows));
+        return has_valid_position(&figure, &self.board);
+    }
+
+    // What would clear if the pose locked now, counted like `active_pose`. The pose is not checked,
+    // see `is_valid_pose`. T-spins need the last move to be a rotation: the active figure's own pose
+    // uses its real last move, any other T pose is taken as rotated in without a kick
+    pub fn predict_clears(&self, pose: &PiecePose) -> ClearPrediction {
+        let hidden_rows = self.config.hidden_rows as i32;
+        let figure = ActiveFigure::from_pose(&pose.translated(0, hidden_rows));
+        let kick_index = if figure == self.active {
+            self.last_rotation_kick
+        } else {
+            Some(0)
+        };
+        let t_spin = kick_index.and_then(|kick| detect_t_spin(&figure, &self.board, kick));
+        let mut board = self.board.clone();
+        for point in figure.cells() {
+            if point.x >= 0 && point.y >= 0 {
+                board.set_figure_at_xy(point.x as usize, point.y as usize, Some(pose.kind.clone()));
+            }
+        }
+        let lines = self.lines_completed_on(&board);
+        let perfect_clear = !lines.is_empty()
+            && (0..board.height()).all(|row| lines.contains(&row) || board.is_line_empty(row));
+        return ClearPrediction {
+            clear_kind: Game::clear_kind_for(lines.len(), t_spin),
+            lines: self.visible_rows(&lines),
+            perfect_clear,
+        };
+    }
+
+    fn shifted_to_wall(&self, step: fn(&ActiveFigure) -> ActiveFigure) -> Option<ActiveFigure> {
+        let moved = shifted(&self.active, &self.board, step);
+        if moved == self.active {
+            return None;
+        }
+        return Some(moved);
+    }
+
+    fn dropped(&self) -> ActiveFigure {
+        return self
+            .active
+            .translated(0, drop_distance(&self.active, &self.board));
+    }
+
+    fn kicked(&self, tests: Vec<ActiveFigure>) -> Option<ActiveFigure> {
+        return self.first_valid_figure(tests).map(|(_, rotated)| rotated);
+    }
+
+    // The held figure, or the next one when nothing is held, at its spawn position
+    fn held_out(&self) -> Option<ActiveFigure> {
+        if !self.config.hold_enabled || !self.can_hold {
+            return None;
+        }
+        let figure_type = self.held.clone().or_else(|| self.next.front().cloned())?;
+        let width = self.board.width();
+        let figure = Game::spawn_figure(figure_type, width, &self.config);
+        return Some(self.spawned(figure));
+    }
+}
+
+#[cfg(test)]
+mod queries_tests {
+    use super::super::super::{
+        Board, ClearKind, FigureType, GameConfig, Point, RandomizerChoice, Rotation,
+    };
+    use super::*;
+    use alloc::vec;
+
+    fn pose_game(rows: Vec<Vec<Option<FigureType>>>) -> Game {
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![5, 0]))
+            .build();
+        return Game::with_board(Board::from_rows(rows), config);
+    }
+
+    #[test]
+    fn test_queries_match_performed_actions() {
+        let game = pose_game(vec![vec![None; 10]; 20]);
+        let actions = [
+            Action::MoveLeft,
+            Action::MoveRight,
+            Action::MoveDown,
+            Action::MoveLeftWall,
+            Action::MoveRightWall,
+            Action::Rotate,
+            Action::RotateCCW,
+            Action::Rotate180,
+            Action::SonicDrop,
+            Action::Hold,
+        ];
+        for action in actions {
+            let pose = game.would_result_in(&action).unwrap();
+            let mut performed = pose_game(vec![vec![None; 10]; 20]);
+            performed.perform(action);
+            assert_eq!(pose, performed.active_pose().unwrap(), "{:?}", action);
+            assert!(game.can_perform(&action));
+        }
+        let landing = game.would_result_in(&Action::HardDrop).unwrap();
+        assert_eq!(landing, game.would_result_in(&Action::SonicDrop).unwrap());
+        assert_eq!(game.would_result_in(&Action::Pause), None);
+        assert!(game.can_perform(&Action::Pause));
+        assert!(!game.can_perform(&Action::Resume));
+        assert!(!game.can_perform(&Action::SoftDropStop));
+        assert!(!game.can_perform(&Action::ActivateSpecial));
+    }
+
+    #[test]
+    fn test_blocked_actions() {
+        // Against the wall and on the floor, and the hold used
+        let mut game = pose_game(vec![vec![None; 10]; 20]);
+        game.perform(Action::MoveLeftWall);
+        assert!(!game.can_perform(&Action::MoveLeft));
+        assert!(!game.can_perform(&Action::MoveLeftWall));
+        assert!(game.can_perform(&Action::MoveRight));
+        game.perform(Action::SonicDrop);
+        assert!(!game.can_perform(&Action::MoveDown));
+        assert!(!game.can_perform(&Action::SonicDrop));
+        assert_eq!(game.would_result_in(&Action::HardDrop), game.active_pose());
+        game.perform(Action::Hold);
+        assert!(!game.can_perform(&Action::Hold));
+        let pose = game.active_pose().unwrap();
+        let moved = game.would_result_in(&Action::MoveLeft).unwrap();
+        assert_eq!(moved, pose.translated(-1, 0));
+        game.pause();
+        assert!(!game.can_perform(&Action::MoveRight));
+        assert!(game.can_perform(&Action::Resume));
+    }
+
+    #[test]
+    fn test_is_valid_pose() {
+        let mut rows = vec![vec![None; 10]; 20];
+        rows[19][0] = Some(FigureType::O);
+        let game = pose_game(rows);
+        assert!(game.is_valid_pose(&game.active_pose().unwrap()));
+        let landing = game.would_result_in(&Action::HardDrop).unwrap();
+        assert!(game.is_valid_pose(&landing));
+        assert!(!game.is_valid_pose(&landing.translated(0, 1)));
+        let o = |x, y| PiecePose::new(FigureType::O, Point { x, y }, Rotation::Spawn);
+        assert!(game.is_valid_pose(&o(1, 18)));
+        assert!(!game.is_valid_pose(&o(0, 18)));
+        assert!(!game.is_valid_pose(&o(9, 0)));
+        assert!(game.is_valid_pose(&o(4, -2)));
+    }
+
+    #[test]
+    fn test_predict_clears() {
+        let mut rows = vec![vec![None; 10]; 20];
+        for row in &mut rows[18..] {
+            row[2..].fill(Some(FigureType::I));
+        }
+        let mut game = pose_game(rows);
+        let o = PiecePose::new(FigureType::O, Point { x: 0, y: 18 }, Rotation::Spawn);
+        let hash = game.get_board().hash();
+        let prediction = game.predict_clears(&o);
+        assert_eq!(prediction.lines, vec![18, 19]);
+        assert_eq!(prediction.clear_kind, ClearKind::Lines(2));
+        assert!(prediction.perfect_clear);
+        assert_eq!(game.get_board().hash(), hash);
+        assert_eq!(game.predict_clears(&o.translated(0, -1)).lines, vec![18]);
+        game.board = game
+            .board
+            .replacing_figure_at_xy(5, 17, Some(FigureType::I));
+        assert!(!game.predict_clears(&o).perfect_clear);
+        let spawn = game.active_pose().unwrap();
+        assert_eq!(game.predict_clears(&spawn).lines_cleared(), 0);
+    }
+
+    #[test]
+    fn test_predict_t_spin() {
+        // The T slot of the game tests, with an overhang on the left
+        let mut rows = vec![vec![None; 10]; 20];
+        rows[19] = (0..10).map(|x| (x != 4).then_some(FigureType::I)).collect();
+        rows[18] = (0..10)
+            .map(|x| (!(3..=5).contains(&x)).then_some(FigureType::I))
+            .collect();
+        rows[17][3] = Some(FigureType::I);
+        let game = pose_game(rows);
+        let slot = PiecePose::new(FigureType::T, Point { x: 3, y: 17 }, Rotation::Reverse);
+        let prediction = game.predict_clears(&slot);
+        assert_eq!(prediction.clear_kind, ClearKind::TSpin(2));
+        assert_eq!(prediction.lines_cleared(), 2);
+        assert!(!prediction.perfect_clear);
+        // The figure at its spawn has not rotated
+        let spawn = game.active_pose().unwrap();
+        assert_eq!(game.predict_clears(&spawn).clear_kind, ClearKind::None);
+    }
+}
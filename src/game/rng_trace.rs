@@ -0,0 +1,141 @@
+use super::super::randomizer::{indexed_figure, Dealer};
+use super::super::FigureType;
+use super::Game;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+// One randomizer output and the figure it dealt, randomizers dealing figures have no raw value
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RngDraw {
+    pub value: Option<i32>,
+    pub kind: FigureType,
+    // Position of the figure in its bag, from 0, for randomizers that deal from bags
+    pub bag_index: Option<usize>,
+    // Dealt past the end of a `ScriptEnd::Report` script
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub overrun: bool,
+}
+
+impl Game {
+    // Every figure dealt so far, previews included, oldest first
+    pub fn rng_trace(&self) -> &[RngDraw] {
+        return &self.rng_trace;
+    }
+
+    pub(super) fn next_random_figure(&mut self) -> FigureType {
+        let piece_set = &self.config.piece_set;
+        return Game::deal(
+            &mut self.randomizer,
+            Rc::make_mut(&mut self.dealt),
+            Rc::make_mut(&mut self.rng_trace),
+            piece_set,
+        );
+    }
+
+    pub(super) fn deal(
+        randomizer: &mut Dealer,
+        dealt: &mut Vec<FigureType>,
+        trace: &mut Vec<RngDraw>,
+        piece_set: &[FigureType],
+    ) -> FigureType {
+        let draw = match randomizer {
+            Dealer::Indexed(randomizer) => {
+                let value = randomizer.random();
+                RngDraw {
+                    value: Some(value),
+                    kind: indexed_figure(value, piece_set),
+                    bag_index: randomizer.bag_index(),
+                    overrun: randomizer.is_overrun(),
+                }
+            }
+            Dealer::Figures(randomizer) => RngDraw {
+                value: None,
+                kind: randomizer.next(dealt),
+                bag_index: randomizer.bag_index(),
+                overrun: randomizer.is_overrun(),
+            },
+        };
+        let kind = draw.kind.clone();
+        dealt.push(kind.clone());
+        trace.push(draw);
+        return kind;
+    }
+}
+
+#[cfg(test)]
+mod rng_trace_tests {
+    use super::super::super::{Action, GameConfig, RandomizerChoice, ScriptEnd};
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_trace_follows_the_queue() {
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![3, 0, 9]))
+            .build();
+        let mut game = Game::with_config(config);
+        game.perform(Action::HardDrop);
+        let trace = game.rng_trace();
+        let values: Vec<i32> = trace.iter().filter_map(|draw| draw.value).collect();
+        assert_eq!(values, vec![3, 0, 9, 3, 0, 9, 3]);
+        assert_eq!(trace[1].kind, FigureType::I);
+        // Out of range values deal the last figure of the set
+        assert_eq!(trace[2].kind, FigureType::Z);
+        assert_eq!(trace[0].bag_index, None);
+        assert_eq!(game.get_next_figure_type(), trace[2].kind);
+    }
+
+    #[test]
+    fn test_bag_indexes() {
+        let game = Game::with_config(GameConfig::builder().seed(7).build());
+        let trace = game.rng_trace();
+        let indexes: Vec<Option<usize>> = trace.iter().map(|draw| draw.bag_index).collect();
+        assert_eq!(
+            indexes,
+            vec![Some(0), Some(1), Some(2), Some(3), Some(4), Some(5)]
+        );
+        let mut values: Vec<i32> = trace.iter().filter_map(|draw| draw.value).collect();
+        values.sort_unstable();
+        values.dedup();
+        assert_eq!(values.len(), 6);
+    }
+
+    #[test]
+    fn test_drill_randomizers() {
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Weighted(3, vec![0, 0, 0, 0, 1, 0, 1]))
+            .build();
+        let mut game = Game::with_config(config);
+        for _ in 0..5 {
+            game.perform(Action::HardDrop);
+        }
+        let kinds = game.rng_trace().iter().map(|draw| draw.kind.clone());
+        assert!(kinds.into_iter().all(|kind| kind == FigureType::S || kind == FigureType::Z));
+        // The previews are dealt up front, five of them by default
+        let script = vec![5, 0, 1, 2, 3, 4, 6];
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Scripted(script, ScriptEnd::Report))
+            .build();
+        let mut game = Game::with_config(config);
+        game.perform(Action::HardDrop);
+        assert_eq!(game.rng_trace().len(), 7);
+        assert_eq!(game.get_next_figure_type(), FigureType::J);
+        assert!(game.rng_trace().iter().all(|draw| !draw.overrun));
+        game.perform(Action::HardDrop);
+        assert!(game.rng_trace()[7].overrun);
+    }
+
+    #[test]
+    fn test_short_scripts_overrun_without_panicking() {
+        for script in [vec![], vec![3, 4]] {
+            let config = GameConfig::builder()
+                .randomizer(RandomizerChoice::Scripted(script, ScriptEnd::Report))
+                .build();
+            let game = Game::with_config(config);
+            assert_eq!(game.rng_trace().len(), 6);
+            assert!(game.rng_trace()[5].overrun);
+        }
+    }
+}
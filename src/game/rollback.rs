@@ -0,0 +1,272 @@
+use super::Game;
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+
+// FNV-1a over little endian integers, so every platform and compiler version gets the same hash
+struct StateHasher {
+    hash: u64,
+}
+
+impl StateHasher {
+    fn new() -> StateHasher {
+        return StateHasher {
+            hash: 0xcbf2_9ce4_8422_2325,
+        };
+    }
+}
+
+impl Hasher for StateHasher {
+    fn finish(&self) -> u64 {
+        return self.hash;
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash ^= *byte as u64;
+            self.hash = self.hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+}
+
+impl Game {
+    // Copy of the game to simulate ahead and throw away, None when the randomizer can't be copied.
+    // The placement and randomizer histories are shared like in snapshots, the undo history is
+    // left out to keep it cheap
+    pub fn try_clone(&self) -> Option<Game> {
+        let randomizer = self.randomizer.try_clone()?;
+        return Some(Game {
+            board: self.board.clone(),
+            score: self.score,
+            active: self.active.clone(),
+            next: self.next.clone(),
+            held: self.held.clone(),
+            can_hold: self.can_hold,
+            gravity_progress: self.gravity_progress,
+            lock_time: self.lock_time,
+            lock_resets: self.lock_resets,
+            lowest_row: self.lowest_row,
+            last_rotation_kick: self.last_rotation_kick,
+            last_clear_kind: self.last_clear_kind.clone(),
+            combo: self.combo,
+            back_to_back: self.back_to_back,
+            soft_dropping: self.soft_dropping,
+            input_state: self.input_state.clone(),
+            clearing_lines: self.clearing_lines.clone(),
+            last_cleared_lines: self.last_cleared_lines.clone(),
+            clear_time: self.clear_time,
+            elapsed: self.elapsed,
+            piece_time: self.piece_time,
+            idle_time: self.idle_time,
+            frame_time: self.frame_time,
+            stats: self.stats.clone(),
+            piece_inputs: self.piece_inputs,
+            queued_actions: self.queued_actions.clone(),
+            buffered_rotation: self.buffered_rotation,
+            buffered_hold: self.buffered_hold,
+            entry_time: self.entry_time,
+            spawning: self.spawning,
+            placements: Rc::clone(&self.placements),
+            rng_trace: Rc::clone(&self.rng_trace),
+            dealt: Rc::clone(&self.dealt),
+            opening: self.opening.clone(),
+            pending_garbage: self.pending_garbage.clone(),
+            special: self.special,
+            items: self.items,
+            referee: self.referee,
+            danger: self.danger,
+            spawn_snapshot: None,
+            undo_history: VecDeque::new(),
+            redo_history: Vec::new(),
+            randomizer,
+            state: self.state.clone(),
+            lines: self.lines,
+            level: self.level,
+            config: self.config.clone(),
+            events: self.events.clone(),
+        });
+    }
+
+    // Hash of everything the next frames depend on, for rollback netplay to compare games cheaply.
+    // The config, events, statistics and histories are left out
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        let state = &mut hasher;
+        Hash::hash(&self.board, state);
+        self.score.hash(state);
+        self.active.hash(state);
+        self.next.hash(state);
+        self.held.hash(state);
+        self.can_hold.hash(state);
+        self.gravity_progress.hash(state);
+        self.lock_time.to_bits().hash(state);
+        self.lock_resets.hash(state);
+        self.lowest_row.hash(state);
+        self.last_rotation_kick.hash(state);
+        self.last_clear_kind.hash(state);
+        self.combo.hash(state);
+        self.back_to_back.hash(state);
+        self.soft_dropping.hash(state);
+        self.input_state.hash(state);
+        self.clearing_lines.hash(state);
+        self.clear_time.to_bits().hash(state);
+        self.elapsed.to_bits().hash(state);
+        self.piece_time.to_bits().hash(state);
+        self.idle_time.to_bits().hash(state);
+        self.frame_time.to_bits().hash(state);
+        self.piece_inputs.hash(state);
+        self.queued_actions.hash(state);
+        self.buffered_rotation.hash(state);
+        self.buffered_hold.hash(state);
+        self.entry_time.map(f64::to_bits).hash(state);
+        self.spawning.hash(state);
+        self.pending_garbage.hash(state);
+        self.special.hash(state);
+        self.items.hash(state);
+        self.referee.hash(state);
+        self.randomizer.hash_state(state);
+        self.state.hash(state);
+        self.lines.hash(state);
+        self.level.hash(state);
+        return hasher.finish();
+    }
+}
+
+#[cfg(test)]
+mod rollback_tests {
+    use super::super::super::{FigureType, GameConfig, IndexRandomizer, Randomizer, Size};
+    use super::super::Action;
+    use super::*;
+    use alloc::boxed::Box;
+
+    struct Stateless;
+
+    impl IndexRandomizer for Stateless {
+        fn random(&self) -> i32 {
+            return 0;
+        }
+    }
+
+    fn frame_game() -> Game {
+        let config = GameConfig::builder().tick_rate(60).seed(4).build();
+        return Game::with_config(config);
+    }
+
+    // A piece moves or drops every few frames, `shift` picks where the first one goes
+    fn play_frames(game: &mut Game, frames: core::ops::Range<usize>, shift: Action) {
+        for frame in frames {
+            match frame % 20 {
+                5 => game.queue_action(shift),
+                12 => game.queue_action(Action::Rotate),
+                19 => game.queue_action(Action::HardDrop),
+                _ => {}
+            }
+            game.step_frame();
+        }
+    }
+
+    #[test]
+    fn test_clones_stay_in_sync() {
+        let mut game = frame_game();
+        play_frames(&mut game, 0..30, Action::MoveLeft);
+        let mut copy = game.try_clone().unwrap();
+        assert_eq!(copy.state_hash(), game.state_hash());
+        let hash = game.state_hash();
+        play_frames(&mut game, 30..90, Action::MoveLeft);
+        play_frames(&mut copy, 30..90, Action::MoveLeft);
+        assert_ne!(game.state_hash(), hash);
+        assert_eq!(copy.state_hash(), game.state_hash());
+        assert_eq!(copy.get_board(), game.get_board());
+    }
+
+    #[test]
+    fn test_hash_covers_the_referee() {
+        let mut game = frame_game();
+        let mut copy = game.try_clone().unwrap();
+        game.validate_and_apply(Action::MoveLeft, 0).unwrap();
+        copy.perform(Action::MoveLeft);
+        assert_ne!(copy.state_hash(), game.state_hash());
+        copy.referee = game.referee;
+        assert_eq!(copy.state_hash(), game.state_hash());
+    }
+
+    #[test]
+    fn test_rollback_and_resimulate() {
+        let mut reference = frame_game();
+        play_frames(&mut reference, 0..100, Action::MoveRight);
+        // The remote input for the frames after 40 arrives late, they were predicted wrong
+        let mut game = frame_game();
+        play_frames(&mut game, 0..40, Action::MoveRight);
+        let saved = game.snapshot();
+        play_frames(&mut game, 40..100, Action::MoveLeft);
+        assert_ne!(game.state_hash(), reference.state_hash());
+        game.restore(&saved);
+        play_frames(&mut game, 40..100, Action::MoveRight);
+        assert_eq!(game.state_hash(), reference.state_hash());
+        assert_eq!(game.get_score(), reference.get_score());
+    }
+
+    #[test]
+    fn test_only_games_with_a_randomizer_state_clone() {
+        let game = Game::new(
+            &Size {
+                height: 20,
+                width: 10,
+            },
+            Stateless,
+        );
+        assert!(game.try_clone().is_none());
+        let mut game = Game::with_config(GameConfig::builder().undo_depth(5).build());
+        game.perform(Action::HardDrop);
+        let copy = game.try_clone().unwrap();
+        assert!(game.can_undo() && !copy.can_undo());
+        assert_eq!(copy.placement_history(), game.placement_history());
+        assert_eq!(copy.rng_trace(), game.rng_trace());
+        assert_eq!(copy.state_hash(), game.state_hash());
+    }
+
+    // Deals the figures in turn, copied through `box_clone` as it has no state to resume from
+    #[derive(Clone)]
+    struct Cycle(usize);
+
+    impl Randomizer for Cycle {
+        fn next(&mut self, _: &[FigureType]) -> FigureType {
+            self.0 += 1;
+            return FigureType::standard_set()[self.0 % 7].clone();
+        }
+
+        fn box_clone(&self) -> Option<Box<dyn Randomizer>> {
+            return Some(Box::new(self.clone()));
+        }
+    }
+
+    #[test]
+    fn test_custom_randomizers_clone_through_box_clone() {
+        let mut game = Game::with_randomizer(GameConfig::default(), Cycle(0));
+        game.perform(Action::HardDrop);
+        let mut copy = game.try_clone().unwrap();
+        for _ in 0..8 {
+            game.perform(Action::HardDrop);
+            copy.perform(Action::HardDrop);
+        }
+        assert_eq!(copy.rng_trace(), game.rng_trace());
+        assert_eq!(copy.state_hash(), game.state_hash());
+    }
+}
@@ -0,0 +1,323 @@
+use super::super::input::InputState;
+use super::super::randomizer::SavedDealer;
+use super::super::stats::Stats;
+use super::super::{ActiveFigure, Board, ClearKind, FigureType};
+use super::{
+    Action, Game, GameState, ItemState, PendingGarbage, PlacementRecord, RefereeState, RngDraw,
+    SpecialMeter,
+};
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameSnapshot {
+    pub(crate) board: Board,
+    pub(crate) score: u64,
+    pub(crate) active: ActiveFigure,
+    pub(crate) next: VecDeque<FigureType>,
+    pub(crate) held: Option<FigureType>,
+    can_hold: bool,
+    gravity_progress: u64,
+    lock_time: f64,
+    lock_resets: usize,
+    lowest_row: i32,
+    last_rotation_kick: Option<usize>,
+    last_clear_kind: ClearKind,
+    combo: Option<usize>,
+    back_to_back: Option<usize>,
+    soft_dropping: bool,
+    input_state: InputState,
+    clearing_lines: Vec<usize>,
+    last_cleared_lines: Vec<usize>,
+    clear_time: f64,
+    elapsed: f64,
+    piece_time: f64,
+    idle_time: f64,
+    frame_time: f64,
+    stats: Stats,
+    piece_inputs: usize,
+    queued_actions: VecDeque<Action>,
+    buffered_rotation: Option<Action>,
+    buffered_hold: bool,
+    entry_time: Option<f64>,
+    spawning: bool,
+    placements: Rc<Vec<PlacementRecord>>,
+    rng_trace: Rc<Vec<RngDraw>>,
+    dealt: Rc<Vec<FigureType>>,
+    pending_garbage: Vec<PendingGarbage>,
+    special: SpecialMeter,
+    items: ItemState,
+    referee: RefereeState,
+    randomizer: Option<SavedDealer>,
+    state: GameState,
+    pub(crate) lines: usize,
+    pub(crate) level: usize,
+}
+
+impl GameSnapshot {
+    pub fn get_score(&self) -> u64 {
+        return self.score;
+    }
+
+    pub fn get_lines_completed(&self) -> usize {
+        return self.lines;
+    }
+
+    pub fn placement_history(&self) -> &[PlacementRecord] {
+        return &self.placements;
+    }
+}
+
+impl Game {
+    // The placement and randomizer histories are shared with the game instead of copied, the
+    // game copies them the next time it adds to them
+    pub fn snapshot(&self) -> GameSnapshot {
+        let mut snapshot = self.state_snapshot();
+        snapshot.placements = Rc::clone(&self.placements);
+        snapshot.rng_trace = Rc::clone(&self.rng_trace);
+        snapshot.dealt = Rc::clone(&self.dealt);
+        return snapshot;
+    }
+
+    // The randomizer is only rolled back when it provides a state
+    pub fn restore(&mut self, snapshot: &GameSnapshot) {
+        self.restore_state(snapshot);
+        self.placements = Rc::clone(&snapshot.placements);
+        self.rng_trace = Rc::clone(&snapshot.rng_trace);
+        self.dealt = Rc::clone(&snapshot.dealt);
+    }
+
+    // Everything but the placement and randomizer histories, for the undo states
+    pub(super) fn state_snapshot(&self) -> GameSnapshot {
+        return GameSnapshot {
+            board: self.board.clone(),
+            score: self.score,
+            active: self.active.clone(),
+            next: self.next.clone(),
+            held: self.held.clone(),
+            can_hold: self.can_hold,
+            gravity_progress: self.gravity_progress,
+            lock_time: self.lock_time,
+            lock_resets: self.lock_resets,
+            lowest_row: self.lowest_row,
+            last_rotation_kick: self.last_rotation_kick,
+            last_clear_kind: self.last_clear_kind.clone(),
+            combo: self.combo,
+            back_to_back: self.back_to_back,
+            soft_dropping: self.soft_dropping,
+            input_state: self.input_state.clone(),
+            clearing_lines: self.clearing_lines.clone(),
+            last_cleared_lines: self.last_cleared_lines.clone(),
+            clear_time: self.clear_time,
+            elapsed: self.elapsed,
+            piece_time: self.piece_time,
+            idle_time: self.idle_time,
+            frame_time: self.frame_time,
+            stats: self.stats.clone(),
+            piece_inputs: self.piece_inputs,
+            queued_actions: self.queued_actions.clone(),
+            buffered_rotation: self.buffered_rotation,
+            buffered_hold: self.buffered_hold,
+            entry_time: self.entry_time,
+            spawning: self.spawning,
+            placements: Rc::default(),
+            rng_trace: Rc::default(),
+            dealt: Rc::default(),
+            pending_garbage: self.pending_garbage.clone(),
+            special: self.special,
+            items: self.items,
+            referee: self.referee,
+            randomizer: self.randomizer.save(),
+            state: self.state.clone(),
+            lines: self.lines,
+            level: self.level,
+        };
+    }
+
+    pub(super) fn restore_state(&mut self, snapshot: &GameSnapshot) {
+        self.board.clone_from(&snapshot.board);
+        self.score = snapshot.score;
+        self.active = snapshot.active.clone();
+        self.next = snapshot.next.clone();
+        self.held = snapshot.held.clone();
+        self.can_hold = snapshot.can_hold;
+        self.gravity_progress = snapshot.gravity_progress;
+        self.lock_time = snapshot.lock_time;
+        self.lock_resets = snapshot.lock_resets;
+        self.lowest_row = snapshot.lowest_row;
+        self.last_rotation_kick = snapshot.last_rotation_kick;
+        self.last_clear_kind = snapshot.last_clear_kind.clone();
+        self.combo = snapshot.combo;
+        self.back_to_back = snapshot.back_to_back;
+        self.soft_dropping = snapshot.soft_dropping;
+        self.input_state = snapshot.input_state.clone();
+        self.clearing_lines = snapshot.clearing_lines.clone();
+        self.last_cleared_lines = snapshot.last_cleared_lines.clone();
+        self.clear_time = snapshot.clear_time;
+        self.elapsed = snapshot.elapsed;
+        self.piece_time = snapshot.piece_time;
+        self.idle_time = snapshot.idle_time;
+        self.frame_time = snapshot.frame_time;
+        self.stats = snapshot.stats.clone();
+        self.piece_inputs = snapshot.piece_inputs;
+        self.queued_actions = snapshot.queued_actions.clone();
+        self.buffered_rotation = snapshot.buffered_rotation;
+        self.buffered_hold = snapshot.buffered_hold;
+        self.entry_time = snapshot.entry_time;
+        self.spawning = snapshot.spawning;
+        self.pending_garbage = snapshot.pending_garbage.clone();
+        self.special = snapshot.special;
+        self.items = snapshot.items;
+        self.referee = snapshot.referee;
+        // Randomizers that can't be resumed keep dealing from where they are, only the queue and
+        // the figures dealt before are restored
+        let saved = snapshot.randomizer.clone();
+        if let Some(randomizer) = saved.and_then(|saved| self.randomizer.resumed(saved)) {
+            self.randomizer = randomizer;
+        }
+        self.state = snapshot.state.clone();
+        self.lines = snapshot.lines;
+        self.level = snapshot.level;
+        self.events.clear();
+    }
+}
+
+#[cfg(test)]
+mod snapshot_tests {
+    use super::super::super::{
+        Action, BagRandomizer, GameConfig, IndexRandomizer, Indexed, Randomizer, RandomizerState,
+        SequenceRandomizer, Size,
+    };
+    use super::*;
+
+    #[test]
+    fn test_restore_rolls_back_the_game() {
+        let size = Size {
+            height: 20,
+            width: 10,
+        };
+        let mut game = Game::new(&size, BagRandomizer::new(5));
+        game.perform(Action::HardDrop);
+        let snapshot = game.snapshot();
+        let drawn = game.draw();
+        let queue = game.get_next_queue();
+
+        for _ in 0..4 {
+            game.perform(Action::HardDrop);
+        }
+        let drawn_after = game.draw();
+        assert_ne!(drawn_after, drawn);
+
+        game.restore(&snapshot);
+        assert_eq!(game.draw(), drawn);
+        assert_eq!(game.get_next_queue(), queue);
+        assert_eq!(game.get_score(), snapshot.get_score());
+        for _ in 0..4 {
+            game.perform(Action::HardDrop);
+        }
+        assert_eq!(game.draw(), drawn_after);
+    }
+
+    #[test]
+    fn test_snapshot_is_a_value() {
+        let size = Size {
+            height: 20,
+            width: 10,
+        };
+        let mut game = Game::new(&size, BagRandomizer::new(5));
+        let snapshot = game.snapshot();
+        game.perform(Action::HardDrop);
+        assert_eq!(snapshot.get_lines_completed(), 0);
+        assert_ne!(game.snapshot(), snapshot);
+    }
+
+    #[test]
+    fn test_figure_randomizers_are_restored_as_figure_randomizers() {
+        let config = GameConfig::default();
+        let mut game = Game::with_randomizer(config, Indexed::new(BagRandomizer::new(5)));
+        let snapshot = game.snapshot();
+        for _ in 0..4 {
+            game.perform(Action::HardDrop);
+        }
+        let drawn = game.draw();
+        game.restore(&snapshot);
+        for _ in 0..4 {
+            game.perform(Action::HardDrop);
+        }
+        assert_eq!(game.draw(), drawn);
+        assert!(game.rng_trace().iter().all(|draw| draw.value.is_none()));
+    }
+
+    #[test]
+    fn test_snapshots_share_the_histories() {
+        let mut game = Game::with_config(GameConfig::default());
+        game.perform(Action::HardDrop);
+        let snapshot = game.snapshot();
+        assert!(Rc::ptr_eq(&snapshot.placements, &game.placements));
+        game.perform(Action::HardDrop);
+        // The game copies them to add the second piece, the snapshot keeps the first one only
+        assert_eq!(snapshot.placement_history().len(), 1);
+        assert_eq!(game.placement_history().len(), 2);
+        game.restore(&snapshot);
+        assert!(Rc::ptr_eq(&snapshot.rng_trace, &game.rng_trace));
+        assert_eq!(game.snapshot(), snapshot);
+    }
+
+    // Deals the figures in turn, without a state to go back to
+    struct Cycle(usize);
+
+    impl Randomizer for Cycle {
+        fn next(&mut self, _: &[FigureType]) -> FigureType {
+            self.0 += 1;
+            return FigureType::standard_set()[self.0 % 7].clone();
+        }
+    }
+
+    // Provides the state of a sequence it does not deal from, only `resume` could rebuild it
+    struct Disguised(SequenceRandomizer);
+
+    impl Randomizer for Disguised {
+        fn next(&mut self, _: &[FigureType]) -> FigureType {
+            self.0.random();
+            return FigureType::T;
+        }
+
+        fn state(&self) -> Option<RandomizerState> {
+            return self.0.state();
+        }
+    }
+
+    #[test]
+    fn test_figure_randomizers_are_not_substituted() {
+        let randomizer = Disguised(SequenceRandomizer::new(vec![0]));
+        let mut game = Game::with_randomizer(GameConfig::default(), randomizer);
+        let snapshot = game.snapshot();
+        game.perform(Action::HardDrop);
+        game.restore(&snapshot);
+        for _ in 0..4 {
+            game.perform(Action::HardDrop);
+        }
+        assert!(game
+            .rng_trace()
+            .iter()
+            .all(|draw| draw.kind == FigureType::T));
+        assert!(!game.reset_keeping_sequence());
+    }
+
+    #[test]
+    fn test_stateless_randomizers_keep_dealing() {
+        let mut game = Game::with_randomizer(GameConfig::default(), Cycle(0));
+        let snapshot = game.snapshot();
+        let queue = game.get_next_queue();
+        game.perform(Action::HardDrop);
+        let dealt = game.rng_trace().last().cloned();
+        game.restore(&snapshot);
+        assert_eq!(game.get_next_queue(), queue);
+        game.perform(Action::HardDrop);
+        // The queue comes back, the figure dealt after it is the next one of the randomizer
+        assert_ne!(game.rng_trace().last().cloned(), dealt);
+        assert_eq!(game.rng_trace().len(), snapshot.rng_trace.len() + 1);
+    }
+}
@@ -0,0 +1,305 @@
+use super::super::{ClearKind, GameEvent};
+use super::Game;
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+
+// State of the `GameConfig::special` mechanic
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpecialMeter {
+    meter: f64,
+    // Set while the mechanic is active, counted like the other timers
+    time_left: Option<f64>,
+    // Lines moved to the bottom of the board since the activation
+    stacked_lines: usize,
+    // Score of the clearing lines, decided at lock and paid if they can't stack after all
+    #[cfg_attr(feature = "serde", serde(default))]
+    held_score: Option<u64>,
+}
+
+impl Hash for SpecialMeter {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.meter.to_bits().hash(state);
+        self.time_left.map(f64::to_bits).hash(state);
+        self.stacked_lines.hash(state);
+        self.held_score.hash(state);
+    }
+}
+
+impl SpecialMeter {
+    // The bottom rows were removed, the stacked lines go first
+    pub(super) fn remove_bottom_rows(&mut self, rows: usize) {
+        self.stacked_lines = self.stacked_lines.saturating_sub(rows);
+    }
+}
+
+impl Game {
+    // From 0 to 1, filled by clears while the mechanic is not active
+    pub fn get_special_meter(&self) -> f64 {
+        return self.special.meter;
+    }
+
+    pub fn is_special_active(&self) -> bool {
+        return self.special.time_left.is_some();
+    }
+
+    // Seconds left before the active mechanic ends
+    pub fn get_special_time_left(&self) -> f64 {
+        return self.seconds(self.special.time_left.unwrap_or(0.0).max(0.0));
+    }
+
+    // Full lines waiting at the bottom of the board for the mechanic to end
+    pub fn get_stacked_lines(&self) -> usize {
+        return self.special.stacked_lines;
+    }
+
+    pub(super) fn charge_special(&mut self, clear_kind: &ClearKind) {
+        if self.is_special_active() {
+            return;
+        }
+        if let Some(mechanic) = self.config.special.mechanic() {
+            let meter = self.special.meter + mechanic.meter_gain(clear_kind);
+            self.special.meter = meter.clamp(0.0, 1.0);
+        }
+    }
+
+    // `Action::ActivateSpecial`, the whole meter is spent
+    pub(super) fn activate_special(&mut self) {
+        let duration = match self.special_duration() {
+            Some(duration) => duration,
+            None => return,
+        };
+        self.special.meter = 0.0;
+        self.special.time_left = Some(self.span(duration));
+        self.emit(GameEvent::SpecialActivated(duration));
+    }
+
+    // Seconds the special would last if activated now, None when the meter does not allow it
+    pub(super) fn special_duration(&self) -> Option<f64> {
+        if self.has_ended() || self.is_special_active() {
+            return None;
+        }
+        let meter = self.special.meter;
+        return match self.config.special.mechanic() {
+            Some(mechanic) if meter > 0.0 && meter >= mechanic.min_meter() => {
+                Some(mechanic.duration(meter))
+            }
+            _ => None,
+        };
+    }
+
+    // The mechanic waits for a line clear to finish before it ends
+    pub(super) fn update_special(&mut self, delta_time: f64) {
+        if let Some(time_left) = self.special.time_left {
+            let time_left = time_left - delta_time;
+            self.special.time_left = Some(time_left);
+            if time_left <= 0.0 && !self.is_clearing() {
+                self.end_special();
+            }
+        }
+    }
+
+    // The stacked lines clear at once and score together
+    fn end_special(&mut self) {
+        let lines = self.special.stacked_lines;
+        self.special.time_left = None;
+        self.special.stacked_lines = 0;
+        let height = self.board.height();
+        let bottom: Vec<usize> = (height - lines..height).collect();
+        self.board.remove_lines_in_place(&bottom);
+        // The figure moves down with the stack it may be tucked under
+        if !self.is_waiting_for_figure() {
+            for _ in 0..lines {
+                self.active = self.active.moved_down();
+            }
+            self.lowest_row += lines as i32;
+        }
+        let score = self
+            .config
+            .special
+            .mechanic()
+            .map_or(0, |mechanic| mechanic.exit_score(lines, self.level));
+        self.score += score;
+        self.emit(GameEvent::SpecialEnded { lines, score });
+        self.push_ready_garbage();
+    }
+
+    // Gravity only works for soft drops while the mechanic is active
+    pub(super) fn is_gravity_frozen(&self) -> bool {
+        let mechanic = self.config.special.mechanic();
+        return self.is_special_active() && mechanic.is_some_and(|rules| rules.freezes_gravity());
+    }
+
+    // Scores are held until the end while the lines stack up
+    pub(super) fn is_stacking_lines(&self) -> bool {
+        let mechanic = self.config.special.mechanic();
+        return self.is_special_active() && mechanic.is_some_and(|rules| rules.stacks_lines());
+    }
+
+    // Decided when the lines complete, a mechanic activated during the clear delay leaves them be
+    pub(super) fn score_or_hold(&mut self, clear_kind: &ClearKind) {
+        let score = self.score_for(clear_kind);
+        self.special.held_score = None;
+        if !self.is_stacking_lines() {
+            self.score += score;
+        } else if self.is_clearing() {
+            self.special.held_score = Some(score);
+        }
+    }
+
+    // Held lines go to the bottom instead, false when they have to clear.
+    // Lines that can't stack, on boards with blocked cells, are paid like a normal clear.
+    pub(super) fn stack_cleared_lines(&mut self, lines: &[usize]) -> bool {
+        let score = match self.special.held_score.take() {
+            Some(score) => score,
+            None => return false,
+        };
+        if !self
+            .board
+            .stack_lines_at_bottom(lines, self.special.stacked_lines)
+        {
+            self.score += score;
+            return false;
+        }
+        self.special.stacked_lines += lines.len();
+        return true;
+    }
+
+    // Garbage pushed in from the bottom goes over the stacked lines
+    pub(super) fn keep_stacked_lines_at_bottom(&mut self, pushed: usize) {
+        let height = self.board.height();
+        let pushed = pushed.min(height);
+        let stacked = self.special.stacked_lines.min(height - pushed);
+        self.special.stacked_lines = stacked;
+        if stacked == 0 {
+            return;
+        }
+        let bottom = height - pushed;
+        let lines: Vec<usize> = (bottom - stacked..bottom).collect();
+        self.board.stack_lines_at_bottom(&lines, 0);
+    }
+}
+
+#[cfg(test)]
+mod special_tests {
+    use super::super::super::scoring::HARD_DROP_POINTS_PER_CELL;
+    use super::super::super::special::{Special, Zone};
+    use super::super::super::{Action, GameConfig, GameConfigBuilder, Point};
+    use super::super::fixtures::o_slot_game;
+    use super::*;
+
+    fn zone_config() -> GameConfigBuilder {
+        return GameConfig::builder().special(Special::Zone(Zone {
+            lines_per_meter: 4,
+            full_duration: 8.0,
+        }));
+    }
+
+    // Every O dropped in the gap clears the two bottom rows, and the next O finds the same gap
+    fn zone_game() -> Game {
+        return o_slot_game(8, zone_config());
+    }
+
+    #[test]
+    fn test_clears_fill_the_meter() {
+        let mut game = zone_game();
+        assert!(game.perform(Action::ActivateSpecial).is_empty());
+        game.perform(Action::HardDrop);
+        assert_eq!(game.get_special_meter(), 0.5);
+        game.perform(Action::HardDrop);
+        game.perform(Action::HardDrop);
+        assert_eq!(game.get_special_meter(), 1.0);
+    }
+
+    #[test]
+    fn test_zone_freezes_gravity_and_stacks_lines() {
+        let mut game = zone_game();
+        game.perform(Action::HardDrop);
+        let events = game.perform(Action::ActivateSpecial);
+        assert!(events.contains(&GameEvent::SpecialActivated(4.0)));
+        assert!(game.is_special_active());
+        assert_eq!(game.get_special_meter(), 0.0);
+        let position = game.access_active_figure();
+        game.update(1.0);
+        assert_eq!(game.access_active_figure(), position);
+        let score = game.get_score();
+        game.perform(Action::HardDrop);
+        assert_eq!(game.get_stacked_lines(), 2);
+        assert_eq!(game.get_lines_completed(), 4);
+        // The stacked lines are full and stay at the bottom
+        let board = game.get_board();
+        assert!(board.is_line_full(19) && board.is_line_full(18));
+        assert!(!board.is_line_full(17));
+        game.perform(Action::HardDrop);
+        assert_eq!(game.get_stacked_lines(), 4);
+        // Only the drops score until the end
+        assert_eq!(
+            game.get_score() - score,
+            (18 + 16) * HARD_DROP_POINTS_PER_CELL
+        );
+        let events = game.update(3.5);
+        let score = 100 * 4 * 4 * game.get_level() as u64;
+        assert!(events.contains(&GameEvent::SpecialEnded { lines: 4, score }));
+        assert!(!game.is_special_active());
+        assert_eq!(game.get_stacked_lines(), 0);
+        assert!(!game.get_board().is_line_full(19));
+    }
+
+    #[test]
+    fn test_garbage_goes_over_the_stacked_lines() {
+        let mut game = zone_game();
+        game.perform(Action::HardDrop);
+        game.perform(Action::ActivateSpecial);
+        game.perform(Action::HardDrop);
+        game.add_garbage_lines(1, 0);
+        assert_eq!(game.get_stacked_lines(), 2);
+        let board = game.get_board();
+        assert!(board.is_line_full(19) && board.is_line_full(18));
+        assert_eq!(board.figure_at_xy(0, 17), &None);
+        // Queued garbage waits for the end
+        game.queue_garbage(1, 0);
+        game.update(1.0);
+        assert_eq!(game.get_pending_garbage(), 1);
+        let events = game.update(3.0);
+        assert!(events.contains(&GameEvent::GarbageReceived(1)));
+    }
+
+    #[test]
+    fn test_activation_during_the_clear_delay_leaves_the_lines() {
+        let mut zone = o_slot_game(8, zone_config().line_clear_delay(0.5));
+        let mut plain = o_slot_game(8, zone_config().line_clear_delay(0.5));
+        for game in [&mut zone, &mut plain] {
+            game.perform(Action::HardDrop);
+            game.update(1.0);
+            game.perform(Action::HardDrop);
+            assert!(game.is_clearing());
+        }
+        zone.perform(Action::ActivateSpecial);
+        assert!(zone.is_special_active());
+        zone.update(1.0);
+        plain.update(1.0);
+        // Scored at lock, the lines clear instead of stacking
+        assert_eq!(zone.get_stacked_lines(), 0);
+        assert_eq!(zone.get_score(), plain.get_score());
+        let events = zone.update(8.0);
+        assert!(events.contains(&GameEvent::SpecialEnded { lines: 0, score: 0 }));
+        assert_eq!(zone.get_score(), plain.get_score());
+    }
+
+    #[test]
+    fn test_lines_that_can_not_stack_are_scored() {
+        let mut zone = zone_game();
+        let mut plain = zone_game();
+        for game in [&mut zone, &mut plain] {
+            game.board = game.board.blocking(&[Point { x: 0, y: 0 }]);
+        }
+        zone.perform(Action::HardDrop);
+        plain.perform(Action::HardDrop);
+        zone.perform(Action::ActivateSpecial);
+        zone.perform(Action::HardDrop);
+        plain.perform(Action::HardDrop);
+        assert_eq!(zone.get_stacked_lines(), 0);
+        assert_eq!(zone.get_lines_completed(), 4);
+        assert_eq!(zone.get_score(), plain.get_score());
+    }
+}
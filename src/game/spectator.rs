@@ -0,0 +1,84 @@
+use super::super::{Board, FigureType, PiecePose};
+use super::{Game, GamePhase};
+use alloc::vec::Vec;
+
+// What a spectator needs to draw a game. The queue stops at the previews and the randomizer is
+// left out, so a broadcast never tells what comes after them
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpectatorView {
+    // The whole board, its first `hidden_rows` rows are above the visible field
+    pub board: Board,
+    pub hidden_rows: usize,
+    // Counted from the first visible row like `Game::active_pose`. None while the next figure waits
+    // for a line clear or the entry delay
+    pub active: Option<PiecePose>,
+    pub next: Vec<FigureType>,
+    pub held: Option<FigureType>,
+    pub score: u64,
+    pub lines: usize,
+    pub level: usize,
+    pub pending_garbage: usize,
+    pub phase: GamePhase,
+}
+
+impl Game {
+    pub fn spectator_view(&self) -> SpectatorView {
+        let active = if self.is_waiting_for_figure() {
+            None
+        } else {
+            Some(self.visible_pose(&self.active))
+        };
+        return SpectatorView {
+            board: self.board.clone(),
+            hidden_rows: self.config.hidden_rows,
+            active,
+            next: self.get_next_queue(),
+            held: self.held.clone(),
+            score: self.score,
+            lines: self.lines,
+            level: self.level,
+            pending_garbage: self.get_pending_garbage(),
+            phase: self.phase(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod spectator_tests {
+    use super::super::super::GameConfig;
+    use super::super::Action;
+    use super::*;
+
+    #[test]
+    fn test_view_stops_at_the_previews() {
+        let config = GameConfig::builder()
+            .seed(3)
+            .preview_count(2)
+            .hidden_rows(20)
+            .build();
+        let mut game = Game::with_config(config);
+        game.perform(Action::HardDrop);
+        game.perform(Action::Hold);
+        let view = game.spectator_view();
+        assert_eq!(view.next, game.get_next_queue());
+        assert_eq!(view.next.len(), 2);
+        assert_eq!(view.held, game.get_held_figure());
+        assert_eq!(view.score, game.get_score());
+        assert_eq!(view.board, *game.get_board());
+        assert_eq!(view.active, game.active_pose());
+        let pose = view.active.unwrap().translated(0, view.hidden_rows as i32);
+        assert_eq!(pose, game.active.pose());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_view_serializes_without_the_randomizer() {
+        let game = Game::with_config(GameConfig::builder().seed(3).build());
+        let view = game.spectator_view();
+        let json = serde_json::to_string(&view).unwrap();
+        assert!(!json.contains("randomizer") && !json.contains("rng"));
+        let restored: SpectatorView = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, view);
+    }
+}
@@ -0,0 +1,98 @@
+use super::super::{FigureType, GameConfig, RandomizerChoice, ScriptEnd};
+use super::Game;
+use alloc::vec::Vec;
+
+// Practicing openers: the same pieces on a fresh board as many times as needed
+impl Game {
+    // Deals `sequence` and starts it over once played. Figures missing from the config's piece set
+    // are added to it
+    pub fn with_sequence(config: GameConfig, sequence: Vec<FigureType>) -> Game {
+        let mut config = config;
+        for figure in &sequence {
+            if !config.piece_set.contains(figure) {
+                config.piece_set.push(figure.clone());
+            }
+        }
+        let script = sequence
+            .iter()
+            .filter_map(|figure| config.piece_set.iter().position(|piece| piece == figure))
+            .map(|index| index as i32)
+            .collect();
+        config.randomizer = RandomizerChoice::Scripted(script, ScriptEnd::Loop);
+        return Game::with_config(config);
+    }
+
+    // Starts over on an empty board with the same config, dealing the same pieces from the first
+    // one. False when the randomizer can't be resumed, the game is then left as is
+    pub fn reset_keeping_sequence(&mut self) -> bool {
+        let opening = self.opening.clone();
+        let randomizer = match opening.and_then(|saved| self.randomizer.resumed(saved)) {
+            Some(randomizer) => randomizer,
+            None => return false,
+        };
+        let size = self.config.size.clone();
+        *self = Game::from_dealer(&size, randomizer, self.config.clone());
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod trainer_tests {
+    use super::super::super::{Action, Randomizer};
+    use super::*;
+    use alloc::vec;
+
+    fn queue(game: &Game) -> Vec<FigureType> {
+        let mut figures = vec![game.active.kind()];
+        figures.extend(game.get_next_queue());
+        return figures;
+    }
+
+    #[test]
+    fn test_reset_replays_the_sequence() {
+        // A PCO like start, with a figure outside the piece set
+        let mut config = GameConfig::builder().preview_count(2).build();
+        config.piece_set.retain(|figure| figure != &FigureType::Z);
+        let sequence = vec![FigureType::I, FigureType::O, FigureType::S, FigureType::Z];
+        let mut game = Game::with_sequence(config, sequence);
+        let start = queue(&game);
+        assert_eq!(start, vec![FigureType::I, FigureType::O, FigureType::S]);
+        assert!(game.config.piece_set.contains(&FigureType::Z));
+        for _ in 0..5 {
+            game.perform(Action::HardDrop);
+        }
+        // Past its end the sequence starts over
+        assert_eq!(game.active.kind(), FigureType::O);
+        assert!(game.reset_keeping_sequence());
+        assert_eq!(queue(&game), start);
+        assert_eq!(game.get_pieces_placed(), 0);
+        let board = game.get_board();
+        assert!((0..board.height()).all(|row| board.is_line_empty(row)));
+        assert!(!game.has_ended());
+    }
+
+    #[test]
+    fn test_reset_keeps_seeded_games() {
+        let mut game = Game::with_config(GameConfig::builder().seed(4).build());
+        let start = queue(&game);
+        game.perform(Action::HardDrop);
+        assert!(game.reset_keeping_sequence());
+        assert_eq!(queue(&game), start);
+    }
+
+    struct Stateless;
+
+    impl Randomizer for Stateless {
+        fn next(&mut self, _history: &[FigureType]) -> FigureType {
+            return FigureType::T;
+        }
+    }
+
+    #[test]
+    fn test_reset_needs_the_randomizer_state() {
+        let mut game = Game::with_randomizer(GameConfig::default(), Stateless);
+        game.perform(Action::HardDrop);
+        assert!(!game.reset_keeping_sequence());
+        assert_eq!(game.get_pieces_placed(), 1);
+    }
+}
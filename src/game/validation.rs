@@ -0,0 +1,243 @@
+use super::super::move_validator::has_valid_position;
+use super::super::{ActiveFigure, GameEvent};
+use super::{Action, Game, DEFAULT_TICK_RATE};
+use alloc::vec::Vec;
+
+// A tap needs a frame to release the key before it is pressed again
+const TAP_FRAMES: u64 = 2;
+
+// Why `Game::validate_and_apply` refused an action
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Rejection {
+    // The game ended, or is paused and the action is not `Action::Resume`
+    NotPlaying,
+    // The game has not reached the frame yet
+    FutureFrame { frame: u64, current: u64 },
+    // An action of a later frame was already accepted
+    OutOfOrder { frame: u64, last: u64 },
+    // There is no figure to control during the line clear delay and the entry delay (ARE)
+    DuringEntryDelay,
+    // The figure would leave the board or overlap the stack
+    OutOfBounds,
+    // Shifts in the same direction repeated faster than the DAS and ARR of the config allow
+    TooFast { frames: u64 },
+    // Hold is disabled or was already used for this piece
+    HoldUnavailable,
+}
+
+// The last shift of a direction and the first of the run it belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct ShiftRun {
+    last: Option<u64>,
+    start: u64,
+}
+
+impl ShiftRun {
+    // A shift continues the run when the key could have been held since its start, DAS charged
+    // and ARR apart from the last one. Otherwise it has to be a new tap
+    fn start_for(&self, frame: u64, das: u64, arr: u64) -> Result<u64, Rejection> {
+        let frames = match self.last {
+            Some(last) => frame - last,
+            None => return Ok(frame),
+        };
+        if frame - self.start >= das && frames >= arr {
+            return Ok(self.start);
+        }
+        if frames >= TAP_FRAMES {
+            return Ok(frame);
+        }
+        return Err(Rejection::TooFast { frames });
+    }
+}
+
+// What the referee remembers of the actions it accepted
+#[derive(Debug, Clone, Copy, PartialEq, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RefereeState {
+    last_frame: Option<u64>,
+    left: ShiftRun,
+    right: ShiftRun,
+}
+
+impl Game {
+    // For servers refereeing the inputs of a client: the action is applied only when the client
+    // could have performed it at that frame, frames count like `step_frame` calls
+    pub fn validate_and_apply(
+        &mut self,
+        action: Action,
+        frame: u64,
+    ) -> Result<Vec<GameEvent>, Rejection> {
+        self.validate(action, frame)?;
+        let das = self.frames_of(self.config.das);
+        let arr = self.frames_of(self.config.arr);
+        if let Some(run) = self.shift_run(action) {
+            *run = ShiftRun {
+                last: Some(frame),
+                start: run.start_for(frame, das, arr)?,
+            };
+        }
+        self.referee.last_frame = Some(frame);
+        self.apply_action(action);
+        return Ok(self.take_events());
+    }
+
+    fn validate(&self, action: Action, frame: u64) -> Result<(), Rejection> {
+        if self.has_ended() || (self.is_paused() && action != Action::Resume) {
+            return Err(Rejection::NotPlaying);
+        }
+        let current = self.current_frame();
+        if frame > current {
+            return Err(Rejection::FutureFrame { frame, current });
+        }
+        if let Some(last) = self.referee.last_frame.filter(|last| frame < *last) {
+            return Err(Rejection::OutOfOrder { frame, last });
+        }
+        if self.is_waiting_for_figure() && Game::moves_figure(action) {
+            return Err(Rejection::DuringEntryDelay);
+        }
+        let system = &self.config.rotation_system;
+        let moved = match action {
+            Action::MoveLeft | Action::MoveLeftWall => Some(self.active.moved_left()),
+            Action::MoveRight | Action::MoveRightWall => Some(self.active.moved_right()),
+            Action::MoveDown | Action::SoftDrop => Some(self.active.moved_down()),
+            Action::Rotate => self.rotation(self.active.wall_kicked_rotation_tests(system)),
+            Action::RotateCCW => {
+                self.rotation(self.active.wall_kicked_counter_clockwise_tests(system))
+            }
+            Action::Rotate180 => self.rotation(self.active.wall_kicked_180_tests(system)),
+            Action::Hold if !self.config.hold_enabled || !self.can_hold => {
+                return Err(Rejection::HoldUnavailable);
+            }
+            _ => return Ok(()),
+        };
+        if !moved.is_some_and(|figure| has_valid_position(&figure, &self.board)) {
+            return Err(Rejection::OutOfBounds);
+        }
+        return Ok(());
+    }
+
+    fn shift_run(&mut self, action: Action) -> Option<&mut ShiftRun> {
+        return match action {
+            Action::MoveLeft | Action::MoveLeftWall => Some(&mut self.referee.left),
+            Action::MoveRight | Action::MoveRightWall => Some(&mut self.referee.right),
+            _ => None,
+        };
+    }
+
+    fn rotation(&self, tests: Vec<ActiveFigure>) -> Option<ActiveFigure> {
+        return self.first_valid_figure(tests).map(|(_, rotated)| rotated);
+    }
+
+    fn frames_of(&self, seconds: f64) -> u64 {
+        let tick_rate = self.config.tick_rate.unwrap_or(DEFAULT_TICK_RATE);
+        return (seconds * tick_rate as f64 + 0.5) as u64;
+    }
+}
+
+#[cfg(test)]
+mod validation_tests {
+    use super::super::super::{Board, FigureType, GameConfig, RandomizerChoice};
+    use super::*;
+    use alloc::vec;
+
+    fn referee_game() -> Game {
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![5]))
+            .tick_rate(60)
+            .das(10.0 / 60.0)
+            .arr(1.0 / 60.0)
+            .build();
+        return Game::with_config(config);
+    }
+
+    fn step_to(game: &mut Game, frame: u64) {
+        while game.current_frame() < frame {
+            game.step_frame();
+        }
+    }
+
+    #[test]
+    fn test_frames_are_checked() {
+        let mut game = referee_game();
+        step_to(&mut game, 5);
+        assert_eq!(
+            game.validate_and_apply(Action::Rotate, 6),
+            Err(Rejection::FutureFrame {
+                frame: 6,
+                current: 5
+            })
+        );
+        assert!(game.validate_and_apply(Action::Rotate, 5).is_ok());
+        assert_eq!(
+            game.validate_and_apply(Action::Rotate, 4),
+            Err(Rejection::OutOfOrder { frame: 4, last: 5 })
+        );
+        assert!(game.validate_and_apply(Action::Hold, 5).is_ok());
+        assert_eq!(
+            game.validate_and_apply(Action::Hold, 5),
+            Err(Rejection::HoldUnavailable)
+        );
+    }
+
+    #[test]
+    fn test_shifts_follow_das_and_arr() {
+        let mut game = referee_game();
+        step_to(&mut game, 1);
+        assert!(game.validate_and_apply(Action::MoveLeft, 1).is_ok());
+        // Taps need a frame in between to release the key
+        assert_eq!(
+            game.validate_and_apply(Action::MoveLeft, 1),
+            Err(Rejection::TooFast { frames: 0 })
+        );
+        step_to(&mut game, 3);
+        assert!(game.validate_and_apply(Action::MoveLeft, 3).is_ok());
+        step_to(&mut game, 4);
+        assert_eq!(
+            game.validate_and_apply(Action::MoveLeft, 4),
+            Err(Rejection::TooFast { frames: 1 })
+        );
+        assert!(game.validate_and_apply(Action::MoveRight, 4).is_ok());
+        // Held from frame 13, auto repeat starts with DAS 10 frames later and shifts every frame
+        let mut game = referee_game();
+        for frame in [13, 23, 24, 25] {
+            step_to(&mut game, frame);
+            assert!(game.validate_and_apply(Action::MoveRight, frame).is_ok());
+        }
+        step_to(&mut game, 26);
+        assert_eq!(
+            game.validate_and_apply(Action::MoveRight, 26),
+            Err(Rejection::OutOfBounds)
+        );
+    }
+
+    #[test]
+    fn test_blocked_moves_and_entry_delay() {
+        let mut rows = vec![vec![None; 10]; 19];
+        let mut row = vec![Some(FigureType::Garbage); 10];
+        row[9] = None;
+        rows.push(row);
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![0]))
+            .tick_rate(60)
+            .entry_delay(0.5)
+            .build();
+        let mut game = Game::with_board(Board::from_rows(rows), config);
+        game.step_frame();
+        for _ in 0..2 {
+            game.perform(Action::HardDrop);
+        }
+        assert_eq!(
+            game.validate_and_apply(Action::MoveLeft, 1),
+            Err(Rejection::DuringEntryDelay)
+        );
+        assert!(game.validate_and_apply(Action::SoftDropStart, 1).is_ok());
+        step_to(&mut game, 40);
+        assert!(game.validate_and_apply(Action::MoveLeftWall, 40).is_ok());
+        assert_eq!(
+            game.validate_and_apply(Action::MoveLeft, 40),
+            Err(Rejection::OutOfBounds)
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use core::hash::{Hash, Hasher};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Key {
+    Left,
+    Right,
+    SoftDrop,
+    HardDrop,
+    RotateCW,
+    RotateCCW,
+    Rotate180,
+    Hold,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyState {
+    Pressed,
+    Released,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    Left,
+    Right,
+}
+
+// Delayed Auto Shift: a held direction waits `das` seconds, then repeats every `arr` seconds
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InputState {
+    left_held: bool,
+    right_held: bool,
+    direction: Option<Direction>,
+    charge: f64,
+}
+
+// Floats hash by their bits, for `Game::state_hash`
+impl Hash for InputState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.left_held.hash(state);
+        self.right_held.hash(state);
+        self.direction.hash(state);
+        self.charge.to_bits().hash(state);
+    }
+}
+
+impl InputState {
+    pub fn new() -> InputState {
+        return InputState {
+            left_held: false,
+            right_held: false,
+            direction: None,
+            charge: 0.0,
+        };
+    }
+
+    pub fn direction(&self) -> Option<Direction> {
+        return self.direction;
+    }
+
+    // The last pressed direction wins
+    pub fn press(&mut self, direction: Direction) {
+        match direction {
+            Direction::Left => self.left_held = true,
+            Direction::Right => self.right_held = true,
+        }
+        self.direction = Some(direction);
+        self.charge = 0.0;
+    }
+
+    // Falls back to the other direction when it is still held
+    pub fn release(&mut self, direction: Direction) {
+        match direction {
+            Direction::Left => self.left_held = false,
+            Direction::Right => self.right_held = false,
+        }
+        if self.direction != Some(direction) {
+            return;
+        }
+        self.charge = 0.0;
+        self.direction = match direction {
+            Direction::Left if self.right_held => Some(Direction::Right),
+            Direction::Right if self.left_held => Some(Direction::Left),
+            _ => None,
+        };
+    }
+
+    // Number of auto repeated moves during the elapsed time, usize::MAX when `arr` is 0
+    pub fn advance(&mut self, delta_time: f64, das: f64, arr: f64) -> usize {
+        if self.direction.is_none() {
+            return 0;
+        }
+        let before = InputState::repeats_at(self.charge, das, arr);
+        self.charge += delta_time;
+        let after = InputState::repeats_at(self.charge, das, arr);
+        return after.saturating_sub(before);
+    }
+
+    fn repeats_at(charge: f64, das: f64, arr: f64) -> usize {
+        if charge < das {
+            return 0;
+        }
+        if arr <= 0.0 {
+            return usize::MAX;
+        }
+        return ((charge - das) / arr) as usize + 1;
+    }
+}
+
+impl Default for InputState {
+    fn default() -> InputState {
+        return InputState::new();
+    }
+}
+
+#[cfg(test)]
+mod input_tests {
+    use super::*;
+
+    #[test]
+    fn test_no_repeat_before_das() {
+        let mut input = InputState::new();
+        input.press(Direction::Left);
+        assert_eq!(input.advance(0.1, 0.15, 0.05), 0);
+        assert_eq!(input.advance(0.05, 0.15, 0.05), 1);
+        assert_eq!(input.advance(0.1, 0.15, 0.05), 2);
+    }
+
+    #[test]
+    fn test_instant_arr() {
+        let mut input = InputState::new();
+        input.press(Direction::Right);
+        assert_eq!(input.advance(0.2, 0.15, 0.0), usize::MAX);
+    }
+
+    #[test]
+    fn test_release_falls_back_to_held_direction() {
+        let mut input = InputState::new();
+        input.press(Direction::Left);
+        input.press(Direction::Right);
+        assert_eq!(input.direction(), Some(Direction::Right));
+        input.advance(0.5, 0.15, 0.05);
+        input.release(Direction::Right);
+        assert_eq!(input.direction(), Some(Direction::Left));
+        assert_eq!(input.advance(0.1, 0.15, 0.05), 0);
+        input.release(Direction::Left);
+        assert_eq!(input.direction(), None);
+        assert_eq!(input.advance(1.0, 0.15, 0.05), 0);
+    }
+
+    #[test]
+    fn test_releasing_other_direction_keeps_charge() {
+        let mut input = InputState::new();
+        input.press(Direction::Right);
+        input.press(Direction::Left);
+        input.release(Direction::Right);
+        assert_eq!(input.direction(), Some(Direction::Left));
+        input.advance(0.1, 0.15, 0.05);
+        input.release(Direction::Right);
+        assert_eq!(input.advance(0.05, 0.15, 0.05), 1);
+    }
+}
@@ -0,0 +1,69 @@
+// Party items, earned by clears through `GameEvent::ItemEarned` and used with `Game::apply_item`
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Item {
+    // Removes the bottom row of the board
+    ClearBottomRow,
+    // Scatters the cells of every row, meant for an opponent
+    ShuffleBoard,
+    // Gravity gets slower for a while
+    SlowGravity,
+}
+
+impl Item {
+    // Stable codes, used by replays
+    pub fn code(self) -> u8 {
+        return match self {
+            Item::ClearBottomRow => 0,
+            Item::ShuffleBoard => 1,
+            Item::SlowGravity => 2,
+        };
+    }
+
+    pub fn from_code(code: u8) -> Option<Item> {
+        return match code {
+            0 => Some(Item::ClearBottomRow),
+            1 => Some(Item::ShuffleBoard),
+            2 => Some(Item::SlowGravity),
+            _ => None,
+        };
+    }
+}
+
+// Which clears give items and what they do, the default gives none
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ItemRules {
+    // Lines to clear for each item, 0 gives none for lines
+    pub lines_per_item: usize,
+    // Tetrises and line clearing T-spins give an item on their own
+    pub difficult_clears: bool,
+    // Items are picked at random from the pool, the seed makes games reproducible
+    pub pool: Vec<Item>,
+    pub seed: u64,
+    // Seconds `Item::SlowGravity` lasts, and how many times slower the gravity gets
+    pub slow_gravity_duration: f64,
+    pub slow_gravity_factor: f64,
+}
+
+impl ItemRules {
+    pub fn is_enabled(&self) -> bool {
+        return (self.lines_per_item > 0 || self.difficult_clears) && !self.pool.is_empty();
+    }
+}
+
+impl Default for ItemRules {
+    fn default() -> ItemRules {
+        return ItemRules {
+            lines_per_item: 0,
+            difficult_clears: false,
+            pool: vec![Item::ClearBottomRow, Item::ShuffleBoard, Item::SlowGravity],
+            seed: 0,
+            slow_gravity_duration: 10.0,
+            slow_gravity_factor: 3.0,
+        };
+    }
+}
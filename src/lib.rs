@@ -1,15 +1,75 @@
-mod active_figure;
-mod board;
-pub mod figure;
-pub mod game;
-mod move_validator;
-
-use active_figure::ActiveFigure;
-use board::Board;
-pub use figure::{block, geometry, graphics, Figure, FigureType, Matrix};
-use geometry::Point;
-use graphics::Color;
-
-pub use block::Block;
-pub use game::{Game, Randomizer, Action};
-pub use geometry::Size;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![allow(clippy::needless_return)]
+#![allow(clippy::module_inception)]
+
+extern crate alloc;
+
+mod active_figure;
+pub mod ai;
+pub mod attack;
+#[cfg(any(feature = "wasm", feature = "capi", feature = "python"))]
+mod bindings;
+pub mod board;
+pub mod config;
+pub mod env;
+pub mod event;
+#[cfg(feature = "capi")]
+pub mod ffi;
+pub mod figure;
+pub mod finesse;
+pub mod game;
+pub mod input;
+pub mod items;
+pub mod modes;
+pub mod netcode;
+mod move_validator;
+pub mod objectives;
+pub mod pose;
+pub mod protocol;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod randomizer;
+pub mod replay;
+pub mod scoring;
+pub mod simulator;
+pub mod special;
+pub mod stats;
+pub mod testing;
+mod t_spin;
+pub mod versus;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use active_figure::ActiveFigure;
+pub use ai::Placement;
+pub use board::{Board, ClearGravity};
+pub use figure::{
+    block, cell, geometry, graphics, Figure, FigureDef, FigureType, KickTable, Matrix, Palette,
+    RotationSystem,
+};
+use geometry::Point;
+pub use graphics::PieceColor;
+
+pub use block::Block;
+pub use cell::Cell;
+pub use config::{
+    Assists, GameConfig, GameConfigBuilder, GravityCurve, LevelGoal, RandomizerChoice, TopOutRules,
+};
+pub use event::GameEvent;
+pub use finesse::FinesseMove;
+pub use game::{
+    Action, ClearPrediction, Game, GamePhase, GameSnapshot, LagReport, PendingGarbage,
+    PlacementRecord, Rejection, RngDraw, SpectatorView,
+};
+pub use input::{Key, KeyState};
+pub use items::{Item, ItemRules};
+pub use modes::{GameMode, ModeResult};
+pub use pose::{PiecePose, Rotation};
+pub use stats::Stats;
+pub use randomizer::{
+    BagRandomizer, ClassicRandomizer, IndexRandomizer, Indexed, Randomizer, RandomizerState,
+    ScriptEnd, ScriptedRandomizer, SequenceRandomizer, WeightedRandomizer,
+};
+pub use scoring::{ClearKind, ScoreTable, Scoring, ScoringRules};
+pub use special::{Special, SpecialMechanic, Zone};
+pub use geometry::Size;
@@ -0,0 +1,160 @@
+use super::randomizer::XorShift;
+use super::{Action, Board, FigureType, Game, GameConfig, GameEvent, Key, KeyState};
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameMode {
+    // Endless, until the game is over
+    Marathon,
+    Sprint { lines: usize },
+    Ultra { seconds: f64 },
+}
+
+impl Default for GameMode {
+    fn default() -> GameMode {
+        return GameMode::Marathon;
+    }
+}
+
+// Final stats reported by `GameEvent::ModeFinished`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModeResult {
+    pub score: u64,
+    pub lines: usize,
+    pub level: usize,
+    pub elapsed: f64,
+}
+
+// Cheese race: dig through garbage rows stacked at the bottom of the board
+pub struct DigRace {
+    game: Game,
+    completed: bool,
+}
+
+impl DigRace {
+    // Consecutive rows never share the same hole, the seed picks the hole columns
+    pub fn new(config: GameConfig, rows: usize, seed: u64) -> DigRace {
+        let mut board = Board::new(&config.size);
+        let rows = rows.min(board.height());
+        let width = board.width();
+        let mut rng = XorShift::new(seed);
+        let mut last_hole = None;
+        for _ in 0..rows {
+            let hole = DigRace::next_hole(&mut rng, width, last_hole);
+            board = board.adding_garbage_lines(1, hole);
+            last_hole = Some(hole);
+        }
+        return DigRace {
+            game: Game::with_board(board, config),
+            completed: rows == 0,
+        };
+    }
+
+    fn next_hole(rng: &mut XorShift, width: usize, last_hole: Option<usize>) -> usize {
+        if width < 2 {
+            return 0;
+        }
+        let hole = rng.next_below(width as u64 - 1) as usize;
+        return match last_hole {
+            Some(last) if hole >= last => hole + 1,
+            _ => hole,
+        };
+    }
+
+    pub fn update(&mut self, delta_time: f64) -> Vec<GameEvent> {
+        let events = self.game.update(delta_time);
+        return self.check_completion(events);
+    }
+
+    pub fn perform(&mut self, action: Action) -> Vec<GameEvent> {
+        let events = self.game.perform(action);
+        return self.check_completion(events);
+    }
+
+    pub fn input(&mut self, key: Key, key_state: KeyState) -> Vec<GameEvent> {
+        let events = self.game.input(key, key_state);
+        return self.check_completion(events);
+    }
+
+    fn check_completion(&mut self, mut events: Vec<GameEvent>) -> Vec<GameEvent> {
+        if !self.completed && self.get_remaining_garbage() == 0 {
+            self.completed = true;
+            events.push(GameEvent::DigRaceCompleted);
+        }
+        return events;
+    }
+
+    // Rows still holding garbage cells
+    pub fn get_remaining_garbage(&self) -> usize {
+        return self
+            .game
+            .get_board()
+            .to_rows()
+            .iter()
+            .filter(|row| row.contains(&Some(FigureType::Garbage)))
+            .count();
+    }
+
+    pub fn is_completed(&self) -> bool {
+        return self.completed;
+    }
+
+    pub fn game(&self) -> &Game {
+        return &self.game;
+    }
+
+    pub fn game_mut(&mut self) -> &mut Game {
+        return &mut self.game;
+    }
+}
+
+#[cfg(test)]
+mod modes_tests {
+    use super::super::RandomizerChoice;
+    use super::*;
+
+    fn hole_of(row: &[Option<FigureType>]) -> usize {
+        return row.iter().position(|cell| cell.is_none()).unwrap();
+    }
+
+    #[test]
+    fn test_dig_race_board() {
+        let race = DigRace::new(GameConfig::default(), 9, 4);
+        let rows = race.game().get_board().to_rows();
+        assert_eq!(race.get_remaining_garbage(), 9);
+        assert!(rows[10].iter().all(|cell| cell.is_none()));
+        for row in &rows[11..20] {
+            assert_eq!(row.iter().filter(|cell| cell.is_none()).count(), 1);
+        }
+        for y in 11..19 {
+            assert_ne!(hole_of(&rows[y]), hole_of(&rows[y + 1]));
+        }
+    }
+
+    #[test]
+    fn test_dig_race_completion() {
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![0]))
+            .build();
+        let mut race = DigRace::new(config, 1, 8);
+        let hole = hole_of(&race.game().get_board().to_rows()[19]);
+        // Drop a vertical I into the hole
+        race.perform(Action::Rotate);
+        for _ in 0..10 {
+            let column = race.game().access_active_figure()[0].x;
+            if column < hole as i32 {
+                race.perform(Action::MoveRight);
+            }
+            if column > hole as i32 {
+                race.perform(Action::MoveLeft);
+            }
+        }
+        let events = race.perform(Action::HardDrop);
+        assert!(events.contains(&GameEvent::DigRaceCompleted));
+        assert_eq!(race.get_remaining_garbage(), 0);
+        assert!(race.is_completed());
+        assert!(!race.perform(Action::HardDrop).contains(&GameEvent::DigRaceCompleted));
+    }
+}
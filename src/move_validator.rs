@@ -1,4 +1,6 @@
-use super::{ActiveFigure, Board};
+use super::board::MAX_WIDTH;
+use super::{ActiveFigure, Board, Point, RotationSystem};
+use alloc::vec::Vec;
 
 pub fn has_valid_position(active_figure: &ActiveFigure, board: &Board) -> bool {
     return !will_colide_with_block(active_figure, board)
@@ -6,18 +8,80 @@ pub fn has_valid_position(active_figure: &ActiveFigure, board: &Board) -> bool {
 }
 
 pub fn can_move_down(figure: &ActiveFigure, board: &Board) -> bool {
-    let moved_down = figure.moved_down();
-    return !is_at_the_bottom(figure, board) && !will_colide_with_block(&moved_down, board);
+    return !is_at_the_bottom(figure, board) && !collides_with_block_below(figure, board, 1);
 }
 
-fn will_colide_with_block(figure: &ActiveFigure, board: &Board) -> bool {
-    let points = figure.to_cartesian();
-    for point in points {
-        if board.contains(point) {
-            return true;
+// Rows the figure falls before it rests on the stack or the floor
+pub fn drop_distance(figure: &ActiveFigure, board: &Board) -> i32 {
+    let mut distance = 0;
+    while can_move_down(&figure.translated(0, distance), board) {
+        distance += 1;
+    }
+    return distance;
+}
+
+// Repeats a move while it fits, a tap held until the wall
+pub fn shifted(
+    figure: &ActiveFigure,
+    board: &Board,
+    step: fn(&ActiveFigure) -> ActiveFigure,
+) -> ActiveFigure {
+    let mut figure = figure.clone();
+    loop {
+        let moved = step(&figure);
+        if !has_valid_position(&moved, board) {
+            return figure;
         }
+        figure = moved;
+    }
+}
+
+// The first rotation test that fits, with its index, following the kick rules of the system
+pub fn first_valid_rotation(
+    tests: Vec<ActiveFigure>,
+    board: &Board,
+    rotation_system: &RotationSystem,
+) -> Option<(usize, ActiveFigure)> {
+    let mut tests = tests.into_iter().enumerate();
+    let (index, plain) = tests.next()?;
+    if has_valid_position(&plain, board) {
+        return Some((index, plain));
+    }
+    if rotation_system.has_center_column_rule(&plain.get_type())
+        && is_center_column_blocked(&plain, board)
+    {
+        return None;
     }
-    return false;
+    return tests.find(|(_, figure)| has_valid_position(figure, board));
+}
+
+fn is_center_column_blocked(rotated: &ActiveFigure, board: &Board) -> bool {
+    let center = rotated.position().x + 1;
+    let mut cells = rotated.to_cartesian();
+    cells.sort_by_key(|point| (point.y, point.x));
+    return cells
+        .iter()
+        .find(|point| is_cell_taken(point, board))
+        .is_some_and(|point| point.x == center);
+}
+
+fn is_cell_taken(point: &Point, board: &Board) -> bool {
+    return board.collides(core::slice::from_ref(point));
+}
+
+fn will_colide_with_block(figure: &ActiveFigure, board: &Board) -> bool {
+    return collides_with_block_below(figure, board, 0);
+}
+
+// Checks the cells `rows` lower without moving the figure, so searches don't copy it,
+// cells out of the board columns are left to the edge checks
+fn collides_with_block_below(figure: &ActiveFigure, board: &Board, rows: i32) -> bool {
+    return figure.cells().any(|point| {
+        if point.x < 0 || point.x as usize >= MAX_WIDTH {
+            return false;
+        }
+        return board.row_mask(point.y + rows) & (1 << point.x) != 0;
+    });
 }
 
 fn will_collide_with_edge(active_figure: &ActiveFigure, board: &Board) -> bool {
@@ -28,7 +92,7 @@ fn will_collide_with_edge(active_figure: &ActiveFigure, board: &Board) -> bool {
 }
 
 fn is_at_the_bottom(figure: &ActiveFigure, board: &Board) -> bool {
-    return figure.bottom_edge() == (board.height() as i32 - 1);
+    return figure.bottom_edge() >= (board.height() as i32 - 1);
 }
 
 #[cfg(test)]
@@ -36,7 +100,6 @@ mod move_validator_tests {
     use super::super::FigureType;
     use super::*;
     use super::super::geometry::{Point, Size};
-    use ActiveFigure;
 
     #[test]
     fn test_is_at_the_bottom() {
@@ -57,6 +120,21 @@ mod move_validator_tests {
         let figure = ActiveFigure::new(FigureType::L, Point { x: 3, y: 7 });
         assert!(!is_at_the_bottom(&figure, &board));
     }
+
+    #[test]
+    fn test_drop_distance() {
+        let board = Board::new(&Size {
+            height: 10,
+            width: 10,
+        })
+        .replacing_figure_at_xy(4, 9, Some(FigureType::T));
+        let figure = ActiveFigure::new(FigureType::L, Point { x: 3, y: 2 });
+        assert_eq!(drop_distance(&figure, &board), 5);
+        let figure = ActiveFigure::new(FigureType::L, Point { x: 6, y: 2 });
+        assert_eq!(drop_distance(&figure, &board), 6);
+        assert_eq!(drop_distance(&figure.translated(0, 6), &board), 0);
+    }
+
     #[test]
     fn test_will_colide_with_block() {
         let mut board = Board::new(&Size {
@@ -75,4 +153,25 @@ mod move_validator_tests {
         assert!(!will_colide_with_block(&colider, &board));
         assert!(will_colide_with_block(&rotated, &board));
     }
+
+    #[test]
+    fn test_ars_center_column_rule() {
+        let board = Board::new(&Size {
+            height: 10,
+            width: 10,
+        });
+        let figure = ActiveFigure::new(FigureType::T, Point { x: 3, y: 5 });
+        let center_blocked = board.replacing_figure_at_xy(4, 7, Some(FigureType::I));
+        let tests = figure.wall_kicked_rotation_tests(&RotationSystem::ARS);
+        assert!(
+            first_valid_rotation(tests.clone(), &center_blocked, &RotationSystem::ARS).is_none()
+        );
+        let srs_tests = figure.wall_kicked_rotation_tests(&RotationSystem::SRS);
+        assert!(first_valid_rotation(srs_tests, &center_blocked, &RotationSystem::SRS).is_some());
+        let side_blocked = board.replacing_figure_at_xy(5, 6, Some(FigureType::I));
+        let (kick, rotated) =
+            first_valid_rotation(tests, &side_blocked, &RotationSystem::ARS).unwrap();
+        assert_eq!(kick, 2);
+        assert_eq!(rotated.position(), Point { x: 2, y: 5 });
+    }
 }
@@ -0,0 +1,402 @@
+// State deltas between two snapshots, for spectators and netplay frontends that keep a copy of
+// the game in sync without sending the whole board every frame
+use super::{ActiveFigure, FigureType, GameSnapshot, PiecePose, Point, Rotation};
+use alloc::vec;
+use alloc::vec::Vec;
+
+const MAGIC: &[u8; 4] = b"TCD1";
+
+const ACTIVE_FLAG: u8 = 1;
+const SCORE_FLAG: u8 = 1 << 1;
+const LINES_FLAG: u8 = 1 << 2;
+const LEVEL_FLAG: u8 = 1 << 3;
+const HELD_FLAG: u8 = 1 << 4;
+const NEXT_FLAG: u8 = 1 << 5;
+
+// Cell codes, custom figures follow the standard ones by their index in the piece set
+const EMPTY_CODE: u8 = 0;
+const CUSTOM_CODE: u8 = 9;
+
+// A board cell and what it holds now
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CellChange {
+    pub x: usize,
+    pub y: usize,
+    pub cell: Option<FigureType>,
+}
+
+// What changed from one snapshot to the next, `None` fields did not change
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateDelta {
+    pub cells: Vec<CellChange>,
+    pub active: Option<PiecePose>,
+    pub score: Option<u64>,
+    pub lines: Option<usize>,
+    pub level: Option<usize>,
+    pub held: Option<Option<FigureType>>,
+    pub next: Option<Vec<FigureType>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeltaError {
+    InvalidHeader,
+    UnexpectedEnd,
+    InvalidFigure(u8),
+    // The delta changes a cell outside the board it is applied to
+    OutOfBounds { x: usize, y: usize },
+}
+
+impl StateDelta {
+    // Boards of different sizes are compared over the cells they share
+    pub fn between(old: &GameSnapshot, new: &GameSnapshot) -> StateDelta {
+        let width = old.board.width().min(new.board.width());
+        let height = old.board.height().min(new.board.height());
+        let mut cells = vec![];
+        for y in 0..height {
+            if old.board.get_line(y) == new.board.get_line(y) {
+                continue;
+            }
+            for x in 0..width {
+                let cell = new.board.figure_at_xy(x, y);
+                if old.board.figure_at_xy(x, y) != cell {
+                    cells.push(CellChange {
+                        x,
+                        y,
+                        cell: cell.clone(),
+                    });
+                }
+            }
+        }
+        return StateDelta {
+            cells,
+            active: changed(old.active.pose(), new.active.pose()),
+            score: changed(old.score, new.score),
+            lines: changed(old.lines, new.lines),
+            level: changed(old.level, new.level),
+            held: changed(old.held.clone(), new.held.clone()),
+            next: changed(
+                old.next.iter().cloned().collect(),
+                new.next.iter().cloned().collect(),
+            ),
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return *self == StateDelta::default();
+    }
+
+    // Brings the old snapshot to the new one, nothing changes when the delta does not fit the board
+    pub fn apply(&self, snapshot: &mut GameSnapshot) -> Result<(), DeltaError> {
+        let (width, height) = (snapshot.board.width(), snapshot.board.height());
+        if let Some(change) = self
+            .cells
+            .iter()
+            .find(|change| change.x >= width || change.y >= height)
+        {
+            return Err(DeltaError::OutOfBounds {
+                x: change.x,
+                y: change.y,
+            });
+        }
+        for change in &self.cells {
+            snapshot
+                .board
+                .set_figure_at_xy(change.x, change.y, change.cell.clone());
+        }
+        if let Some(active) = &self.active {
+            snapshot.active = ActiveFigure::from_pose(active);
+        }
+        if let Some(score) = self.score {
+            snapshot.score = score;
+        }
+        if let Some(lines) = self.lines {
+            snapshot.lines = lines;
+        }
+        if let Some(level) = self.level {
+            snapshot.level = level;
+        }
+        if let Some(held) = &self.held {
+            snapshot.held = held.clone();
+        }
+        if let Some(next) = &self.next {
+            snapshot.next = next.iter().cloned().collect();
+        }
+        return Ok(());
+    }
+
+    // Compact format, custom figures are sent as their index in the piece set of the game
+
+    pub fn to_bytes(&self, piece_set: &[FigureType]) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        let flags = [
+            (self.active.is_some(), ACTIVE_FLAG),
+            (self.score.is_some(), SCORE_FLAG),
+            (self.lines.is_some(), LINES_FLAG),
+            (self.level.is_some(), LEVEL_FLAG),
+            (self.held.is_some(), HELD_FLAG),
+            (self.next.is_some(), NEXT_FLAG),
+        ];
+        bytes.push(
+            flags
+                .iter()
+                .filter(|(set, _)| *set)
+                .fold(0, |flags, (_, flag)| flags | flag),
+        );
+        bytes.extend_from_slice(&(self.cells.len() as u32).to_le_bytes());
+        for change in &self.cells {
+            bytes.extend_from_slice(&(change.x as u16).to_le_bytes());
+            bytes.extend_from_slice(&(change.y as u16).to_le_bytes());
+            bytes.push(figure_code(change.cell.as_ref(), piece_set));
+        }
+        if let Some(active) = &self.active {
+            bytes.push(figure_code(Some(&active.kind), piece_set));
+            bytes.extend_from_slice(&active.origin.x.to_le_bytes());
+            bytes.extend_from_slice(&active.origin.y.to_le_bytes());
+            bytes.push(active.rotation.steps() as u8);
+        }
+        if let Some(score) = self.score {
+            bytes.extend_from_slice(&score.to_le_bytes());
+        }
+        if let Some(lines) = self.lines {
+            bytes.extend_from_slice(&(lines as u32).to_le_bytes());
+        }
+        if let Some(level) = self.level {
+            bytes.extend_from_slice(&(level as u32).to_le_bytes());
+        }
+        if let Some(held) = &self.held {
+            bytes.push(figure_code(held.as_ref(), piece_set));
+        }
+        if let Some(next) = &self.next {
+            bytes.push(next.len().min(u8::MAX as usize) as u8);
+            for figure in next.iter().take(u8::MAX as usize) {
+                bytes.push(figure_code(Some(figure), piece_set));
+            }
+        }
+        return bytes;
+    }
+
+    pub fn from_bytes(bytes: &[u8], piece_set: &[FigureType]) -> Result<StateDelta, DeltaError> {
+        let mut reader = Reader { bytes, position: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(DeltaError::InvalidHeader);
+        }
+        let flags = reader.read_u8()?;
+        let mut delta = StateDelta::default();
+        let count = reader.read_u32()?;
+        for _ in 0..count {
+            let x = reader.read_u16()? as usize;
+            let y = reader.read_u16()? as usize;
+            let cell = reader.read_figure(piece_set)?;
+            delta.cells.push(CellChange { x, y, cell });
+        }
+        if flags & ACTIVE_FLAG != 0 {
+            let code = reader.read_u8()?;
+            let figure_type = figure_from_code(code, piece_set)
+                .flatten()
+                .ok_or(DeltaError::InvalidFigure(code))?;
+            let x = reader.read_u32()? as i32;
+            let y = reader.read_u32()? as i32;
+            let rotation = Rotation::from_steps(reader.read_u8()? as usize);
+            delta.active = Some(PiecePose::new(figure_type, Point { x, y }, rotation));
+        }
+        if flags & SCORE_FLAG != 0 {
+            delta.score = Some(reader.read_u64()?);
+        }
+        if flags & LINES_FLAG != 0 {
+            delta.lines = Some(reader.read_u32()? as usize);
+        }
+        if flags & LEVEL_FLAG != 0 {
+            delta.level = Some(reader.read_u32()? as usize);
+        }
+        if flags & HELD_FLAG != 0 {
+            delta.held = Some(reader.read_figure(piece_set)?);
+        }
+        if flags & NEXT_FLAG != 0 {
+            let mut next = vec![];
+            for _ in 0..reader.read_u8()? {
+                let code = reader.read_u8()?;
+                let figure = figure_from_code(code, piece_set).flatten();
+                next.push(figure.ok_or(DeltaError::InvalidFigure(code))?);
+            }
+            delta.next = Some(next);
+        }
+        return Ok(delta);
+    }
+}
+
+fn changed<T: PartialEq>(old: T, new: T) -> Option<T> {
+    if old != new {
+        return Some(new);
+    }
+    return None;
+}
+
+// Custom figures missing from the piece set are sent as garbage
+fn figure_code(figure: Option<&FigureType>, piece_set: &[FigureType]) -> u8 {
+    return match figure {
+        None => EMPTY_CODE,
+        Some(FigureType::Custom(_)) => {
+            let index = piece_set.iter().position(|piece| Some(piece) == figure);
+            match index {
+                Some(index) if index <= (u8::MAX - CUSTOM_CODE) as usize => {
+                    CUSTOM_CODE + index as u8
+                }
+                _ => FigureType::Garbage.code(),
+            }
+        }
+        Some(figure) => figure.code(),
+    };
+}
+
+// `Some(None)` is an empty cell
+fn figure_from_code(code: u8, piece_set: &[FigureType]) -> Option<Option<FigureType>> {
+    let figure = match code {
+        EMPTY_CODE => return Some(None),
+        1 => FigureType::I,
+        2 => FigureType::J,
+        3 => FigureType::L,
+        4 => FigureType::O,
+        5 => FigureType::S,
+        6 => FigureType::T,
+        7 => FigureType::Z,
+        8 => FigureType::Garbage,
+        _ => piece_set.get((code - CUSTOM_CODE) as usize)?.clone(),
+    };
+    return Some(Some(figure));
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], DeltaError> {
+        let end = self.position + count;
+        if end > self.bytes.len() {
+            return Err(DeltaError::UnexpectedEnd);
+        }
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        return Ok(slice);
+    }
+
+    fn read_u8(&mut self) -> Result<u8, DeltaError> {
+        return Ok(self.take(1)?[0]);
+    }
+
+    fn read_u16(&mut self) -> Result<u16, DeltaError> {
+        let mut buffer = [0; 2];
+        buffer.copy_from_slice(self.take(2)?);
+        return Ok(u16::from_le_bytes(buffer));
+    }
+
+    fn read_u32(&mut self) -> Result<u32, DeltaError> {
+        let mut buffer = [0; 4];
+        buffer.copy_from_slice(self.take(4)?);
+        return Ok(u32::from_le_bytes(buffer));
+    }
+
+    fn read_u64(&mut self) -> Result<u64, DeltaError> {
+        let mut buffer = [0; 8];
+        buffer.copy_from_slice(self.take(8)?);
+        return Ok(u64::from_le_bytes(buffer));
+    }
+
+    fn read_figure(&mut self, piece_set: &[FigureType]) -> Result<Option<FigureType>, DeltaError> {
+        let code = self.read_u8()?;
+        return figure_from_code(code, piece_set).ok_or(DeltaError::InvalidFigure(code));
+    }
+}
+
+#[cfg(test)]
+mod netcode_tests {
+    use super::super::{Action, Game, GameConfig};
+    use super::*;
+
+    fn played_game() -> Game {
+        let mut game = Game::with_config(GameConfig::builder().seed(12).build());
+        for action in [
+            Action::MoveLeft,
+            Action::HardDrop,
+            Action::Hold,
+            Action::Rotate,
+        ] {
+            game.update(0.2);
+            game.perform(action);
+        }
+        return game;
+    }
+
+    #[test]
+    fn test_delta_brings_the_old_snapshot_up_to_date() {
+        let mut game = played_game();
+        let old = game.snapshot();
+        game.perform(Action::MoveRight);
+        let moved = game.snapshot();
+        let delta = StateDelta::between(&old, &moved);
+        assert!(delta.cells.is_empty());
+        assert_eq!(delta.active, Some(moved.active.pose()));
+        assert_eq!(delta.score, None);
+        game.perform(Action::HardDrop);
+        let new = game.snapshot();
+        let delta = StateDelta::between(&moved, &new);
+        assert_eq!(delta.cells.len(), 4);
+        let mut synced = moved.clone();
+        delta.apply(&mut synced).unwrap();
+        assert_eq!(synced.board, new.board);
+        assert_eq!(synced.active, new.active);
+        assert_eq!(synced.score, new.score);
+        assert_eq!(synced.next, new.next);
+        assert!(StateDelta::between(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let mut game = played_game();
+        let old = game.snapshot();
+        game.perform(Action::Rotate);
+        game.perform(Action::HardDrop);
+        game.perform(Action::Hold);
+        let delta = StateDelta::between(&old, &game.snapshot());
+        let piece_set = FigureType::standard_set();
+        let bytes = delta.to_bytes(&piece_set);
+        assert_eq!(StateDelta::from_bytes(&bytes, &piece_set), Ok(delta));
+        assert_eq!(
+            StateDelta::from_bytes(&bytes[..bytes.len() - 1], &piece_set),
+            Err(DeltaError::UnexpectedEnd)
+        );
+        assert_eq!(
+            StateDelta::from_bytes(b"nope", &piece_set),
+            Err(DeltaError::InvalidHeader)
+        );
+    }
+
+    #[test]
+    fn test_out_of_bounds_cells_are_rejected() {
+        let mut snapshot = played_game().snapshot();
+        let before = snapshot.clone();
+        let delta = StateDelta {
+            cells: vec![
+                CellChange {
+                    x: 0,
+                    y: 0,
+                    cell: Some(FigureType::Garbage),
+                },
+                CellChange {
+                    x: 10,
+                    y: 0,
+                    cell: None,
+                },
+            ],
+            ..StateDelta::default()
+        };
+        assert_eq!(
+            delta.apply(&mut snapshot),
+            Err(DeltaError::OutOfBounds { x: 10, y: 0 })
+        );
+        assert_eq!(snapshot, before);
+    }
+}
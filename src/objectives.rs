@@ -0,0 +1,415 @@
+// Mission goals for campaign and puzzle modes, evaluated from the events of a game
+use super::{Action, ClearKind, Game, GameEvent, Key, KeyState};
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Goal {
+    // Clears of this kind, `ClearKind::TSpin(2)` for a T-spin double
+    Clear { kind: ClearKind, times: usize },
+    // Empty the board within this many pieces
+    PerfectClear { pieces: usize },
+    // Play this many seconds at this level or above
+    Survive { seconds: f64, level: usize },
+    Lines(usize),
+    Score(u64),
+    Combo(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ObjectiveStatus {
+    InProgress,
+    Completed,
+    // The game ended first, or the pieces of a perfect clear ran out
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Objective {
+    pub goal: Goal,
+    status: ObjectiveStatus,
+    // Clears, pieces, seconds, lines, score or combo, depending on the goal
+    value: f64,
+}
+
+impl Objective {
+    pub fn new(goal: Goal) -> Objective {
+        return Objective {
+            goal,
+            status: ObjectiveStatus::InProgress,
+            value: 0.0,
+        };
+    }
+
+    pub fn status(&self) -> ObjectiveStatus {
+        return self.status;
+    }
+
+    // What counts towards the goal so far, perfect clears count the pieces used
+    pub fn get_value(&self) -> f64 {
+        return self.value;
+    }
+
+    pub fn get_target(&self) -> f64 {
+        return match &self.goal {
+            Goal::Clear { times, .. } => *times as f64,
+            Goal::PerfectClear { pieces } => *pieces as f64,
+            Goal::Survive { seconds, .. } => *seconds,
+            Goal::Lines(lines) => *lines as f64,
+            Goal::Score(score) => *score as f64,
+            Goal::Combo(combo) => *combo as f64,
+        };
+    }
+
+    // From 0 to 1, for progress bars, a perfect clear only moves when it is made
+    pub fn progress(&self) -> f64 {
+        if self.status == ObjectiveStatus::Completed {
+            return 1.0;
+        }
+        if matches!(self.goal, Goal::PerfectClear { .. }) || self.get_target() <= 0.0 {
+            return 0.0;
+        }
+        return (self.value / self.get_target()).min(1.0);
+    }
+}
+
+// Follows the objectives of a game, feed it the events of every call made on the game
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Objectives {
+    objectives: Vec<Objective>,
+    elapsed: f64,
+    // The clear of the last locked piece, until its events are all in
+    locked: Option<ClearKind>,
+    // Lines were cleared since the last check, the board may be empty once they are gone
+    cleared: bool,
+}
+
+impl Objectives {
+    // Time counts from the current state of the game
+    pub fn new(game: &Game, goals: Vec<Goal>) -> Objectives {
+        return Objectives {
+            objectives: goals.into_iter().map(Objective::new).collect(),
+            elapsed: game.get_elapsed_time(),
+            locked: None,
+            cleared: false,
+        };
+    }
+
+    pub fn objectives(&self) -> &[Objective] {
+        return &self.objectives;
+    }
+
+    pub fn is_completed(&self) -> bool {
+        return self
+            .objectives
+            .iter()
+            .all(|objective| objective.status == ObjectiveStatus::Completed);
+    }
+
+    pub fn is_failed(&self) -> bool {
+        return self
+            .objectives
+            .iter()
+            .any(|objective| objective.status == ObjectiveStatus::Failed);
+    }
+
+    // Returns `GameEvent::ObjectiveCompleted` and `GameEvent::ObjectiveFailed` with the index of the
+    // objectives that changed
+    pub fn observe(&mut self, game: &Game, events: &[GameEvent]) -> Vec<GameEvent> {
+        let before: Vec<ObjectiveStatus> = self.objectives.iter().map(|o| o.status).collect();
+        for event in events {
+            match event {
+                GameEvent::PieceLocked(_) => {
+                    self.finish_piece();
+                    self.locked = Some(ClearKind::None);
+                    self.count_piece();
+                }
+                GameEvent::LinesCleared(lines) => {
+                    self.cleared = true;
+                    // Chain reactions after it don't change its kind
+                    if self.locked == Some(ClearKind::None) {
+                        self.locked = Some(ClearKind::Lines(lines.len()));
+                    }
+                }
+                // Comes after the lines of the same piece
+                GameEvent::TSpin(kind) if self.locked.is_some() => {
+                    self.locked = Some(kind.clone());
+                }
+                _ => {}
+            }
+        }
+        self.finish_piece();
+        self.check_game(game);
+        let mut changes = vec![];
+        for (index, objective) in self.objectives.iter().enumerate() {
+            if objective.status == before[index] {
+                continue;
+            }
+            changes.push(match objective.status {
+                ObjectiveStatus::Completed => GameEvent::ObjectiveCompleted(index),
+                _ => GameEvent::ObjectiveFailed(index),
+            });
+        }
+        return changes;
+    }
+
+    fn in_progress(&mut self) -> impl Iterator<Item = &mut Objective> {
+        return self
+            .objectives
+            .iter_mut()
+            .filter(|objective| objective.status == ObjectiveStatus::InProgress);
+    }
+
+    // Clear goals count the kind of the last locked piece once all its events are in
+    fn finish_piece(&mut self) {
+        let kind = match self.locked.take() {
+            Some(kind) => kind,
+            None => return,
+        };
+        for objective in self.in_progress() {
+            if let Goal::Clear { kind: goal, times } = &objective.goal {
+                if *goal == kind {
+                    objective.value += 1.0;
+                    if objective.value >= *times as f64 {
+                        objective.status = ObjectiveStatus::Completed;
+                    }
+                }
+            }
+        }
+    }
+
+    fn count_piece(&mut self) {
+        for objective in self.in_progress() {
+            if let Goal::PerfectClear { .. } = objective.goal {
+                objective.value += 1.0;
+            }
+        }
+    }
+
+    fn check_game(&mut self, game: &Game) {
+        let elapsed = game.get_elapsed_time();
+        let delta = elapsed - self.elapsed;
+        self.elapsed = elapsed;
+        let perfect_clear = self.cleared
+            && !game.is_clearing()
+            && game.get_board().taken_rows().iter().all(|row| *row == 0);
+        if !game.is_clearing() {
+            self.cleared = false;
+        }
+        let ended = game.is_game_over() || game.is_finished();
+        for objective in self.in_progress() {
+            let (done, failed) = match objective.goal {
+                Goal::Clear { .. } => (false, false),
+                // The last piece can still clear the board once its lines are gone
+                Goal::PerfectClear { pieces } => {
+                    let out_of_pieces = objective.value >= pieces as f64 && !game.is_clearing();
+                    (perfect_clear, out_of_pieces)
+                }
+                Goal::Survive { seconds, level } => {
+                    if game.get_level() >= level && !game.is_game_over() {
+                        objective.value += delta;
+                    }
+                    (objective.value >= seconds, false)
+                }
+                Goal::Lines(lines) => {
+                    objective.value = game.get_lines_completed() as f64;
+                    (objective.value >= lines as f64, false)
+                }
+                Goal::Score(score) => {
+                    objective.value = game.get_score() as f64;
+                    (objective.value >= score as f64, false)
+                }
+                Goal::Combo(combo) => {
+                    objective.value = objective.value.max(game.get_combo() as f64);
+                    (objective.value >= combo as f64, false)
+                }
+            };
+            if done {
+                objective.status = ObjectiveStatus::Completed;
+            } else if failed || ended {
+                objective.status = ObjectiveStatus::Failed;
+            }
+        }
+    }
+}
+
+// A game played for a list of goals, the objective events come after the game ones
+pub struct Mission {
+    game: Game,
+    objectives: Objectives,
+}
+
+impl Mission {
+    pub fn new(game: Game, goals: Vec<Goal>) -> Mission {
+        return Mission {
+            objectives: Objectives::new(&game, goals),
+            game,
+        };
+    }
+
+    pub fn update(&mut self, delta_time: f64) -> Vec<GameEvent> {
+        let events = self.game.update(delta_time);
+        return self.observe(events);
+    }
+
+    pub fn perform(&mut self, action: Action) -> Vec<GameEvent> {
+        let events = self.game.perform(action);
+        return self.observe(events);
+    }
+
+    pub fn input(&mut self, key: Key, key_state: KeyState) -> Vec<GameEvent> {
+        let events = self.game.input(key, key_state);
+        return self.observe(events);
+    }
+
+    fn observe(&mut self, mut events: Vec<GameEvent>) -> Vec<GameEvent> {
+        let changes = self.objectives.observe(&self.game, &events);
+        events.extend(changes);
+        return events;
+    }
+
+    pub fn objectives(&self) -> &[Objective] {
+        return self.objectives.objectives();
+    }
+
+    pub fn is_completed(&self) -> bool {
+        return self.objectives.is_completed();
+    }
+
+    pub fn is_failed(&self) -> bool {
+        return self.objectives.is_failed();
+    }
+
+    pub fn game(&self) -> &Game {
+        return &self.game;
+    }
+
+    pub fn game_mut(&mut self) -> &mut Game {
+        return &mut self.game;
+    }
+}
+
+#[cfg(test)]
+mod objectives_tests {
+    use super::super::{Board, FigureType, GameConfig, GameMode, RandomizerChoice};
+    use super::*;
+
+    // The O fills the gap of the two bottom rows
+    fn gap_board(filled_rows: usize) -> Board {
+        let mut rows = vec![vec![None; 10]; 20 - filled_rows];
+        for _ in 0..filled_rows {
+            let mut row = vec![Some(FigureType::Garbage); 10];
+            row[3] = None;
+            row[4] = None;
+            rows.push(row);
+        }
+        return Board::from_rows(rows);
+    }
+
+    fn o_config() -> GameConfig {
+        return GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![3]))
+            .build();
+    }
+
+    #[test]
+    fn test_clear_goal_counts_the_kind() {
+        let game = Game::with_board(gap_board(4), o_config());
+        let goals = vec![Goal::Clear {
+            kind: ClearKind::Lines(2),
+            times: 2,
+        }];
+        let mut mission = Mission::new(game, goals);
+        mission.perform(Action::HardDrop);
+        assert_eq!(mission.objectives()[0].progress(), 0.5);
+        let events = mission.perform(Action::HardDrop);
+        assert!(events.contains(&GameEvent::ObjectiveCompleted(0)));
+        assert!(mission.is_completed());
+    }
+
+    #[test]
+    fn test_t_spin_double_goal() {
+        // A T shaped slot at the bottom with an overhang on the left
+        let mut rows = vec![vec![None; 10]; 20];
+        rows[19] = vec![Some(FigureType::I); 10];
+        rows[19][4] = None;
+        rows[18] = vec![Some(FigureType::I); 10];
+        for cell in &mut rows[18][3..=5] {
+            *cell = None;
+        }
+        rows[17][3] = Some(FigureType::I);
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![5]))
+            .build();
+        let game = Game::with_board(Board::from_rows(rows), config);
+        let goals = vec![
+            Goal::Clear {
+                kind: ClearKind::TSpin(2),
+                times: 1,
+            },
+            Goal::Clear {
+                kind: ClearKind::Lines(2),
+                times: 1,
+            },
+        ];
+        let mut mission = Mission::new(game, goals);
+        mission.perform(Action::Rotate);
+        mission.perform(Action::SonicDrop);
+        let events = mission.perform(Action::Rotate);
+        assert!(events.is_empty());
+        let events = mission.perform(Action::HardDrop);
+        assert!(events.contains(&GameEvent::ObjectiveCompleted(0)));
+        assert_eq!(mission.objectives()[1].get_value(), 0.0);
+    }
+
+    #[test]
+    fn test_perfect_clear_within_the_pieces() {
+        let game = Game::with_board(gap_board(2), o_config());
+        let mut mission = Mission::new(game, vec![Goal::PerfectClear { pieces: 1 }]);
+        let events = mission.perform(Action::HardDrop);
+        assert!(events.contains(&GameEvent::ObjectiveCompleted(0)));
+        assert_eq!(mission.objectives()[0].get_value(), 1.0);
+
+        let game = Game::with_board(gap_board(4), o_config());
+        let mut mission = Mission::new(game, vec![Goal::PerfectClear { pieces: 1 }]);
+        let events = mission.perform(Action::HardDrop);
+        assert!(events.contains(&GameEvent::ObjectiveFailed(0)));
+        assert!(mission.is_failed());
+    }
+
+    #[test]
+    fn test_survive_counts_time_at_the_level() {
+        let config = GameConfig {
+            start_level: 15,
+            mode: GameMode::Marathon,
+            ..GameConfig::default()
+        };
+        let goals = vec![
+            Goal::Survive {
+                seconds: 1.0,
+                level: 15,
+            },
+            Goal::Survive {
+                seconds: 1.0,
+                level: 16,
+            },
+        ];
+        let mut mission = Mission::new(Game::with_config(config), goals);
+        for _ in 0..5 {
+            mission.update(0.1);
+        }
+        assert_eq!(
+            mission.objectives()[0].status(),
+            ObjectiveStatus::InProgress
+        );
+        for _ in 0..6 {
+            mission.update(0.1);
+        }
+        assert_eq!(mission.objectives()[0].status(), ObjectiveStatus::Completed);
+        assert_eq!(mission.objectives()[1].get_value(), 0.0);
+    }
+}
@@ -0,0 +1,121 @@
+// Where a piece is and which way it faces, the one type placements, events and queries share
+use super::{ActiveFigure, FigureType, Point};
+use alloc::vec::Vec;
+
+// Orientations named like the SRS states, by clockwise turns from the spawn orientation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Rotation {
+    #[default]
+    Spawn,
+    Right,
+    Reverse,
+    Left,
+}
+
+impl Rotation {
+    pub fn from_steps(steps: usize) -> Rotation {
+        return match steps % 4 {
+            0 => Rotation::Spawn,
+            1 => Rotation::Right,
+            2 => Rotation::Reverse,
+            _ => Rotation::Left,
+        };
+    }
+
+    // Clockwise turns from the spawn orientation
+    pub fn steps(self) -> usize {
+        return match self {
+            Rotation::Spawn => 0,
+            Rotation::Right => 1,
+            Rotation::Reverse => 2,
+            Rotation::Left => 3,
+        };
+    }
+
+    pub fn clockwise(self) -> Rotation {
+        return Rotation::from_steps(self.steps() + 1);
+    }
+
+    pub fn counter_clockwise(self) -> Rotation {
+        return Rotation::from_steps(self.steps() + 3);
+    }
+}
+
+// `origin` is the top left corner of the figure matrix. `Game` methods count its row from the first
+// visible row like the drawing functions, boards and snapshots from their top row
+#[derive(Debug, Clone, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PiecePose {
+    pub kind: FigureType,
+    pub origin: Point,
+    pub rotation: Rotation,
+}
+
+impl PiecePose {
+    pub fn new(kind: FigureType, origin: Point, rotation: Rotation) -> PiecePose {
+        return PiecePose {
+            kind,
+            origin,
+            rotation,
+        };
+    }
+
+    // The cells the piece covers, in the order of its matrix
+    pub fn cells(&self) -> Vec<Point> {
+        return ActiveFigure::from_pose(self).cells().collect();
+    }
+
+    // The same pose moved by `dx` and `dy`
+    pub fn translated(&self, dx: i32, dy: i32) -> PiecePose {
+        let origin = Point {
+            x: self.origin.x + dx,
+            y: self.origin.y + dy,
+        };
+        return PiecePose {
+            origin,
+            ..self.clone()
+        };
+    }
+}
+
+#[cfg(test)]
+mod pose_tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_rotation_steps() {
+        assert_eq!(Rotation::Spawn.counter_clockwise(), Rotation::Left);
+        assert_eq!(Rotation::Left.clockwise(), Rotation::Spawn);
+        assert_eq!(Rotation::from_steps(6), Rotation::Reverse);
+        for steps in 0..4 {
+            assert_eq!(Rotation::from_steps(steps).steps(), steps);
+        }
+    }
+
+    #[test]
+    fn test_pose_round_trip() {
+        let figure = ActiveFigure::new(FigureType::T, Point { x: 3, y: 5 }).rotated();
+        let pose = figure.pose();
+        assert_eq!(pose.rotation, Rotation::Right);
+        assert_eq!(pose.origin, Point { x: 3, y: 5 });
+        assert_eq!(ActiveFigure::from_pose(&pose), figure);
+        assert_eq!(pose.cells(), figure.to_cartesian());
+        let moved = pose.translated(-1, 2);
+        assert_eq!(
+            moved.cells(),
+            figure.moved_left().moved_down().moved_down().to_cartesian()
+        );
+        let o = PiecePose::new(FigureType::O, Point { x: 0, y: 0 }, Rotation::Spawn);
+        assert_eq!(
+            o.cells(),
+            vec![
+                Point { x: 0, y: 0 },
+                Point { x: 1, y: 0 },
+                Point { x: 0, y: 1 },
+                Point { x: 1, y: 1 },
+            ]
+        );
+    }
+}
@@ -0,0 +1,103 @@
+// What frontends and netcode feed the engine: the actions pressed on each frame, so local play,
+// replays and remote inputs all go through `Game::apply_input_frame`
+use super::Action;
+use smallvec::SmallVec;
+
+// The actions of one frame, most frames have no more than a few of them
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InputFrame {
+    pub frame: u32,
+    pub actions: SmallVec<[Action; 4]>,
+}
+
+impl InputFrame {
+    pub fn new(frame: u32) -> InputFrame {
+        return InputFrame {
+            frame,
+            actions: SmallVec::new(),
+        };
+    }
+
+    pub fn with_actions(frame: u32, actions: &[Action]) -> InputFrame {
+        return InputFrame {
+            frame,
+            actions: SmallVec::from_slice(actions),
+        };
+    }
+
+    pub fn push(&mut self, action: Action) {
+        self.actions.push(action);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.actions.is_empty();
+    }
+}
+
+#[cfg(test)]
+mod protocol_tests {
+    use super::super::{Game, GameConfig, RandomizerChoice};
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn frame_game() -> Game {
+        let config = GameConfig::builder()
+            .randomizer(RandomizerChoice::Sequence(vec![5]))
+            .tick_rate(60)
+            .build();
+        return Game::with_config(config);
+    }
+
+    #[test]
+    fn test_input_frames_match_queued_actions() {
+        let inputs = [
+            InputFrame::with_actions(3, &[Action::MoveLeft]),
+            InputFrame::with_actions(10, &[Action::Rotate, Action::MoveLeft]),
+            InputFrame::with_actions(25, &[Action::HardDrop]),
+        ];
+        let mut game = frame_game();
+        let mut events = Vec::new();
+        for input in &inputs {
+            events.extend(game.apply_input_frame(input));
+        }
+        let mut reference = frame_game();
+        let mut expected = Vec::new();
+        for frame in 0..=25 {
+            if let Some(input) = inputs.iter().find(|input| input.frame == frame) {
+                for action in &input.actions {
+                    reference.queue_action(*action);
+                }
+            }
+            expected.extend(reference.step_frame());
+        }
+        assert_eq!(events, expected);
+        assert!(!events.is_empty());
+        assert_eq!(game.state_hash(), reference.state_hash());
+    }
+
+    #[test]
+    fn test_late_frames_run_on_the_next_frame() {
+        let mut game = frame_game();
+        game.apply_input_frame(&InputFrame::new(20));
+        let hash = game.state_hash();
+        let mut late = InputFrame::new(5);
+        late.push(Action::MoveRight);
+        game.apply_input_frame(&late);
+        let mut reference = frame_game();
+        reference.apply_input_frame(&InputFrame::new(20));
+        assert_eq!(reference.state_hash(), hash);
+        reference.queue_action(Action::MoveRight);
+        reference.step_frame();
+        assert_eq!(game.state_hash(), reference.state_hash());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_input_frame_serializes() {
+        let input = InputFrame::with_actions(7, &[Action::Hold, Action::HardDrop]);
+        let json = serde_json::to_string(&input).unwrap();
+        assert_eq!(serde_json::from_str::<InputFrame>(&json).unwrap(), input);
+    }
+}
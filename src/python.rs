@@ -0,0 +1,186 @@
+use super::bindings::cell_codes_into;
+use super::{Action, Game, GameConfig, Size};
+use alloc::vec;
+use alloc::vec::Vec;
+use pyo3::prelude::*;
+
+// Python side copy of `Action`, pyclass enums can't wrap a foreign type
+#[pyclass(name = "Action", eq, eq_int)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PyAction {
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Rotate,
+    RotateCCW,
+    Rotate180,
+    Hold,
+    HardDrop,
+    SoftDrop,
+    SoftDropStart,
+    SoftDropStop,
+    Pause,
+    Resume,
+    SonicDrop,
+    MoveLeftWall,
+    MoveRightWall,
+    ActivateSpecial,
+}
+
+impl From<PyAction> for Action {
+    fn from(action: PyAction) -> Action {
+        return match action {
+            PyAction::MoveDown => Action::MoveDown,
+            PyAction::MoveLeft => Action::MoveLeft,
+            PyAction::MoveRight => Action::MoveRight,
+            PyAction::Rotate => Action::Rotate,
+            PyAction::RotateCCW => Action::RotateCCW,
+            PyAction::Rotate180 => Action::Rotate180,
+            PyAction::Hold => Action::Hold,
+            PyAction::HardDrop => Action::HardDrop,
+            PyAction::SoftDrop => Action::SoftDrop,
+            PyAction::SoftDropStart => Action::SoftDropStart,
+            PyAction::SoftDropStop => Action::SoftDropStop,
+            PyAction::Pause => Action::Pause,
+            PyAction::Resume => Action::Resume,
+            PyAction::SonicDrop => Action::SonicDrop,
+            PyAction::MoveLeftWall => Action::MoveLeftWall,
+            PyAction::MoveRightWall => Action::MoveRightWall,
+            PyAction::ActivateSpecial => Action::ActivateSpecial,
+        };
+    }
+}
+
+#[pymethods]
+impl PyAction {
+    // Same value as `Action::code`
+    #[getter]
+    pub fn code(&self) -> u8 {
+        return Action::from(*self).code();
+    }
+}
+
+// The randomizer uses interior mutability, so a game stays on the thread that created it
+#[pyclass(name = "Game", unsendable)]
+pub struct PyGame {
+    game: Game,
+}
+
+#[pymethods]
+impl PyGame {
+    #[new]
+    #[pyo3(signature = (width = 10, height = 20, seed = 0))]
+    pub fn new(width: usize, height: usize, seed: u64) -> PyGame {
+        let config = GameConfig::builder()
+            .size(Size { height, width })
+            .seed(seed)
+            .build();
+        return PyGame {
+            game: Game::with_config(config),
+        };
+    }
+
+    // Advances the game by the given seconds
+    pub fn update(&mut self, delta_time: f64) {
+        self.game.update(delta_time);
+    }
+
+    pub fn perform(&mut self, action: PyAction) {
+        self.game.perform(action.into());
+    }
+
+    #[getter]
+    pub fn width(&self) -> usize {
+        return self.game.get_visible_size().width;
+    }
+
+    #[getter]
+    pub fn height(&self) -> usize {
+        return self.game.get_visible_size().height;
+    }
+
+    // One `FigureType::code` per cell (0 when empty), row by row, active figure included
+    pub fn cells(&self) -> Vec<u8> {
+        let mut cells = vec![0; self.width() * self.height()];
+        cell_codes_into(&self.game, &mut cells);
+        return cells;
+    }
+
+    // Same codes as `cells`, one list per row
+    pub fn board(&self) -> Vec<Vec<u8>> {
+        return self
+            .cells()
+            .chunks(self.width().max(1))
+            .map(|row| row.to_vec())
+            .collect();
+    }
+
+    #[getter]
+    pub fn next_figure(&self) -> u8 {
+        return self.game.get_next_figure_type().code();
+    }
+
+    #[getter]
+    pub fn held_figure(&self) -> u8 {
+        return self.game.get_held_figure().map_or(0, |figure| figure.code());
+    }
+
+    #[getter]
+    pub fn score(&self) -> u64 {
+        return self.game.get_score();
+    }
+
+    #[getter]
+    pub fn lines(&self) -> usize {
+        return self.game.get_lines_completed();
+    }
+
+    #[getter]
+    pub fn level(&self) -> usize {
+        return self.game.get_level();
+    }
+
+    #[getter]
+    pub fn is_game_over(&self) -> bool {
+        return self.game.is_game_over();
+    }
+}
+
+impl PyGame {
+    pub fn game(&self) -> &Game {
+        return &self.game;
+    }
+}
+
+#[pymodule]
+fn tetris_core_mod(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyGame>()?;
+    module.add_class::<PyAction>()?;
+    return Ok(());
+}
+
+#[cfg(test)]
+mod python_tests {
+    use super::*;
+
+    #[test]
+    fn test_board_accessors() {
+        let mut game = PyGame::new(10, 20, 1);
+        let board = game.board();
+        assert_eq!(board.len(), 20);
+        assert_eq!(board[0].len(), 10);
+        assert_eq!(game.cells().iter().filter(|cell| **cell != 0).count(), 4);
+        game.perform(PyAction::HardDrop);
+        game.update(0.1);
+        assert_eq!(game.cells().iter().filter(|cell| **cell != 0).count(), 8);
+        assert!(game.score() > 0);
+    }
+
+    #[test]
+    fn test_action_codes() {
+        assert_eq!(PyAction::MoveDown.code(), Action::MoveDown.code());
+        assert_eq!(PyAction::Resume.code(), Action::Resume.code());
+        assert_eq!(PyAction::SonicDrop.code(), Action::SonicDrop.code());
+        assert_eq!(PyAction::MoveRightWall.code(), Action::MoveRightWall.code());
+    }
+}
@@ -0,0 +1,865 @@
+use super::FigureType;
+use core::cell::{Cell, RefCell};
+use core::convert::TryFrom;
+use core::hash::{Hash, Hasher};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+const BAG_SIZE: i32 = 7;
+
+// Deals the figures themselves, for history based randomizers like TGM's and bag systems that keep
+// their own state
+pub trait Randomizer {
+    // `history` holds every figure dealt so far, oldest first
+    fn next(&mut self, history: &[FigureType]) -> FigureType;
+
+    // Position of the last figure in its bag, from 0, for `Game::rng_trace`
+    fn bag_index(&self) -> Option<usize> {
+        return None;
+    }
+
+    // The last figure was dealt past the end of a `ScriptEnd::Report` script
+    fn is_overrun(&self) -> bool {
+        return false;
+    }
+
+    // Randomizers that can be saved and resumed, used to serialize a game
+    fn state(&self) -> Option<RandomizerState> {
+        return None;
+    }
+
+    // A copy dealing the same figures from here, for `Game::try_clone`
+    fn box_clone(&self) -> Option<Box<dyn Randomizer>> {
+        return None;
+    }
+
+    // The randomizer at a state it provided before, for `Game::restore`. None when it can't be
+    // rebuilt, the game then keeps dealing from where it is
+    fn resume(&self, _state: RandomizerState) -> Option<Box<dyn Randomizer>> {
+        return None;
+    }
+
+    // Feeds the state to `Game::state_hash`, randomizers can hash their fields instead of
+    // copying them into a `RandomizerState`
+    fn hash_state(&self, mut state: &mut dyn Hasher) {
+        self.state().hash(&mut state);
+    }
+}
+
+impl<R: Randomizer + ?Sized> Randomizer for Box<R> {
+    fn next(&mut self, history: &[FigureType]) -> FigureType {
+        return (**self).next(history);
+    }
+
+    fn bag_index(&self) -> Option<usize> {
+        return (**self).bag_index();
+    }
+
+    fn is_overrun(&self) -> bool {
+        return (**self).is_overrun();
+    }
+
+    fn state(&self) -> Option<RandomizerState> {
+        return (**self).state();
+    }
+
+    fn box_clone(&self) -> Option<Box<dyn Randomizer>> {
+        return (**self).box_clone();
+    }
+
+    fn resume(&self, state: RandomizerState) -> Option<Box<dyn Randomizer>> {
+        return (**self).resume(state);
+    }
+
+    fn hash_state(&self, state: &mut dyn Hasher) {
+        (**self).hash_state(state);
+    }
+}
+
+// The first randomizer interface, kept for the built in randomizers and older code: values index
+// the config's piece set, out of range values pick its last figure
+pub trait IndexRandomizer {
+    fn random(&self) -> i32;
+
+    // Same as `Randomizer::bag_index`, for the last value
+    fn bag_index(&self) -> Option<usize> {
+        return None;
+    }
+
+    // Same as `Randomizer::is_overrun`, for the last value
+    fn is_overrun(&self) -> bool {
+        return false;
+    }
+
+    fn state(&self) -> Option<RandomizerState> {
+        return None;
+    }
+
+    // Same as `Randomizer::box_clone`, randomizers providing a state are resumed from it
+    fn box_clone(&self) -> Option<Box<dyn IndexRandomizer>> {
+        return self.state().map(RandomizerState::into_randomizer);
+    }
+
+    // Same as `Randomizer::hash_state`
+    fn hash_state(&self, mut state: &mut dyn Hasher) {
+        self.state().hash(&mut state);
+    }
+}
+
+impl<R: IndexRandomizer + ?Sized> IndexRandomizer for Box<R> {
+    fn random(&self) -> i32 {
+        return (**self).random();
+    }
+
+    fn bag_index(&self) -> Option<usize> {
+        return (**self).bag_index();
+    }
+
+    fn is_overrun(&self) -> bool {
+        return (**self).is_overrun();
+    }
+
+    fn state(&self) -> Option<RandomizerState> {
+        return (**self).state();
+    }
+
+    fn box_clone(&self) -> Option<Box<dyn IndexRandomizer>> {
+        return (**self).box_clone();
+    }
+
+    fn hash_state(&self, state: &mut dyn Hasher) {
+        (**self).hash_state(state);
+    }
+}
+
+// Deals the figures of an `IndexRandomizer`, for code written against the first interface to
+// pass where a `Randomizer` is expected
+pub struct Indexed<R> {
+    randomizer: R,
+    piece_set: Vec<FigureType>,
+}
+
+impl<R: IndexRandomizer> Indexed<R> {
+    // Values index `FigureType::standard_set()`
+    pub fn new(randomizer: R) -> Indexed<R> {
+        return Indexed::with_piece_set(randomizer, FigureType::standard_set());
+    }
+
+    pub fn with_piece_set(randomizer: R, piece_set: Vec<FigureType>) -> Indexed<R> {
+        return Indexed {
+            randomizer,
+            piece_set,
+        };
+    }
+}
+
+impl<R: IndexRandomizer> Randomizer for Indexed<R> {
+    fn next(&mut self, _: &[FigureType]) -> FigureType {
+        return indexed_figure(self.randomizer.random(), &self.piece_set);
+    }
+
+    fn bag_index(&self) -> Option<usize> {
+        return self.randomizer.bag_index();
+    }
+
+    fn is_overrun(&self) -> bool {
+        return self.randomizer.is_overrun();
+    }
+
+    fn state(&self) -> Option<RandomizerState> {
+        return self.randomizer.state();
+    }
+
+    fn box_clone(&self) -> Option<Box<dyn Randomizer>> {
+        let randomizer = self.randomizer.box_clone()?;
+        return Some(Box::new(Indexed::with_piece_set(
+            randomizer,
+            self.piece_set.clone(),
+        )));
+    }
+
+    fn resume(&self, state: RandomizerState) -> Option<Box<dyn Randomizer>> {
+        return Some(Box::new(Indexed::with_piece_set(
+            state.into_randomizer(),
+            self.piece_set.clone(),
+        )));
+    }
+
+    fn hash_state(&self, state: &mut dyn Hasher) {
+        self.randomizer.hash_state(state);
+    }
+}
+
+// Randomizer values index the piece set, out of range values pick its last figure
+pub(crate) fn indexed_figure(value: i32, piece_set: &[FigureType]) -> FigureType {
+    let figure = if value >= 0 {
+        piece_set.get(value as usize)
+    } else {
+        None
+    };
+    let figure = figure.or_else(|| piece_set.last());
+    return figure.cloned().unwrap_or(FigureType::I);
+}
+
+// What a game deals from, index randomizers are mapped on its piece set
+pub(crate) enum Dealer {
+    Indexed(Box<dyn IndexRandomizer>),
+    Figures(Box<dyn Randomizer>),
+}
+
+impl Dealer {
+    pub(crate) fn save(&self) -> Option<SavedDealer> {
+        let (kind, state) = match self {
+            Dealer::Indexed(randomizer) => (DealerKind::Indexed, randomizer.state()),
+            Dealer::Figures(randomizer) => (DealerKind::Figures, randomizer.state()),
+        };
+        return state.map(|state| SavedDealer { kind, state });
+    }
+
+    // Only index randomizers can be rebuilt from their state alone
+    pub(crate) fn from_saved(saved: SavedDealer) -> Option<Dealer> {
+        return match saved.kind {
+            DealerKind::Indexed => Some(Dealer::Indexed(saved.state.into_randomizer())),
+            DealerKind::Figures => None,
+        };
+    }
+
+    pub(crate) fn hash_state(&self, state: &mut dyn Hasher) {
+        match self {
+            Dealer::Indexed(randomizer) => randomizer.hash_state(state),
+            Dealer::Figures(randomizer) => randomizer.hash_state(state),
+        }
+    }
+
+    pub(crate) fn try_clone(&self) -> Option<Dealer> {
+        return match self {
+            Dealer::Indexed(randomizer) => randomizer.box_clone().map(Dealer::Indexed),
+            Dealer::Figures(randomizer) => randomizer.box_clone().map(Dealer::Figures),
+        };
+    }
+
+    // The dealer at a saved state. A figure randomizer is only resumed by itself, through
+    // `Randomizer::resume`, so None when it can't or the state was saved by another kind of dealer
+    pub(crate) fn resumed(&self, saved: SavedDealer) -> Option<Dealer> {
+        return match (saved.kind, self) {
+            (DealerKind::Figures, Dealer::Figures(randomizer)) => {
+                randomizer.resume(saved.state).map(Dealer::Figures)
+            }
+            _ => Dealer::from_saved(saved),
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum DealerKind {
+    Indexed,
+    Figures,
+}
+
+// A randomizer state and the interface of the randomizer that provided it
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub(crate) struct SavedDealer {
+    kind: DealerKind,
+    state: RandomizerState,
+}
+
+// Saves from before the kind was recorded hold the state alone, they came from index randomizers
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SavedDealer {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<SavedDealer, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum SavedData {
+            Saved {
+                kind: DealerKind,
+                state: RandomizerState,
+            },
+            State(RandomizerState),
+        }
+        return Ok(match SavedData::deserialize(deserializer)? {
+            SavedData::Saved { kind, state } => SavedDealer { kind, state },
+            SavedData::State(state) => SavedDealer {
+                kind: DealerKind::Indexed,
+                state,
+            },
+        });
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RandomizerState {
+    Bag { rng: XorShift, bag: Vec<i32>, size: i32 },
+    Sequence { sequence: Vec<i32>, index: usize },
+    Classic { rng: XorShift, last: Option<i32>, size: i32 },
+    Weighted { rng: XorShift, weights: Vec<u32> },
+    Scripted { script: Vec<i32>, index: usize, end: ScriptEnd },
+}
+
+impl RandomizerState {
+    pub fn into_randomizer(self) -> Box<dyn IndexRandomizer> {
+        return match self {
+            RandomizerState::Bag { rng, bag, size } => Box::new(BagRandomizer {
+                rng: Cell::new(rng),
+                bag: RefCell::new(bag),
+                size,
+            }),
+            RandomizerState::Sequence { sequence, index } => Box::new(SequenceRandomizer {
+                sequence,
+                index: Cell::new(index),
+            }),
+            RandomizerState::Classic { rng, last, size } => Box::new(ClassicRandomizer {
+                rng: Cell::new(rng),
+                last: Cell::new(last),
+                size,
+            }),
+            RandomizerState::Weighted { rng, weights } => Box::new(WeightedRandomizer {
+                rng: Cell::new(rng),
+                weights,
+            }),
+            RandomizerState::Scripted { script, index, end } => Box::new(ScriptedRandomizer {
+                script,
+                index: Cell::new(index),
+                end,
+            }),
+        };
+    }
+}
+
+// xorshift64* generator, small and good enough for shuffling pieces
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> XorShift {
+        // A zero state would only ever produce zeros
+        let state = if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed };
+        return XorShift { state };
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = x;
+        return x.wrapping_mul(0x2545_F491_4F6C_DD1D);
+    }
+
+    pub fn next_below(&mut self, upper: u64) -> u64 {
+        return self.next_u64() % upper;
+    }
+}
+
+pub struct BagRandomizer {
+    rng: Cell<XorShift>,
+    bag: RefCell<Vec<i32>>,
+    size: i32,
+}
+
+impl BagRandomizer {
+    pub fn new(seed: u64) -> BagRandomizer {
+        return BagRandomizer::with_size(seed, BAG_SIZE as usize);
+    }
+
+    // A bag holding every figure of a custom piece set once
+    pub fn with_size(seed: u64, size: usize) -> BagRandomizer {
+        return BagRandomizer {
+            rng: Cell::new(XorShift::new(seed)),
+            bag: RefCell::new(vec![]),
+            size: size.max(1) as i32,
+        };
+    }
+
+    fn refill(&self, bag: &mut Vec<i32>) {
+        let mut rng = self.rng.get();
+        bag.extend(0..self.size);
+        for i in (1..bag.len()).rev() {
+            let j = rng.next_below(i as u64 + 1) as usize;
+            bag.swap(i, j);
+        }
+        self.rng.set(rng);
+    }
+}
+
+impl IndexRandomizer for BagRandomizer {
+    fn random(&self) -> i32 {
+        let mut bag = self.bag.borrow_mut();
+        if bag.is_empty() {
+            self.refill(&mut bag);
+        }
+        return bag.pop().unwrap_or(0);
+    }
+
+    // None for a bag holding as many figures as its size or more, which only a state built by
+    // hand can
+    fn bag_index(&self) -> Option<usize> {
+        let left = self.bag.borrow().len();
+        return usize::try_from(self.size)
+            .ok()
+            .and_then(|size| size.checked_sub(left + 1));
+    }
+
+    fn hash_state(&self, mut state: &mut dyn Hasher) {
+        self.rng.get().hash(&mut state);
+        self.bag.borrow().hash(&mut state);
+        self.size.hash(&mut state);
+    }
+
+    fn state(&self) -> Option<RandomizerState> {
+        return Some(RandomizerState::Bag {
+            rng: self.rng.get(),
+            bag: self.bag.borrow().clone(),
+            size: self.size,
+        });
+    }
+}
+
+pub struct SequenceRandomizer {
+    sequence: Vec<i32>,
+    index: Cell<usize>,
+}
+
+impl SequenceRandomizer {
+    pub fn new(sequence: Vec<i32>) -> SequenceRandomizer {
+        return SequenceRandomizer {
+            sequence,
+            index: Cell::new(0),
+        };
+    }
+}
+
+impl IndexRandomizer for SequenceRandomizer {
+    fn random(&self) -> i32 {
+        if self.sequence.is_empty() {
+            return 0;
+        }
+        let index = self.index.get();
+        self.index.set(index + 1);
+        return self.sequence[index % self.sequence.len()];
+    }
+
+    fn hash_state(&self, mut state: &mut dyn Hasher) {
+        self.sequence.hash(&mut state);
+        self.index.get().hash(&mut state);
+    }
+
+    fn state(&self) -> Option<RandomizerState> {
+        return Some(RandomizerState::Sequence {
+            sequence: self.sequence.clone(),
+            index: self.index.get(),
+        });
+    }
+}
+
+// NES randomizer: one roll with an extra reroll value, rolled again once on a repeat or a reroll
+pub struct ClassicRandomizer {
+    rng: Cell<XorShift>,
+    last: Cell<Option<i32>>,
+    size: i32,
+}
+
+impl ClassicRandomizer {
+    pub fn new(seed: u64) -> ClassicRandomizer {
+        return ClassicRandomizer::with_size(seed, BAG_SIZE as usize);
+    }
+
+    pub fn with_size(seed: u64, size: usize) -> ClassicRandomizer {
+        return ClassicRandomizer {
+            rng: Cell::new(XorShift::new(seed)),
+            last: Cell::new(None),
+            size: size.max(1) as i32,
+        };
+    }
+}
+
+impl IndexRandomizer for ClassicRandomizer {
+    fn random(&self) -> i32 {
+        let mut rng = self.rng.get();
+        let mut piece = rng.next_below(self.size as u64 + 1) as i32;
+        if piece == self.size || Some(piece) == self.last.get() {
+            // The second roll is taken as is, so repeats stay possible but rare
+            piece = rng.next_below(self.size as u64) as i32;
+        }
+        self.rng.set(rng);
+        self.last.set(Some(piece));
+        return piece;
+    }
+
+    fn hash_state(&self, mut state: &mut dyn Hasher) {
+        self.rng.get().hash(&mut state);
+        self.last.get().hash(&mut state);
+        self.size.hash(&mut state);
+    }
+
+    fn state(&self) -> Option<RandomizerState> {
+        return Some(RandomizerState::Classic {
+            rng: self.rng.get(),
+            last: self.last.get(),
+            size: self.size,
+        });
+    }
+}
+
+// Rolls each figure of the piece set with its own weight, so a weight of 0 never deals it. Drills
+// like S and Z only give every other figure 0
+pub struct WeightedRandomizer {
+    rng: Cell<XorShift>,
+    weights: Vec<u32>,
+}
+
+impl WeightedRandomizer {
+    // `weights` follows the piece set, figures past its end get 0
+    pub fn new(seed: u64, weights: Vec<u32>) -> WeightedRandomizer {
+        return WeightedRandomizer {
+            rng: Cell::new(XorShift::new(seed)),
+            weights,
+        };
+    }
+
+    // Weights given per figure, figures of the piece set left out get 0
+    pub fn for_figures(
+        seed: u64,
+        piece_set: &[FigureType],
+        weights: &[(FigureType, u32)],
+    ) -> WeightedRandomizer {
+        let weights = piece_set
+            .iter()
+            .map(|figure| {
+                return weights
+                    .iter()
+                    .filter(|(weighted, _)| weighted == figure)
+                    .map(|(_, weight)| weight)
+                    .sum();
+            })
+            .collect();
+        return WeightedRandomizer::new(seed, weights);
+    }
+
+    // Chance of dealing the figure at `index` of the piece set, between 0 and 1
+    pub fn probability(&self, index: usize) -> f64 {
+        let total: u64 = self.weights.iter().map(|&weight| weight as u64).sum();
+        if total == 0 {
+            return 0.0;
+        }
+        return self.weights.get(index).copied().unwrap_or(0) as f64 / total as f64;
+    }
+}
+
+impl IndexRandomizer for WeightedRandomizer {
+    fn random(&self) -> i32 {
+        let total: u64 = self.weights.iter().map(|&weight| weight as u64).sum();
+        if total == 0 {
+            return 0;
+        }
+        let mut rng = self.rng.get();
+        let mut roll = rng.next_below(total);
+        self.rng.set(rng);
+        for (index, &weight) in self.weights.iter().enumerate() {
+            if roll < weight as u64 {
+                return index as i32;
+            }
+            roll -= weight as u64;
+        }
+        return 0;
+    }
+
+    fn hash_state(&self, mut state: &mut dyn Hasher) {
+        self.rng.get().hash(&mut state);
+        self.weights.hash(&mut state);
+    }
+
+    fn state(&self) -> Option<RandomizerState> {
+        return Some(RandomizerState::Weighted {
+            rng: self.rng.get(),
+            weights: self.weights.clone(),
+        });
+    }
+}
+
+// What a scripted randomizer does once every figure of its script was dealt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScriptEnd {
+    // Starts the script over
+    Loop,
+    // Starts over too, but marks the figures dealt past the end as `overrun` in `Game::rng_trace`,
+    // so a test dealing more figures than it wrote down can fail on it
+    Report,
+}
+
+// Deals a fixed list of figures, for puzzles and reproducing a game exactly. The game deals the
+// previews up front, so a script ending with `ScriptEnd::Report` needs `preview_count` figures
+// more than the pieces played
+pub struct ScriptedRandomizer {
+    script: Vec<i32>,
+    index: Cell<usize>,
+    end: ScriptEnd,
+}
+
+impl ScriptedRandomizer {
+    // `script` indexes the piece set like `IndexRandomizer` values
+    pub fn new(script: Vec<i32>, end: ScriptEnd) -> ScriptedRandomizer {
+        return ScriptedRandomizer {
+            script,
+            index: Cell::new(0),
+            end,
+        };
+    }
+
+    // The script written with figures, figures missing from the piece set deal its last figure
+    pub fn from_figures(
+        piece_set: &[FigureType],
+        figures: &[FigureType],
+        end: ScriptEnd,
+    ) -> ScriptedRandomizer {
+        let script = figures
+            .iter()
+            .map(|figure| {
+                return piece_set
+                    .iter()
+                    .position(|piece| piece == figure)
+                    .map_or(piece_set.len() as i32, |index| index as i32);
+            })
+            .collect();
+        return ScriptedRandomizer::new(script, end);
+    }
+
+    // Figures left before the end of the script, None when it loops
+    pub fn remaining(&self) -> Option<usize> {
+        return match self.end {
+            ScriptEnd::Loop => None,
+            ScriptEnd::Report => Some(self.script.len().saturating_sub(self.index.get())),
+        };
+    }
+}
+
+impl IndexRandomizer for ScriptedRandomizer {
+    fn random(&self) -> i32 {
+        let index = self.index.get();
+        self.index.set(index + 1);
+        if self.script.is_empty() {
+            return 0;
+        }
+        return self.script[index % self.script.len()];
+    }
+
+    fn is_overrun(&self) -> bool {
+        return self.end == ScriptEnd::Report && self.index.get() > self.script.len();
+    }
+
+    fn hash_state(&self, mut state: &mut dyn Hasher) {
+        self.script.hash(&mut state);
+        self.index.get().hash(&mut state);
+        self.end.hash(&mut state);
+    }
+
+    fn state(&self) -> Option<RandomizerState> {
+        return Some(RandomizerState::Scripted {
+            script: self.script.clone(),
+            index: self.index.get(),
+            end: self.end,
+        });
+    }
+}
+
+#[cfg(feature = "serde")]
+pub(crate) mod serde_state {
+    use super::{Dealer, SavedDealer};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(randomizer: &Dealer, serializer: S) -> Result<S::Ok, S::Error> {
+        return match randomizer.save() {
+            Some(saved) => saved.serialize(serializer),
+            None => Err(serde::ser::Error::custom(
+                "the randomizer does not provide a serializable state",
+            )),
+        };
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Dealer, D::Error> {
+        let saved = SavedDealer::deserialize(deserializer)?;
+        return Dealer::from_saved(saved).ok_or_else(|| {
+            serde::de::Error::custom("a figure randomizer can not be rebuilt from its state")
+        });
+    }
+}
+
+#[cfg(test)]
+mod randomizer_tests {
+    use super::*;
+
+    #[test]
+    fn test_bag_contains_every_piece_once() {
+        let randomizer = BagRandomizer::new(42);
+        for _bag in 0..3 {
+            let mut drawn: Vec<i32> = (0..BAG_SIZE).map(|_| randomizer.random()).collect();
+            drawn.sort();
+            assert_eq!(drawn, vec![0, 1, 2, 3, 4, 5, 6]);
+        }
+    }
+
+    #[test]
+    fn test_bag_with_custom_size() {
+        let randomizer = BagRandomizer::with_size(42, 4);
+        let mut drawn: Vec<i32> = (0..4).map(|_| randomizer.random()).collect();
+        drawn.sort();
+        assert_eq!(drawn, vec![0, 1, 2, 3]);
+        let resumed = randomizer.state().unwrap().into_randomizer();
+        let mut drawn: Vec<i32> = (0..4).map(|_| resumed.random()).collect();
+        drawn.sort();
+        assert_eq!(drawn, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_bag_same_seed_same_sequence() {
+        let first = BagRandomizer::new(7);
+        let second = BagRandomizer::new(7);
+        for _ in 0..21 {
+            assert_eq!(first.random(), second.random());
+        }
+    }
+
+    #[test]
+    fn test_sequence_loops() {
+        let randomizer = SequenceRandomizer::new(vec![3, 1]);
+        let drawn: Vec<i32> = (0..5).map(|_| randomizer.random()).collect();
+        assert_eq!(drawn, vec![3, 1, 3, 1, 3]);
+    }
+
+    #[test]
+    fn test_state_resumes_randomizer() {
+        let randomizer = BagRandomizer::new(3);
+        randomizer.random();
+        randomizer.random();
+        let resumed = randomizer.state().unwrap().into_randomizer();
+        for _ in 0..10 {
+            assert_eq!(randomizer.random(), resumed.random());
+        }
+        let sequence = SequenceRandomizer::new(vec![1, 2, 3]);
+        sequence.random();
+        let resumed = sequence.state().unwrap().into_randomizer();
+        assert_eq!(resumed.random(), 2);
+    }
+
+    #[test]
+    fn test_classic_stays_in_range_and_rarely_repeats() {
+        let randomizer = ClassicRandomizer::new(11);
+        let drawn: Vec<i32> = (0..7000).map(|_| randomizer.random()).collect();
+        assert!(drawn.iter().all(|piece| (0..BAG_SIZE).contains(piece)));
+        for piece in 0..BAG_SIZE {
+            assert!(drawn.contains(&piece));
+        }
+        // A plain roll repeats 1/7 of the time, the reroll brings it down to about 1/28
+        let repeats = drawn.windows(2).filter(|pair| pair[0] == pair[1]).count();
+        assert!(repeats > 0);
+        assert!(repeats < 7000 / 14);
+    }
+
+    #[test]
+    fn test_classic_state_resumes_randomizer() {
+        let randomizer = ClassicRandomizer::with_size(5, 4);
+        randomizer.random();
+        let resumed = randomizer.state().unwrap().into_randomizer();
+        for _ in 0..20 {
+            let piece = randomizer.random();
+            assert!((0..4).contains(&piece));
+            assert_eq!(piece, resumed.random());
+        }
+    }
+
+    #[test]
+    fn test_weighted_deals_weighted_figures_only() {
+        let randomizer = WeightedRandomizer::new(8, vec![0, 0, 0, 0, 3, 0, 1]);
+        let drawn: Vec<i32> = (0..4000).map(|_| randomizer.random()).collect();
+        assert!(drawn.iter().all(|&piece| piece == 4 || piece == 6));
+        // About three S for each Z
+        let s = drawn.iter().filter(|&&piece| piece == 4).count();
+        assert!((2700..3300).contains(&s));
+        assert_eq!(randomizer.probability(4), 0.75);
+        assert_eq!(randomizer.probability(9), 0.0);
+        let resumed = randomizer.state().unwrap().into_randomizer();
+        for _ in 0..20 {
+            assert_eq!(randomizer.random(), resumed.random());
+        }
+    }
+
+    #[test]
+    fn test_weighted_for_figures() {
+        let pieces = [FigureType::I, FigureType::S, FigureType::Z];
+        let weights = [(FigureType::S, 1), (FigureType::Z, 2), (FigureType::O, 5)];
+        let randomizer = WeightedRandomizer::for_figures(1, &pieces, &weights);
+        assert_eq!(randomizer.weights, vec![0, 1, 2]);
+        // Nothing to roll deals the first figure
+        assert_eq!(WeightedRandomizer::new(1, vec![0, 0]).random(), 0);
+    }
+
+    #[test]
+    fn test_scripted_loops() {
+        let pieces = [FigureType::S, FigureType::Z];
+        let script = [FigureType::Z, FigureType::Z, FigureType::S];
+        let randomizer = ScriptedRandomizer::from_figures(&pieces, &script, ScriptEnd::Loop);
+        let drawn: Vec<i32> = (0..5).map(|_| randomizer.random()).collect();
+        assert_eq!(drawn, vec![1, 1, 0, 1, 1]);
+        assert_eq!(randomizer.remaining(), None);
+        let resumed = randomizer.state().unwrap().into_randomizer();
+        assert_eq!(resumed.random(), 0);
+    }
+
+    #[test]
+    fn test_scripted_reports_the_end() {
+        let randomizer = ScriptedRandomizer::new(vec![3, 5], ScriptEnd::Report);
+        assert_eq!(randomizer.remaining(), Some(2));
+        randomizer.random();
+        randomizer.random();
+        assert_eq!(randomizer.remaining(), Some(0));
+        assert!(!randomizer.is_overrun());
+        assert_eq!(randomizer.random(), 3);
+        assert!(randomizer.is_overrun());
+        let empty = ScriptedRandomizer::new(vec![], ScriptEnd::Report);
+        assert_eq!(empty.random(), 0);
+        assert!(empty.is_overrun());
+    }
+
+    #[test]
+    fn test_indexed_deals_from_the_piece_set() {
+        let mut standard = Indexed::new(SequenceRandomizer::new(vec![3, 0, 9]));
+        let drawn: Vec<FigureType> = (0..3).map(|_| standard.next(&[])).collect();
+        assert_eq!(drawn, vec![FigureType::O, FigureType::I, FigureType::Z]);
+        let pieces = vec![FigureType::S, FigureType::Z];
+        let mut bag = Indexed::with_piece_set(BagRandomizer::with_size(1, 2), pieces);
+        let first = bag.next(&[]);
+        assert_eq!(bag.bag_index(), Some(0));
+        assert_ne!(bag.next(&[]), first);
+        assert_eq!(bag.bag_index(), Some(1));
+        assert!(bag.state().is_some());
+    }
+
+    #[test]
+    fn test_bag_index_of_a_full_bag() {
+        let state = RandomizerState::Bag {
+            rng: XorShift::new(1),
+            bag: vec![0, 1, 2],
+            size: 2,
+        };
+        assert_eq!(state.into_randomizer().bag_index(), None);
+        let state = RandomizerState::Bag {
+            rng: XorShift::new(1),
+            bag: vec![0, 1],
+            size: -3,
+        };
+        assert_eq!(state.into_randomizer().bag_index(), None);
+    }
+
+    #[test]
+    fn test_xorshift_zero_seed_is_not_stuck() {
+        let mut rng = XorShift::new(0);
+        assert_ne!(rng.next_u64(), 0);
+    }
+}
@@ -0,0 +1,568 @@
+use super::{
+    Action, BagRandomizer, Game, GameConfig, GameEvent, GameSnapshot, Item, Key, KeyState,
+    PlacementRecord, RandomizerChoice, Size,
+};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+const MAGIC: &[u8; 4] = b"TCR1";
+
+const UPDATE_TAG: u8 = 0;
+const ACTION_TAG: u8 = 1;
+const GARBAGE_TAG: u8 = 2;
+const INPUT_TAG: u8 = 3;
+const FRAME_TAG: u8 = 4;
+const QUEUED_TAG: u8 = 5;
+const QUEUED_GARBAGE_TAG: u8 = 6;
+const ITEM_TAG: u8 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReplayInput {
+    Update(f64),
+    Action(Action),
+    Garbage { count: usize, hole_column: usize },
+    Input { key: Key, key_state: KeyState },
+    Frame,
+    // An action given to `Game::queue_action`
+    Queued(Action),
+    // Garbage given to `Game::queue_garbage`
+    QueuedGarbage { count: usize, hole_column: usize },
+    // An item given to `Game::apply_item`
+    Item(Item),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReplayEntry {
+    pub frame: usize,
+    pub time: f64,
+    pub input: ReplayInput,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+    InvalidHeader,
+    UnexpectedEnd,
+    InvalidInput(u8),
+}
+
+// What `verify` found different from the expected game
+#[derive(Debug, Clone, PartialEq)]
+pub enum Divergence {
+    // The piece locked at this index of the placement history differs, or only one game locked it
+    Placement {
+        index: usize,
+        expected: Option<Box<PlacementRecord>>,
+        actual: Option<Box<PlacementRecord>>,
+    },
+    // Every piece matched but the final state differs
+    FinalState,
+}
+
+// Where a replayed game first stopped matching, `inputs` were played when it was seen
+#[derive(Debug, Clone, PartialEq)]
+pub struct DivergenceReport {
+    pub inputs: usize,
+    pub frame: usize,
+    pub time: f64,
+    pub divergence: Divergence,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Replay {
+    pub seed: u64,
+    pub size: Size,
+    pub entries: Vec<ReplayEntry>,
+}
+
+impl Replay {
+    pub fn new(size: &Size, seed: u64) -> Replay {
+        return Replay {
+            seed,
+            size: size.clone(),
+            entries: vec![],
+        };
+    }
+
+    pub fn duration(&self) -> f64 {
+        return self.entries.last().map_or(0.0, |entry| match entry.input {
+            ReplayInput::Update(delta_time) => entry.time + delta_time,
+            _ => entry.time,
+        });
+    }
+
+    pub fn frames(&self) -> usize {
+        return self.entries.last().map_or(0, |entry| match entry.input {
+            ReplayInput::Update(_) | ReplayInput::Frame => entry.frame + 1,
+            _ => entry.frame,
+        });
+    }
+
+    fn push(&mut self, input: ReplayInput) {
+        let frame = self.frames();
+        let time = self.duration();
+        self.entries.push(ReplayEntry { frame, time, input });
+    }
+
+    // Playback
+
+    pub fn play(&self) -> Game {
+        return self.play_with_config(GameConfig::default());
+    }
+
+    // The config has to match the one used while recording
+    pub fn play_with_config(&self, config: GameConfig) -> Game {
+        let mut game = Replay::new_game(&self.size, self.seed, config);
+        for entry in &self.entries {
+            Replay::apply(&mut game, &entry.input);
+        }
+        return game;
+    }
+
+    // Checks the replay against the final state of the recorded game, see `verify`
+    pub fn verify(
+        &self,
+        config: GameConfig,
+        expected: &GameSnapshot,
+    ) -> Result<(), DivergenceReport> {
+        let config = GameConfig {
+            size: self.size.clone(),
+            randomizer: RandomizerChoice::Bag(self.seed),
+            ..config
+        };
+        return verify(config, &self.entries, expected);
+    }
+
+    fn new_game(size: &Size, seed: u64, config: GameConfig) -> Game {
+        let randomizer = BagRandomizer::with_size(seed, config.piece_set.len());
+        return Game::new_with_config(size, randomizer, config);
+    }
+
+    fn apply(game: &mut Game, input: &ReplayInput) -> Vec<GameEvent> {
+        return match *input {
+            ReplayInput::Update(delta_time) => game.update(delta_time),
+            ReplayInput::Action(action) => game.perform(action),
+            ReplayInput::Garbage { count, hole_column } => {
+                game.add_garbage_lines(count, hole_column)
+            }
+            ReplayInput::Input { key, key_state } => game.input(key, key_state),
+            ReplayInput::Frame => game.step_frame(),
+            ReplayInput::Queued(action) => {
+                game.queue_action(action);
+                vec![]
+            }
+            ReplayInput::QueuedGarbage { count, hole_column } => {
+                game.queue_garbage(count, hole_column)
+            }
+            ReplayInput::Item(item) => game.apply_item(item),
+        };
+    }
+
+    // Compact format
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&(self.size.width as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.size.height as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for entry in &self.entries {
+            match entry.input {
+                ReplayInput::Update(delta_time) => {
+                    bytes.push(UPDATE_TAG);
+                    bytes.extend_from_slice(&delta_time.to_le_bytes());
+                }
+                ReplayInput::Action(action) => {
+                    bytes.push(ACTION_TAG);
+                    bytes.push(action.code());
+                }
+                ReplayInput::Garbage { count, hole_column } => {
+                    bytes.push(GARBAGE_TAG);
+                    bytes.extend_from_slice(&(count as u32).to_le_bytes());
+                    bytes.extend_from_slice(&(hole_column as u32).to_le_bytes());
+                }
+                ReplayInput::Input { key, key_state } => {
+                    bytes.push(INPUT_TAG);
+                    bytes.push(key_code(key));
+                    bytes.push((key_state == KeyState::Pressed) as u8);
+                }
+                ReplayInput::Frame => bytes.push(FRAME_TAG),
+                ReplayInput::Queued(action) => {
+                    bytes.push(QUEUED_TAG);
+                    bytes.push(action.code());
+                }
+                ReplayInput::QueuedGarbage { count, hole_column } => {
+                    bytes.push(QUEUED_GARBAGE_TAG);
+                    bytes.extend_from_slice(&(count as u32).to_le_bytes());
+                    bytes.extend_from_slice(&(hole_column as u32).to_le_bytes());
+                }
+                ReplayInput::Item(item) => {
+                    bytes.push(ITEM_TAG);
+                    bytes.push(item.code());
+                }
+            }
+        }
+        return bytes;
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, ReplayError> {
+        let mut reader = Reader { bytes, position: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(ReplayError::InvalidHeader);
+        }
+        let seed = reader.read_u64()?;
+        let width = reader.read_u32()? as usize;
+        let height = reader.read_u32()? as usize;
+        let mut replay = Replay::new(&Size { height, width }, seed);
+        let count = reader.read_u32()?;
+        for _ in 0..count {
+            let input = match reader.read_u8()? {
+                UPDATE_TAG => ReplayInput::Update(f64::from_bits(reader.read_u64()?)),
+                ACTION_TAG => {
+                    let code = reader.read_u8()?;
+                    let action = Action::from_code(code).ok_or(ReplayError::InvalidInput(code))?;
+                    ReplayInput::Action(action)
+                }
+                GARBAGE_TAG => ReplayInput::Garbage {
+                    count: reader.read_u32()? as usize,
+                    hole_column: reader.read_u32()? as usize,
+                },
+                INPUT_TAG => {
+                    let code = reader.read_u8()?;
+                    let key = key_from_code(code).ok_or(ReplayError::InvalidInput(code))?;
+                    let key_state = match reader.read_u8()? {
+                        0 => KeyState::Released,
+                        1 => KeyState::Pressed,
+                        state => return Err(ReplayError::InvalidInput(state)),
+                    };
+                    ReplayInput::Input { key, key_state }
+                }
+                FRAME_TAG => ReplayInput::Frame,
+                QUEUED_TAG => {
+                    let code = reader.read_u8()?;
+                    let action = Action::from_code(code).ok_or(ReplayError::InvalidInput(code))?;
+                    ReplayInput::Queued(action)
+                }
+                QUEUED_GARBAGE_TAG => ReplayInput::QueuedGarbage {
+                    count: reader.read_u32()? as usize,
+                    hole_column: reader.read_u32()? as usize,
+                },
+                ITEM_TAG => {
+                    let code = reader.read_u8()?;
+                    let item = Item::from_code(code).ok_or(ReplayError::InvalidInput(code))?;
+                    ReplayInput::Item(item)
+                }
+                tag => return Err(ReplayError::InvalidInput(tag)),
+            };
+            replay.push(input);
+        }
+        return Ok(replay);
+    }
+}
+
+pub struct Recorder {
+    game: Game,
+    replay: Replay,
+}
+
+impl Recorder {
+    pub fn new(size: &Size, seed: u64) -> Recorder {
+        return Recorder::new_with_config(size, seed, GameConfig::default());
+    }
+
+    pub fn new_with_config(size: &Size, seed: u64, config: GameConfig) -> Recorder {
+        return Recorder {
+            game: Replay::new_game(size, seed, config),
+            replay: Replay::new(size, seed),
+        };
+    }
+
+    pub fn update(&mut self, delta_time: f64) -> Vec<GameEvent> {
+        return self.record(ReplayInput::Update(delta_time));
+    }
+
+    pub fn perform(&mut self, action: Action) -> Vec<GameEvent> {
+        return self.record(ReplayInput::Action(action));
+    }
+
+    pub fn add_garbage_lines(&mut self, count: usize, hole_column: usize) -> Vec<GameEvent> {
+        return self.record(ReplayInput::Garbage { count, hole_column });
+    }
+
+    pub fn input(&mut self, key: Key, key_state: KeyState) -> Vec<GameEvent> {
+        return self.record(ReplayInput::Input { key, key_state });
+    }
+
+    pub fn step_frame(&mut self) -> Vec<GameEvent> {
+        return self.record(ReplayInput::Frame);
+    }
+
+    pub fn queue_action(&mut self, action: Action) {
+        self.record(ReplayInput::Queued(action));
+    }
+
+    pub fn queue_garbage(&mut self, count: usize, hole_column: usize) -> Vec<GameEvent> {
+        return self.record(ReplayInput::QueuedGarbage { count, hole_column });
+    }
+
+    pub fn apply_item(&mut self, item: Item) -> Vec<GameEvent> {
+        return self.record(ReplayInput::Item(item));
+    }
+
+    fn record(&mut self, input: ReplayInput) -> Vec<GameEvent> {
+        self.replay.push(input);
+        return Replay::apply(&mut self.game, &input);
+    }
+
+    pub fn game(&self) -> &Game {
+        return &self.game;
+    }
+
+    pub fn replay(&self) -> &Replay {
+        return &self.replay;
+    }
+
+    pub fn finish(self) -> Replay {
+        return self.replay;
+    }
+}
+
+// Plays the inputs on a game made from the config and compares it with the final state of the
+// recorded one. The placement history of the expected state dates every piece, so a desync is
+// reported at the first input that locked a different piece or went past the lock of a missing one
+pub fn verify(
+    game_config: GameConfig,
+    inputs: &[ReplayEntry],
+    expected_final_snapshot: &GameSnapshot,
+) -> Result<(), DivergenceReport> {
+    let expected = expected_final_snapshot.placement_history();
+    let mut game = Game::with_config(game_config);
+    let mut matched = 0;
+    for (played, entry) in inputs.iter().enumerate() {
+        Replay::apply(&mut game, &entry.input);
+        let actual = game.placement_history();
+        let index = matched
+            + actual[matched..]
+                .iter()
+                .zip(expected.get(matched..).unwrap_or(&[]))
+                .take_while(|(actual, expected)| actual == expected)
+                .count();
+        let missed = expected
+            .get(index)
+            .is_some_and(|placement| placement.time < game.get_elapsed_time());
+        if index < actual.len() || missed {
+            return Err(DivergenceReport {
+                inputs: played + 1,
+                frame: entry.frame,
+                time: entry.time,
+                divergence: Divergence::Placement {
+                    index,
+                    expected: expected.get(index).cloned().map(Box::new),
+                    actual: actual.get(index).cloned().map(Box::new),
+                },
+            });
+        }
+        matched = index;
+    }
+    if game.snapshot() != *expected_final_snapshot {
+        let last = inputs.last();
+        return Err(DivergenceReport {
+            inputs: inputs.len(),
+            frame: last.map_or(0, |entry| entry.frame),
+            time: last.map_or(0.0, |entry| entry.time),
+            divergence: Divergence::FinalState,
+        });
+    }
+    return Ok(());
+}
+
+fn key_code(key: Key) -> u8 {
+    return match key {
+        Key::Left => 0,
+        Key::Right => 1,
+        Key::SoftDrop => 2,
+        Key::HardDrop => 3,
+        Key::RotateCW => 4,
+        Key::RotateCCW => 5,
+        Key::Rotate180 => 6,
+        Key::Hold => 7,
+    };
+}
+
+fn key_from_code(code: u8) -> Option<Key> {
+    return match code {
+        0 => Some(Key::Left),
+        1 => Some(Key::Right),
+        2 => Some(Key::SoftDrop),
+        3 => Some(Key::HardDrop),
+        4 => Some(Key::RotateCW),
+        5 => Some(Key::RotateCCW),
+        6 => Some(Key::Rotate180),
+        7 => Some(Key::Hold),
+        _ => None,
+    };
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], ReplayError> {
+        let end = self.position + count;
+        if end > self.bytes.len() {
+            return Err(ReplayError::UnexpectedEnd);
+        }
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        return Ok(slice);
+    }
+
+    fn read_u8(&mut self) -> Result<u8, ReplayError> {
+        return Ok(self.take(1)?[0]);
+    }
+
+    fn read_u32(&mut self) -> Result<u32, ReplayError> {
+        let mut buffer = [0; 4];
+        buffer.copy_from_slice(self.take(4)?);
+        return Ok(u32::from_le_bytes(buffer));
+    }
+
+    fn read_u64(&mut self) -> Result<u64, ReplayError> {
+        let mut buffer = [0; 8];
+        buffer.copy_from_slice(self.take(8)?);
+        return Ok(u64::from_le_bytes(buffer));
+    }
+}
+
+#[cfg(test)]
+mod replay_tests {
+    use super::*;
+
+    fn record_session() -> Recorder {
+        let size = Size {
+            height: 20,
+            width: 10,
+        };
+        let mut recorder = Recorder::new(&size, 77);
+        let actions = [Action::MoveLeft, Action::Rotate, Action::HardDrop, Action::Hold];
+        for round in 0..40 {
+            recorder.update(0.3);
+            recorder.perform(actions[round % actions.len()]);
+            if round == 10 {
+                recorder.queue_action(Action::MoveRight);
+            }
+            if round == 20 {
+                recorder.add_garbage_lines(2, 4);
+            }
+            if round == 25 {
+                recorder.queue_garbage(1, 7);
+            }
+            if round == 28 {
+                recorder.apply_item(Item::ShuffleBoard);
+            }
+            if round == 30 {
+                recorder.input(Key::Right, KeyState::Pressed);
+                recorder.update(0.5);
+                recorder.input(Key::Right, KeyState::Released);
+            }
+        }
+        return recorder;
+    }
+
+    #[test]
+    fn test_entries_have_frame_and_time() {
+        let recorder = record_session();
+        let entries = &recorder.replay().entries;
+        assert_eq!(entries[0].frame, 0);
+        assert_eq!(entries[1].frame, 1);
+        assert!((entries[3].time - 0.6).abs() < 1e-9);
+        assert_eq!(entries[1].input, ReplayInput::Action(Action::MoveLeft));
+    }
+
+    #[test]
+    fn test_play_reproduces_session() {
+        let recorder = record_session();
+        let replayed = recorder.replay().play();
+        assert_eq!(replayed.draw(), recorder.game().draw());
+        assert_eq!(replayed.get_score(), recorder.game().get_score());
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let replay = record_session().finish();
+        let decoded = Replay::from_bytes(&replay.to_bytes()).unwrap();
+        assert_eq!(decoded, replay);
+    }
+
+    #[test]
+    fn test_frame_replay() {
+        let size = Size {
+            height: 20,
+            width: 10,
+        };
+        let config = GameConfig::builder().tick_rate(60).build();
+        let mut recorder = Recorder::new_with_config(&size, 5, config.clone());
+        for frame in 0..300 {
+            recorder.step_frame();
+            if frame % 40 == 0 {
+                recorder.perform(Action::MoveRight);
+            }
+        }
+        let replay = Replay::from_bytes(&recorder.replay().to_bytes()).unwrap();
+        assert_eq!(replay.frames(), 300);
+        let replayed = replay.play_with_config(config);
+        assert_eq!(replayed.draw(), recorder.game().draw());
+    }
+
+    #[test]
+    fn test_verify_finds_the_first_divergence() {
+        let recorder = record_session();
+        let replay = recorder.replay().clone();
+        let expected = recorder.game().snapshot();
+        assert_eq!(replay.verify(GameConfig::default(), &expected), Ok(()));
+        // The first piece moves right instead of left, and differs once hard dropped
+        let mut changed = replay.clone();
+        changed.entries[1].input = ReplayInput::Action(Action::MoveRight);
+        let report = changed
+            .verify(GameConfig::default(), &expected)
+            .unwrap_err();
+        assert_eq!(report.inputs, 6);
+        assert_eq!(report.frame, replay.entries[5].frame);
+        match report.divergence {
+            Divergence::Placement {
+                index,
+                expected,
+                actual,
+            } => {
+                assert_eq!(index, 0);
+                assert_ne!(expected, actual);
+            }
+            Divergence::FinalState => panic!("the placement should differ"),
+        }
+        // No piece locks before the rotation, only the final state tells them apart
+        let mut shorter = replay.clone();
+        shorter.entries.truncate(3);
+        let expected = shorter.play().snapshot();
+        shorter.entries.push(replay.entries[3]);
+        let report = shorter
+            .verify(GameConfig::default(), &expected)
+            .unwrap_err();
+        assert_eq!(report.inputs, 4);
+        assert_eq!(report.divergence, Divergence::FinalState);
+    }
+
+    #[test]
+    fn test_invalid_bytes() {
+        assert_eq!(Replay::from_bytes(b"nope"), Err(ReplayError::InvalidHeader));
+        let bytes = record_session().finish().to_bytes();
+        assert_eq!(
+            Replay::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ReplayError::UnexpectedEnd)
+        );
+    }
+}
@@ -0,0 +1,423 @@
+use alloc::sync::Arc;
+use core::fmt;
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClearKind {
+    None,
+    Lines(usize),
+    TSpin(usize),
+    TSpinMini(usize),
+}
+
+impl ClearKind {
+    pub fn lines(&self) -> usize {
+        return match self {
+            ClearKind::None => 0,
+            ClearKind::Lines(lines) | ClearKind::TSpin(lines) | ClearKind::TSpinMini(lines) => {
+                *lines
+            }
+        };
+    }
+
+    pub fn is_t_spin(&self) -> bool {
+        return matches!(self, ClearKind::TSpin(_) | ClearKind::TSpinMini(_));
+    }
+}
+
+impl ClearKind {
+    // Tetrises and line clearing T-spins keep the back-to-back chain going
+    pub fn is_difficult(&self) -> bool {
+        return match self {
+            ClearKind::Lines(lines) => *lines >= 4,
+            ClearKind::TSpin(lines) | ClearKind::TSpinMini(lines) => *lines > 0,
+            ClearKind::None => false,
+        };
+    }
+}
+
+pub fn combo_score(combo: usize) -> u64 {
+    return 50 * combo as u64;
+}
+
+pub fn back_to_back_score(score: u64) -> u64 {
+    return score * 3 / 2;
+}
+
+// Guideline T-spin scores, to be multiplied by the level
+pub fn t_spin_score(kind: &ClearKind) -> u64 {
+    return match kind {
+        ClearKind::TSpin(0) => 400,
+        ClearKind::TSpin(1) => 800,
+        ClearKind::TSpin(2) => 1200,
+        ClearKind::TSpin(_) => 1600,
+        ClearKind::TSpinMini(0) => 100,
+        ClearKind::TSpinMini(1) => 200,
+        ClearKind::TSpinMini(_) => 400,
+        _ => 0,
+    };
+}
+
+pub const SOFT_DROP_POINTS_PER_CELL: u64 = 1;
+pub const HARD_DROP_POINTS_PER_CELL: u64 = 2;
+// NES points for 1 to 4 lines, multiplied by the level plus one
+const CLASSIC_LINE_SCORES: [u64; 4] = [40, 100, 300, 1200];
+const GUIDELINE_LINE_SCORES: [u64; 4] = [100, 300, 500, 800];
+
+// Points given by the game, implement it to plug custom scoring into `Scoring::Custom`
+pub trait ScoringRules {
+    fn score_clear(&self, kind: &ClearKind, level: usize, combo: usize, back_to_back: usize)
+        -> u64;
+
+    fn score_soft_drop(&self, cells: u64) -> u64 {
+        return cells * SOFT_DROP_POINTS_PER_CELL;
+    }
+
+    fn score_hard_drop(&self, cells: u64) -> u64 {
+        return cells * HARD_DROP_POINTS_PER_CELL;
+    }
+}
+
+// 100 points per line, guideline T-spins, combos and back-to-back
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Standard;
+
+impl ScoringRules for Standard {
+    fn score_clear(
+        &self,
+        kind: &ClearKind,
+        level: usize,
+        combo: usize,
+        back_to_back: usize,
+    ) -> u64 {
+        let score = match kind {
+            ClearKind::None => 0,
+            ClearKind::Lines(lines) => (*lines as u64) * 100,
+            _ => t_spin_score(kind) * level as u64,
+        };
+        return with_chains(score, kind, level, combo, back_to_back);
+    }
+}
+
+// Guideline line scores (100, 300, 500 and 800) and T-spins times the level, combos and back-to-back
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Guideline;
+
+impl ScoringRules for Guideline {
+    fn score_clear(
+        &self,
+        kind: &ClearKind,
+        level: usize,
+        combo: usize,
+        back_to_back: usize,
+    ) -> u64 {
+        let score = match kind {
+            ClearKind::None | ClearKind::Lines(0) => 0,
+            ClearKind::Lines(lines) => GUIDELINE_LINE_SCORES[lines.min(&4) - 1] * level as u64,
+            _ => t_spin_score(kind) * level as u64,
+        };
+        return with_chains(score, kind, level, combo, back_to_back);
+    }
+}
+
+fn with_chains(
+    score: u64,
+    kind: &ClearKind,
+    level: usize,
+    combo: usize,
+    back_to_back: usize,
+) -> u64 {
+    let mut score = score;
+    if kind.is_difficult() && back_to_back > 0 {
+        score = back_to_back_score(score);
+    }
+    if kind.lines() > 0 {
+        score += combo_score(combo) * level as u64;
+    }
+    return score;
+}
+
+// NES scoring, levels count from 0 like on the NES. Hard drops give nothing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Classic;
+
+impl ScoringRules for Classic {
+    fn score_clear(
+        &self,
+        kind: &ClearKind,
+        level: usize,
+        _combo: usize,
+        _back_to_back: usize,
+    ) -> u64 {
+        let lines = kind.lines().min(4);
+        if lines == 0 {
+            return 0;
+        }
+        return CLASSIC_LINE_SCORES[lines - 1] * (level as u64 + 1);
+    }
+
+    fn score_hard_drop(&self, _cells: u64) -> u64 {
+        return 0;
+    }
+}
+
+// 100 points per line, nothing else
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Simple100;
+
+impl ScoringRules for Simple100 {
+    fn score_clear(
+        &self,
+        kind: &ClearKind,
+        _level: usize,
+        _combo: usize,
+        _back_to_back: usize,
+    ) -> u64 {
+        return kind.lines() as u64 * 100;
+    }
+}
+
+// Raw points for clones of specific games, without combos or back-to-back. Clears score their
+// value times the level plus `level_offset`, drops their value per cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScoreTable {
+    // 0 to 4 lines, more lines count as 4
+    pub lines: [u64; 5],
+    // T-spins clearing 0 to 3 lines and minis clearing 0 to 2
+    pub t_spin: [u64; 4],
+    pub t_spin_mini: [u64; 3],
+    pub soft_drop: u64,
+    pub hard_drop: u64,
+    // 1 for games counting levels from 0, like the NES
+    pub level_offset: u64,
+}
+
+impl ScoreTable {
+    // No spin points, the usual drop points
+    pub fn new(lines: [u64; 5]) -> ScoreTable {
+        return ScoreTable {
+            lines,
+            t_spin: [0; 4],
+            t_spin_mini: [0; 3],
+            soft_drop: SOFT_DROP_POINTS_PER_CELL,
+            hard_drop: HARD_DROP_POINTS_PER_CELL,
+            level_offset: 0,
+        };
+    }
+
+    // The NES table, the same points as `Scoring::Classic`
+    pub fn classic() -> ScoreTable {
+        return ScoreTable {
+            hard_drop: 0,
+            level_offset: 1,
+            ..ScoreTable::new([0, 40, 100, 300, 1200])
+        };
+    }
+}
+
+impl ScoringRules for ScoreTable {
+    fn score_clear(
+        &self,
+        kind: &ClearKind,
+        level: usize,
+        _combo: usize,
+        _back_to_back: usize,
+    ) -> u64 {
+        let points = match kind {
+            ClearKind::None => self.lines[0],
+            ClearKind::Lines(lines) => self.lines[*lines.min(&4)],
+            ClearKind::TSpin(lines) => self.t_spin[*lines.min(&3)],
+            ClearKind::TSpinMini(lines) => self.t_spin_mini[*lines.min(&2)],
+        };
+        return points * (level as u64 + self.level_offset);
+    }
+
+    fn score_soft_drop(&self, cells: u64) -> u64 {
+        return cells * self.soft_drop;
+    }
+
+    fn score_hard_drop(&self, cells: u64) -> u64 {
+        return cells * self.hard_drop;
+    }
+}
+
+// The scoring used by a game config, custom rules can't be serialized
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Scoring {
+    Standard,
+    Guideline,
+    Classic,
+    Simple100,
+    Table(ScoreTable),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Arc<dyn ScoringRules>),
+}
+
+impl Scoring {
+    pub fn custom<R: ScoringRules + 'static>(rules: R) -> Scoring {
+        return Scoring::Custom(Arc::new(rules));
+    }
+
+    fn rules(&self) -> &dyn ScoringRules {
+        return match self {
+            Scoring::Standard => &Standard,
+            Scoring::Guideline => &Guideline,
+            Scoring::Classic => &Classic,
+            Scoring::Simple100 => &Simple100,
+            Scoring::Table(table) => table,
+            Scoring::Custom(rules) => rules.as_ref(),
+        };
+    }
+}
+
+impl ScoringRules for Scoring {
+    fn score_clear(
+        &self,
+        kind: &ClearKind,
+        level: usize,
+        combo: usize,
+        back_to_back: usize,
+    ) -> u64 {
+        return self.rules().score_clear(kind, level, combo, back_to_back);
+    }
+
+    fn score_soft_drop(&self, cells: u64) -> u64 {
+        return self.rules().score_soft_drop(cells);
+    }
+
+    fn score_hard_drop(&self, cells: u64) -> u64 {
+        return self.rules().score_hard_drop(cells);
+    }
+}
+
+impl Default for Scoring {
+    fn default() -> Scoring {
+        return Scoring::Standard;
+    }
+}
+
+// Custom rules are only equal to themselves
+impl PartialEq for Scoring {
+    fn eq(&self, other: &Scoring) -> bool {
+        return match (self, other) {
+            (Scoring::Standard, Scoring::Standard)
+            | (Scoring::Guideline, Scoring::Guideline)
+            | (Scoring::Classic, Scoring::Classic)
+            | (Scoring::Simple100, Scoring::Simple100) => true,
+            (Scoring::Table(table), Scoring::Table(other_table)) => table == other_table,
+            (Scoring::Custom(rules), Scoring::Custom(other_rules)) => {
+                Arc::ptr_eq(rules, other_rules)
+            }
+            _ => false,
+        };
+    }
+}
+
+impl fmt::Debug for Scoring {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            Scoring::Standard => write!(formatter, "Standard"),
+            Scoring::Guideline => write!(formatter, "Guideline"),
+            Scoring::Classic => write!(formatter, "Classic"),
+            Scoring::Simple100 => write!(formatter, "Simple100"),
+            Scoring::Table(table) => write!(formatter, "Table({:?})", table),
+            Scoring::Custom(_) => write!(formatter, "Custom"),
+        };
+    }
+}
+
+#[cfg(test)]
+mod scoring_tests {
+    use super::*;
+
+    #[test]
+    fn test_scoring_rules() {
+        let standard = Scoring::Standard;
+        assert_eq!(standard.score_clear(&ClearKind::Lines(4), 1, 0, 1), 600);
+        assert_eq!(standard.score_clear(&ClearKind::TSpin(1), 2, 1, 0), 1700);
+        let simple = Scoring::Simple100;
+        assert_eq!(simple.score_clear(&ClearKind::Lines(4), 1, 0, 1), 400);
+        assert_eq!(simple.score_clear(&ClearKind::TSpin(1), 2, 1, 0), 100);
+    }
+
+    #[test]
+    fn test_guideline_scoring() {
+        let guideline = Scoring::Guideline;
+        assert_eq!(guideline.score_clear(&ClearKind::Lines(2), 3, 0, 0), 900);
+        assert_eq!(guideline.score_clear(&ClearKind::Lines(4), 2, 0, 1), 2400);
+        assert_eq!(guideline.score_clear(&ClearKind::Lines(1), 1, 2, 0), 200);
+        assert_eq!(guideline.score_hard_drop(3), 6);
+    }
+
+    #[test]
+    fn test_classic_scoring() {
+        let classic = Scoring::Classic;
+        assert_eq!(classic.score_clear(&ClearKind::Lines(1), 0, 0, 0), 40);
+        assert_eq!(classic.score_clear(&ClearKind::Lines(4), 9, 3, 1), 12000);
+        assert_eq!(classic.score_clear(&ClearKind::None, 9, 0, 0), 0);
+        assert_eq!(classic.score_soft_drop(5), 5);
+        assert_eq!(classic.score_hard_drop(5), 0);
+    }
+
+    #[test]
+    fn test_score_table() {
+        let classic = Scoring::Table(ScoreTable::classic());
+        for lines in 0..6 {
+            let kind = ClearKind::Lines(lines);
+            assert_eq!(
+                classic.score_clear(&kind, 7, 2, 1),
+                Scoring::Classic.score_clear(&kind, 7, 2, 1)
+            );
+        }
+        assert_eq!(classic.score_hard_drop(5), 0);
+        assert_eq!(classic, Scoring::Table(ScoreTable::classic()));
+        let table = ScoreTable {
+            t_spin: [400, 800, 1200, 1600],
+            ..ScoreTable::new([0, 100, 200, 400, 800])
+        };
+        assert_eq!(table.score_clear(&ClearKind::Lines(3), 2, 0, 0), 800);
+        assert_eq!(table.score_clear(&ClearKind::TSpin(2), 3, 0, 0), 3600);
+        assert_eq!(table.score_clear(&ClearKind::TSpinMini(1), 3, 0, 0), 0);
+        assert_eq!(table.score_hard_drop(3), 6);
+        assert_ne!(Scoring::Table(table), classic);
+    }
+
+    #[test]
+    fn test_custom_scoring() {
+        struct Flat;
+        impl ScoringRules for Flat {
+            fn score_clear(&self, kind: &ClearKind, _: usize, _: usize, _: usize) -> u64 {
+                return kind.lines() as u64;
+            }
+        }
+        let custom = Scoring::custom(Flat);
+        assert_eq!(custom.score_clear(&ClearKind::Lines(3), 5, 0, 0), 3);
+        assert_eq!(custom, custom.clone());
+        assert_ne!(custom, Scoring::custom(Flat));
+        assert_ne!(custom, Scoring::Standard);
+    }
+
+    #[test]
+    fn test_lines() {
+        assert_eq!(ClearKind::None.lines(), 0);
+        assert_eq!(ClearKind::Lines(4).lines(), 4);
+        assert_eq!(ClearKind::TSpinMini(1).lines(), 1);
+    }
+
+    #[test]
+    fn test_is_difficult() {
+        assert!(ClearKind::Lines(4).is_difficult());
+        assert!(ClearKind::TSpinMini(1).is_difficult());
+        assert!(!ClearKind::Lines(3).is_difficult());
+        assert!(!ClearKind::TSpin(0).is_difficult());
+    }
+
+    #[test]
+    fn test_t_spin_score() {
+        assert_eq!(t_spin_score(&ClearKind::TSpin(2)), 1200);
+        assert_eq!(t_spin_score(&ClearKind::TSpinMini(0)), 100);
+        assert_eq!(t_spin_score(&ClearKind::Lines(2)), 0);
+    }
+}
@@ -0,0 +1,142 @@
+// Headless games for training and benchmarks: the policy picks one action per frame and frames
+// are stepped as fast as possible instead of following the clock
+use super::{Action, Game, GameConfig, Stats};
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+
+// How a simulated game went
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameResult {
+    pub pieces: usize,
+    pub lines: usize,
+    pub score: u64,
+    pub level: usize,
+    pub frames: u64,
+    // The stack reached the top before `max_pieces` were placed
+    pub topped_out: bool,
+    pub stats: Stats,
+}
+
+pub struct Simulator;
+
+impl Simulator {
+    // Plays until the game ends or `max_pieces` are placed. Each policy call is one frame, so gravity
+    // and the lock delay keep the game going whatever the policy does. Pause and Resume are
+    // ignored, a paused game would never end
+    pub fn run(
+        config: GameConfig,
+        mut policy: impl FnMut(&Game) -> Action,
+        max_pieces: usize,
+    ) -> GameResult {
+        let mut game = Game::with_config(config);
+        let mut frames = 0;
+        while !game.is_game_over() && !game.is_finished() && game.get_pieces_placed() < max_pieces {
+            let action = policy(&game);
+            if !action.is_pause_control() {
+                game.queue_action(action);
+            }
+            game.step_frame();
+            frames += 1;
+        }
+        let stats = game.stats();
+        return GameResult {
+            pieces: stats.total_pieces,
+            lines: game.get_lines_completed(),
+            score: game.get_score(),
+            level: game.get_level(),
+            frames,
+            topped_out: game.is_game_over(),
+            stats,
+        };
+    }
+
+    // Runs `games` games on every available core. Configs can hold types that stay on one thread,
+    // so each game builds its own from its index, which also gives it its seed
+    #[cfg(feature = "std")]
+    pub fn run_many(
+        games: usize,
+        config: impl Fn(usize) -> GameConfig + Sync,
+        policy: impl Fn(&Game) -> Action + Sync,
+        max_pieces: usize,
+    ) -> Vec<GameResult> {
+        let threads = std::thread::available_parallelism()
+            .map_or(1, |threads| threads.get())
+            .min(games.max(1));
+        let (config, policy) = (&config, &policy);
+        return std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|thread| {
+                    return scope.spawn(move || {
+                        return (thread..games)
+                            .step_by(threads)
+                            .map(|index| Simulator::run(config(index), policy, max_pieces))
+                            .collect::<Vec<_>>();
+                    });
+                })
+                .collect();
+            let mut per_thread: Vec<_> = handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .expect("a simulated game panicked")
+                        .into_iter()
+                })
+                .collect();
+            // Back in index order, game `index` ran on thread `index % threads`
+            return (0..games)
+                .filter_map(|index| per_thread[index % threads].next())
+                .collect();
+        });
+    }
+}
+
+#[cfg(test)]
+mod simulator_tests {
+    use super::*;
+
+    fn seeded(seed: usize) -> GameConfig {
+        return GameConfig::builder().seed(seed as u64).build();
+    }
+
+    #[test]
+    fn test_run_stops_after_max_pieces() {
+        let result = Simulator::run(seeded(1), |_| Action::HardDrop, 5);
+        assert_eq!(result.pieces, 5);
+        assert!(!result.topped_out);
+        assert_eq!(result.stats.total_pieces, 5);
+        // Pieces dropped in the middle top out before long
+        let result = Simulator::run(seeded(1), |_| Action::HardDrop, 1000);
+        assert!(result.topped_out);
+        assert!(result.pieces < 1000);
+    }
+
+    #[test]
+    fn test_gravity_ends_idle_games() {
+        let result = Simulator::run(seeded(2), |_| Action::SoftDropStop, 3);
+        assert_eq!(result.pieces, 3);
+        assert!(result.frames > 60);
+    }
+
+    #[test]
+    fn test_policies_can_not_pause() {
+        let result = Simulator::run(seeded(3), |_| Action::Pause, 2);
+        assert_eq!(result.pieces, 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_run_many_keeps_the_game_order() {
+        let policy = |game: &Game| match game.get_pieces_placed() % 3 {
+            0 => Action::MoveLeftWall,
+            1 => Action::MoveRightWall,
+            _ => Action::HardDrop,
+        };
+        let results = Simulator::run_many(5, seeded, policy, 20);
+        assert_eq!(results.len(), 5);
+        for (index, result) in results.iter().enumerate() {
+            assert_eq!(result, &Simulator::run(seeded(index), policy, 20));
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// Special mechanics charged by clears and activated with `Action::ActivateSpecial`
+use super::scoring::ClearKind;
+use alloc::sync::Arc;
+use core::fmt;
+
+// Rules of a special mechanic, implement it to plug a custom one into `Special::Custom`.
+// The game keeps the meter, the timer and the stacked lines, the rules only give the numbers.
+pub trait SpecialMechanic {
+    // Meter gained by a clear, the meter is full at 1
+    fn meter_gain(&self, kind: &ClearKind) -> f64;
+
+    // Seconds the mechanic lasts when activated with this much meter
+    fn duration(&self, meter: f64) -> f64;
+
+    // Smallest meter that can be activated
+    fn min_meter(&self) -> f64 {
+        return 0.25;
+    }
+
+    // The active figure only falls with soft drops
+    fn freezes_gravity(&self) -> bool {
+        return true;
+    }
+
+    // Cleared lines move to the bottom of the board instead of disappearing, until the end
+    fn stacks_lines(&self) -> bool {
+        return true;
+    }
+
+    // Points given when it ends, for the lines stacked meanwhile
+    fn exit_score(&self, lines: usize, level: usize) -> u64;
+}
+
+// Time stops while the cleared lines pile up at the bottom, they all score when the zone ends
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Zone {
+    // Lines that fill the meter
+    pub lines_per_meter: usize,
+    // Seconds of a full meter
+    pub full_duration: f64,
+}
+
+impl Default for Zone {
+    fn default() -> Zone {
+        return Zone {
+            lines_per_meter: 16,
+            full_duration: 20.0,
+        };
+    }
+}
+
+impl SpecialMechanic for Zone {
+    fn meter_gain(&self, kind: &ClearKind) -> f64 {
+        return kind.lines() as f64 / self.lines_per_meter.max(1) as f64;
+    }
+
+    fn duration(&self, meter: f64) -> f64 {
+        return meter * self.full_duration;
+    }
+
+    // 100 points per line times the lines stacked, times the level
+    fn exit_score(&self, lines: usize, level: usize) -> u64 {
+        return 100 * (lines * lines * level.max(1)) as u64;
+    }
+}
+
+// The special mechanic of a game config, custom rules can't be serialized
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Special {
+    None,
+    Zone(Zone),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Arc<dyn SpecialMechanic>),
+}
+
+impl Special {
+    pub fn custom<M: SpecialMechanic + 'static>(mechanic: M) -> Special {
+        return Special::Custom(Arc::new(mechanic));
+    }
+
+    pub fn mechanic(&self) -> Option<&dyn SpecialMechanic> {
+        return match self {
+            Special::None => None,
+            Special::Zone(zone) => Some(zone),
+            Special::Custom(mechanic) => Some(mechanic.as_ref()),
+        };
+    }
+}
+
+impl Default for Special {
+    fn default() -> Special {
+        return Special::None;
+    }
+}
+
+// Custom rules are only equal to themselves
+impl PartialEq for Special {
+    fn eq(&self, other: &Special) -> bool {
+        return match (self, other) {
+            (Special::None, Special::None) => true,
+            (Special::Zone(zone), Special::Zone(other_zone)) => zone == other_zone,
+            (Special::Custom(mechanic), Special::Custom(other_mechanic)) => {
+                Arc::ptr_eq(mechanic, other_mechanic)
+            }
+            _ => false,
+        };
+    }
+}
+
+impl fmt::Debug for Special {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            Special::None => write!(formatter, "None"),
+            Special::Zone(zone) => write!(formatter, "Zone({:?})", zone),
+            Special::Custom(_) => write!(formatter, "Custom"),
+        };
+    }
+}
@@ -0,0 +1,100 @@
+use super::{ClearKind, FigureType};
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stats {
+    pub pieces_by_type: Vec<(FigureType, usize)>,
+    pub total_pieces: usize,
+    pub singles: usize,
+    pub doubles: usize,
+    pub triples: usize,
+    pub tetrises: usize,
+    pub t_spins: usize,
+    pub t_spin_minis: usize,
+    pub max_combo: usize,
+    pub holds: usize,
+    pub elapsed: f64,
+}
+
+impl Stats {
+    pub fn new() -> Stats {
+        return Stats::default();
+    }
+
+    pub fn pieces_of(&self, figure_type: &FigureType) -> usize {
+        return self
+            .pieces_by_type
+            .iter()
+            .find(|(placed_type, _)| placed_type == figure_type)
+            .map_or(0, |(_, count)| *count);
+    }
+
+    // Pieces per second
+    pub fn pps(&self) -> f64 {
+        if self.elapsed <= 0.0 {
+            return 0.0;
+        }
+        return self.total_pieces as f64 / self.elapsed;
+    }
+
+    pub fn record_piece(&mut self, figure_type: &FigureType) {
+        self.total_pieces += 1;
+        match self
+            .pieces_by_type
+            .iter_mut()
+            .find(|(placed_type, _)| placed_type == figure_type)
+        {
+            Some((_, count)) => *count += 1,
+            None => self.pieces_by_type.push((figure_type.clone(), 1)),
+        }
+    }
+
+    pub fn record_clear(&mut self, clear_kind: &ClearKind, combo: usize) {
+        match clear_kind {
+            ClearKind::None => {}
+            ClearKind::Lines(1) => self.singles += 1,
+            ClearKind::Lines(2) => self.doubles += 1,
+            ClearKind::Lines(3) => self.triples += 1,
+            ClearKind::Lines(_) => self.tetrises += 1,
+            ClearKind::TSpin(_) => self.t_spins += 1,
+            ClearKind::TSpinMini(_) => self.t_spin_minis += 1,
+        }
+        self.max_combo = self.max_combo.max(combo);
+    }
+
+    pub fn record_hold(&mut self) {
+        self.holds += 1;
+    }
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use super::*;
+
+    #[test]
+    fn test_record_pieces() {
+        let mut stats = Stats::new();
+        stats.record_piece(&FigureType::T);
+        stats.record_piece(&FigureType::I);
+        stats.record_piece(&FigureType::T);
+        assert_eq!(stats.total_pieces, 3);
+        assert_eq!(stats.pieces_of(&FigureType::T), 2);
+        assert_eq!(stats.pieces_of(&FigureType::O), 0);
+        stats.elapsed = 2.0;
+        assert_eq!(stats.pps(), 1.5);
+    }
+
+    #[test]
+    fn test_record_clears() {
+        let mut stats = Stats::new();
+        stats.record_clear(&ClearKind::Lines(1), 0);
+        stats.record_clear(&ClearKind::Lines(4), 1);
+        stats.record_clear(&ClearKind::TSpin(2), 2);
+        stats.record_clear(&ClearKind::None, 0);
+        assert_eq!(stats.singles, 1);
+        assert_eq!(stats.tetrises, 1);
+        assert_eq!(stats.t_spins, 1);
+        assert_eq!(stats.max_combo, 2);
+    }
+}
@@ -0,0 +1,103 @@
+use super::{ActiveFigure, Board, FigureType, Point};
+use alloc::vec::Vec;
+
+// Kick index of the SRS test that always counts as a full T-spin (the 1x2 kick)
+const FULL_T_SPIN_KICK: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TSpin {
+    Full,
+    Mini,
+}
+
+// 3-corner rule: at least three of the four cells diagonal to the T center must be taken,
+// it is a full T-spin when both corners next to the pointing side are taken.
+pub fn detect_t_spin(figure: &ActiveFigure, board: &Board, kick_index: usize) -> Option<TSpin> {
+    if figure.get_type() != FigureType::T {
+        return None;
+    }
+    let center = Point {
+        x: figure.position().x + 1,
+        y: figure.position().y + 1,
+    };
+    let corners = [(-1, -1), (1, -1), (1, 1), (-1, 1)];
+    let taken: Vec<bool> = corners
+        .iter()
+        .map(|(dx, dy)| is_taken(board, center.x + dx, center.y + dy))
+        .collect();
+    if taken.iter().filter(|is_taken| **is_taken).count() < 3 {
+        return None;
+    }
+    let front = figure.rotation_step();
+    let front_corners_taken = taken[front] && taken[(front + 1) % 4];
+    if front_corners_taken || kick_index == FULL_T_SPIN_KICK {
+        return Some(TSpin::Full);
+    }
+    return Some(TSpin::Mini);
+}
+
+fn is_taken(board: &Board, x: i32, y: i32) -> bool {
+    if x < 0 || y < 0 || x >= board.width() as i32 || y >= board.height() as i32 {
+        return true;
+    }
+    return board.contains(Point { x, y });
+}
+
+#[cfg(test)]
+mod t_spin_tests {
+    use super::*;
+    use super::super::Size;
+
+    fn board_with(points: &[(usize, usize)]) -> Board {
+        let mut board = Board::new(&Size {
+            height: 5,
+            width: 5,
+        });
+        for (x, y) in points {
+            board = board.replacing_figure_at_xy(*x, *y, Some(FigureType::I));
+        }
+        return board;
+    }
+
+    #[test]
+    fn test_not_a_t_figure() {
+        let board = board_with(&[(0, 2), (2, 2), (0, 4), (2, 4)]);
+        let figure = ActiveFigure::new(FigureType::L, Point { x: 0, y: 2 });
+        assert_eq!(detect_t_spin(&figure, &board, 0), None);
+    }
+
+    #[test]
+    fn test_full_t_spin() {
+        // T pointing down into a slot, both bottom corners and one top corner taken
+        let board = board_with(&[(0, 2), (0, 4), (2, 4)]);
+        let figure = ActiveFigure::new(FigureType::T, Point { x: 0, y: 2 })
+            .rotated()
+            .rotated();
+        assert_eq!(detect_t_spin(&figure, &board, 0), Some(TSpin::Full));
+    }
+
+    #[test]
+    fn test_mini_t_spin() {
+        // T pointing up, only one top corner taken
+        let board = board_with(&[(0, 2), (0, 4), (2, 4)]);
+        let figure = ActiveFigure::new(FigureType::T, Point { x: 0, y: 2 });
+        assert_eq!(detect_t_spin(&figure, &board, 0), Some(TSpin::Mini));
+        assert_eq!(detect_t_spin(&figure, &board, 4), Some(TSpin::Full));
+    }
+
+    #[test]
+    fn test_floor_counts_as_corners() {
+        let figure = ActiveFigure::new(FigureType::T, Point { x: 1, y: 3 });
+        let board = board_with(&[(1, 3)]);
+        assert_eq!(detect_t_spin(&figure, &board, 0), Some(TSpin::Mini));
+        let board = board_with(&[(1, 3), (3, 3)]);
+        assert_eq!(detect_t_spin(&figure, &board, 0), Some(TSpin::Full));
+    }
+
+    #[test]
+    fn test_two_corners_is_not_a_t_spin() {
+        let board = board_with(&[(0, 4), (2, 4)]);
+        let figure = ActiveFigure::new(FigureType::T, Point { x: 0, y: 2 });
+        assert_eq!(detect_t_spin(&figure, &board, 0), None);
+    }
+}
@@ -0,0 +1,350 @@
+// Random play and invariant checks, to catch state machine regressions with fuzzing
+use super::randomizer::XorShift;
+use super::{Action, Game, GameEvent, Item};
+use alloc::vec::Vec;
+
+const DRIVER_ACTIONS: [Action; 15] = [
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::MoveDown,
+    Action::Rotate,
+    Action::RotateCCW,
+    Action::Rotate180,
+    Action::Hold,
+    Action::HardDrop,
+    Action::SoftDrop,
+    Action::SoftDropStart,
+    Action::SoftDropStop,
+    Action::SonicDrop,
+    Action::MoveLeftWall,
+    Action::MoveRightWall,
+    Action::ActivateSpecial,
+];
+const DRIVER_ITEMS: [Item; 3] = [Item::ClearBottomRow, Item::ShuffleBoard, Item::SlowGravity];
+// Longest update of a random step, in seconds, or frames in frame mode
+const MAX_UPDATE: f64 = 0.1;
+const MAX_GARBAGE_LINES: u64 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DriverStep {
+    Perform(Action),
+    Update(f64),
+    Garbage(usize, usize),
+    QueuedGarbage(usize, usize),
+    Item(Item),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InvariantViolation {
+    // The active figure covers a taken or blocked cell
+    OverlappingCells { x: i32, y: i32 },
+    OutOfBounds { x: i32, y: i32 },
+    ScoreDecreased { before: u64, after: u64 },
+    LinesMismatch { expected: usize, actual: usize },
+    // A full row stayed on the board outside of the line clear phase and the stacked lines
+    FullLineLeft(usize),
+    CellCountMismatch { expected: usize, actual: usize },
+}
+
+// Feeds random actions, updates, garbage and items into a game, the same seed plays the same steps
+pub struct RandomActionDriver {
+    rng: XorShift,
+    // One step in this many sends garbage or an item, 0 never does
+    garbage_rate: u64,
+}
+
+impl RandomActionDriver {
+    pub fn new(seed: u64) -> RandomActionDriver {
+        return RandomActionDriver {
+            rng: XorShift::new(seed),
+            garbage_rate: 0,
+        };
+    }
+
+    pub fn with_garbage_rate(mut self, garbage_rate: u64) -> RandomActionDriver {
+        self.garbage_rate = garbage_rate;
+        return self;
+    }
+
+    pub fn next_step(&mut self, game: &Game) -> DriverStep {
+        if self.garbage_rate > 0 && self.rng.next_below(self.garbage_rate) == 0 {
+            let count = 1 + self.rng.next_below(MAX_GARBAGE_LINES) as usize;
+            let width = game.get_visible_size().width as u64;
+            let hole_column = self.rng.next_below(width.max(1)) as usize;
+            // A third of them are items, half of the garbage goes through the queue
+            if self.rng.next_below(3) == 0 {
+                let index = self.rng.next_below(DRIVER_ITEMS.len() as u64) as usize;
+                return DriverStep::Item(DRIVER_ITEMS[index]);
+            }
+            if self.rng.next_below(2) == 0 {
+                return DriverStep::QueuedGarbage(count, hole_column);
+            }
+            return DriverStep::Garbage(count, hole_column);
+        }
+        // Half of the steps let time go by
+        if self.rng.next_below(2) == 0 {
+            let fraction = self.rng.next_below(1000) as f64 / 1000.0;
+            return DriverStep::Update(fraction * MAX_UPDATE);
+        }
+        let index = self.rng.next_below(DRIVER_ACTIONS.len() as u64) as usize;
+        return DriverStep::Perform(DRIVER_ACTIONS[index]);
+    }
+
+    pub fn step(&mut self, game: &mut Game) -> Vec<GameEvent> {
+        return match self.next_step(game) {
+            DriverStep::Perform(action) => game.perform(action),
+            DriverStep::Update(delta_time) => game.update(delta_time),
+            DriverStep::Garbage(count, hole_column) => game.add_garbage_lines(count, hole_column),
+            DriverStep::QueuedGarbage(count, hole_column) => game.queue_garbage(count, hole_column),
+            DriverStep::Item(item) => game.apply_item(item),
+        };
+    }
+
+    // Plays until the game ends or `steps` run out, checking the invariants after every step
+    pub fn drive(&mut self, game: &mut Game, steps: usize) -> Result<(), InvariantViolation> {
+        let mut checker = InvariantChecker::new(game);
+        for _ in 0..steps {
+            if game.is_game_over() || game.is_finished() {
+                break;
+            }
+            let events = self.step(game);
+            checker.check(game, &events)?;
+        }
+        return Ok(());
+    }
+}
+
+// Compares each state with the previous one, feed it the events of every call made on the game
+pub struct InvariantChecker {
+    score: u64,
+    lines: usize,
+    placed_lines: usize,
+    taken_cells: usize,
+}
+
+impl InvariantChecker {
+    pub fn new(game: &Game) -> InvariantChecker {
+        return InvariantChecker {
+            score: game.get_score(),
+            lines: game.get_lines_completed(),
+            placed_lines: placed_lines(game),
+            taken_cells: taken_cells(game),
+        };
+    }
+
+    pub fn check(&mut self, game: &Game, events: &[GameEvent]) -> Result<(), InvariantViolation> {
+        check_invariants(game)?;
+        let score = game.get_score();
+        if score < self.score {
+            return Err(InvariantViolation::ScoreDecreased {
+                before: self.score,
+                after: score,
+            });
+        }
+        // Events only list visible rows, the placements also count the hidden ones
+        let visible_cleared: usize = events
+            .iter()
+            .map(|event| match event {
+                GameEvent::LinesCleared(lines) => lines.len(),
+                _ => 0,
+            })
+            .sum();
+        let placed_lines = placed_lines(game);
+        let cleared = placed_lines.saturating_sub(self.placed_lines);
+        let lines = game.get_lines_completed();
+        if lines != self.lines + cleared || visible_cleared > cleared {
+            return Err(InvariantViolation::LinesMismatch {
+                expected: self.lines + cleared,
+                actual: lines,
+            });
+        }
+        let taken = taken_cells(game);
+        let only_locks = !events.iter().any(|event| {
+            return matches!(
+                event,
+                GameEvent::LinesCleared(_)
+                    | GameEvent::GarbageReceived(_)
+                    | GameEvent::TopOutAvoided(_)
+                    | GameEvent::SpecialEnded { .. }
+                    | GameEvent::ItemApplied(Item::ClearBottomRow)
+                    | GameEvent::GameOver
+            );
+        });
+        // Without clears or garbage the board only grows by the cells of the locked figures
+        if only_locks && !game.is_clearing() {
+            let expected = self.taken_cells + locked_cells(game, events);
+            if taken != expected {
+                return Err(InvariantViolation::CellCountMismatch {
+                    expected,
+                    actual: taken,
+                });
+            }
+        }
+        self.score = score;
+        self.lines = lines;
+        self.placed_lines = placed_lines;
+        self.taken_cells = taken;
+        return Ok(());
+    }
+}
+
+// Checks that hold for any single state of a game
+pub fn check_invariants(game: &Game) -> Result<(), InvariantViolation> {
+    let board = game.get_board();
+    let hidden_rows = game.get_hidden_rows();
+    if !game.is_clearing() {
+        let stack_top = board.height() - game.get_stacked_lines();
+        if let Some(line) = (0..stack_top).find(|line| board.is_line_full(*line)) {
+            return Err(InvariantViolation::FullLineLeft(line));
+        }
+    }
+    // A locked figure stays the active one until the next spawns
+    let ended = game.is_game_over() || game.is_finished();
+    if ended || game.is_clearing() || game.is_in_entry_delay() {
+        return Ok(());
+    }
+    for point in game.access_active_figure() {
+        let (x, y) = (point.x, point.y + hidden_rows as i32);
+        if x < 0 || x >= board.width() as i32 || y >= board.height() as i32 {
+            return Err(InvariantViolation::OutOfBounds { x, y });
+        }
+        if board.row_mask(y) & (1 << x) != 0 {
+            return Err(InvariantViolation::OverlappingCells { x, y });
+        }
+    }
+    return Ok(());
+}
+
+fn taken_cells(game: &Game) -> usize {
+    return game
+        .get_board()
+        .taken_rows()
+        .iter()
+        .map(|row| row.count_ones() as usize)
+        .sum();
+}
+
+fn placed_lines(game: &Game) -> usize {
+    return game
+        .placement_history()
+        .iter()
+        .map(|placement| placement.lines_cleared)
+        .sum();
+}
+
+// Cells of the figures locked by the events that landed on the board, the ones above it are lost
+fn locked_cells(game: &Game, events: &[GameEvent]) -> usize {
+    let count = events
+        .iter()
+        .filter(|event| matches!(event, GameEvent::PieceLocked(_)))
+        .count();
+    let history = game.placement_history();
+    let top = -(game.get_hidden_rows() as i32);
+    return history[history.len().saturating_sub(count)..]
+        .iter()
+        .flat_map(|placement| placement.cells.iter())
+        .filter(|point| point.y >= top)
+        .count();
+}
+
+#[cfg(test)]
+mod testing_tests {
+    use super::super::figure::sets::{PENTOMINOES, TROMINOES};
+    use super::super::{
+        Assists, ClearGravity, GameConfig, ItemRules, RotationSystem, Special, Zone,
+    };
+    use super::*;
+    use proptest::prelude::*;
+
+    fn config_for(rotation: u8, gravity: u8, hidden_rows: usize) -> GameConfig {
+        let rotation_system = match rotation % 4 {
+            0 => RotationSystem::SRS,
+            1 => RotationSystem::Classic,
+            2 => RotationSystem::Nintendo,
+            _ => RotationSystem::ARS,
+        };
+        let clear_gravity = match gravity % 3 {
+            0 => ClearGravity::Naive,
+            1 => ClearGravity::Sticky,
+            _ => ClearGravity::Cascade,
+        };
+        return GameConfig {
+            rotation_system,
+            clear_gravity,
+            hidden_rows,
+            ..GameConfig::default()
+        };
+    }
+
+    #[test]
+    fn test_driver_is_deterministic() {
+        let mut first = Game::with_config(GameConfig::default());
+        let mut second = Game::with_config(GameConfig::default());
+        RandomActionDriver::new(3).drive(&mut first, 500).unwrap();
+        RandomActionDriver::new(3).drive(&mut second, 500).unwrap();
+        assert_eq!(first.draw(), second.draw());
+        assert_eq!(first.get_score(), second.get_score());
+    }
+
+    #[test]
+    fn test_checker_catches_a_lower_score() {
+        let mut game = Game::with_config(GameConfig::default());
+        game.perform(Action::HardDrop);
+        let mut checker = InvariantChecker::new(&game);
+        let restarted = Game::with_config(GameConfig::default());
+        let result = checker.check(&restarted, &[]);
+        assert!(matches!(
+            result,
+            Err(InvariantViolation::ScoreDecreased { .. })
+        ));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn random_play_keeps_the_invariants(
+            seed in any::<u64>(),
+            rotation in any::<u8>(),
+            gravity in any::<u8>(),
+            hidden_rows in 0usize..3,
+            garbage_rate in 0u64..20,
+            kids in any::<bool>(),
+            zone in any::<bool>(),
+        ) {
+            let mut config = config_for(rotation, gravity, hidden_rows);
+            config.randomizer = super::super::RandomizerChoice::Bag(seed);
+            // Hold suggestions only add events, their placement search would slow the cases down
+            if kids {
+                config.assists = Assists {
+                    hold_suggestion: false,
+                    ..Assists::kids()
+                };
+            }
+            // A short zone meter gets activated often
+            if zone {
+                config.special = Special::Zone(Zone {
+                    lines_per_meter: 2,
+                    full_duration: 4.0,
+                });
+            }
+            config.items = ItemRules {
+                lines_per_item: 1,
+                difficult_clears: true,
+                ..ItemRules::default()
+            };
+            let mut game = Game::with_config(config);
+            let mut driver = RandomActionDriver::new(seed).with_garbage_rate(garbage_rate);
+            prop_assert_eq!(driver.drive(&mut game, 2000), Ok(()));
+        }
+
+        #[test]
+        fn random_play_with_piece_sets(seed in any::<u64>(), pentominoes in any::<bool>()) {
+            let set = if pentominoes { &PENTOMINOES } else { &TROMINOES };
+            let config = GameConfig::builder().piece_set(set.figures()).seed(seed).build();
+            let mut game = Game::with_config(config);
+            let mut driver = RandomActionDriver::new(seed).with_garbage_rate(10);
+            prop_assert_eq!(driver.drive(&mut game, 2000), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,421 @@
+use super::randomizer::XorShift;
+use super::{Action, Game, GameEvent, Key, KeyState};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::{Ordering, Reverse};
+
+// Who receives the attack of a player, ties go to the next player in order
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Targeting {
+    // The next player still alive
+    Next,
+    Random,
+    // The player with the highest score
+    Leader,
+    // The player who knocked out the most players
+    MostKOs,
+    // Every player whose last attack went to this one, the next player when nobody did
+    Attackers,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatchRules {
+    // Seconds an attack waits before its garbage is pushed into the target board
+    pub garbage_delay: f64,
+    // Attacks cancel the garbage waiting for the attacker before anything is sent
+    pub cancellation: bool,
+    // Targeting of every player when the match starts
+    pub targeting: Targeting,
+}
+
+impl Default for MatchRules {
+    fn default() -> MatchRules {
+        return MatchRules {
+            garbage_delay: 0.5,
+            cancellation: true,
+            targeting: Targeting::Next,
+        };
+    }
+}
+
+// Runs the games of a battle together, sending the attack of each player to its targets
+pub struct Match {
+    games: Vec<Game>,
+    rules: MatchRules,
+    rng: XorShift,
+    targeting: Vec<Targeting>,
+    kos: Vec<usize>,
+    // The last player who sent garbage to each player, credited with the KO when it tops out
+    last_attacker: Vec<Option<usize>>,
+    // The players each player sent its last attack to
+    last_targets: Vec<Vec<usize>>,
+}
+
+impl Match {
+    // The seed picks the hole columns of the garbage and the random targets. None with fewer than
+    // two games
+    pub fn new(games: Vec<Game>, rules: MatchRules, seed: u64) -> Option<Match> {
+        if games.len() < 2 {
+            return None;
+        }
+        let mut games = games;
+        for game in games.iter_mut() {
+            game.set_garbage_rules(rules.garbage_delay, rules.cancellation);
+        }
+        let count = games.len();
+        return Some(Match {
+            games,
+            targeting: vec![rules.targeting; count],
+            rules,
+            rng: XorShift::new(seed),
+            kos: vec![0; count],
+            last_attacker: vec![None; count],
+            last_targets: vec![vec![]; count],
+        });
+    }
+
+    // Advances every game by the same time, events are paired with the index of their player
+    pub fn update(&mut self, delta_time: f64) -> Vec<(usize, GameEvent)> {
+        let mut events = vec![];
+        if self.is_over() {
+            return events;
+        }
+        for player in 0..self.games.len() {
+            let game_events = self.games[player].update(delta_time);
+            events.extend(self.route(player, game_events));
+        }
+        return events;
+    }
+
+    pub fn perform(&mut self, player: usize, action: Action) -> Vec<(usize, GameEvent)> {
+        if self.is_over() {
+            return vec![];
+        }
+        let events = self.games[player].perform(action);
+        return self.route(player, events);
+    }
+
+    pub fn input(
+        &mut self,
+        player: usize,
+        key: Key,
+        key_state: KeyState,
+    ) -> Vec<(usize, GameEvent)> {
+        if self.is_over() {
+            return vec![];
+        }
+        let events = self.games[player].input(key, key_state);
+        return self.route(player, events);
+    }
+
+    // Attacks come after the game cancelled its own queued garbage
+    fn route(&mut self, player: usize, events: Vec<GameEvent>) -> Vec<(usize, GameEvent)> {
+        let mut routed = vec![];
+        for event in events {
+            if let GameEvent::Attack(lines) = event {
+                let targets = self.targets_of(player);
+                for target in targets.iter() {
+                    routed.extend(self.send(player, *target, lines));
+                }
+                self.last_targets[player] = targets;
+            }
+            routed.push((player, event));
+        }
+        for (knocked_out, event) in routed.iter() {
+            if *event == GameEvent::GameOver {
+                if let Some(attacker) = self.last_attacker[*knocked_out] {
+                    self.kos[attacker] += 1;
+                }
+            }
+        }
+        return routed;
+    }
+
+    fn targets_of(&mut self, player: usize) -> Vec<usize> {
+        let count = self.games.len();
+        let others: Vec<usize> = (1..count)
+            .map(|offset| (player + offset) % count)
+            .filter(|other| self.is_playing(*other))
+            .collect();
+        if others.is_empty() {
+            return vec![];
+        }
+        let target = match self.targeting[player] {
+            Targeting::Next => others[0],
+            Targeting::Random => others[self.rng.next_below(others.len() as u64) as usize],
+            // The minimum of the reversed keys is the first of the highest ones
+            Targeting::Leader => others
+                .iter()
+                .copied()
+                .min_by_key(|other| Reverse(self.games[*other].get_score()))
+                .unwrap_or(others[0]),
+            Targeting::MostKOs => others
+                .iter()
+                .copied()
+                .min_by_key(|other| Reverse(self.kos[*other]))
+                .unwrap_or(others[0]),
+            Targeting::Attackers => {
+                let attackers: Vec<usize> = others
+                    .iter()
+                    .copied()
+                    .filter(|other| self.last_targets[*other].contains(&player))
+                    .collect();
+                if !attackers.is_empty() {
+                    return attackers;
+                }
+                others[0]
+            }
+        };
+        return vec![target];
+    }
+
+    fn send(&mut self, player: usize, target: usize, lines: usize) -> Vec<(usize, GameEvent)> {
+        self.last_attacker[target] = Some(player);
+        let width = self.games[target].get_board().width().max(1);
+        let hole_column = self.rng.next_below(width as u64) as usize;
+        return self.games[target]
+            .queue_garbage(lines, hole_column)
+            .into_iter()
+            .map(|event| (target, event))
+            .collect();
+    }
+
+    // Garbage lines waiting to be pushed into the board of a player
+    pub fn get_pending_garbage(&self, player: usize) -> usize {
+        return self.games[player].get_pending_garbage();
+    }
+
+    pub fn get_targeting(&self, player: usize) -> Targeting {
+        return self.targeting[player];
+    }
+
+    // Switches the targeting of a player, it applies from its next attack
+    pub fn set_targeting(&mut self, player: usize, targeting: Targeting) {
+        self.targeting[player] = targeting;
+    }
+
+    // Players knocked out by the garbage of a player, the last one to send garbage gets the KO
+    pub fn get_kos(&self, player: usize) -> usize {
+        return self.kos[player];
+    }
+
+    pub fn player_count(&self) -> usize {
+        return self.games.len();
+    }
+
+    // Over once a player reaches the goal of the mode, or when one player is left standing
+    pub fn is_over(&self) -> bool {
+        return self.games.iter().any(Game::is_finished) || self.playing_players().len() <= 1;
+    }
+
+    // The player who reached the goal of the mode first, ties going to the higher score,
+    // or else the last player standing once every other game is over
+    pub fn winner(&self) -> Option<usize> {
+        let first = (0..self.games.len())
+            .filter(|player| self.games[*player].is_finished())
+            .min_by(|first, second| {
+                let (first, second) = (&self.games[*first], &self.games[*second]);
+                let time = first
+                    .get_elapsed_time()
+                    .partial_cmp(&second.get_elapsed_time());
+                return time
+                    .unwrap_or(Ordering::Equal)
+                    .then(second.get_score().cmp(&first.get_score()));
+            });
+        if first.is_some() {
+            return first;
+        }
+        let playing = self.playing_players();
+        if playing.len() == 1 {
+            return Some(playing[0]);
+        }
+        return None;
+    }
+
+    // Neither topped out nor done with the mode, the only players garbage can be sent to
+    fn is_playing(&self, player: usize) -> bool {
+        let game = &self.games[player];
+        return !game.is_game_over() && !game.is_finished();
+    }
+
+    fn playing_players(&self) -> Vec<usize> {
+        return (0..self.games.len())
+            .filter(|player| self.is_playing(*player))
+            .collect();
+    }
+
+    pub fn game(&self, player: usize) -> &Game {
+        return &self.games[player];
+    }
+
+    pub fn game_mut(&mut self, player: usize) -> &mut Game {
+        return &mut self.games[player];
+    }
+
+    pub fn rules(&self) -> &MatchRules {
+        return &self.rules;
+    }
+}
+
+#[cfg(test)]
+mod versus_tests {
+    use super::super::game::fixtures::o_slot_game;
+    use super::super::{GameConfig, GameMode};
+    use super::*;
+
+    // Every O piece dropped clears two lines, which sends one garbage line
+    fn double_ready_game() -> Game {
+        return o_slot_game(2, GameConfig::builder());
+    }
+
+    fn new_match(rules: MatchRules) -> Match {
+        let games = vec![double_ready_game(), double_ready_game(), double_ready_game()];
+        return Match::new(games, rules, 5).unwrap();
+    }
+
+    // Rows with a single hole, the prepared rows have two
+    fn garbage_rows(game: &Game) -> usize {
+        return game
+            .get_board()
+            .to_rows()
+            .iter()
+            .filter(|row| row.iter().filter(|cell| cell.is_some()).count() == 9)
+            .count();
+    }
+
+    #[test]
+    fn test_match_needs_two_games() {
+        assert!(Match::new(vec![double_ready_game()], MatchRules::default(), 5).is_none());
+        assert!(Match::new(vec![], MatchRules::default(), 5).is_none());
+    }
+
+    #[test]
+    fn test_attack_lands_after_the_delay() {
+        let mut versus = new_match(MatchRules::default());
+        let events = versus.perform(0, Action::HardDrop);
+        assert!(events.contains(&(0, GameEvent::Attack(1))));
+        assert_eq!(versus.get_pending_garbage(1), 1);
+        assert_eq!(versus.get_pending_garbage(2), 0);
+        versus.update(0.3);
+        assert_eq!(versus.get_pending_garbage(1), 1);
+        let events = versus.update(0.3);
+        assert!(events.contains(&(1, GameEvent::GarbageReceived(1))));
+        assert_eq!(versus.get_pending_garbage(1), 0);
+        assert_eq!(garbage_rows(versus.game(1)), 1);
+    }
+
+    #[test]
+    fn test_attack_cancels_pending_garbage() {
+        let mut versus = new_match(MatchRules::default());
+        versus.perform(0, Action::HardDrop);
+        versus.perform(1, Action::HardDrop);
+        assert_eq!(versus.get_pending_garbage(1), 0);
+        assert_eq!(versus.get_pending_garbage(2), 0);
+        let rules = MatchRules {
+            cancellation: false,
+            ..MatchRules::default()
+        };
+        let mut versus = new_match(rules);
+        versus.perform(0, Action::HardDrop);
+        versus.perform(1, Action::HardDrop);
+        assert_eq!(versus.get_pending_garbage(1), 1);
+        assert_eq!(versus.get_pending_garbage(2), 1);
+    }
+
+    #[test]
+    fn test_no_delay_sends_garbage_at_once() {
+        let rules = MatchRules {
+            garbage_delay: 0.0,
+            ..MatchRules::default()
+        };
+        let mut versus = new_match(rules);
+        let events = versus.perform(2, Action::HardDrop);
+        assert!(events.contains(&(0, GameEvent::GarbageReceived(1))));
+    }
+
+    #[test]
+    fn test_leader_is_targeted() {
+        let rules = MatchRules {
+            cancellation: false,
+            targeting: Targeting::Leader,
+            ..MatchRules::default()
+        };
+        let mut versus = new_match(rules);
+        // Nobody leads yet, the next player gets it
+        versus.perform(2, Action::HardDrop);
+        assert_eq!(versus.get_pending_garbage(0), 1);
+        versus.perform(0, Action::HardDrop);
+        assert_eq!(versus.get_pending_garbage(1), 0);
+        assert_eq!(versus.get_pending_garbage(2), 1);
+    }
+
+    #[test]
+    fn test_targeting_switches_mid_game() {
+        let rules = MatchRules {
+            cancellation: false,
+            ..MatchRules::default()
+        };
+        let mut versus = new_match(rules);
+        versus.perform(2, Action::HardDrop);
+        assert_eq!(versus.get_targeting(0), Targeting::Next);
+        versus.set_targeting(0, Targeting::Attackers);
+        versus.perform(0, Action::HardDrop);
+        assert_eq!(versus.get_pending_garbage(1), 0);
+        assert_eq!(versus.get_pending_garbage(2), 1);
+    }
+
+    #[test]
+    fn test_knock_outs_are_credited_and_targeted() {
+        let games = vec![
+            double_ready_game(),
+            double_ready_game(),
+            double_ready_game(),
+            double_ready_game(),
+        ];
+        let mut versus = Match::new(games, MatchRules::default(), 5).unwrap();
+        versus.perform(0, Action::HardDrop);
+        versus.game_mut(1).add_garbage_lines(18, 0);
+        assert!(!versus.game(1).is_game_over());
+        let events = versus.update(0.6);
+        assert!(events.contains(&(1, GameEvent::GameOver)));
+        assert_eq!(versus.get_kos(0), 1);
+        versus.set_targeting(2, Targeting::MostKOs);
+        versus.perform(2, Action::HardDrop);
+        assert_eq!(versus.get_pending_garbage(0), 1);
+        assert_eq!(versus.get_pending_garbage(3), 0);
+    }
+
+    #[test]
+    fn test_winner_is_the_last_player_standing() {
+        let mut versus = new_match(MatchRules::default());
+        assert_eq!(versus.winner(), None);
+        versus.game_mut(1).add_garbage_lines(20, 0);
+        assert!(!versus.is_over());
+        versus.game_mut(0).add_garbage_lines(20, 0);
+        assert!(versus.is_over());
+        assert_eq!(versus.winner(), Some(2));
+        assert!(versus.perform(2, Action::HardDrop).is_empty());
+    }
+
+    #[test]
+    fn test_first_player_to_finish_the_mode_wins() {
+        let sprint = || o_slot_game(2, GameConfig::builder().mode(GameMode::Sprint { lines: 2 }));
+        let games = vec![sprint(), sprint(), sprint()];
+        let mut versus = Match::new(games, MatchRules::default(), 5).unwrap();
+        versus.update(1.0);
+        versus.perform(1, Action::HardDrop);
+        assert!(versus.game(1).is_finished());
+        assert!(versus.is_over());
+        assert_eq!(versus.winner(), Some(1));
+        assert!(versus.perform(0, Action::HardDrop).is_empty());
+        let mode = GameMode::Ultra { seconds: 1.0 };
+        let ultra = || o_slot_game(2, GameConfig::builder().mode(mode.clone()));
+        let mut versus = Match::new(vec![ultra(), ultra()], MatchRules::default(), 5).unwrap();
+        versus.perform(1, Action::HardDrop);
+        versus.update(2.0);
+        assert!(versus.game(0).is_finished() && versus.game(1).is_finished());
+        assert_eq!(versus.winner(), Some(1));
+    }
+}
@@ -0,0 +1,141 @@
+use super::bindings::cell_codes_into;
+use super::{Action, Game, GameConfig, Size};
+use alloc::vec;
+use alloc::vec::Vec;
+use wasm_bindgen::prelude::*;
+
+// Browser friendly wrapper, boards are returned as flat row by row arrays
+#[wasm_bindgen]
+pub struct WasmGame {
+    game: Game,
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: usize, height: usize, seed: u64) -> WasmGame {
+        let config = GameConfig::builder()
+            .size(Size { height, width })
+            .seed(seed)
+            .build();
+        return WasmGame {
+            game: Game::with_config(config),
+        };
+    }
+
+    // Advances the game by the given milliseconds
+    pub fn tick(&mut self, ms: f64) {
+        self.game.update(ms / 1000.0);
+    }
+
+    // Uses the `Action::code` values, unknown codes are ignored and return false
+    pub fn perform(&mut self, action_code: u8) -> bool {
+        return match Action::from_code(action_code) {
+            Some(action) => {
+                self.game.perform(action);
+                true
+            }
+            None => false,
+        };
+    }
+
+    pub fn width(&self) -> usize {
+        return self.game.get_visible_size().width;
+    }
+
+    pub fn height(&self) -> usize {
+        return self.game.get_visible_size().height;
+    }
+
+    // One `FigureType::code` per cell (0 when empty), active figure included
+    pub fn cells(&self) -> Vec<u8> {
+        let mut cells = vec![0; self.width() * self.height()];
+        cell_codes_into(&self.game, &mut cells);
+        return cells;
+    }
+
+    // Red, green, blue and alpha of every cell, transparent when empty
+    pub fn colors(&self) -> Vec<f32> {
+        let mut colors = vec![0.0; self.width() * self.height() * 4];
+        for block in self.game.blocks() {
+            let origin = block.position();
+            if let Some(index) = self.index_of(origin.x, origin.y) {
+                colors[index * 4..index * 4 + 4].copy_from_slice(&block.color.to_rgba_f32());
+            }
+        }
+        return colors;
+    }
+
+    fn index_of(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x as usize >= self.width() || y as usize >= self.height() {
+            return None;
+        }
+        return Some(y as usize * self.width() + x as usize);
+    }
+
+    // Cell code of the upcoming figure
+    pub fn next_figure(&self) -> u8 {
+        return self.game.get_next_figure_type().code();
+    }
+
+    pub fn held_figure(&self) -> u8 {
+        return self.game.get_held_figure().map_or(0, |figure| figure.code());
+    }
+
+    pub fn score(&self) -> u64 {
+        return self.game.get_score();
+    }
+
+    pub fn lines(&self) -> usize {
+        return self.game.get_lines_completed();
+    }
+
+    pub fn level(&self) -> usize {
+        return self.game.get_level();
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        return self.game.is_game_over();
+    }
+}
+
+impl WasmGame {
+    pub fn game(&self) -> &Game {
+        return &self.game;
+    }
+}
+
+#[cfg(test)]
+mod wasm_tests {
+    use super::super::FigureType;
+    use super::*;
+
+    #[test]
+    fn test_cells_include_the_active_figure() {
+        let mut game = WasmGame::new(10, 20, 1);
+        let active = game.game().active_figure_type().code();
+        let cells = game.cells();
+        assert_eq!(cells.len(), 200);
+        assert_eq!(cells.iter().filter(|cell| **cell == active).count(), 4);
+        assert!(game.perform(Action::HardDrop.code()));
+        assert!(!game.perform(200));
+        assert_eq!(game.cells().iter().filter(|cell| **cell != 0).count(), 8);
+        assert_eq!(game.colors().len(), 800);
+    }
+
+    #[test]
+    fn test_tick_uses_milliseconds() {
+        let mut game = WasmGame::new(10, 20, 1);
+        let before = game.game().access_active_figure();
+        game.tick(1100.0);
+        assert_ne!(game.game().access_active_figure(), before);
+    }
+
+    #[test]
+    fn test_figure_types() {
+        let game = WasmGame::new(10, 20, 1);
+        assert_eq!(game.next_figure(), game.game().get_next_figure_type().code());
+        assert_eq!(game.held_figure(), 0);
+        assert_eq!(FigureType::Garbage.code(), 8);
+    }
+}